@@ -0,0 +1,233 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+use font_writer::woff;
+use serde_json::json;
+use ttf_parser::{Face, GlyphId, OutlineBuilder, PlatformId};
+use wasm_bindgen::prelude::*;
+
+use crate::{Contour, Contours, Point, Segment};
+
+// 读取到的字体信息，字段命名与前端 IFont / ICharacter 保持一致
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FontFile {
+    pub units_per_em: u16,
+    pub ascender: i16,
+    pub descender: i16,
+    pub line_gap: i16,
+    pub cap_height: Option<i16>,
+    pub x_height: Option<i16>,
+    pub italic_angle: f32,
+    pub weight: u16,
+    // "truetype"、"cff" 或 "cff2"
+    pub outline_format: &'static str,
+    pub num_glyphs: u16,
+    pub names: Vec<FontName>,
+    // Unicode 码位 -> 字形索引
+    pub cmap: BTreeMap<u32, u16>,
+    pub characters: Vec<FontCharacter>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FontName {
+    pub platform_id: u16,
+    pub encoding_id: u16,
+    pub language_id: u16,
+    pub name_id: u16,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FontCharacter {
+    pub unicode: u32,
+    pub glyph_id: u16,
+    pub name: Option<String>,
+    pub advance_width: u16,
+    pub left_side_bearing: i16,
+    pub contours: Contours,
+    pub contour_num: usize,
+}
+
+// 将 ttf-parser 的绘制回调转换为 Segment 轮廓：glyf 保留二次曲线，CFF/CFF2 保留三次曲线
+struct ContourCollector {
+    contours: Contours,
+    current: Contour,
+    start: Point,
+    pos: Point,
+}
+
+impl ContourCollector {
+    fn new() -> Self {
+        let origin = Point { x: 0.0, y: 0.0 };
+        ContourCollector { contours: Vec::new(), current: Vec::new(), start: origin, pos: origin }
+    }
+
+    fn finish(&mut self) {
+        if self.current.is_empty() {
+            return;
+        }
+        if self.pos.x != self.start.x || self.pos.y != self.start.y {
+            self.current.push(Segment::Line { start: self.pos, end: self.start });
+        }
+        self.contours.push(std::mem::take(&mut self.current));
+        self.pos = self.start;
+    }
+}
+
+fn pt(x: f32, y: f32) -> Point {
+    Point { x: x as f64, y: y as f64 }
+}
+
+impl OutlineBuilder for ContourCollector {
+    fn move_to(&mut self, x: f32, y: f32) {
+        self.finish();
+        self.start = pt(x, y);
+        self.pos = self.start;
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        let end = pt(x, y);
+        self.current.push(Segment::Line { start: self.pos, end });
+        self.pos = end;
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        let end = pt(x, y);
+        self.current.push(Segment::Quadratic { start: self.pos, control: pt(x1, y1), end });
+        self.pos = end;
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        let end = pt(x, y);
+        self.current.push(Segment::Cubic { start: self.pos, control1: pt(x1, y1), control2: pt(x2, y2), end });
+        self.pos = end;
+    }
+
+    fn close(&mut self) {
+        self.finish();
+    }
+}
+
+pub fn glyph_contours(face: &Face, glyph_id: GlyphId) -> Contours {
+    let mut collector = ContourCollector::new();
+    // 空字形（如空格）没有轮廓，outline_glyph 返回 None
+    if face.outline_glyph(glyph_id, &mut collector).is_some() {
+        collector.finish();
+    }
+    collector.contours
+}
+
+fn decode_name(name: &ttf_parser::name::Name) -> Option<String> {
+    if let Some(value) = name.to_string() {
+        return Some(value);
+    }
+    // Mac Roman 编码只解码 ASCII 部分
+    if name.platform_id == PlatformId::Macintosh && name.encoding_id == 0 && name.name.is_ascii() {
+        return Some(String::from_utf8_lossy(name.name).into_owned());
+    }
+    None
+}
+
+// WOFF 1.0 先解包为 sfnt 数据，其余格式原样返回
+pub fn unwrap_font(data: &[u8]) -> Result<Cow<'_, [u8]>, String> {
+    if woff::is_woff(data) {
+        Ok(Cow::Owned(woff::decode(data)?.sfnt))
+    } else {
+        Ok(Cow::Borrowed(data))
+    }
+}
+
+// 字体集合（TTC/OTC）中的字体数量，单个字体返回 1
+pub fn font_count(data: &[u8]) -> u32 {
+    ttf_parser::fonts_in_collection(data).unwrap_or(1)
+}
+
+// 解析 TTF/OTF/TTC/WOFF 字体，index 为字体集合中的序号
+pub fn read_font(data: &[u8], index: u32) -> Result<FontFile, String> {
+    let data = unwrap_font(data)?;
+    let face = Face::parse(&data, index).map_err(|err| format!("invalid font: {err}"))?;
+    let tables = face.tables();
+
+    let outline_format = if tables.glyf.is_some() {
+        "truetype"
+    } else if tables.cff2.is_some() {
+        "cff2"
+    } else {
+        "cff"
+    };
+
+    let names = face
+        .names()
+        .into_iter()
+        .filter_map(|name| {
+            Some(FontName {
+                platform_id: name.platform_id as u16,
+                encoding_id: name.encoding_id,
+                language_id: name.language_id,
+                name_id: name.name_id,
+                value: decode_name(&name)?,
+            })
+        })
+        .collect();
+
+    let mut cmap: BTreeMap<u32, u16> = BTreeMap::new();
+    if let Some(cmap_table) = tables.cmap {
+        for subtable in cmap_table.subtables.into_iter().filter(|s| s.is_unicode()) {
+            subtable.codepoints(|cp| {
+                if let Some(gid) = subtable.glyph_index(cp) {
+                    cmap.entry(cp).or_insert(gid.0);
+                }
+            });
+        }
+    }
+
+    let characters = cmap
+        .iter()
+        .map(|(&unicode, &gid)| {
+            let glyph_id = GlyphId(gid);
+            let contours = glyph_contours(&face, glyph_id);
+            FontCharacter {
+                unicode,
+                glyph_id: gid,
+                name: face.glyph_name(glyph_id).map(|n| n.to_string()),
+                advance_width: face.glyph_hor_advance(glyph_id).unwrap_or(0),
+                left_side_bearing: face.glyph_hor_side_bearing(glyph_id).unwrap_or(0),
+                contour_num: contours.len(),
+                contours,
+            }
+        })
+        .collect();
+
+    Ok(FontFile {
+        units_per_em: face.units_per_em(),
+        ascender: face.ascender(),
+        descender: face.descender(),
+        line_gap: face.line_gap(),
+        cap_height: face.capital_height(),
+        x_height: face.x_height(),
+        italic_angle: face.italic_angle(),
+        weight: face.weight().to_number(),
+        outline_format,
+        num_glyphs: face.number_of_glyphs(),
+        names,
+        cmap,
+        characters,
+    })
+}
+
+#[wasm_bindgen(js_name = read_font)]
+pub fn read_font_json(data: &[u8], index: u32) -> String {
+    match read_font(data, index) {
+        Ok(font) => json!({ "ok": true, "font": font }).to_string(),
+        Err(err) => json!({ "ok": false, "error": err }).to_string(),
+    }
+}
+
+#[wasm_bindgen(js_name = font_count)]
+pub fn font_count_js(data: &[u8]) -> u32 {
+    font_count(data)
+}
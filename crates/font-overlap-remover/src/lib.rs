@@ -1,11 +1,16 @@
 use wasm_bindgen::prelude::*;
-use serde::{Deserialize, Serialize};
 use serde_json::json;
 
 use flo_curves::bezier::path::{SimpleBezierPath, path_add};
 use flo_curves::Coordinate2D;
 use flo_curves::geo::Coord2;
 
+pub mod transform;
+pub mod svg_import;
+pub mod svg_export;
+pub mod font_reader;
+pub mod web_font;
+
 #[wasm_bindgen(start)]
 pub fn start() {
     #[cfg(feature = "console_error_panic_hook")]
@@ -14,27 +19,8 @@ pub fn start() {
     }
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
-pub struct Point {
-    pub x: f64,
-    pub y: f64,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(tag = "type")]
-pub enum Segment {
-    #[serde(rename = "LINE")]
-    Line { start: Point, end: Point },
-
-    #[serde(rename = "QUADRATIC_BEZIER")]
-    Quadratic { start: Point, control: Point, end: Point },
-
-    #[serde(rename = "CUBIC_BEZIER")]
-    Cubic { start: Point, control1: Point, control2: Point, end: Point },
-}
-
-type Contour = Vec<Segment>;
-type Contours = Vec<Contour>;
+pub use font_writer::outline::{Contour, Contours, Point, Segment};
+use font_writer::outline::reverse_contour;
 
 fn p_to_coord(p: Point) -> Coord2 { 
     Coord2(p.x, p.y) 
@@ -80,27 +66,6 @@ fn calculate_winding_number(contour: &Contour) -> f64 {
     winding
 }
 
-// 反转轮廓方向
-fn reverse_contour(contour: &Contour) -> Contour {
-    let mut reversed = Vec::new();
-    
-    for seg in contour.iter().rev() {
-        match seg {
-            Segment::Line { start, end } => {
-                reversed.push(Segment::Line { start: *end, end: *start });
-            }
-            Segment::Quadratic { start, control, end } => {
-                reversed.push(Segment::Quadratic { start: *end, control: *control, end: *start });
-            }
-            Segment::Cubic { start, control1, control2, end } => {
-                reversed.push(Segment::Cubic { start: *end, control1: *control2, control2: *control1, end: *start });
-            }
-        }
-    }
-    
-    reversed
-}
-
 // 确保轮廓方向正确（外轮廓逆时针，内轮廓（孔洞）顺时针）
 fn normalize_contour_direction(contour: &Contour) -> Contour {
     let winding = calculate_winding_number(contour);
@@ -434,6 +399,21 @@ fn normalize_union_result(mut united: Vec<SimpleBezierPath>) -> Vec<SimpleBezier
     united
 }
 
+// 对轮廓做并集运算去除重叠，供其他模块直接调用
+pub fn remove_overlap_contours(contours: &Contours) -> Contours {
+    let paths: Vec<SimpleBezierPath> = contours.iter().filter_map(contour_to_simple_path).collect();
+    if paths.is_empty() {
+        return Vec::new();
+    }
+
+    let united = normalize_union_result(union_paths(&paths));
+    united
+        .iter()
+        .map(simple_path_to_contour)
+        .filter(|c| !c.is_empty())
+        .collect()
+}
+
 #[wasm_bindgen]
 pub fn remove_overlap(contours_json: &str) -> String {
     let contours: Contours = match serde_json::from_str(contours_json) {
@@ -0,0 +1,319 @@
+use std::collections::HashSet;
+use std::io::{Cursor, Write};
+
+use serde::Deserialize;
+use serde_json::json;
+use wasm_bindgen::prelude::*;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::{Contours, Point, Segment};
+
+// 导出选项：默认以 unitsPerEm 见方的字框作为 viewBox，并翻转 y 轴
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SvgExportOptions {
+    // 坐标保留的小数位数
+    pub precision: usize,
+    pub units_per_em: f64,
+    pub descender: f64,
+    // 自定义 viewBox：[minX, minY, width, height]，缺省时使用字框
+    pub view_box: Option<[f64; 4]>,
+    // 绘制字框、基线等辅助线
+    pub guides: bool,
+    // 字体坐标 y 轴向上，SVG 坐标 y 轴向下
+    pub flip_y: bool,
+    pub fill: String,
+}
+
+impl Default for SvgExportOptions {
+    fn default() -> Self {
+        SvgExportOptions {
+            precision: 2,
+            units_per_em: 1000.0,
+            descender: -200.0,
+            view_box: None,
+            guides: false,
+            flip_y: true,
+            fill: "#000".to_string(),
+        }
+    }
+}
+
+impl SvgExportOptions {
+    fn ascender(&self) -> f64 {
+        self.units_per_em + self.descender
+    }
+
+    fn map(&self, p: Point) -> Point {
+        if self.flip_y {
+            Point { x: p.x, y: self.ascender() - p.y }
+        } else {
+            p
+        }
+    }
+
+    fn view_box(&self) -> [f64; 4] {
+        match self.view_box {
+            Some(vb) => vb,
+            None if self.flip_y => [0.0, 0.0, self.units_per_em, self.units_per_em],
+            None => [0.0, self.descender, self.units_per_em, self.units_per_em],
+        }
+    }
+}
+
+// 批量导出时的单个字符
+#[derive(Debug, Clone, Deserialize)]
+pub struct GlyphSvg {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub unicode: Option<u32>,
+    pub contours: Contours,
+}
+
+fn format_number(value: f64, precision: usize) -> String {
+    let mut s = format!("{:.*}", precision, value);
+    if s.contains('.') {
+        s.truncate(s.trim_end_matches('0').trim_end_matches('.').len());
+    }
+    if s == "-0" {
+        return "0".to_string();
+    }
+    if let Some(rest) = s.strip_prefix("0.") {
+        s = format!(".{rest}");
+    } else if let Some(rest) = s.strip_prefix("-0.") {
+        s = format!("-.{rest}");
+    }
+    s
+}
+
+fn round_to(value: f64, precision: usize) -> f64 {
+    let factor = 10f64.powi(precision as i32);
+    (value * factor).round() / factor
+}
+
+// 紧凑路径数据：全部使用相对命令，省略重复的命令字母和不必要的分隔符
+struct PathWriter {
+    out: String,
+    precision: usize,
+    last_command: Option<char>,
+    last_number_has_dot: bool,
+}
+
+impl PathWriter {
+    fn command(&mut self, cmd: char) {
+        // moveto 之后的隐式命令是 lineto，因此 m 之后总是写出命令字母
+        if self.last_command != Some(cmd) || cmd == 'm' || cmd == 'z' {
+            self.out.push(cmd);
+            self.last_command = Some(cmd);
+            self.last_number_has_dot = false;
+        }
+    }
+
+    fn number(&mut self, value: f64) {
+        let s = format_number(value, self.precision);
+        let after_number = self.out.chars().last().is_some_and(|c| c.is_ascii_digit() || c == '.');
+        let needs_separator = after_number && !s.starts_with('-') && !(s.starts_with('.') && self.last_number_has_dot);
+        if needs_separator {
+            self.out.push(' ');
+        }
+        self.last_number_has_dot = s.contains('.');
+        self.out.push_str(&s);
+    }
+}
+
+// 将轮廓转换为 SVG 路径数据（d 属性）
+pub fn contours_to_path_data(contours: &Contours, options: &SvgExportOptions) -> String {
+    let precision = options.precision;
+    let mut writer = PathWriter { out: String::new(), precision, last_command: None, last_number_has_dot: false };
+    // 以取整后的坐标计算相对量，避免误差累积
+    let round = |p: Point| {
+        let p = options.map(p);
+        Point { x: round_to(p.x, precision), y: round_to(p.y, precision) }
+    };
+    let mut pos = Point { x: 0.0, y: 0.0 };
+
+    for contour in contours {
+        let Some(first) = contour.first() else { continue };
+        let start = round(first.start());
+        writer.command('m');
+        writer.number(start.x - pos.x);
+        writer.number(start.y - pos.y);
+        pos = start;
+
+        let mut last_cubic_control: Option<Point> = None;
+        let mut last_quad_control: Option<Point> = None;
+        for (i, seg) in contour.iter().enumerate() {
+            let mut cubic_control = None;
+            let mut quad_control = None;
+            match seg {
+                Segment::Line { end, .. } => {
+                    let end = round(*end);
+                    // 闭合线段由 z 命令代替
+                    if i + 1 == contour.len() && end.x == start.x && end.y == start.y {
+                        continue;
+                    }
+                    if end.y == pos.y && end.x != pos.x {
+                        writer.command('h');
+                        writer.number(end.x - pos.x);
+                    } else if end.x == pos.x && end.y != pos.y {
+                        writer.command('v');
+                        writer.number(end.y - pos.y);
+                    } else if end.x != pos.x || end.y != pos.y {
+                        writer.command('l');
+                        writer.number(end.x - pos.x);
+                        writer.number(end.y - pos.y);
+                    }
+                    pos = end;
+                }
+                Segment::Quadratic { control, end, .. } => {
+                    let (control, end) = (round(*control), round(*end));
+                    if reflects(last_quad_control, pos, control) {
+                        writer.command('t');
+                    } else {
+                        writer.command('q');
+                        writer.number(control.x - pos.x);
+                        writer.number(control.y - pos.y);
+                    }
+                    writer.number(end.x - pos.x);
+                    writer.number(end.y - pos.y);
+                    quad_control = Some(control);
+                    pos = end;
+                }
+                Segment::Cubic { control1, control2, end, .. } => {
+                    let (control1, control2, end) = (round(*control1), round(*control2), round(*end));
+                    if reflects(last_cubic_control, pos, control1) {
+                        writer.command('s');
+                    } else {
+                        writer.command('c');
+                        writer.number(control1.x - pos.x);
+                        writer.number(control1.y - pos.y);
+                    }
+                    writer.number(control2.x - pos.x);
+                    writer.number(control2.y - pos.y);
+                    writer.number(end.x - pos.x);
+                    writer.number(end.y - pos.y);
+                    cubic_control = Some(control2);
+                    pos = end;
+                }
+            }
+            last_cubic_control = cubic_control;
+            last_quad_control = quad_control;
+        }
+        writer.command('z');
+        // z 之后当前点回到子路径起点
+        pos = start;
+    }
+
+    writer.out
+}
+
+// 判断控制点是否为上一段控制点关于当前点的反射，可用 s/t 简写
+fn reflects(previous: Option<Point>, pos: Point, control: Point) -> bool {
+    match previous {
+        Some(prev) => (2.0 * pos.x - prev.x - control.x).abs() < 1e-9 && (2.0 * pos.y - prev.y - control.y).abs() < 1e-9,
+        None => false,
+    }
+}
+
+// 生成完整的 SVG 文档
+pub fn contours_to_svg(contours: &Contours, options: &SvgExportOptions) -> String {
+    let vb = options.view_box();
+    let num = |v: f64| format_number(v, options.precision);
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{} {} {} {}\" width=\"{}\" height=\"{}\">\n",
+        num(vb[0]), num(vb[1]), num(vb[2]), num(vb[3]), num(vb[2]), num(vb[3]),
+    );
+
+    if options.guides {
+        let upm = options.units_per_em;
+        let left_bottom = options.map(Point { x: 0.0, y: options.descender });
+        let right_top = options.map(Point { x: upm, y: options.ascender() });
+        let baseline = options.map(Point { x: 0.0, y: 0.0 }).y;
+        let top = left_bottom.y.min(right_top.y);
+        svg.push_str("  <g fill=\"none\" stroke=\"#c0c0c0\" stroke-width=\"1\">\n");
+        svg.push_str(&format!(
+            "    <rect x=\"0\" y=\"{}\" width=\"{}\" height=\"{}\"/>\n",
+            num(top), num(upm), num(upm),
+        ));
+        svg.push_str(&format!(
+            "    <line x1=\"0\" y1=\"{}\" x2=\"{}\" y2=\"{}\"/>\n",
+            num(baseline), num(upm), num(baseline),
+        ));
+        svg.push_str("  </g>\n");
+    }
+
+    let d = contours_to_path_data(contours, options);
+    if !d.is_empty() {
+        svg.push_str(&format!("  <path d=\"{}\" fill=\"{}\"/>\n", d, options.fill));
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+// 文件名中不允许出现的字符替换为下划线，空名称使用 Unicode 编码命名
+fn glyph_file_name(glyph: &GlyphSvg, used: &mut HashSet<String>) -> String {
+    let mut base: String = glyph
+        .name
+        .chars()
+        .map(|c| if matches!(c, '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|') || c.is_control() { '_' } else { c })
+        .collect();
+    if base.trim().is_empty() || base == "." || base == ".." {
+        base = match glyph.unicode {
+            Some(u) => format!("uni{u:04X}"),
+            None => "glyph".to_string(),
+        };
+    }
+    let mut name = format!("{base}.svg");
+    let mut n = 2;
+    while !used.insert(name.clone()) {
+        name = format!("{base}_{n}.svg");
+        n += 1;
+    }
+    name
+}
+
+// 批量导出：每个字符一个 SVG 文件，打包为 zip
+pub fn glyphs_to_svg_zip(glyphs: &[GlyphSvg], options: &SvgExportOptions) -> Result<Vec<u8>, String> {
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let file_options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    let mut used = HashSet::new();
+    for glyph in glyphs {
+        let name = glyph_file_name(glyph, &mut used);
+        zip.start_file(name.as_str(), file_options).map_err(|err| format!("failed to write {name}: {err}"))?;
+        zip.write_all(contours_to_svg(&glyph.contours, options).as_bytes())
+            .map_err(|err| format!("failed to write {name}: {err}"))?;
+    }
+    let cursor = zip.finish().map_err(|err| format!("failed to finish zip: {err}"))?;
+    Ok(cursor.into_inner())
+}
+
+fn parse_options(options_json: &str) -> Result<SvgExportOptions, String> {
+    if options_json.trim().is_empty() {
+        return Ok(SvgExportOptions::default());
+    }
+    serde_json::from_str(options_json).map_err(|err| format!("invalid options: {err}"))
+}
+
+#[wasm_bindgen]
+pub fn export_svg(contours_json: &str, options_json: &str) -> String {
+    let contours: Contours = match serde_json::from_str(contours_json) {
+        Ok(c) => c,
+        Err(err) => {
+            return json!({ "ok": false, "error": format!("invalid json: {err}") }).to_string();
+        }
+    };
+    match parse_options(options_json) {
+        Ok(options) => json!({ "ok": true, "svg": contours_to_svg(&contours, &options) }).to_string(),
+        Err(err) => json!({ "ok": false, "error": err }).to_string(),
+    }
+}
+
+#[wasm_bindgen]
+pub fn export_svg_zip(glyphs_json: &str, options_json: &str) -> Result<Vec<u8>, JsValue> {
+    let glyphs: Vec<GlyphSvg> = serde_json::from_str(glyphs_json)
+        .map_err(|err| JsValue::from_str(&format!("invalid json: {err}")))?;
+    let options = parse_options(options_json).map_err(|err| JsValue::from_str(&err))?;
+    glyphs_to_svg_zip(&glyphs, &options).map_err(|err| JsValue::from_str(&err))
+}
@@ -0,0 +1,846 @@
+use std::f64::consts::PI;
+
+use serde::Deserialize;
+use serde_json::json;
+use wasm_bindgen::prelude::*;
+
+use crate::transform::{transform_contour, Matrix};
+use crate::{reverse_contour, Contour, Contours, Point, Segment};
+
+// 四分之一圆弧的三次贝塞尔近似系数
+const KAPPA: f64 = 0.552_284_749_830_793_4;
+
+// 导入选项：SVG 画布（viewBox）会按比例居中缩放到 unitsPerEm 见方的字框中
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SvgImportOptions {
+    pub units_per_em: f64,
+    pub descender: f64,
+}
+
+impl Default for SvgImportOptions {
+    fn default() -> Self {
+        SvgImportOptions { units_per_em: 1000.0, descender: -200.0 }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Style {
+    even_odd: bool,
+    filled: bool,
+    visible: bool,
+}
+
+// 单个图形元素的轮廓，各元素独立绘制，需分别处理填充规则
+struct Shape {
+    contours: Contours,
+    even_odd: bool,
+}
+
+// 解析 SVG 文本，输出字体坐标系（y 轴向上）下的轮廓
+pub fn parse_svg(svg: &str, options: &SvgImportOptions) -> Result<Contours, String> {
+    let parse_options = roxmltree::ParsingOptions { allow_dtd: true, ..Default::default() };
+    let doc = roxmltree::Document::parse_with_options(svg, parse_options)
+        .map_err(|err| format!("invalid svg: {err}"))?;
+    let root = doc.root_element();
+    if root.tag_name().name() != "svg" {
+        return Err("invalid svg: root element is not <svg>".to_string());
+    }
+
+    let root_style = Style { even_odd: false, filled: true, visible: true };
+    let ctm = viewport_matrix(&root, options);
+    let mut shapes: Vec<Shape> = Vec::new();
+    collect_shapes(&root, &ctm, root_style, &mut shapes)?;
+
+    let mut contours: Contours = Vec::new();
+    for shape in shapes {
+        contours.extend(orient_shape(shape));
+    }
+    Ok(contours)
+}
+
+// 将 viewBox（或 width/height）映射到字框，同时翻转 y 轴
+fn viewport_matrix(root: &roxmltree::Node, options: &SvgImportOptions) -> Matrix {
+    let upm = options.units_per_em;
+    let ascender = upm + options.descender;
+
+    let view_box = root.attribute("viewBox").and_then(|v| {
+        let nums = parse_number_list(v);
+        if nums.len() == 4 && nums[2] > 0.0 && nums[3] > 0.0 {
+            Some((nums[0], nums[1], nums[2], nums[3]))
+        } else {
+            None
+        }
+    });
+    let view_box = view_box.or_else(|| {
+        let w = root.attribute("width").and_then(parse_length)?;
+        let h = root.attribute("height").and_then(parse_length)?;
+        if w > 0.0 && h > 0.0 { Some((0.0, 0.0, w, h)) } else { None }
+    });
+
+    match view_box {
+        // 默认 preserveAspectRatio 为 xMidYMid meet
+        Some((vx, vy, vw, vh)) => {
+            let s = (upm / vw).min(upm / vh);
+            let tx = (upm - vw * s) / 2.0;
+            let ty = (upm - vh * s) / 2.0;
+            Matrix::new(s, 0.0, 0.0, -s, tx - vx * s, ascender + vy * s - ty)
+        }
+        None => Matrix::new(1.0, 0.0, 0.0, -1.0, 0.0, ascender),
+    }
+}
+
+fn collect_shapes(
+    node: &roxmltree::Node,
+    parent_ctm: &Matrix,
+    parent_style: Style,
+    shapes: &mut Vec<Shape>,
+) -> Result<(), String> {
+    let style = resolve_style(node, parent_style);
+    if !style.visible {
+        return Ok(());
+    }
+    let ctm = match node.attribute("transform") {
+        Some(t) => parent_ctm.multiply(&parse_transform(t)?),
+        None => *parent_ctm,
+    };
+
+    let tag = node.tag_name().name();
+    let contours = match tag {
+        "svg" | "g" | "a" | "switch" => {
+            for child in node.children().filter(|n| n.is_element()) {
+                collect_shapes(&child, &ctm, style, shapes)?;
+            }
+            return Ok(());
+        }
+        "path" => parse_path_data(node.attribute("d").unwrap_or(""))?,
+        "rect" => rect_contours(node),
+        "circle" => {
+            let r = attr_length(node, "r");
+            ellipse_contours(attr_length(node, "cx"), attr_length(node, "cy"), r, r)
+        }
+        "ellipse" => ellipse_contours(
+            attr_length(node, "cx"),
+            attr_length(node, "cy"),
+            attr_length(node, "rx"),
+            attr_length(node, "ry"),
+        ),
+        "polygon" | "polyline" => polygon_contours(node.attribute("points").unwrap_or("")),
+        // defs、clipPath、mask、symbol 等元素不直接绘制
+        _ => return Ok(()),
+    };
+
+    if style.filled && !contours.is_empty() {
+        shapes.push(Shape {
+            contours: contours.iter().map(|c| transform_contour(c, &ctm)).collect(),
+            even_odd: style.even_odd,
+        });
+    }
+    Ok(())
+}
+
+// 读取表现属性与 style 属性，style 中的声明优先
+fn resolve_style(node: &roxmltree::Node, parent: Style) -> Style {
+    let mut style = Style { visible: true, ..parent };
+    let mut apply = |name: &str, value: &str| match (name, value.trim()) {
+        ("fill-rule", v) => style.even_odd = v == "evenodd",
+        ("fill", v) => style.filled = v != "none",
+        ("display", "none") => style.visible = false,
+        _ => {}
+    };
+    for name in ["fill-rule", "fill", "display"] {
+        if let Some(value) = node.attribute(name) {
+            apply(name, value);
+        }
+    }
+    if let Some(decls) = node.attribute("style") {
+        for decl in decls.split(';') {
+            if let Some((name, value)) = decl.split_once(':') {
+                apply(name.trim(), value);
+            }
+        }
+    }
+    style
+}
+
+fn parse_transform(value: &str) -> Result<Matrix, String> {
+    let mut result = Matrix::IDENTITY;
+    let mut rest = value.trim();
+    while !rest.is_empty() {
+        let open = rest.find('(').ok_or_else(|| format!("invalid transform: {value}"))?;
+        let close = rest.find(')').ok_or_else(|| format!("invalid transform: {value}"))?;
+        let name = rest[..open].trim_matches(|c: char| c.is_whitespace() || c == ',');
+        let args = parse_number_list(&rest[open + 1..close]);
+        let arg = |i: usize| args.get(i).copied().unwrap_or(0.0);
+        let m = match (name, args.len()) {
+            ("matrix", 6) => Matrix::new(arg(0), arg(1), arg(2), arg(3), arg(4), arg(5)),
+            ("translate", 1) => Matrix::translate(arg(0), 0.0),
+            ("translate", 2) => Matrix::translate(arg(0), arg(1)),
+            ("scale", 1) => Matrix::scale(arg(0), arg(0)),
+            ("scale", 2) => Matrix::scale(arg(0), arg(1)),
+            ("rotate", 1) => Matrix::rotate(arg(0)),
+            ("rotate", 3) => Matrix::translate(arg(1), arg(2))
+                .multiply(&Matrix::rotate(arg(0)))
+                .multiply(&Matrix::translate(-arg(1), -arg(2))),
+            ("skewX", 1) => Matrix::skew_x(arg(0)),
+            ("skewY", 1) => Matrix::skew_y(arg(0)),
+            _ => return Err(format!("invalid transform: {value}")),
+        };
+        // transform 列表从左到右嵌套，最右侧的变换最先作用于图形
+        result = result.multiply(&m);
+        rest = rest[close + 1..].trim_start();
+    }
+    Ok(result)
+}
+
+fn parse_length(value: &str) -> Option<f64> {
+    let value = value.trim();
+    if value.ends_with('%') {
+        return None;
+    }
+    let value = value.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    value.trim().parse::<f64>().ok()
+}
+
+fn attr_length(node: &roxmltree::Node, name: &str) -> f64 {
+    node.attribute(name).and_then(parse_length).unwrap_or(0.0)
+}
+
+fn parse_number_list(value: &str) -> Vec<f64> {
+    let mut parser = PathParser::new(value);
+    let mut nums = Vec::new();
+    while parser.has_number() {
+        match parser.number() {
+            Ok(n) => nums.push(n),
+            Err(_) => break,
+        }
+    }
+    nums
+}
+
+// 逐个子路径构建轮廓，子路径在结束时自动闭合
+struct ContourBuilder {
+    contours: Contours,
+    current: Contour,
+    start: Point,
+    pos: Point,
+}
+
+impl ContourBuilder {
+    fn new() -> Self {
+        let origin = Point { x: 0.0, y: 0.0 };
+        ContourBuilder { contours: Vec::new(), current: Vec::new(), start: origin, pos: origin }
+    }
+
+    fn move_to(&mut self, p: Point) {
+        self.finish();
+        self.start = p;
+        self.pos = p;
+    }
+
+    fn line_to(&mut self, p: Point) {
+        if !same_point(self.pos, p) {
+            self.current.push(Segment::Line { start: self.pos, end: p });
+        }
+        self.pos = p;
+    }
+
+    fn quad_to(&mut self, control: Point, end: Point) {
+        self.current.push(Segment::Quadratic { start: self.pos, control, end });
+        self.pos = end;
+    }
+
+    fn cubic_to(&mut self, control1: Point, control2: Point, end: Point) {
+        self.current.push(Segment::Cubic { start: self.pos, control1, control2, end });
+        self.pos = end;
+    }
+
+    fn close(&mut self) {
+        self.finish();
+        self.pos = self.start;
+    }
+
+    fn finish(&mut self) {
+        if self.current.is_empty() {
+            return;
+        }
+        if !same_point(self.pos, self.start) {
+            self.current.push(Segment::Line { start: self.pos, end: self.start });
+        }
+        self.contours.push(std::mem::take(&mut self.current));
+    }
+
+    // SVG 规范 F.6.5：端点参数化转中心参数化，再按不超过 90° 拆分为三次贝塞尔
+    #[allow(clippy::too_many_arguments)]
+    fn arc_to(&mut self, rx: f64, ry: f64, rotation: f64, large_arc: bool, sweep: bool, end: Point) {
+        let start = self.pos;
+        if same_point(start, end) {
+            return;
+        }
+        let (mut rx, mut ry) = (rx.abs(), ry.abs());
+        if rx == 0.0 || ry == 0.0 {
+            self.line_to(end);
+            return;
+        }
+
+        let (sin, cos) = rotation.to_radians().sin_cos();
+        let dx2 = (start.x - end.x) / 2.0;
+        let dy2 = (start.y - end.y) / 2.0;
+        let x1p = cos * dx2 + sin * dy2;
+        let y1p = -sin * dx2 + cos * dy2;
+
+        let lambda = (x1p * x1p) / (rx * rx) + (y1p * y1p) / (ry * ry);
+        if lambda > 1.0 {
+            rx *= lambda.sqrt();
+            ry *= lambda.sqrt();
+        }
+
+        let num = rx * rx * ry * ry - rx * rx * y1p * y1p - ry * ry * x1p * x1p;
+        let den = rx * rx * y1p * y1p + ry * ry * x1p * x1p;
+        let sign = if large_arc == sweep { -1.0 } else { 1.0 };
+        let coef = sign * (num / den).max(0.0).sqrt();
+        let cxp = coef * rx * y1p / ry;
+        let cyp = -coef * ry * x1p / rx;
+        let cx = cos * cxp - sin * cyp + (start.x + end.x) / 2.0;
+        let cy = sin * cxp + cos * cyp + (start.y + end.y) / 2.0;
+
+        let angle = |ux: f64, uy: f64, vx: f64, vy: f64| (ux * vy - uy * vx).atan2(ux * vx + uy * vy);
+        let theta1 = angle(1.0, 0.0, (x1p - cxp) / rx, (y1p - cyp) / ry);
+        let mut delta = angle((x1p - cxp) / rx, (y1p - cyp) / ry, (-x1p - cxp) / rx, (-y1p - cyp) / ry);
+        if !sweep && delta > 0.0 {
+            delta -= 2.0 * PI;
+        } else if sweep && delta < 0.0 {
+            delta += 2.0 * PI;
+        }
+
+        let count = (delta.abs() / (PI / 2.0)).ceil().max(1.0) as usize;
+        let step = delta / count as f64;
+        let k = 4.0 / 3.0 * (step / 4.0).tan();
+        let point_at = |t: f64| Point {
+            x: cx + rx * cos * t.cos() - ry * sin * t.sin(),
+            y: cy + rx * sin * t.cos() + ry * cos * t.sin(),
+        };
+        let tangent_at = |t: f64| Point {
+            x: -rx * cos * t.sin() - ry * sin * t.cos(),
+            y: -rx * sin * t.sin() + ry * cos * t.cos(),
+        };
+
+        for i in 0..count {
+            let t1 = theta1 + step * i as f64;
+            let t2 = t1 + step;
+            let p1 = point_at(t1);
+            let p2 = if i + 1 == count { end } else { point_at(t2) };
+            let d1 = tangent_at(t1);
+            let d2 = tangent_at(t2);
+            let c1 = Point { x: p1.x + k * d1.x, y: p1.y + k * d1.y };
+            let c2 = Point { x: p2.x - k * d2.x, y: p2.y - k * d2.y };
+            self.cubic_to(c1, c2, p2);
+        }
+    }
+
+    fn into_contours(mut self) -> Contours {
+        self.finish();
+        self.contours
+    }
+}
+
+fn same_point(a: Point, b: Point) -> bool {
+    (a.x - b.x).abs() < 1e-9 && (a.y - b.y).abs() < 1e-9
+}
+
+struct PathParser<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> PathParser<'a> {
+    fn new(data: &'a str) -> Self {
+        PathParser { bytes: data.as_bytes(), pos: 0 }
+    }
+
+    fn skip_separators(&mut self) {
+        while self.pos < self.bytes.len() && (self.bytes[self.pos].is_ascii_whitespace() || self.bytes[self.pos] == b',') {
+            self.pos += 1;
+        }
+    }
+
+    fn has_number(&mut self) -> bool {
+        self.skip_separators();
+        matches!(self.bytes.get(self.pos), Some(b'0'..=b'9' | b'.' | b'-' | b'+'))
+    }
+
+    fn command(&mut self) -> Option<u8> {
+        self.skip_separators();
+        let c = *self.bytes.get(self.pos)?;
+        if c.is_ascii_alphabetic() {
+            self.pos += 1;
+            Some(c)
+        } else {
+            None
+        }
+    }
+
+    fn number(&mut self) -> Result<f64, String> {
+        self.skip_separators();
+        let begin = self.pos;
+        let at = |p: usize| self.bytes.get(p).copied();
+        let mut p = self.pos;
+        if matches!(at(p), Some(b'-' | b'+')) {
+            p += 1;
+        }
+        while matches!(at(p), Some(b'0'..=b'9')) {
+            p += 1;
+        }
+        if at(p) == Some(b'.') {
+            p += 1;
+            while matches!(at(p), Some(b'0'..=b'9')) {
+                p += 1;
+            }
+        }
+        if matches!(at(p), Some(b'e' | b'E')) && matches!(at(p + 1), Some(b'0'..=b'9' | b'-' | b'+')) {
+            p += 2;
+            while matches!(at(p), Some(b'0'..=b'9')) {
+                p += 1;
+            }
+        }
+        let text = std::str::from_utf8(&self.bytes[begin..p]).unwrap_or("");
+        let value = text.parse::<f64>().map_err(|_| format!("invalid path data near offset {begin}"))?;
+        self.pos = p;
+        Ok(value)
+    }
+
+    // 弧线标志位可以不带分隔符地紧挨着书写，例如 "a1 1 0 011 1"
+    fn flag(&mut self) -> Result<bool, String> {
+        self.skip_separators();
+        let value = match self.bytes.get(self.pos) {
+            Some(b'0') => false,
+            Some(b'1') => true,
+            _ => return Err(format!("invalid arc flag near offset {}", self.pos)),
+        };
+        self.pos += 1;
+        Ok(value)
+    }
+
+    fn point(&mut self) -> Result<Point, String> {
+        let x = self.number()?;
+        let y = self.number()?;
+        Ok(Point { x, y })
+    }
+}
+
+fn parse_path_data(d: &str) -> Result<Contours, String> {
+    let mut parser = PathParser::new(d);
+    let mut builder = ContourBuilder::new();
+    // 用于 S/T 命令的反射控制点
+    let mut last_cubic_control: Option<Point> = None;
+    let mut last_quad_control: Option<Point> = None;
+    let mut command: Option<u8> = None;
+
+    loop {
+        let cmd = match parser.command() {
+            Some(c) => c,
+            None => {
+                if parser.pos >= parser.bytes.len() {
+                    break;
+                }
+                // 省略命令字母时重复上一个命令，moveto 之后的隐式命令为 lineto
+                match command {
+                    Some(b'M') if parser.has_number() => b'L',
+                    Some(b'm') if parser.has_number() => b'l',
+                    Some(c) if c != b'Z' && c != b'z' && parser.has_number() => c,
+                    _ => return Err(format!("invalid path data near offset {}", parser.pos)),
+                }
+            }
+        };
+        command = Some(cmd);
+
+        let relative = cmd.is_ascii_lowercase();
+        let base = builder.pos;
+        let abs = |p: Point| if relative { Point { x: p.x + base.x, y: p.y + base.y } } else { p };
+        let mut cubic_control = None;
+        let mut quad_control = None;
+
+        match cmd.to_ascii_uppercase() {
+            b'M' => builder.move_to(abs(parser.point()?)),
+            b'L' => builder.line_to(abs(parser.point()?)),
+            b'H' => {
+                let x = parser.number()?;
+                let x = if relative { base.x + x } else { x };
+                builder.line_to(Point { x, y: base.y });
+            }
+            b'V' => {
+                let y = parser.number()?;
+                let y = if relative { base.y + y } else { y };
+                builder.line_to(Point { x: base.x, y });
+            }
+            b'C' => {
+                let c1 = abs(parser.point()?);
+                let c2 = abs(parser.point()?);
+                let end = abs(parser.point()?);
+                builder.cubic_to(c1, c2, end);
+                cubic_control = Some(c2);
+            }
+            b'S' => {
+                let c1 = reflect(last_cubic_control, base);
+                let c2 = abs(parser.point()?);
+                let end = abs(parser.point()?);
+                builder.cubic_to(c1, c2, end);
+                cubic_control = Some(c2);
+            }
+            b'Q' => {
+                let c = abs(parser.point()?);
+                let end = abs(parser.point()?);
+                builder.quad_to(c, end);
+                quad_control = Some(c);
+            }
+            b'T' => {
+                let c = reflect(last_quad_control, base);
+                let end = abs(parser.point()?);
+                builder.quad_to(c, end);
+                quad_control = Some(c);
+            }
+            b'A' => {
+                let rx = parser.number()?;
+                let ry = parser.number()?;
+                let rotation = parser.number()?;
+                let large_arc = parser.flag()?;
+                let sweep = parser.flag()?;
+                let end = abs(parser.point()?);
+                builder.arc_to(rx, ry, rotation, large_arc, sweep, end);
+            }
+            b'Z' => builder.close(),
+            _ => return Err(format!("unsupported path command '{}'", cmd as char)),
+        }
+        last_cubic_control = cubic_control;
+        last_quad_control = quad_control;
+    }
+
+    Ok(builder.into_contours())
+}
+
+fn reflect(control: Option<Point>, pos: Point) -> Point {
+    match control {
+        Some(c) => Point { x: 2.0 * pos.x - c.x, y: 2.0 * pos.y - c.y },
+        None => pos,
+    }
+}
+
+fn rect_contours(node: &roxmltree::Node) -> Contours {
+    let x = attr_length(node, "x");
+    let y = attr_length(node, "y");
+    let w = attr_length(node, "width");
+    let h = attr_length(node, "height");
+    if w <= 0.0 || h <= 0.0 {
+        return Vec::new();
+    }
+    // rx、ry 缺省时互相取值，且不超过边长的一半
+    let rx_attr = node.attribute("rx").and_then(parse_length);
+    let ry_attr = node.attribute("ry").and_then(parse_length);
+    let rx = rx_attr.or(ry_attr).unwrap_or(0.0).clamp(0.0, w / 2.0);
+    let ry = ry_attr.or(rx_attr).unwrap_or(0.0).clamp(0.0, h / 2.0);
+
+    let p = |x: f64, y: f64| Point { x, y };
+    let mut builder = ContourBuilder::new();
+    if rx == 0.0 || ry == 0.0 {
+        builder.move_to(p(x, y));
+        builder.line_to(p(x + w, y));
+        builder.line_to(p(x + w, y + h));
+        builder.line_to(p(x, y + h));
+    } else {
+        let (kx, ky) = (rx * KAPPA, ry * KAPPA);
+        builder.move_to(p(x + rx, y));
+        builder.line_to(p(x + w - rx, y));
+        builder.cubic_to(p(x + w - rx + kx, y), p(x + w, y + ry - ky), p(x + w, y + ry));
+        builder.line_to(p(x + w, y + h - ry));
+        builder.cubic_to(p(x + w, y + h - ry + ky), p(x + w - rx + kx, y + h), p(x + w - rx, y + h));
+        builder.line_to(p(x + rx, y + h));
+        builder.cubic_to(p(x + rx - kx, y + h), p(x, y + h - ry + ky), p(x, y + h - ry));
+        builder.line_to(p(x, y + ry));
+        builder.cubic_to(p(x, y + ry - ky), p(x + rx - kx, y), p(x + rx, y));
+    }
+    builder.into_contours()
+}
+
+fn ellipse_contours(cx: f64, cy: f64, rx: f64, ry: f64) -> Contours {
+    if rx <= 0.0 || ry <= 0.0 {
+        return Vec::new();
+    }
+    let (kx, ky) = (rx * KAPPA, ry * KAPPA);
+    let p = |x: f64, y: f64| Point { x, y };
+    let mut builder = ContourBuilder::new();
+    builder.move_to(p(cx + rx, cy));
+    builder.cubic_to(p(cx + rx, cy + ky), p(cx + kx, cy + ry), p(cx, cy + ry));
+    builder.cubic_to(p(cx - kx, cy + ry), p(cx - rx, cy + ky), p(cx - rx, cy));
+    builder.cubic_to(p(cx - rx, cy - ky), p(cx - kx, cy - ry), p(cx, cy - ry));
+    builder.cubic_to(p(cx + kx, cy - ry), p(cx + rx, cy - ky), p(cx + rx, cy));
+    builder.into_contours()
+}
+
+fn polygon_contours(points: &str) -> Contours {
+    let nums = parse_number_list(points);
+    let mut builder = ContourBuilder::new();
+    for (i, pair) in nums.chunks_exact(2).enumerate() {
+        let p = Point { x: pair[0], y: pair[1] };
+        if i == 0 {
+            builder.move_to(p);
+        } else {
+            builder.line_to(p);
+        }
+    }
+    builder.into_contours()
+}
+
+// 字体按非零环绕规则填充：外轮廓统一为逆时针（与去除重叠、CFF 一致），evenodd 图形按嵌套层级交替方向
+fn orient_shape(shape: Shape) -> Contours {
+    let polys: Vec<Vec<Point>> = shape.contours.iter().map(flatten_contour).collect();
+    let areas: Vec<f64> = polys.iter().map(|poly| poly_signed_area(poly)).collect();
+
+    if shape.even_odd {
+        let mut out = Vec::with_capacity(shape.contours.len());
+        for (i, contour) in shape.contours.iter().enumerate() {
+            let probe = probe_point(&polys[i]);
+            let depth = (0..polys.len())
+                .filter(|&j| j != i && point_in_poly(&polys[j], probe))
+                .count();
+            let want_counter_clockwise = depth % 2 == 0;
+            if (areas[i] > 0.0) == want_counter_clockwise {
+                out.push(contour.clone());
+            } else {
+                out.push(reverse_contour(contour));
+            }
+        }
+        out
+    } else {
+        let outer = (0..areas.len()).max_by(|&a, &b| areas[a].abs().total_cmp(&areas[b].abs()));
+        match outer {
+            Some(i) if areas[i] < 0.0 => shape.contours.iter().map(reverse_contour).collect(),
+            _ => shape.contours,
+        }
+    }
+}
+
+fn flatten_contour(contour: &Contour) -> Vec<Point> {
+    const SAMPLES: usize = 8;
+    let mut pts = Vec::new();
+    for seg in contour {
+        match seg {
+            Segment::Line { start, .. } => pts.push(*start),
+            Segment::Quadratic { start, control, end } => {
+                for i in 0..SAMPLES {
+                    let t = i as f64 / SAMPLES as f64;
+                    let mt = 1.0 - t;
+                    pts.push(Point {
+                        x: mt * mt * start.x + 2.0 * mt * t * control.x + t * t * end.x,
+                        y: mt * mt * start.y + 2.0 * mt * t * control.y + t * t * end.y,
+                    });
+                }
+            }
+            Segment::Cubic { start, control1, control2, end } => {
+                for i in 0..SAMPLES {
+                    let t = i as f64 / SAMPLES as f64;
+                    let mt = 1.0 - t;
+                    pts.push(Point {
+                        x: mt * mt * mt * start.x + 3.0 * mt * mt * t * control1.x + 3.0 * mt * t * t * control2.x + t * t * t * end.x,
+                        y: mt * mt * mt * start.y + 3.0 * mt * mt * t * control1.y + 3.0 * mt * t * t * control2.y + t * t * t * end.y,
+                    });
+                }
+            }
+        }
+    }
+    pts
+}
+
+// 标准鞋带公式，y 轴向上时正值为逆时针
+fn poly_signed_area(poly: &[Point]) -> f64 {
+    if poly.len() < 3 {
+        return 0.0;
+    }
+    let mut area = 0.0;
+    let mut prev = poly[poly.len() - 1];
+    for p in poly {
+        area += prev.x * p.y - p.x * prev.y;
+        prev = *p;
+    }
+    area * 0.5
+}
+
+// 取第一条边的中点作为包含测试点，避免与其他轮廓共享顶点时误判
+fn probe_point(poly: &[Point]) -> Point {
+    match poly {
+        [] => Point { x: 0.0, y: 0.0 },
+        [p] => *p,
+        [a, b, ..] => Point { x: (a.x + b.x) / 2.0, y: (a.y + b.y) / 2.0 },
+    }
+}
+
+fn point_in_poly(poly: &[Point], point: Point) -> bool {
+    let mut inside = false;
+    let mut j = poly.len().wrapping_sub(1);
+    for i in 0..poly.len() {
+        let (pi, pj) = (poly[i], poly[j]);
+        if ((pi.y > point.y) != (pj.y > point.y))
+            && (point.x < (pj.x - pi.x) * (point.y - pi.y) / (pj.y - pi.y) + pi.x)
+        {
+            inside = !inside;
+        }
+        j = i;
+    }
+    inside
+}
+
+#[wasm_bindgen]
+pub fn import_svg(svg: &str, options_json: &str) -> String {
+    let options: SvgImportOptions = if options_json.trim().is_empty() {
+        SvgImportOptions::default()
+    } else {
+        match serde_json::from_str(options_json) {
+            Ok(o) => o,
+            Err(err) => {
+                return json!({ "ok": false, "error": format!("invalid options: {err}") }).to_string();
+            }
+        }
+    };
+
+    match parse_svg(svg, &options) {
+        Ok(contours) => json!({ "ok": true, "contours": contours }).to_string(),
+        Err(err) => json!({ "ok": false, "error": err }).to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(p: Point, x: f64, y: f64) {
+        assert!((p.x - x).abs() < 1e-6 && (p.y - y).abs() < 1e-6, "({}, {}) != ({x}, {y})", p.x, p.y);
+    }
+
+    fn ends(contour: &Contour) -> Vec<(f64, f64)> {
+        contour.iter().map(|s| ((s.end().x * 1e6).round() / 1e6, (s.end().y * 1e6).round() / 1e6)).collect()
+    }
+
+    fn bbox(contours: &Contours) -> (f64, f64, f64, f64) {
+        let points = contours.iter().flatten().flat_map(|s| [s.start(), s.end()]);
+        points.fold((f64::MAX, f64::MAX, f64::MIN, f64::MIN), |(x0, y0, x1, y1), p| {
+            (x0.min(p.x), y0.min(p.y), x1.max(p.x), y1.max(p.y))
+        })
+    }
+
+    // viewBox 与字框相同，只翻转 y 轴：字体坐标 y = 800 - y
+    fn import(body: &str) -> Contours {
+        let svg = format!(r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 1000 1000">{body}</svg>"#);
+        parse_svg(&svg, &SvgImportOptions::default()).unwrap()
+    }
+
+    #[test]
+    fn arc_sweep_flag() {
+        // 半圆：sweep=1 在 SVG 坐标中经过上方，sweep=0 经过下方
+        let upper = parse_path_data("M 0 0 A 10 10 0 0 1 20 0").unwrap();
+        let lower = parse_path_data("M 0 0 A 10 10 0 0 0 20 0").unwrap();
+        for (contours, y) in [(upper, -10.0), (lower, 10.0)] {
+            assert_eq!(contours.len(), 1);
+            let contour = &contours[0];
+            // 两段三次曲线加上闭合直线
+            assert!(matches!(contour[0], Segment::Cubic { .. }) && matches!(contour[1], Segment::Cubic { .. }));
+            assert_close(contour[0].end(), 10.0, y);
+            assert_close(contour[1].end(), 20.0, 0.0);
+        }
+    }
+
+    #[test]
+    fn arc_large_arc_flag() {
+        // 同样的端点与半径，小弧 90°，大弧 270°
+        let small = parse_path_data("M 0 0 A 10 10 0 0 1 10 10").unwrap();
+        let large = parse_path_data("M 0 0 A 10 10 0 1 1 10 10").unwrap();
+        let cubics = |contours: &Contours| contours[0].iter().filter(|s| matches!(s, Segment::Cubic { .. })).count();
+        assert_eq!(cubics(&small), 1);
+        assert_eq!(cubics(&large), 3);
+        assert_close(small[0][0].end(), 10.0, 10.0);
+        assert_close(large[0][2].end(), 10.0, 10.0);
+        // 大弧的圆心为 (10, 0)，沿正角度方向经过 (10, -10) 与 (20, 0)
+        assert_close(large[0][0].end(), 10.0, -10.0);
+        assert_close(large[0][1].end(), 20.0, 0.0);
+    }
+
+    #[test]
+    fn arc_radius_scaled_up() {
+        // 半径不足以连接两端点时按比例放大
+        let contours = parse_path_data("M 0 0 A 1 1 0 0 1 20 0").unwrap();
+        assert_close(contours[0][0].end(), 10.0, -10.0);
+        assert_close(contours[0][1].end(), 20.0, 0.0);
+    }
+
+    #[test]
+    fn relative_commands() {
+        let absolute = parse_path_data("M 10 10 L 20 10 L 20 20 L 10 20 Z").unwrap();
+        let relative = parse_path_data("m 10 10 l 10 0 v 10 h -10 z").unwrap();
+        // moveto 之后省略命令字母的坐标按 lineto 处理
+        let implicit = parse_path_data("m 10 10 10 0 0 10 -10 0 z").unwrap();
+        assert_eq!(ends(&relative[0]), ends(&absolute[0]));
+        assert_eq!(ends(&implicit[0]), ends(&absolute[0]));
+
+        // 闭合后的相对 moveto 以子路径起点为基准
+        let two = parse_path_data("M 10 10 h 10 v 10 z m 20 0 h 10 v 10 z").unwrap();
+        assert_eq!(two.len(), 2);
+        assert_close(two[1][0].start(), 30.0, 10.0);
+
+        // 相对三次曲线与 S 命令的反射控制点
+        let curve = parse_path_data("M 0 0 c 0 10 10 10 10 0 s 10 -10 10 0").unwrap();
+        match curve[0][1] {
+            Segment::Cubic { control1, end, .. } => {
+                assert_close(control1, 10.0, -10.0);
+                assert_close(end, 20.0, 0.0);
+            }
+            _ => panic!("expected a cubic segment"),
+        }
+    }
+
+    #[test]
+    fn nested_group_transforms() {
+        let contours = import(
+            r#"<g transform="translate(100 0)"><g transform="scale(2)">
+                <rect x="10" y="10" width="10" height="10"/>
+            </g></g>"#,
+        );
+        assert_eq!(contours.len(), 1);
+        assert_eq!(bbox(&contours), (120.0, 760.0, 140.0, 780.0));
+
+        // 同一 transform 属性中的变换从右到左作用
+        let contours = import(r#"<rect x="0" y="0" width="10" height="10" transform="translate(50 50) rotate(90)"/>"#);
+        let (x0, y0, x1, y1) = bbox(&contours);
+        assert!((x0 - 40.0).abs() < 1e-9 && (x1 - 50.0).abs() < 1e-9);
+        assert!((y0 - 740.0).abs() < 1e-9 && (y1 - 750.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn rounded_rect() {
+        let contours = import(r#"<rect x="0" y="0" width="100" height="50" rx="10"/>"#);
+        assert_eq!(contours.len(), 1);
+        // 四条边加四个圆角，ry 缺省时取 rx
+        assert_eq!(contours[0].len(), 8);
+        assert_eq!(contours[0].iter().filter(|s| matches!(s, Segment::Cubic { .. })).count(), 4);
+        assert_eq!(bbox(&contours), (0.0, 750.0, 100.0, 800.0));
+
+        // 圆角半径不超过边长的一半，长度为 0 的边被省略，只剩四段圆角
+        let pill = import(r#"<rect x="0" y="0" width="100" height="50" rx="80"/>"#);
+        assert_eq!(pill[0].len(), 4);
+        assert_eq!(bbox(&pill), (0.0, 750.0, 100.0, 800.0));
+    }
+
+    #[test]
+    fn circle_and_ellipse() {
+        let circle = import(r#"<circle cx="100" cy="100" r="50"/>"#);
+        assert_eq!(circle[0].len(), 4);
+        assert_eq!(bbox(&circle), (50.0, 650.0, 150.0, 750.0));
+
+        let ellipse = import(r#"<ellipse cx="100" cy="100" rx="50" ry="20"/>"#);
+        assert_eq!(bbox(&ellipse), (50.0, 680.0, 150.0, 720.0));
+
+        // 外轮廓统一为逆时针
+        for contours in [&circle, &ellipse] {
+            assert!(poly_signed_area(&flatten_contour(&contours[0])) > 0.0);
+        }
+        // 半径为 0 时不绘制
+        assert!(import(r#"<circle cx="100" cy="100" r="0"/>"#).is_empty());
+    }
+}
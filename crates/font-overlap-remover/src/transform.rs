@@ -0,0 +1,138 @@
+use serde::Deserialize;
+use serde_json::json;
+use wasm_bindgen::prelude::*;
+
+use crate::{remove_overlap_contours, Contours};
+
+// 矩阵与轮廓变换和字体导出中组件的展开共用 font_writer 的实现
+pub use font_writer::outline::{place_contour, transform_contour, transform_contours, transform_segment, Matrix};
+
+// 组件（部首、笔画等）及其放置矩阵
+#[derive(Debug, Clone, Deserialize)]
+pub struct ComponentPlacement {
+    pub contours: Contours,
+    #[serde(default = "identity")]
+    pub matrix: Matrix,
+}
+
+fn identity() -> Matrix {
+    Matrix::IDENTITY
+}
+
+// 将各组件按矩阵放置后拼合为一个字形，可选地去除重叠
+pub fn compose_glyph(components: &[ComponentPlacement], remove_overlap: bool) -> Contours {
+    let mut contours: Contours = Vec::new();
+    for component in components {
+        contours.extend(component.contours.iter().map(|contour| place_contour(contour, &component.matrix)));
+    }
+    if remove_overlap {
+        remove_overlap_contours(&contours)
+    } else {
+        contours
+    }
+}
+
+#[wasm_bindgen(js_name = transform_contours)]
+pub fn transform_contours_json(contours_json: &str, matrix_json: &str) -> String {
+    let contours: Contours = match serde_json::from_str(contours_json) {
+        Ok(c) => c,
+        Err(err) => {
+            return json!({ "ok": false, "error": format!("invalid json: {err}") }).to_string();
+        }
+    };
+    let matrix: Matrix = match serde_json::from_str(matrix_json) {
+        Ok(m) => m,
+        Err(err) => {
+            return json!({ "ok": false, "error": format!("invalid matrix: {err}") }).to_string();
+        }
+    };
+    json!({ "ok": true, "contours": transform_contours(&contours, &matrix) }).to_string()
+}
+
+#[wasm_bindgen(js_name = compose_glyph)]
+pub fn compose_glyph_json(components_json: &str, remove_overlap: bool) -> String {
+    let components: Vec<ComponentPlacement> = match serde_json::from_str(components_json) {
+        Ok(c) => c,
+        Err(err) => {
+            return json!({ "ok": false, "error": format!("invalid json: {err}") }).to_string();
+        }
+    };
+    json!({ "ok": true, "contours": compose_glyph(&components, remove_overlap) }).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Contour, Point, Segment};
+
+    fn square() -> Contour {
+        let p = [(0.0, 0.0), (100.0, 0.0), (100.0, 100.0), (0.0, 100.0)];
+        (0..4)
+            .map(|i| Segment::Line {
+                start: Point { x: p[i].0, y: p[i].1 },
+                end: Point { x: p[(i + 1) % 4].0, y: p[(i + 1) % 4].1 },
+            })
+            .collect()
+    }
+
+    fn signed_area(contour: &Contour) -> f64 {
+        contour.iter().map(|s| s.start().x * s.end().y - s.end().x * s.start().y).sum::<f64>() / 2.0
+    }
+
+    fn assert_close(p: Point, x: f64, y: f64) {
+        assert!((p.x - x).abs() < 1e-9 && (p.y - y).abs() < 1e-9, "({}, {}) != ({x}, {y})", p.x, p.y);
+    }
+
+    // 相邻线段共用的端点按同一矩阵变换，变换后轮廓仍严格闭合
+    fn assert_closed(contour: &Contour) {
+        for i in 0..contour.len() {
+            let (end, start) = (contour[i].end(), contour[(i + 1) % contour.len()].start());
+            assert!(end.x == start.x && end.y == start.y);
+        }
+    }
+
+    #[test]
+    fn rotation_keeps_endpoints() {
+        let rotated = &transform_contours(&vec![square()], &Matrix::rotate(90.0))[0];
+        assert_closed(rotated);
+        assert_close(rotated[0].start(), 0.0, 0.0);
+        assert_close(rotated[1].start(), 0.0, 100.0);
+        assert_close(rotated[2].start(), -100.0, 100.0);
+        assert_close(rotated[3].start(), -100.0, 0.0);
+        assert!((signed_area(rotated) - 10000.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn skew_keeps_endpoints() {
+        let m = Matrix::translate(10.0, 20.0).multiply(&Matrix::skew_x(45.0));
+        let skewed = transform_contour(&square(), &m);
+        assert_closed(&skewed);
+        assert_close(skewed[0].start(), 10.0, 20.0);
+        assert_close(skewed[1].start(), 110.0, 20.0);
+        assert_close(skewed[2].start(), 210.0, 120.0);
+        assert_close(skewed[3].start(), 110.0, 120.0);
+        // 错切不改变面积
+        assert!((signed_area(&skewed) - 10000.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn negative_determinant_reverses_direction() {
+        let mirror = Matrix::scale(-1.0, 1.0);
+        assert!(mirror.determinant() < 0.0);
+        // 单纯变换会翻转方向，放置组件时反转回来
+        assert!(signed_area(&transform_contour(&square(), &mirror)) < 0.0);
+        let placed = place_contour(&square(), &mirror);
+        assert_closed(&placed);
+        assert!(signed_area(&placed) > 0.0);
+
+        let components = vec![
+            ComponentPlacement { contours: vec![square()], matrix: Matrix::IDENTITY },
+            ComponentPlacement { contours: vec![square()], matrix: Matrix::translate(300.0, 0.0).multiply(&mirror) },
+        ];
+        let glyph = compose_glyph(&components, false);
+        assert_eq!(glyph.len(), 2);
+        assert!(glyph.iter().all(|contour| signed_area(contour) > 0.0));
+        assert_close(glyph[1][0].start(), 300.0, 0.0);
+        assert_close(glyph[1][1].start(), 300.0, 100.0);
+    }
+}
@@ -0,0 +1,32 @@
+use font_writer::woff::{self, WoffMetadata, WoffOptions};
+use wasm_bindgen::prelude::*;
+
+// 将 TTF/OTF 字节编码为 WOFF2，供 Web 版导出使用
+#[wasm_bindgen]
+pub fn encode_woff2(data: &[u8]) -> Result<Vec<u8>, JsValue> {
+    font_writer::woff2::encode(data).map_err(|err| JsValue::from_str(&err))
+}
+
+// 将 TTF/OTF 字节封装为 WOFF 1.0，metadata_json 为空时不写入元数据块
+#[wasm_bindgen]
+pub fn encode_woff(data: &[u8], metadata_json: &str) -> Result<Vec<u8>, JsValue> {
+    let metadata: Option<WoffMetadata> = if metadata_json.trim().is_empty() {
+        None
+    } else {
+        Some(serde_json::from_str(metadata_json).map_err(|err| JsValue::from_str(&format!("invalid metadata: {err}")))?)
+    };
+    let options = WoffOptions { metadata, private_data: None };
+    woff::encode(data, &options).map_err(|err| JsValue::from_str(&err))
+}
+
+// 解包 WOFF 1.0，返回 sfnt 字体数据
+#[wasm_bindgen]
+pub fn decode_woff(data: &[u8]) -> Result<Vec<u8>, JsValue> {
+    woff::decode(data).map(|file| file.sfnt).map_err(|err| JsValue::from_str(&err))
+}
+
+// 校验导出的字体数据（TTF/OTF/WOFF），返回 JSON 格式的校验报告
+#[wasm_bindgen]
+pub fn validate_font(data: &[u8]) -> String {
+    serde_json::to_string(&font_writer::validate::validate(data)).unwrap_or_default()
+}
@@ -0,0 +1,504 @@
+use std::collections::{BTreeSet, HashMap};
+
+use crate::binary::Writer;
+use crate::tables::glyf::{GlyphPoint, SimpleGlyph};
+use crate::tables::maxp::InstructionLimits;
+use crate::GlyphData;
+
+// TrueType 指令操作码
+const SVTCA_Y: u8 = 0x00;
+const SVTCA_X: u8 = 0x01;
+const SRP0: u8 = 0x10;
+const SLOOP: u8 = 0x17;
+const SCVTCI: u8 = 0x1D;
+const DUP: u8 = 0x20;
+const LOOPCALL: u8 = 0x2A;
+const CALL: u8 = 0x2B;
+const FDEF: u8 = 0x2C;
+const ENDF: u8 = 0x2D;
+const MDAP_ROUND: u8 = 0x2F;
+const IUP_Y: u8 = 0x30;
+const IUP_X: u8 = 0x31;
+const ALIGNRP: u8 = 0x3C;
+const MIAP_ROUND: u8 = 0x3F;
+const NPUSHB: u8 = 0x40;
+const NPUSHW: u8 = 0x41;
+const WCVTP: u8 = 0x44;
+const RCVT: u8 = 0x45;
+const ROUND_BLACK: u8 = 0x69;
+const SCANCTRL: u8 = 0x85;
+const MAX: u8 = 0x8B;
+const SCANTYPE: u8 = 0x8D;
+const PUSHB: u8 = 0xB0;
+const PUSHW: u8 = 0xB8;
+// MIRP[设置 rp0, 最小距离, 取整, 黑色距离]
+const MIRP_STEM: u8 = 0xFD;
+
+// fpgm 中定义的函数编号
+const FN_ROUND_ZONE: u16 = 0;
+const FN_ROUND_WIDTH: u16 = 1;
+const FN_LINK: u16 = 2;
+const FN_ALIGN: u16 = 3;
+const FUNCTION_COUNT: u16 = 4;
+
+// 控制值截断阈值（1/64 像素）：实际宽度与标准宽度相差超过约 1.1 像素时使用实际宽度
+const CVT_CUT_IN: u16 = 70;
+// 每个方向最多保留的标准笔画宽度数
+const MAX_STANDARD_WIDTHS: usize = 3;
+
+// Latin 字母的对齐区：(参考字符, 是否为上边缘)
+const LATIN_ZONES: [(&str, bool); 5] =
+    [("HILxzn", false), ("xzvw", true), ("HIEFTZ", true), ("bdhkl", true), ("pq", false)];
+// CJK 表意文字的字面框上下边缘
+const CJK_ZONES: [(&str, bool); 2] = [("口国田日目回囲", true), ("口国田日目回囲", false)];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Script {
+    Latin,
+    Cjk,
+}
+
+fn script(unicode: u32) -> Script {
+    match unicode {
+        0x1100..=0x11FF
+        | 0x2E80..=0x9FFF
+        | 0xA960..=0xA97F
+        | 0xAC00..=0xD7FF
+        | 0xF900..=0xFAFF
+        | 0xFE30..=0xFE4F
+        | 0xFF00..=0xFFEF
+        | 0x20000..=0x3FFFF => Script::Cjk,
+        _ => Script::Latin,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Axis {
+    // 水平边缘，在 y 方向上对齐
+    Y,
+    // 竖直边缘，在 x 方向上对齐
+    X,
+}
+
+// 边缘：一组在同一坐标上近似水平（或竖直）的线段。high 表示笔画墨迹在边缘的低坐标一侧，即笔画的上边或右边
+#[derive(Debug, Clone)]
+struct Edge {
+    pos: i32,
+    high: bool,
+    min: i32,
+    max: i32,
+    points: Vec<usize>,
+}
+
+// 笔画：一对相对的边缘
+#[derive(Debug, Clone, Copy)]
+struct Stem {
+    low: usize,
+    high: usize,
+}
+
+impl Stem {
+    fn width(&self, edges: &[Edge]) -> i32 {
+        edges[self.high].pos - edges[self.low].pos
+    }
+}
+
+struct Zone {
+    pos: i32,
+    high: bool,
+    cvt: u16,
+}
+
+// 自动提示的结果：字体级的 fpgm、prep、cvt 与 gasp，以及与字形一一对应的字形程序
+#[derive(Debug, Clone, Default)]
+pub struct Hints {
+    pub fpgm: Vec<u8>,
+    pub prep: Vec<u8>,
+    pub cvt: Vec<u8>,
+    pub glyph_programs: Vec<Vec<u8>>,
+    pub limits: InstructionLimits,
+}
+
+// 指令流，记录最大栈深度用于 maxp
+#[derive(Default)]
+struct Program {
+    code: Vec<u8>,
+    max_stack: usize,
+}
+
+impl Program {
+    fn push(&mut self, values: &[u16]) {
+        self.max_stack = self.max_stack.max(values.len());
+        for chunk in values.chunks(255) {
+            let words = chunk.iter().any(|&v| v > 255);
+            match (words, chunk.len()) {
+                (false, 1..=8) => self.code.push(PUSHB + chunk.len() as u8 - 1),
+                (false, n) => self.code.extend([NPUSHB, n as u8]),
+                (true, 1..=8) => self.code.push(PUSHW + chunk.len() as u8 - 1),
+                (true, n) => self.code.extend([NPUSHW, n as u8]),
+            }
+            for &v in chunk {
+                if words {
+                    self.code.extend(v.to_be_bytes());
+                } else {
+                    self.code.push(v as u8);
+                }
+            }
+        }
+    }
+
+    fn op(&mut self, op: u8) {
+        self.code.push(op);
+    }
+
+    fn call(&mut self, args: &[u16], function: u16) {
+        let mut values = args.to_vec();
+        values.push(function);
+        self.push(&values);
+        self.op(CALL);
+    }
+
+    // 以 rp0 为参考对齐边缘上其余的点
+    fn align(&mut self, points: &[usize]) {
+        if points.is_empty() {
+            return;
+        }
+        let mut args: Vec<u16> = points.iter().map(|&p| p as u16).collect();
+        args.push(points.len() as u16);
+        self.call(&args, FN_ALIGN);
+    }
+}
+
+// 轮廓的有向面积，正值表示逆时针
+fn signed_area(glyph: &SimpleGlyph) -> i64 {
+    glyph
+        .contours
+        .iter()
+        .map(|contour| {
+            (0..contour.len())
+                .map(|i| {
+                    let (a, b) = (contour[i], contour[(i + 1) % contour.len()]);
+                    a.x as i64 * b.y as i64 - b.x as i64 * a.y as i64
+                })
+                .sum::<i64>()
+        })
+        .sum()
+}
+
+// 提取某一方向的边缘：近似水平（Y）或竖直（X）且足够长的线段按坐标聚类
+fn edges(glyph: &SimpleGlyph, axis: Axis, units_per_em: i32) -> Vec<Edge> {
+    let counter_clockwise = signed_area(glyph) > 0;
+    let min_length = (units_per_em / 100).max(1);
+    let tolerance = (units_per_em / 250).max(1);
+    let coords = |p: &GlyphPoint| match axis {
+        Axis::Y => (p.x as i32, p.y as i32),
+        Axis::X => (p.y as i32, p.x as i32),
+    };
+
+    // (坐标, high, 沿边缘方向的范围, 两个端点)
+    let mut segments: Vec<(i32, bool, i32, i32, [usize; 2])> = Vec::new();
+    let mut start = 0;
+    for contour in &glyph.contours {
+        for i in 0..contour.len() {
+            let j = (i + 1) % contour.len();
+            let ((a_along, a_pos), (b_along, b_pos)) = (coords(&contour[i]), coords(&contour[j]));
+            let (d_along, d_pos) = (b_along - a_along, b_pos - a_pos);
+            // 倾斜不超过约 4 度
+            if d_along.abs() < min_length || d_pos.abs() * 14 > d_along.abs() {
+                continue;
+            }
+            // 逆时针轮廓沿 +x 前进时墨迹在上方（下边缘）；竖直方向沿 +y 前进时墨迹在左侧（右边缘）
+            let high = match axis {
+                Axis::Y => (d_along > 0) != counter_clockwise,
+                Axis::X => (d_along > 0) == counter_clockwise,
+            };
+            let pos = (a_pos + b_pos) / 2;
+            segments.push((pos, high, a_along.min(b_along), a_along.max(b_along), [start + i, start + j]));
+        }
+        start += contour.len();
+    }
+    segments.sort_by_key(|s| (s.1, s.0));
+
+    let mut edges: Vec<Edge> = Vec::new();
+    let mut used = BTreeSet::new();
+    for (pos, high, min, max, points) in segments {
+        let edge = match edges.last_mut() {
+            Some(edge) if edge.high == high && (pos - edge.pos).abs() <= tolerance => edge,
+            _ => {
+                edges.push(Edge { pos, high, min, max, points: Vec::new() });
+                edges.last_mut().unwrap()
+            }
+        };
+        edge.min = edge.min.min(min);
+        edge.max = edge.max.max(max);
+        // 每个点只属于一条边缘
+        edge.points.extend(points.into_iter().filter(|&p| used.insert(p)));
+    }
+    edges.retain(|edge| !edge.points.is_empty());
+    edges
+}
+
+// 配对笔画：下（左）边缘与其上方最近的、范围重叠的上（右）边缘，按宽度从小到大贪心选择
+fn stems(edges: &[Edge], units_per_em: i32) -> Vec<Stem> {
+    let max_width = units_per_em / 4;
+    let mut candidates = Vec::new();
+    for (low, a) in edges.iter().enumerate().filter(|(_, e)| !e.high) {
+        for (high, b) in edges.iter().enumerate().filter(|(_, e)| e.high) {
+            let width = b.pos - a.pos;
+            let overlap = a.max.min(b.max) - a.min.max(b.min);
+            if width > 0 && width <= max_width && overlap > 0 {
+                candidates.push((width, low, high));
+            }
+        }
+    }
+    candidates.sort();
+    let mut used = BTreeSet::new();
+    let mut stems = Vec::new();
+    for (_, low, high) in candidates {
+        if !used.contains(&low) && !used.contains(&high) {
+            used.insert(low);
+            used.insert(high);
+            stems.push(Stem { low, high });
+        }
+    }
+    stems
+}
+
+// 出现最多的几个笔画宽度，相近的宽度（1% em 以内）合并为一组
+fn standard_widths(mut widths: Vec<i32>, units_per_em: i32) -> Vec<i32> {
+    widths.sort_unstable();
+    let tolerance = (units_per_em / 100).max(1);
+    let mut clusters: Vec<Vec<i32>> = Vec::new();
+    for width in widths {
+        match clusters.last_mut() {
+            Some(cluster) if width - cluster[0] <= tolerance => cluster.push(width),
+            _ => clusters.push(vec![width]),
+        }
+    }
+    clusters.sort_by_key(|c| std::cmp::Reverse(c.len()));
+    clusters.iter().take(MAX_STANDARD_WIDTHS).map(|c| c[c.len() / 2]).collect()
+}
+
+// 字形分析结果：两个方向的边缘与笔画
+struct Analysis {
+    script: Script,
+    y_edges: Vec<Edge>,
+    y_stems: Vec<Stem>,
+    x_edges: Vec<Edge>,
+    x_stems: Vec<Stem>,
+}
+
+fn analyze(glyph: &GlyphData, simple: &SimpleGlyph, units_per_em: i32) -> Analysis {
+    let script = script(glyph.unicode);
+    let y_edges = edges(simple, Axis::Y, units_per_em);
+    let y_stems = stems(&y_edges, units_per_em);
+    // Latin 只在 y 方向提示；CJK 横竖笔画都需要对齐
+    let (x_edges, x_stems) = if script == Script::Cjk {
+        let x_edges = edges(simple, Axis::X, units_per_em);
+        let x_stems = stems(&x_edges, units_per_em);
+        (x_edges, x_stems)
+    } else {
+        (Vec::new(), Vec::new())
+    };
+    Analysis { script, y_edges, y_stems, x_edges, x_stems }
+}
+
+// 由参考字符的最高上边缘或最低下边缘取中位数得到对齐区位置
+fn detect_zone(analyses: &[Analysis], by_unicode: &HashMap<u32, usize>, chars: &str, high: bool) -> Option<i32> {
+    let mut positions: Vec<i32> = chars
+        .chars()
+        .filter_map(|c| by_unicode.get(&(c as u32)))
+        .filter_map(|&gid| {
+            let edges = analyses[gid].y_edges.iter().filter(|e| e.high == high).map(|e| e.pos);
+            if high {
+                edges.max()
+            } else {
+                edges.min()
+            }
+        })
+        .collect();
+    positions.sort_unstable();
+    positions.get(positions.len() / 2).copied()
+}
+
+// 一个方向上的提示：先对齐笔画（有一侧落在对齐区时从该侧开始），再对齐其余落在对齐区的边缘
+fn hint_axis(program: &mut Program, edges: &[Edge], stems: &[Stem], zones: &[&Zone], widths: &[(i32, u16)], fuzz: i32) {
+    let zone_of = |edge: &Edge| {
+        zones.iter().find(|zone| zone.high == edge.high && (zone.pos - edge.pos).abs() <= fuzz).map(|zone| zone.cvt)
+    };
+    let anchor = |program: &mut Program, edge: &Edge| {
+        let first = edge.points[0] as u16;
+        match zone_of(edge) {
+            Some(cvt) => {
+                program.push(&[first, cvt]);
+                program.op(MIAP_ROUND);
+            }
+            None => {
+                program.push(&[first]);
+                program.op(MDAP_ROUND);
+            }
+        }
+        program.align(&edge.points[1..]);
+    };
+
+    let mut touched = vec![false; edges.len()];
+    for stem in stems {
+        let width = stem.width(edges);
+        let (from, to) = if zone_of(&edges[stem.low]).is_none() && zone_of(&edges[stem.high]).is_some() {
+            (stem.high, stem.low)
+        } else {
+            (stem.low, stem.high)
+        };
+        anchor(program, &edges[from]);
+        let Some(&(_, cvt)) = widths.iter().min_by_key(|(w, _)| (w - width).abs()) else {
+            continue;
+        };
+        program.call(&[edges[to].points[0] as u16, cvt, edges[from].points[0] as u16], FN_LINK);
+        program.align(&edges[to].points[1..]);
+        touched[from] = true;
+        touched[to] = true;
+    }
+    for (edge, _) in edges.iter().zip(&touched).filter(|(edge, &touched)| !touched && zone_of(edge).is_some()) {
+        anchor(program, edge);
+    }
+}
+
+fn font_program() -> Program {
+    let mut p = Program::default();
+    let function = |p: &mut Program, number: u16, body: &[u8]| {
+        p.push(&[number]);
+        p.op(FDEF);
+        p.code.extend_from_slice(body);
+        p.op(ENDF);
+    };
+    // 对齐区：控制值取整到像素
+    function(&mut p, FN_ROUND_ZONE, &[DUP, RCVT, ROUND_BLACK, WCVTP]);
+    // 笔画宽度：取整且不小于 1 像素
+    function(&mut p, FN_ROUND_WIDTH, &[DUP, RCVT, ROUND_BLACK, PUSHB, 64, MAX, WCVTP]);
+    // 参数 (p2, cvt, p1)：以 p1 为参考，按标准宽度放置 p2
+    function(&mut p, FN_LINK, &[SRP0, MIRP_STEM]);
+    // 参数 (点..., 数量)：以 rp0 为参考对齐各点
+    function(&mut p, FN_ALIGN, &[SLOOP, ALIGNRP]);
+    p
+}
+
+fn control_program(zone_cvts: &[u16], width_cvts: &[u16]) -> Program {
+    let mut p = Program::default();
+    // 所有字号启用漏点控制
+    p.push(&[0x01FF]);
+    p.op(SCANCTRL);
+    p.push(&[4]);
+    p.op(SCANTYPE);
+    p.push(&[CVT_CUT_IN]);
+    p.op(SCVTCI);
+    for (cvts, function) in [(zone_cvts, FN_ROUND_ZONE), (width_cvts, FN_ROUND_WIDTH)] {
+        if !cvts.is_empty() {
+            let mut args = cvts.to_vec();
+            args.extend([cvts.len() as u16, function]);
+            p.push(&args);
+            p.op(LOOPCALL);
+        }
+    }
+    p
+}
+
+// 对 glyf 轮廓自动生成提示指令：检测各文字的对齐区与标准笔画宽度，写入 cvt，
+// 字形程序按对齐区和笔画宽度对齐边缘，其余点由 IUP 插值
+pub fn autohint(glyphs: &[GlyphData], simple_glyphs: &[SimpleGlyph], units_per_em: u16) -> Hints {
+    let upm = units_per_em as i32;
+    let analyses: Vec<Analysis> = glyphs.iter().zip(simple_glyphs).map(|(g, s)| analyze(g, s, upm)).collect();
+    let by_unicode: HashMap<u32, usize> =
+        glyphs.iter().enumerate().filter(|(_, g)| g.unicode != 0).map(|(gid, g)| (g.unicode, gid)).collect();
+
+    let mut cvt: Vec<i16> = Vec::new();
+    let mut zones: HashMap<Script, Vec<Zone>> = HashMap::new();
+    let fuzz = (upm / 64).max(1);
+    for (script, definitions) in [(Script::Latin, &LATIN_ZONES[..]), (Script::Cjk, &CJK_ZONES[..])] {
+        let list = zones.entry(script).or_default();
+        for &(chars, high) in definitions {
+            let Some(pos) = detect_zone(&analyses, &by_unicode, chars, high) else {
+                continue;
+            };
+            // 与已有对齐区重合（如大写高度与升部）时只保留一个
+            if list.iter().any(|zone| zone.high == high && (zone.pos - pos).abs() <= fuzz) {
+                continue;
+            }
+            list.push(Zone { pos, high, cvt: cvt.len() as u16 });
+            cvt.push(pos as i16);
+        }
+    }
+    let zone_cvts: Vec<u16> = (0..cvt.len() as u16).collect();
+
+    // 各文字、各方向的标准笔画宽度
+    let mut widths: HashMap<(Script, Axis), Vec<(i32, u16)>> = HashMap::new();
+    for script in [Script::Latin, Script::Cjk] {
+        for axis in [Axis::Y, Axis::X] {
+            let samples: Vec<i32> = analyses
+                .iter()
+                .filter(|a| a.script == script)
+                .flat_map(|a| match axis {
+                    Axis::Y => a.y_stems.iter().map(|s| s.width(&a.y_edges)).collect::<Vec<_>>(),
+                    Axis::X => a.x_stems.iter().map(|s| s.width(&a.x_edges)).collect(),
+                })
+                .collect();
+            let list = standard_widths(samples, upm)
+                .into_iter()
+                .map(|width| {
+                    cvt.push(width as i16);
+                    (width, cvt.len() as u16 - 1)
+                })
+                .collect();
+            widths.insert((script, axis), list);
+        }
+    }
+    let width_cvts: Vec<u16> = (zone_cvts.len() as u16..cvt.len() as u16).collect();
+
+    let mut max_stack = 0;
+    let mut max_instructions = 0;
+    let glyph_programs: Vec<Vec<u8>> = analyses
+        .iter()
+        .map(|analysis| {
+            let mut program = Program::default();
+            let script_zones: Vec<&Zone> = zones[&analysis.script].iter().collect();
+            if !analysis.y_edges.is_empty() {
+                program.op(SVTCA_Y);
+                let y_widths = &widths[&(analysis.script, Axis::Y)];
+                hint_axis(&mut program, &analysis.y_edges, &analysis.y_stems, &script_zones, y_widths, fuzz);
+                program.op(IUP_Y);
+            }
+            if !analysis.x_stems.is_empty() {
+                program.op(SVTCA_X);
+                let x_widths = &widths[&(analysis.script, Axis::X)];
+                hint_axis(&mut program, &analysis.x_edges, &analysis.x_stems, &[], x_widths, fuzz);
+                program.op(IUP_X);
+            }
+            // 没有任何点被移动，或指令长度超出 glyf 中 u16 的长度字段时不写指令
+            if program.code.len() <= 2 || program.code.len() > u16::MAX as usize {
+                return Vec::new();
+            }
+            max_stack = max_stack.max(program.max_stack);
+            max_instructions = max_instructions.max(program.code.len());
+            program.code
+        })
+        .collect();
+
+    let fpgm = font_program();
+    let prep = control_program(&zone_cvts, &width_cvts);
+    let mut cvt_data = Writer::new();
+    for value in cvt {
+        cvt_data.i16(value);
+    }
+    // 函数内部最多在调用者的参数之上再压入两个值
+    let max_stack = max_stack.max(fpgm.max_stack).max(prep.max_stack) + 2;
+    Hints {
+        fpgm: fpgm.code,
+        prep: prep.code,
+        cvt: cvt_data.into_bytes(),
+        glyph_programs,
+        limits: InstructionLimits {
+            max_function_defs: FUNCTION_COUNT,
+            max_stack_elements: max_stack as u16,
+            max_size_of_instructions: max_instructions as u16,
+        },
+    }
+}
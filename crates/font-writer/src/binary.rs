@@ -0,0 +1,191 @@
+// 大端序二进制写入工具，OpenType 中所有数值均为大端序
+#[derive(Debug, Default, Clone)]
+pub struct Writer {
+    data: Vec<u8>,
+}
+
+impl Writer {
+    pub fn new() -> Self {
+        Writer { data: Vec::new() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn u8(&mut self, v: u8) {
+        self.data.push(v);
+    }
+
+    pub fn i8(&mut self, v: i8) {
+        self.data.push(v as u8);
+    }
+
+    pub fn u16(&mut self, v: u16) {
+        self.data.extend_from_slice(&v.to_be_bytes());
+    }
+
+    pub fn i16(&mut self, v: i16) {
+        self.data.extend_from_slice(&v.to_be_bytes());
+    }
+
+    pub fn u24(&mut self, v: u32) {
+        self.data.extend_from_slice(&v.to_be_bytes()[1..]);
+    }
+
+    pub fn u32(&mut self, v: u32) {
+        self.data.extend_from_slice(&v.to_be_bytes());
+    }
+
+    pub fn i32(&mut self, v: i32) {
+        self.data.extend_from_slice(&v.to_be_bytes());
+    }
+
+    pub fn i64(&mut self, v: i64) {
+        self.data.extend_from_slice(&v.to_be_bytes());
+    }
+
+    // 16.16 定点数
+    pub fn fixed(&mut self, v: f64) {
+        self.i32((v * 65536.0).round() as i32);
+    }
+
+    // 2.14 定点数
+    pub fn f2dot14(&mut self, v: f64) {
+        self.i16(f2dot14(v));
+    }
+
+    pub fn tag(&mut self, tag: &[u8; 4]) {
+        self.data.extend_from_slice(tag);
+    }
+
+    pub fn bytes(&mut self, bytes: &[u8]) {
+        self.data.extend_from_slice(bytes);
+    }
+
+    // 补零对齐到 align 字节
+    pub fn align(&mut self, align: usize) {
+        while self.data.len() % align != 0 {
+            self.data.push(0);
+        }
+    }
+
+    pub fn set_u16(&mut self, pos: usize, v: u16) {
+        self.data[pos..pos + 2].copy_from_slice(&v.to_be_bytes());
+    }
+
+    pub fn set_u32(&mut self, pos: usize, v: u32) {
+        self.data[pos..pos + 4].copy_from_slice(&v.to_be_bytes());
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.data
+    }
+}
+
+pub fn f2dot14(v: f64) -> i16 {
+    (v * 16384.0).round().clamp(i16::MIN as f64, i16::MAX as f64) as i16
+}
+
+// 表校验和：按大端 u32 累加，末尾不足 4 字节补零
+pub fn checksum(data: &[u8]) -> u32 {
+    let mut sum: u32 = 0;
+    let mut chunks = data.chunks_exact(4);
+    for chunk in &mut chunks {
+        sum = sum.wrapping_add(u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]));
+    }
+    let rest = chunks.remainder();
+    if !rest.is_empty() {
+        let mut last = [0u8; 4];
+        last[..rest.len()].copy_from_slice(rest);
+        sum = sum.wrapping_add(u32::from_be_bytes(last));
+    }
+    sum
+}
+
+// 大端序二进制读取工具，越界时返回错误
+#[derive(Debug, Clone)]
+pub struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Reader { data, pos: 0 }
+    }
+
+    pub fn at(data: &'a [u8], pos: usize) -> Self {
+        Reader { data, pos }
+    }
+
+    pub fn pos(&self) -> usize {
+        self.pos
+    }
+
+    pub fn set_pos(&mut self, pos: usize) {
+        self.pos = pos;
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.data.len().saturating_sub(self.pos)
+    }
+
+    pub fn bytes(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self.pos.checked_add(len).filter(|&end| end <= self.data.len());
+        let Some(end) = end else {
+            return Err(format!("unexpected end of data at offset {}", self.pos));
+        };
+        let bytes = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    pub fn skip(&mut self, len: usize) -> Result<(), String> {
+        self.bytes(len).map(|_| ())
+    }
+
+    pub fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    pub fn i8(&mut self) -> Result<i8, String> {
+        Ok(self.u8()? as i8)
+    }
+
+    pub fn u16(&mut self) -> Result<u16, String> {
+        let b = self.bytes(2)?;
+        Ok(u16::from_be_bytes([b[0], b[1]]))
+    }
+
+    pub fn i16(&mut self) -> Result<i16, String> {
+        Ok(self.u16()? as i16)
+    }
+
+    pub fn u24(&mut self) -> Result<u32, String> {
+        let b = self.bytes(3)?;
+        Ok(u32::from_be_bytes([0, b[0], b[1], b[2]]))
+    }
+
+    pub fn u32(&mut self) -> Result<u32, String> {
+        let b = self.bytes(4)?;
+        Ok(u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    pub fn i32(&mut self) -> Result<i32, String> {
+        Ok(self.u32()? as i32)
+    }
+
+    pub fn tag(&mut self) -> Result<[u8; 4], String> {
+        let b = self.bytes(4)?;
+        Ok([b[0], b[1], b[2], b[3]])
+    }
+}
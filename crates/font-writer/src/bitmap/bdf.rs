@@ -0,0 +1,47 @@
+use super::{BitmapFont, Property};
+
+// BDF 字符串中的双引号写两次
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('"', "\"\"").replace('\n', " "))
+}
+
+// 生成 BDF 2.1 文本
+pub fn write(font: &BitmapFont) -> String {
+    let mut out = String::from("STARTFONT 2.1\n");
+    out.push_str(&format!("FONT {}\n", font.name));
+    out.push_str(&format!(
+        "SIZE {} {} {}\n",
+        (font.point_size as f64 / 10.0).round() as u32,
+        font.resolution,
+        font.resolution
+    ));
+    let (width, height, x_offset, y_offset) = font.bounding_box();
+    out.push_str(&format!("FONTBOUNDINGBOX {width} {height} {x_offset} {y_offset}\n"));
+    out.push_str(&format!("STARTPROPERTIES {}\n", font.properties.len()));
+    for (name, value) in &font.properties {
+        match value {
+            Property::Integer(v) => out.push_str(&format!("{name} {v}\n")),
+            Property::String(s) => out.push_str(&format!("{name} {}\n", quote(s))),
+        }
+    }
+    out.push_str("ENDPROPERTIES\n");
+    out.push_str(&format!("CHARS {}\n", font.glyphs.len()));
+    for glyph in &font.glyphs {
+        let bitmap = &glyph.bitmap;
+        out.push_str(&format!("STARTCHAR {}\n", glyph.name));
+        out.push_str(&format!("ENCODING {}\n", glyph.encoding));
+        out.push_str(&format!("SWIDTH {} 0\n", glyph.swidth));
+        out.push_str(&format!("DWIDTH {} 0\n", glyph.dwidth));
+        out.push_str(&format!("BBX {} {} {} {}\n", bitmap.width, bitmap.height, bitmap.x_offset, bitmap.y_offset));
+        out.push_str("BITMAP\n");
+        for row in &bitmap.rows {
+            for byte in row {
+                out.push_str(&format!("{byte:02X}"));
+            }
+            out.push('\n');
+        }
+        out.push_str("ENDCHAR\n");
+    }
+    out.push_str("ENDFONT\n");
+    out
+}
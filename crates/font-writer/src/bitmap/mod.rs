@@ -0,0 +1,230 @@
+pub mod bdf;
+pub mod pcf;
+pub mod raster;
+
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{composite, FontData};
+use raster::Bitmap;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BitmapOptions {
+    // 导出的像素大小（每 em 的像素数），每个大小生成一个字体文件
+    pub sizes: Vec<u16>,
+    // 像素被轮廓覆盖的比例达到该值时点亮（0–1）
+    pub threshold: f64,
+    // 设计分辨率（dpi），用于换算点数
+    pub resolution: u16,
+    // 是否同时生成 PCF
+    pub pcf: bool,
+}
+
+impl Default for BitmapOptions {
+    fn default() -> Self {
+        BitmapOptions { sizes: vec![16], threshold: 0.5, resolution: 75, pcf: false }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Property {
+    Integer(i32),
+    String(String),
+}
+
+// 一个字形的位图与度量：swidth 以 1/1000 em 为单位，dwidth 为像素步进
+#[derive(Debug, Clone)]
+pub struct BitmapGlyph {
+    pub name: String,
+    pub encoding: u32,
+    pub swidth: i32,
+    pub dwidth: i32,
+    pub bitmap: Bitmap,
+}
+
+// 一个像素大小的位图字体，字形按码位升序排列
+#[derive(Debug, Clone)]
+pub struct BitmapFont {
+    // XLFD 字体名
+    pub name: String,
+    pub pixel_size: u16,
+    pub point_size: u16,
+    pub resolution: u16,
+    pub ascent: i32,
+    pub descent: i32,
+    pub properties: Vec<(String, Property)>,
+    pub glyphs: Vec<BitmapGlyph>,
+}
+
+impl BitmapFont {
+    pub fn default_char(&self) -> Option<u32> {
+        self.properties.iter().find_map(|(name, value)| match value {
+            Property::Integer(v) if name == "DEFAULT_CHAR" => Some(*v as u32),
+            _ => None,
+        })
+    }
+
+    // 所有字形位图的并集，(宽, 高, x 偏移, y 偏移)
+    pub fn bounding_box(&self) -> (i32, i32, i32, i32) {
+        let boxes = self.glyphs.iter().map(|g| &g.bitmap).filter(|b| b.width > 0);
+        let (mut x0, mut y0, mut x1, mut y1) = (i32::MAX, i32::MAX, i32::MIN, i32::MIN);
+        for b in boxes {
+            x0 = x0.min(b.x_offset);
+            y0 = y0.min(b.y_offset);
+            x1 = x1.max(b.x_offset + b.width as i32);
+            y1 = y1.max(b.y_offset + b.height as i32);
+        }
+        if x0 > x1 {
+            return (0, 0, 0, 0);
+        }
+        (x1 - x0, y1 - y0, x0, y0)
+    }
+}
+
+// XLFD 字段中不能出现 - * ? , 与引号
+fn xlfd_field(value: &str, fallback: &str) -> String {
+    let field: String = value.chars().filter(|c| !matches!(c, '-' | '*' | '?' | ',' | '"')).collect();
+    let field = field.trim();
+    if field.is_empty() {
+        fallback.to_string()
+    } else {
+        field.to_string()
+    }
+}
+
+fn weight_name(weight_class: u16) -> &'static str {
+    match weight_class {
+        0..=150 => "Thin",
+        151..=250 => "ExtraLight",
+        251..=350 => "Light",
+        351..=549 => "Medium",
+        550..=649 => "SemiBold",
+        650..=749 => "Bold",
+        750..=849 => "ExtraBold",
+        _ => "Black",
+    }
+}
+
+fn setwidth_name(width_class: u16) -> &'static str {
+    match width_class {
+        1 => "UltraCondensed",
+        2 => "ExtraCondensed",
+        3 => "Condensed",
+        4 => "SemiCondensed",
+        6 => "SemiExpanded",
+        7 => "Expanded",
+        8 => "ExtraExpanded",
+        9 => "UltraExpanded",
+        _ => "Normal",
+    }
+}
+
+// 栅格化有码位的字形生成一个像素大小的位图字体，同一码位只取第一个字形
+pub fn build(font: &FontData, pixel_size: u16, options: &BitmapOptions) -> Result<BitmapFont, String> {
+    if pixel_size == 0 {
+        return Err(String::from("bitmap pixel size must be positive"));
+    }
+    let info = &font.info;
+    let units_per_em = info.units_per_em.max(1) as f64;
+    let scale = pixel_size as f64 / units_per_em;
+    let resolution = options.resolution.max(1);
+    let glyphs = composite::flatten(&font.glyphs, 0)?;
+
+    let mut seen = HashSet::new();
+    let mut encoded: Vec<(u32, usize)> = glyphs
+        .iter()
+        .enumerate()
+        .filter(|(_, g)| g.unicode != 0 && seen.insert(g.unicode))
+        .map(|(i, g)| (g.unicode, i))
+        .collect();
+    encoded.sort_unstable();
+    let bitmap_glyphs: Vec<BitmapGlyph> = encoded
+        .iter()
+        .map(|&(unicode, i)| {
+            let glyph = &glyphs[i];
+            let name = if glyph.name.is_empty() || glyph.name.contains(char::is_whitespace) {
+                if unicode <= 0xFFFF {
+                    format!("uni{unicode:04X}")
+                } else {
+                    format!("u{unicode:X}")
+                }
+            } else {
+                glyph.name.clone()
+            };
+            BitmapGlyph {
+                name,
+                encoding: unicode,
+                swidth: (glyph.advance_width * 1000.0 / units_per_em).round() as i32,
+                dwidth: (glyph.advance_width * scale).round() as i32,
+                bitmap: raster::rasterize(&glyph.contours, scale, options.threshold),
+            }
+        })
+        .collect();
+
+    // POINT_SIZE 以 1/10 点为单位（1 点 = 1/72.27 英寸）
+    let point_size = (pixel_size as f64 * 722.7 / resolution as f64).round() as u16;
+    let ascent = (info.ascender as f64 * scale).round() as i32;
+    let descent = (-(info.descender as f64) * scale).round() as i32;
+    let widths: HashSet<i32> = bitmap_glyphs.iter().map(|g| g.dwidth).collect();
+    let spacing = if widths.len() == 1 { "M" } else { "P" };
+    let average_width = if bitmap_glyphs.is_empty() {
+        0
+    } else {
+        (bitmap_glyphs.iter().map(|g| g.dwidth as f64 * 10.0).sum::<f64>() / bitmap_glyphs.len() as f64).round() as i32
+    };
+    let foundry = xlfd_field(&info.manufacturer, "Misc");
+    let family = xlfd_field(&info.family_name, "Untitled");
+    let weight = weight_name(info.weight_class);
+    let slant = if info.is_italic() { "I" } else { "R" };
+    let setwidth = setwidth_name(info.width_class);
+    let name = format!(
+        "-{foundry}-{family}-{weight}-{slant}-{setwidth}--{pixel_size}-{point_size}-{resolution}-{resolution}-{spacing}-{average_width}-ISO10646-1"
+    );
+
+    let string = |v: &str| Property::String(v.to_string());
+    let mut properties = vec![
+        (String::from("FOUNDRY"), string(&foundry)),
+        (String::from("FAMILY_NAME"), string(&family)),
+        (String::from("WEIGHT_NAME"), string(weight)),
+        (String::from("SLANT"), string(slant)),
+        (String::from("SETWIDTH_NAME"), string(setwidth)),
+        (String::from("ADD_STYLE_NAME"), string("")),
+        (String::from("PIXEL_SIZE"), Property::Integer(pixel_size as i32)),
+        (String::from("POINT_SIZE"), Property::Integer(point_size as i32)),
+        (String::from("RESOLUTION_X"), Property::Integer(resolution as i32)),
+        (String::from("RESOLUTION_Y"), Property::Integer(resolution as i32)),
+        (String::from("SPACING"), string(spacing)),
+        (String::from("AVERAGE_WIDTH"), Property::Integer(average_width)),
+        (String::from("CHARSET_REGISTRY"), string("ISO10646")),
+        (String::from("CHARSET_ENCODING"), string("1")),
+        (String::from("FONT_ASCENT"), Property::Integer(ascent)),
+        (String::from("FONT_DESCENT"), Property::Integer(descent)),
+        (String::from("FACE_NAME"), string(&info.full_name())),
+        (String::from("FONT_VERSION"), string(&info.version)),
+    ];
+    if !info.copyright.is_empty() {
+        properties.push((String::from("COPYRIGHT"), string(&info.copyright)));
+    }
+    if let Some(cap_height) = info.cap_height {
+        properties.push((String::from("CAP_HEIGHT"), Property::Integer((cap_height as f64 * scale).round() as i32)));
+    }
+    if let Some(x_height) = info.x_height {
+        properties.push((String::from("X_HEIGHT"), Property::Integer((x_height as f64 * scale).round() as i32)));
+    }
+    // 缺字时显示替换字符
+    if encoded.iter().any(|&(unicode, _)| unicode == 0xFFFD) {
+        properties.push((String::from("DEFAULT_CHAR"), Property::Integer(0xFFFD)));
+    }
+
+    Ok(BitmapFont { name, pixel_size, point_size, resolution, ascent, descent, properties, glyphs: bitmap_glyphs })
+}
+
+pub fn build_bdf(font: &FontData, pixel_size: u16, options: &BitmapOptions) -> Result<Vec<u8>, String> {
+    Ok(bdf::write(&build(font, pixel_size, options)?).into_bytes())
+}
+
+pub fn build_pcf(font: &FontData, pixel_size: u16, options: &BitmapOptions) -> Result<Vec<u8>, String> {
+    Ok(pcf::write(&build(font, pixel_size, options)?))
+}
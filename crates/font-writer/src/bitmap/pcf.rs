@@ -0,0 +1,253 @@
+use crate::binary::Writer;
+
+use super::{BitmapFont, Property};
+
+// 表类型
+const PCF_PROPERTIES: u32 = 1 << 0;
+const PCF_ACCELERATORS: u32 = 1 << 1;
+const PCF_METRICS: u32 = 1 << 2;
+const PCF_BITMAPS: u32 = 1 << 3;
+const PCF_BDF_ENCODINGS: u32 = 1 << 5;
+const PCF_SWIDTHS: u32 = 1 << 6;
+const PCF_GLYPH_NAMES: u32 = 1 << 7;
+const PCF_BDF_ACCELERATORS: u32 = 1 << 8;
+
+// 数据按大端序、MSB 在前，位图每行补齐到 4 字节（与 bdftopcf 的缺省格式相同）
+const PCF_BYTE_MASK: u32 = 1 << 2;
+const PCF_BIT_MASK: u32 = 1 << 3;
+const GLYPH_PAD_INDEX: u32 = 2;
+const FORMAT: u32 = PCF_BYTE_MASK | PCF_BIT_MASK | GLYPH_PAD_INDEX;
+const NO_SUCH_CHAR: u16 = 0xFFFF;
+
+// 字形度量：左右边距以原点为基准，ascent/descent 为位图在基线上下的高度
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Metric {
+    left: i16,
+    right: i16,
+    width: i16,
+    ascent: i16,
+    descent: i16,
+}
+
+impl Metric {
+    fn write(&self, w: &mut Writer) {
+        w.i16(self.left);
+        w.i16(self.right);
+        w.i16(self.width);
+        w.i16(self.ascent);
+        w.i16(self.descent);
+        w.u16(0);
+    }
+}
+
+fn clamp_i16(v: i32) -> i16 {
+    v.clamp(i16::MIN as i32, i16::MAX as i32) as i16
+}
+
+// 每个表以小端序的格式字段开头
+fn table(format: u32) -> Writer {
+    let mut w = Writer::new();
+    w.bytes(&format.to_le_bytes());
+    w
+}
+
+fn properties(font: &BitmapFont) -> Writer {
+    let mut properties = vec![(String::from("FONT"), Property::String(font.name.clone()))];
+    properties.extend(font.properties.iter().cloned());
+    let mut strings = Vec::new();
+    let mut string = |s: &str| {
+        let offset = strings.len() as i32;
+        strings.extend_from_slice(s.as_bytes());
+        strings.push(0);
+        offset
+    };
+    let mut w = table(FORMAT);
+    w.i32(properties.len() as i32);
+    for (name, value) in &properties {
+        w.i32(string(name));
+        match value {
+            Property::Integer(v) => {
+                w.u8(0);
+                w.i32(*v);
+            }
+            Property::String(s) => {
+                w.u8(1);
+                w.i32(string(s));
+            }
+        }
+    }
+    w.align(4);
+    w.i32(strings.len() as i32);
+    w.bytes(&strings);
+    w
+}
+
+fn accelerators(font: &BitmapFont, metrics: &[Metric]) -> Writer {
+    let fold = |f: fn(i16, i16) -> i16| Metric {
+        left: metrics.iter().map(|m| m.left).reduce(f).unwrap_or(0),
+        right: metrics.iter().map(|m| m.right).reduce(f).unwrap_or(0),
+        width: metrics.iter().map(|m| m.width).reduce(f).unwrap_or(0),
+        ascent: metrics.iter().map(|m| m.ascent).reduce(f).unwrap_or(0),
+        descent: metrics.iter().map(|m| m.descent).reduce(f).unwrap_or(0),
+    };
+    let (min, max) = (fold(i16::min), fold(i16::max));
+    let max_overlap = metrics.iter().map(|m| m.right as i32 - m.width as i32).max().unwrap_or(0);
+    let (ascent, descent) = (clamp_i16(font.ascent), clamp_i16(font.descent));
+    let ink_inside =
+        metrics.iter().all(|m| m.left >= 0 && m.right <= m.width && m.ascent <= ascent && m.descent <= descent);
+    let constant_width = min.width == max.width;
+    let terminal = constant_width
+        && metrics.iter().all(|m| m.left >= 0 && m.right <= m.width && m.ascent == ascent && m.descent == descent);
+
+    let mut w = table(FORMAT);
+    w.u8((max_overlap <= min.left as i32) as u8);
+    w.u8((min == max) as u8);
+    w.u8(terminal as u8);
+    w.u8(constant_width as u8);
+    w.u8(ink_inside as u8);
+    // 没有单独的墨迹度量表
+    w.u8(0);
+    w.u8(0);
+    w.u8(0);
+    w.i32(font.ascent);
+    w.i32(font.descent);
+    w.i32(max_overlap);
+    min.write(&mut w);
+    max.write(&mut w);
+    w
+}
+
+fn metrics_table(metrics: &[Metric]) -> Writer {
+    let mut w = table(FORMAT);
+    w.i32(metrics.len() as i32);
+    for m in metrics {
+        m.write(&mut w);
+    }
+    w
+}
+
+fn bitmaps(font: &BitmapFont) -> Writer {
+    // 4 种行补齐方式下的位图总大小都要写出
+    let sizes: Vec<i32> = [1, 2, 4, 8]
+        .iter()
+        .map(|&pad| {
+            let row = |bytes: usize| bytes.div_ceil(pad) * pad;
+            font.glyphs.iter().map(|g| (row(g.bitmap.row_bytes()) * g.bitmap.rows.len()) as i32).sum()
+        })
+        .collect();
+    let pad = 1 << GLYPH_PAD_INDEX;
+    let mut data = Vec::new();
+    let mut offsets = Vec::with_capacity(font.glyphs.len());
+    for glyph in &font.glyphs {
+        offsets.push(data.len() as i32);
+        for row in &glyph.bitmap.rows {
+            data.extend_from_slice(row);
+            data.resize(data.len() + (pad - row.len() % pad) % pad, 0);
+        }
+    }
+    let mut w = table(FORMAT);
+    w.i32(font.glyphs.len() as i32);
+    offsets.iter().for_each(|&offset| w.i32(offset));
+    sizes.iter().for_each(|&size| w.i32(size));
+    w.bytes(&data);
+    w
+}
+
+// 编码表只能表示 BMP 内的码位，其余字形不编码
+fn encodings(font: &BitmapFont) -> Writer {
+    let encoded: Vec<(u16, u16)> =
+        font.glyphs.iter().enumerate().filter_map(|(i, g)| Some((u16::try_from(g.encoding).ok()?, i as u16))).collect();
+    let byte1 = |c: u16| c >> 8;
+    let byte2 = |c: u16| c & 0xFF;
+    let (min1, max1) = (encoded.iter().map(|e| byte1(e.0)).min(), encoded.iter().map(|e| byte1(e.0)).max());
+    let (min2, max2) = (encoded.iter().map(|e| byte2(e.0)).min(), encoded.iter().map(|e| byte2(e.0)).max());
+    let (Some(min1), Some(max1), Some(min2), Some(max2)) = (min1, max1, min2, max2) else {
+        let mut w = table(FORMAT);
+        [0, 0, 0, 0].iter().for_each(|&v| w.i16(v));
+        w.u16(NO_SUCH_CHAR);
+        w.u16(NO_SUCH_CHAR);
+        return w;
+    };
+    let columns = (max2 - min2 + 1) as usize;
+    let mut indices = vec![NO_SUCH_CHAR; (max1 - min1 + 1) as usize * columns];
+    for &(code, index) in &encoded {
+        indices[(byte1(code) - min1) as usize * columns + (byte2(code) - min2) as usize] = index;
+    }
+    let default_char = font.default_char().and_then(|c| u16::try_from(c).ok()).unwrap_or(NO_SUCH_CHAR);
+    let mut w = table(FORMAT);
+    w.u16(min2);
+    w.u16(max2);
+    w.u16(min1);
+    w.u16(max1);
+    w.u16(default_char);
+    indices.iter().for_each(|&index| w.u16(index));
+    w
+}
+
+fn swidths(font: &BitmapFont) -> Writer {
+    let mut w = table(FORMAT);
+    w.i32(font.glyphs.len() as i32);
+    font.glyphs.iter().for_each(|g| w.i32(g.swidth));
+    w
+}
+
+fn glyph_names(font: &BitmapFont) -> Writer {
+    let mut strings = Vec::new();
+    let mut w = table(FORMAT);
+    w.i32(font.glyphs.len() as i32);
+    for glyph in &font.glyphs {
+        w.i32(strings.len() as i32);
+        strings.extend_from_slice(glyph.name.as_bytes());
+        strings.push(0);
+    }
+    w.i32(strings.len() as i32);
+    w.bytes(&strings);
+    w
+}
+
+// 生成 X11 PCF 二进制字体
+pub fn write(font: &BitmapFont) -> Vec<u8> {
+    let metrics: Vec<Metric> = font
+        .glyphs
+        .iter()
+        .map(|g| {
+            let b = &g.bitmap;
+            Metric {
+                left: clamp_i16(b.x_offset),
+                right: clamp_i16(b.x_offset + b.width as i32),
+                width: clamp_i16(g.dwidth),
+                ascent: clamp_i16(b.y_offset + b.height as i32),
+                descent: clamp_i16(-b.y_offset),
+            }
+        })
+        .collect();
+    let accelerators = accelerators(font, &metrics);
+    let tables = [
+        (PCF_PROPERTIES, properties(font)),
+        (PCF_ACCELERATORS, accelerators.clone()),
+        (PCF_METRICS, metrics_table(&metrics)),
+        (PCF_BITMAPS, bitmaps(font)),
+        (PCF_BDF_ENCODINGS, encodings(font)),
+        (PCF_SWIDTHS, swidths(font)),
+        (PCF_GLYPH_NAMES, glyph_names(font)),
+        (PCF_BDF_ACCELERATORS, accelerators),
+    ];
+
+    // 文件头与目录为小端序
+    let mut out = Vec::new();
+    out.extend_from_slice(b"\x01fcp");
+    out.extend_from_slice(&(tables.len() as u32).to_le_bytes());
+    let mut offset = 8 + 16 * tables.len();
+    for (kind, table) in &tables {
+        for v in [*kind, FORMAT, table.len() as u32, offset as u32] {
+            out.extend_from_slice(&v.to_le_bytes());
+        }
+        offset += table.len().div_ceil(4) * 4;
+    }
+    for (_, table) in tables {
+        let mut data = table.into_bytes();
+        data.resize(data.len().div_ceil(4) * 4, 0);
+        out.extend_from_slice(&data);
+    }
+    out
+}
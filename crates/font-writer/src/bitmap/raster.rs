@@ -0,0 +1,150 @@
+use crate::outline::{Contours, Point, Segment};
+
+// 每个像素在 x、y 方向各取 SAMPLES 个采样点计算覆盖率
+const SAMPLES: usize = 8;
+// 曲线拆分为折线时允许的误差（像素）
+const TOLERANCE: f64 = 0.05;
+
+// 单色位图：rows 自上而下，每行按 MSB 在前打包，行宽补齐到整字节；偏移为左下角像素相对原点的位置
+#[derive(Debug, Clone, Default)]
+pub struct Bitmap {
+    pub width: u32,
+    pub height: u32,
+    pub x_offset: i32,
+    pub y_offset: i32,
+    pub rows: Vec<Vec<u8>>,
+}
+
+impl Bitmap {
+    pub fn row_bytes(&self) -> usize {
+        (self.width as usize).div_ceil(8)
+    }
+}
+
+fn lerp(a: Point, b: Point, t: f64) -> Point {
+    Point { x: a.x + (b.x - a.x) * t, y: a.y + (b.y - a.y) * t }
+}
+
+fn distance(a: Point, b: Point) -> f64 {
+    (a.x - b.x).hypot(a.y - b.y)
+}
+
+// 按控制多边形偏离弦的程度决定拆分段数
+fn subdivisions(deviation: f64) -> usize {
+    ((deviation / TOLERANCE).sqrt().ceil() as usize).clamp(1, 64)
+}
+
+// 轮廓缩放到像素坐标并拆分为折线
+fn polygons(contours: &Contours, scale: f64) -> Vec<Vec<Point>> {
+    let s = |p: Point| Point { x: p.x * scale, y: p.y * scale };
+    contours
+        .iter()
+        .filter(|contour| !contour.is_empty())
+        .map(|contour| {
+            let mut points = vec![s(contour[0].start())];
+            for segment in contour {
+                match *segment {
+                    Segment::Line { end, .. } => points.push(s(end)),
+                    Segment::Quadratic { start, control, end } => {
+                        let (p0, p1, p2) = (s(start), s(control), s(end));
+                        let n = subdivisions(distance(p1, lerp(p0, p2, 0.5)));
+                        for i in 1..=n {
+                            let t = i as f64 / n as f64;
+                            points.push(lerp(lerp(p0, p1, t), lerp(p1, p2, t), t));
+                        }
+                    }
+                    Segment::Cubic { start, control1, control2, end } => {
+                        let (p0, p1, p2, p3) = (s(start), s(control1), s(control2), s(end));
+                        let n = subdivisions(
+                            distance(p1, lerp(p0, p3, 1.0 / 3.0)).max(distance(p2, lerp(p0, p3, 2.0 / 3.0))),
+                        );
+                        for i in 1..=n {
+                            let t = i as f64 / n as f64;
+                            let (a, b, c) = (lerp(p0, p1, t), lerp(p1, p2, t), lerp(p2, p3, t));
+                            points.push(lerp(lerp(a, b, t), lerp(b, c, t), t));
+                        }
+                    }
+                }
+            }
+            points
+        })
+        .collect()
+}
+
+// 按非零环绕规则栅格化，像素的覆盖率达到 threshold 时点亮，结果裁剪到点亮像素的范围
+pub fn rasterize(contours: &Contours, scale: f64, threshold: f64) -> Bitmap {
+    let polygons = polygons(contours, scale);
+    let points = polygons.iter().flatten();
+    let (mut x_min, mut y_min, mut x_max, mut y_max) = (f64::MAX, f64::MAX, f64::MIN, f64::MIN);
+    for p in points {
+        x_min = x_min.min(p.x);
+        y_min = y_min.min(p.y);
+        x_max = x_max.max(p.x);
+        y_max = y_max.max(p.y);
+    }
+    if x_min >= x_max || y_min >= y_max {
+        return Bitmap::default();
+    }
+    let (left, bottom) = (x_min.floor() as i32, y_min.floor() as i32);
+    let width = (x_max.ceil() as i32 - left) as usize;
+    let height = (y_max.ceil() as i32 - bottom) as usize;
+    let edges: Vec<(Point, Point)> = polygons
+        .iter()
+        .flat_map(|polygon| polygon.iter().zip(polygon.iter().cycle().skip(1)).map(|(&a, &b)| (a, b)))
+        .collect();
+
+    // 每个像素被覆盖的采样点数，coverage[0] 为最下面一行
+    let mut coverage = vec![0u32; width * height];
+    let mut crossings: Vec<(f64, i32)> = Vec::new();
+    for row in 0..height * SAMPLES {
+        let y = bottom as f64 + (row as f64 + 0.5) / SAMPLES as f64;
+        crossings.clear();
+        for &(a, b) in &edges {
+            if (a.y <= y) != (b.y <= y) {
+                let x = a.x + (y - a.y) * (b.x - a.x) / (b.y - a.y);
+                crossings.push((x, if b.y > a.y { 1 } else { -1 }));
+            }
+        }
+        crossings.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let mut winding = 0;
+        for pair in crossings.windows(2) {
+            winding += pair[0].1;
+            if winding == 0 {
+                continue;
+            }
+            // 采样点 x = left + (i + 0.5) / SAMPLES 落在 [x0, x1) 内
+            let sample =
+                |x: f64| (((x - left as f64) * SAMPLES as f64 - 0.5).ceil().max(0.0) as usize).min(width * SAMPLES);
+            let line = &mut coverage[row / SAMPLES * width..][..width];
+            for i in sample(pair[0].0)..sample(pair[1].0) {
+                line[i / SAMPLES] += 1;
+            }
+        }
+    }
+
+    let needed = ((threshold.clamp(0.0, 1.0) * (SAMPLES * SAMPLES) as f64).ceil() as u32).max(1);
+    let lit = |x: usize, y: usize| coverage[y * width + x] >= needed;
+    let columns: Vec<usize> = (0..width).filter(|&x| (0..height).any(|y| lit(x, y))).collect();
+    let lines: Vec<usize> = (0..height).filter(|&y| (0..width).any(|x| lit(x, y))).collect();
+    let (Some(&x0), Some(&x1), Some(&y0), Some(&y1)) = (columns.first(), columns.last(), lines.first(), lines.last())
+    else {
+        return Bitmap::default();
+    };
+    let mut bitmap = Bitmap {
+        width: (x1 - x0 + 1) as u32,
+        height: (y1 - y0 + 1) as u32,
+        x_offset: left + x0 as i32,
+        y_offset: bottom + y0 as i32,
+        rows: Vec::new(),
+    };
+    for y in (y0..=y1).rev() {
+        let mut row = vec![0u8; bitmap.row_bytes()];
+        for x in x0..=x1 {
+            if lit(x, y) {
+                row[(x - x0) / 8] |= 0x80 >> ((x - x0) % 8);
+            }
+        }
+        bitmap.rows.push(row);
+    }
+    bitmap
+}
@@ -0,0 +1,435 @@
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::outline::{Contours, Point, Segment};
+use crate::tables::colr::{self, ColorLine, LayerGlyph, Paint};
+use crate::tables::cpal;
+use crate::tables::svg::{self, SvgDocument};
+use crate::xml::number;
+use crate::{sfnt, ExportOptions, FontData, GlyphData};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Extend {
+    #[default]
+    Pad,
+    Repeat,
+    Reflect,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ColorStop {
+    pub offset: f64,
+    pub color: String,
+}
+
+// 图层填充：纯色或渐变，颜色为 #RRGGBB[AA]、rgb()/rgba() 或 currentColor，坐标为字体单位
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum Fill {
+    Solid {
+        color: String,
+    },
+    LinearGradient {
+        start: Point,
+        end: Point,
+        // 旋转参考点，缺省时取与渐变方向垂直的点
+        rotation: Option<Point>,
+        stops: Vec<ColorStop>,
+        #[serde(default)]
+        extend: Extend,
+    },
+    RadialGradient {
+        start_center: Point,
+        start_radius: f64,
+        end_center: Point,
+        end_radius: f64,
+        stops: Vec<ColorStop>,
+        #[serde(default)]
+        extend: Extend,
+    },
+}
+
+// 彩色字形的一个图层：图层轮廓生成单独的字形，transform 为 [a, b, c, d, e, f]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ColorLayer {
+    pub contours: Contours,
+    pub fill: Fill,
+    #[serde(default)]
+    pub transform: Option<[f64; 6]>,
+}
+
+// glyph 为 font.glyphs 中基础字形的序号，图层从下到上排列
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ColorGlyph {
+    pub glyph: usize,
+    pub layers: Vec<ColorLayer>,
+}
+
+// SVG 表的写入方式
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SvgOptions {
+    // 每个 SVG 文档用 gzip 压缩
+    pub compress: bool,
+    // 字形 ID 连续的彩色字形合并到同一个 SVG 文档
+    pub group_ranges: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ColorFontData {
+    pub font: FontData,
+    pub color_glyphs: Vec<ColorGlyph>,
+    // COLR 版本：0 只支持纯色图层，1 支持渐变与变换
+    pub version: u16,
+    // 同时由图层生成 SVG 表，为 None 时只写 COLR 与 CPAL
+    pub svg: Option<SvgOptions>,
+}
+
+impl Default for ColorFontData {
+    fn default() -> Self {
+        ColorFontData { font: FontData::default(), color_glyphs: Vec::new(), version: 1, svg: None }
+    }
+}
+
+fn hex_digits(hex: &str) -> Option<Vec<u8>> {
+    let digits: Option<Vec<u8>> = hex.chars().map(|c| c.to_digit(16).map(|d| d as u8)).collect();
+    match hex.len() {
+        3 | 4 => digits.map(|d| d.iter().map(|v| v * 17).collect()),
+        6 | 8 => digits.map(|d| d.chunks(2).map(|pair| pair[0] * 16 + pair[1]).collect()),
+        _ => None,
+    }
+}
+
+// 解析颜色为 RGBA，currentColor 返回 None
+pub fn parse_color(color: &str) -> Result<Option<[u8; 4]>, String> {
+    let value = color.trim();
+    let invalid = || format!("invalid color: {color:?}");
+    if value.eq_ignore_ascii_case("currentColor") {
+        return Ok(None);
+    }
+    if value.eq_ignore_ascii_case("transparent") {
+        return Ok(Some([0, 0, 0, 0]));
+    }
+    if let Some(hex) = value.strip_prefix('#') {
+        let mut rgba = hex_digits(hex).ok_or_else(invalid)?;
+        rgba.resize(4, 255);
+        return Ok(Some([rgba[0], rgba[1], rgba[2], rgba[3]]));
+    }
+    let args = value
+        .strip_prefix("rgba(")
+        .or_else(|| value.strip_prefix("rgb("))
+        .and_then(|rest| rest.strip_suffix(')'))
+        .ok_or_else(invalid)?;
+    let parts: Vec<&str> = args.split(',').map(str::trim).collect();
+    if parts.len() != 3 && parts.len() != 4 {
+        return Err(invalid());
+    }
+    let mut rgba = [255u8; 4];
+    for (i, part) in parts.iter().enumerate() {
+        let v: f64 = part.parse().map_err(|_| invalid())?;
+        rgba[i] = if i == 3 { (v * 255.0).round().clamp(0.0, 255.0) as u8 } else { v.round().clamp(0.0, 255.0) as u8 };
+    }
+    Ok(Some(rgba))
+}
+
+fn fword(v: f64) -> i16 {
+    v.round().clamp(i16::MIN as f64, i16::MAX as f64) as i16
+}
+
+fn ufword(v: f64) -> u16 {
+    v.round().clamp(0.0, u16::MAX as f64) as u16
+}
+
+fn extend_value(extend: Extend) -> u8 {
+    match extend {
+        Extend::Pad => 0,
+        Extend::Repeat => 1,
+        Extend::Reflect => 2,
+    }
+}
+
+// 相同颜色共用一个调色板条目
+#[derive(Default)]
+struct Palette {
+    colors: Vec<[u8; 4]>,
+    indices: HashMap<[u8; 4], u16>,
+}
+
+impl Palette {
+    fn index(&mut self, color: &str) -> Result<u16, String> {
+        let Some(rgba) = parse_color(color)? else {
+            return Ok(colr::FOREGROUND);
+        };
+        if let Some(&index) = self.indices.get(&rgba) {
+            return Ok(index);
+        }
+        let index = self.colors.len() as u16;
+        self.colors.push(rgba);
+        self.indices.insert(rgba, index);
+        Ok(index)
+    }
+
+    fn color_line(&mut self, stops: &[ColorStop], extend: Extend) -> Result<ColorLine, String> {
+        if stops.is_empty() {
+            return Err(String::from("gradient needs at least one color stop"));
+        }
+        let stops = stops
+            .iter()
+            .map(|stop| Ok(colr::ColorStop { offset: stop.offset, palette_index: self.index(&stop.color)?, alpha: 1.0 }))
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok(ColorLine { extend: extend_value(extend), stops })
+    }
+
+    fn paint(&mut self, fill: &Fill) -> Result<Paint, String> {
+        Ok(match fill {
+            Fill::Solid { color } => Paint::Solid { palette_index: self.index(color)?, alpha: 1.0 },
+            Fill::LinearGradient { start, end, rotation, stops, extend } => {
+                let rotation =
+                    rotation.unwrap_or(Point { x: start.x - (end.y - start.y), y: start.y + (end.x - start.x) });
+                Paint::LinearGradient {
+                    color_line: self.color_line(stops, *extend)?,
+                    points: [
+                        (fword(start.x), fword(start.y)),
+                        (fword(end.x), fword(end.y)),
+                        (fword(rotation.x), fword(rotation.y)),
+                    ],
+                }
+            }
+            Fill::RadialGradient { start_center, start_radius, end_center, end_radius, stops, extend } => {
+                Paint::RadialGradient {
+                    color_line: self.color_line(stops, *extend)?,
+                    start: (fword(start_center.x), fword(start_center.y)),
+                    start_radius: ufword(*start_radius),
+                    end: (fword(end_center.x), fword(end_center.y)),
+                    end_radius: ufword(*end_radius),
+                }
+            }
+        })
+    }
+}
+
+fn svg_path(contours: &Contours) -> String {
+    let mut d = Vec::new();
+    for contour in contours.iter().filter(|c| !c.is_empty()) {
+        let start = contour[0].start();
+        d.push(format!("M{} {}", number(start.x), number(start.y)));
+        for segment in contour {
+            d.push(match segment {
+                Segment::Line { end, .. } => format!("L{} {}", number(end.x), number(end.y)),
+                Segment::Quadratic { control, end, .. } => {
+                    format!("Q{} {} {} {}", number(control.x), number(control.y), number(end.x), number(end.y))
+                }
+                Segment::Cubic { control1, control2, end, .. } => format!(
+                    "C{} {} {} {} {} {}",
+                    number(control1.x),
+                    number(control1.y),
+                    number(control2.x),
+                    number(control2.y),
+                    number(end.x),
+                    number(end.y)
+                ),
+            });
+        }
+        d.push(String::from("Z"));
+    }
+    d.join("")
+}
+
+// SVG 颜色与不透明度，currentColor 使用文字颜色
+fn svg_color(color: &str, property: &str, opacity: &str) -> Result<String, String> {
+    let Some([r, g, b, a]) = parse_color(color)? else {
+        return Ok(format!(" {property}=\"currentColor\""));
+    };
+    let mut out = format!(" {property}=\"#{r:02x}{g:02x}{b:02x}\"");
+    if a < 255 {
+        out.push_str(&format!(" {opacity}=\"{}\"", number((a as f64 / 255.0 * 1000.0).round() / 1000.0)));
+    }
+    Ok(out)
+}
+
+fn svg_stops(stops: &[ColorStop], extend: Extend) -> Result<(String, String), String> {
+    if stops.is_empty() {
+        return Err(String::from("gradient needs at least one color stop"));
+    }
+    let spread = match extend {
+        Extend::Pad => "",
+        Extend::Repeat => " spreadMethod=\"repeat\"",
+        Extend::Reflect => " spreadMethod=\"reflect\"",
+    };
+    // SVG 要求色标偏移不递减
+    let mut stops: Vec<&ColorStop> = stops.iter().collect();
+    stops.sort_by(|a, b| a.offset.total_cmp(&b.offset));
+    let mut out = String::new();
+    for stop in stops {
+        let color = svg_color(&stop.color, "stop-color", "stop-opacity")?;
+        out.push_str(&format!("<stop offset=\"{}\"{color}/>", number(stop.offset)));
+    }
+    Ok((spread.to_string(), out))
+}
+
+// 渐变定义与引用它的填充属性，渐变坐标为图层（含变换）的字体单位
+fn svg_fill(fill: &Fill, id: &str) -> Result<(String, String), String> {
+    Ok(match fill {
+        Fill::Solid { color } => (String::new(), svg_color(color, "fill", "fill-opacity")?),
+        Fill::LinearGradient { start, end, rotation, stops, extend } => {
+            // COLR 的等色线平行于 start→rotation，SVG 的等色线垂直于渐变方向，终点投影到垂直方向上
+            let end = match rotation {
+                Some(rotation) => {
+                    let (nx, ny) = (start.y - rotation.y, rotation.x - start.x);
+                    let length = nx * nx + ny * ny;
+                    if length == 0.0 {
+                        *end
+                    } else {
+                        let t = ((end.x - start.x) * nx + (end.y - start.y) * ny) / length;
+                        Point { x: start.x + nx * t, y: start.y + ny * t }
+                    }
+                }
+                None => *end,
+            };
+            let (spread, stops) = svg_stops(stops, *extend)?;
+            let definition = format!(
+                "<linearGradient id=\"{id}\" gradientUnits=\"userSpaceOnUse\" x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\"{spread}>{stops}</linearGradient>",
+                number(start.x),
+                number(start.y),
+                number(end.x),
+                number(end.y)
+            );
+            (definition, format!(" fill=\"url(#{id})\""))
+        }
+        Fill::RadialGradient { start_center, start_radius, end_center, end_radius, stops, extend } => {
+            let (spread, stops) = svg_stops(stops, *extend)?;
+            let definition = format!(
+                "<radialGradient id=\"{id}\" gradientUnits=\"userSpaceOnUse\" cx=\"{}\" cy=\"{}\" r=\"{}\" fx=\"{}\" fy=\"{}\" fr=\"{}\"{spread}>{stops}</radialGradient>",
+                number(end_center.x),
+                number(end_center.y),
+                number(*end_radius),
+                number(start_center.x),
+                number(start_center.y),
+                number(*start_radius)
+            );
+            (definition, format!(" fill=\"url(#{id})\""))
+        }
+    })
+}
+
+// 一个彩色字形的 SVG 元素：SVG 的 y 轴向下，整体翻转后按字体单位绘制各图层
+fn svg_glyph(glyph_id: u16, layers: &[ColorLayer]) -> Result<String, String> {
+    let mut definitions = String::new();
+    let mut paths = String::new();
+    for (i, layer) in layers.iter().enumerate() {
+        let (definition, fill) = svg_fill(&layer.fill, &format!("glyph{glyph_id}.fill{i}"))?;
+        definitions.push_str(&definition);
+        let transform =
+            layer.transform.map_or_else(String::new, |m| format!(" transform=\"matrix({})\"", m.map(number).join(" ")));
+        paths.push_str(&format!("<path d=\"{}\"{fill}{transform}/>", svg_path(&layer.contours)));
+    }
+    let definitions = if definitions.is_empty() { definitions } else { format!("<defs>{definitions}</defs>") };
+    Ok(format!("<g id=\"glyph{glyph_id}\"><g transform=\"scale(1,-1)\">{definitions}{paths}</g></g>"))
+}
+
+fn svg_document(glyphs: &[(u16, String)]) -> String {
+    let content: String = glyphs.iter().map(|(_, element)| element.as_str()).collect();
+    format!("<svg xmlns=\"http://www.w3.org/2000/svg\" version=\"1.1\">{content}</svg>")
+}
+
+// 按字形 ID 排序生成 SVG 文档，合并时字形 ID 连续的字形放在同一个文档中
+fn svg_table(mut glyphs: Vec<(u16, String)>, options: &SvgOptions) -> Result<Vec<u8>, String> {
+    glyphs.sort_by_key(|(glyph_id, _)| *glyph_id);
+    let mut documents = Vec::new();
+    let mut start = 0;
+    for i in 1..=glyphs.len() {
+        let split = !options.group_ranges || i == glyphs.len() || glyphs[i].0 != glyphs[i - 1].0 + 1;
+        if split {
+            let group = &glyphs[start..i];
+            documents.push(SvgDocument { start: group[0].0, end: group[group.len() - 1].0, data: svg_document(group) });
+            start = i;
+        }
+    }
+    svg::build(&documents, options.compress)
+}
+
+// 生成彩色字体的各表：图层轮廓追加为不映射字符的字形，再写入 COLR 与 CPAL
+pub fn build_tables(data: &ColorFontData, options: &ExportOptions) -> Result<(u32, Vec<sfnt::Table>), String> {
+    if data.version > 1 {
+        return Err(format!("unsupported COLR version: {}", data.version));
+    }
+    let font = &data.font;
+    // 自动插入 .notdef 时，字形 ID 整体后移一位
+    let shift = font.glyphs_with_notdef().len() - font.glyphs.len();
+    let mut glyphs = font.glyphs.clone();
+    let mut palette = Palette::default();
+    let mut v0 = Vec::new();
+    let mut v1 = Vec::new();
+    let mut svg_glyphs = Vec::new();
+    let mut defined = HashSet::new();
+
+    for color_glyph in &data.color_glyphs {
+        let Some(base) = font.glyphs.get(color_glyph.glyph) else {
+            return Err(format!("color glyph refers to missing glyph {}", color_glyph.glyph));
+        };
+        let base_name = if base.name.is_empty() { format!("glyph{}", color_glyph.glyph) } else { base.name.clone() };
+        // COLR 中每个字形只能有一条记录；没有图层的彩色字形会显示为空白
+        if !defined.insert(color_glyph.glyph) {
+            return Err(format!("glyph {base_name:?} has more than one color definition"));
+        }
+        if color_glyph.layers.is_empty() {
+            return Err(format!("color glyph {base_name:?} has no layers"));
+        }
+        let mut layers = Vec::with_capacity(color_glyph.layers.len());
+        let mut solid_layers = Vec::with_capacity(color_glyph.layers.len());
+        for (i, layer) in color_glyph.layers.iter().enumerate() {
+            let glyph_id = glyphs.len() + shift;
+            if glyph_id > u16::MAX as usize {
+                return Err(format!("too many glyphs: {}", glyph_id + 1));
+            }
+            glyphs.push(GlyphData {
+                name: format!("{base_name}.color{i}"),
+                unicode: 0,
+                advance_width: base.advance_width,
+                contours: layer.contours.clone(),
+                components: Vec::new(),
+            });
+            let fill = palette.paint(&layer.fill).map_err(|err| format!("color glyph {base_name:?} layer {i}: {err}"))?;
+            if let (Paint::Solid { palette_index, .. }, None) = (&fill, layer.transform) {
+                solid_layers.push((glyph_id as u16, *palette_index));
+            }
+            let paint = Paint::Glyph { glyph_id: glyph_id as u16, paint: Box::new(fill) };
+            layers.push(match layer.transform {
+                Some(matrix) => Paint::Transform { matrix, paint: Box::new(paint) },
+                None => paint,
+            });
+        }
+
+        let glyph_id = (color_glyph.glyph + shift) as u16;
+        if data.svg.is_some() {
+            let element =
+                svg_glyph(glyph_id, &color_glyph.layers).map_err(|err| format!("color glyph {base_name:?}: {err}"))?;
+            svg_glyphs.push((glyph_id, element));
+        }
+        // 只有纯色且无变换的图层时同时写入 v0 记录，兼容只支持 COLRv0 的环境
+        if solid_layers.len() == layers.len() {
+            v0.push(LayerGlyph { glyph_id, layers: solid_layers });
+        } else if data.version == 0 {
+            return Err(format!("color glyph {base_name:?} uses gradients or transforms, which need COLR version 1"));
+        }
+        if data.version == 1 {
+            let paint = if layers.len() == 1 { layers.remove(0) } else { Paint::Layers(layers) };
+            v1.push((glyph_id, paint));
+        }
+    }
+
+    let expanded = FontData { glyphs, ..font.clone() };
+    let (version, mut tables) = crate::outline_tables(&expanded, options)?;
+    tables.push(sfnt::Table::new(b"COLR", colr::build(&v0, &v1)));
+    tables.push(sfnt::Table::new(b"CPAL", cpal::build(&palette.colors)));
+    if let Some(options) = &data.svg {
+        tables.push(sfnt::Table::new(b"SVG ", svg_table(svg_glyphs, options)?));
+    }
+    Ok((version, tables))
+}
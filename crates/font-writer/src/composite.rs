@@ -0,0 +1,176 @@
+use serde::{Deserialize, Serialize};
+
+use crate::outline::{place_contour, Contours, Matrix};
+use crate::tables::glyf::{Component, CompositeGlyph, GlyphPoint, SimpleGlyph};
+use crate::GlyphData;
+
+// 字形组件：glyph 为 font.glyphs 中的序号，transform 为 [a, b, c, d, e, f]，
+// 即 x' = a·x + c·y + e，y' = b·x + d·y + f，缺省时原样引用
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct GlyphComponent {
+    pub glyph: usize,
+    pub transform: Option<[f64; 6]>,
+}
+
+const IDENTITY: [f64; 6] = [1.0, 0.0, 0.0, 1.0, 0.0, 0.0];
+
+// F2Dot14 可表示的范围
+const F2DOT14_MIN: f64 = -2.0;
+const F2DOT14_MAX: f64 = 32767.0 / 16384.0;
+
+impl GlyphComponent {
+    fn matrix(&self) -> [f64; 6] {
+        self.transform.unwrap_or(IDENTITY)
+    }
+}
+
+// 按序号展开组件，glyphs 中第 shift 个之后才是工程中的字形（前面为自动插入的 .notdef）
+struct Resolver<'a> {
+    glyphs: &'a [GlyphData],
+    shift: usize,
+    visiting: Vec<bool>,
+    contours: Vec<Option<Contours>>,
+    truetype: Vec<Option<(SimpleGlyph, Option<CompositeGlyph>)>>,
+}
+
+impl<'a> Resolver<'a> {
+    fn new(glyphs: &'a [GlyphData], shift: usize) -> Self {
+        Resolver {
+            glyphs,
+            shift,
+            visiting: vec![false; glyphs.len()],
+            contours: vec![None; glyphs.len()],
+            truetype: vec![None; glyphs.len()],
+        }
+    }
+
+    fn component_index(&self, gid: usize, component: &GlyphComponent) -> Result<usize, String> {
+        let index = component.glyph + self.shift;
+        if index >= self.glyphs.len() {
+            return Err(format!(
+                "glyph {} refers to missing component glyph {}",
+                self.glyphs[gid].name, component.glyph
+            ));
+        }
+        if self.visiting[index] {
+            return Err(format!("glyph {} has recursive components", self.glyphs[gid].name));
+        }
+        Ok(index)
+    }
+
+    // 字形自身轮廓加上各组件变换后的轮廓
+    fn contours(&mut self, gid: usize) -> Result<Contours, String> {
+        if let Some(contours) = &self.contours[gid] {
+            return Ok(contours.clone());
+        }
+        let glyph = &self.glyphs[gid];
+        let mut contours = glyph.contours.clone();
+        self.visiting[gid] = true;
+        for component in &glyph.components {
+            let index = self.component_index(gid, component)?;
+            let matrix = Matrix::from(component.matrix());
+            contours.extend(self.contours(index)?.iter().map(|contour| place_contour(contour, &matrix)));
+        }
+        self.visiting[gid] = false;
+        self.contours[gid] = Some(contours.clone());
+        Ok(contours)
+    }
+
+    // 只由组件组成、且各组件变换都能用 F2Dot14 矩阵与整数偏移表示时写成复合字形
+    fn composite_components(&self, gid: usize) -> Option<Vec<([f64; 4], i16, i16)>> {
+        let glyph = &self.glyphs[gid];
+        if glyph.components.is_empty() || !glyph.contours.is_empty() {
+            return None;
+        }
+        glyph
+            .components
+            .iter()
+            .map(|component| {
+                let [a, b, c, d, e, f] = component.matrix();
+                let in_range = |v: f64| (F2DOT14_MIN..=F2DOT14_MAX).contains(&v);
+                let offset =
+                    |v: f64| (i16::MIN as f64..=i16::MAX as f64).contains(&v.round()).then_some(v.round() as i16);
+                let quantize = |v: f64| (v * 16384.0).round() / 16384.0;
+                let matrix = [a, b, c, d].map(quantize);
+                let determinant = matrix[0] * matrix[3] - matrix[1] * matrix[2];
+                if ![a, b, c, d].into_iter().all(in_range) || determinant <= 0.0 {
+                    return None;
+                }
+                Some((matrix, offset(e)?, offset(f)?))
+            })
+            .collect()
+    }
+
+    fn truetype(&mut self, gid: usize) -> Result<(SimpleGlyph, Option<CompositeGlyph>), String> {
+        if let Some(glyph) = &self.truetype[gid] {
+            return Ok(glyph.clone());
+        }
+        let glyph = match self.composite_components(gid) {
+            Some(placements) => {
+                let mut components = Vec::with_capacity(placements.len());
+                let mut points = Vec::new();
+                let mut depth = 0;
+                self.visiting[gid] = true;
+                for (component, (matrix, dx, dy)) in self.glyphs[gid].components.iter().zip(placements) {
+                    let index = self.component_index(gid, component)?;
+                    let (child, child_composite) = self.truetype(index)?;
+                    depth = depth.max(child_composite.map_or(0, |c| c.depth));
+                    let [a, b, c, d] = matrix;
+                    let round = |v: f64| v.round().clamp(i16::MIN as f64, i16::MAX as f64) as i16;
+                    points.extend(child.contours.iter().map(|contour| {
+                        contour
+                            .iter()
+                            .map(|p| {
+                                let (x, y) = (p.x as f64, p.y as f64);
+                                GlyphPoint {
+                                    x: round(a * x + c * y + dx as f64),
+                                    y: round(b * x + d * y + dy as f64),
+                                    on_curve: p.on_curve,
+                                }
+                            })
+                            .collect::<Vec<GlyphPoint>>()
+                    }));
+                    components.push(Component { glyph_id: index as u16, matrix, dx, dy });
+                }
+                self.visiting[gid] = false;
+                // 组件都是空字形时按空的简单字形输出
+                let composite = (!points.is_empty()).then_some(CompositeGlyph { components, depth: depth + 1 });
+                (SimpleGlyph::from_points(points), composite)
+            }
+            None => (SimpleGlyph::from_contours(&self.contours(gid)?), None),
+        };
+        self.truetype[gid] = Some(glyph.clone());
+        Ok(glyph)
+    }
+}
+
+// 把组件展开为普通轮廓，用于 CFF 轮廓、可变字体与去除重叠
+pub fn flatten(glyphs: &[GlyphData], shift: usize) -> Result<Vec<GlyphData>, String> {
+    if glyphs.iter().all(|g| g.components.is_empty()) {
+        return Ok(glyphs.to_vec());
+    }
+    let mut resolver = Resolver::new(glyphs, shift);
+    (0..glyphs.len())
+        .map(|gid| {
+            let contours = resolver.contours(gid)?;
+            Ok(GlyphData { contours, components: Vec::new(), ..glyphs[gid].clone() })
+        })
+        .collect()
+}
+
+// TrueType 轮廓：能用复合字形表示的字形返回组件引用，simple_glyphs 中对应项为展开后的点，用于度量与 maxp 统计
+pub fn truetype_glyphs(
+    glyphs: &[GlyphData],
+    shift: usize,
+) -> Result<(Vec<SimpleGlyph>, Vec<Option<CompositeGlyph>>), String> {
+    let mut resolver = Resolver::new(glyphs, shift);
+    let mut simple_glyphs = Vec::with_capacity(glyphs.len());
+    let mut composites = Vec::with_capacity(glyphs.len());
+    for gid in 0..glyphs.len() {
+        let (simple, composite) = resolver.truetype(gid)?;
+        simple_glyphs.push(simple);
+        composites.push(composite);
+    }
+    Ok((simple_glyphs, composites))
+}
@@ -0,0 +1,217 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+use crate::ufo;
+use crate::variable::{self, Axis, VariableFontData};
+use crate::xml::{escape, number};
+use crate::{FontData, GlyphData};
+
+// 用户坐标到设计坐标的映射：工程中的 map 加上轴的两端与默认值（与 avar 的补全方式一致）
+fn design_map(axis: &Axis) -> Vec<(f64, f64)> {
+    let mut map: Vec<(f64, f64)> = axis.map.iter().map(|&[input, output]| (input, output)).collect();
+    map.extend([axis.min_value, axis.default_value, axis.max_value].map(|v| (v, v)));
+    map.sort_by(|a, b| a.0.total_cmp(&b.0));
+    map.dedup_by(|a, b| a.0 == b.0);
+    map
+}
+
+fn axis_name(axis: &Axis) -> &str {
+    if axis.name.is_empty() {
+        &axis.tag
+    } else {
+        &axis.name
+    }
+}
+
+// designspace 中源与实例的位置使用设计坐标，未给出的轴取默认值
+fn write_location(xml: &mut String, axes: &[Axis], location: &BTreeMap<String, f64>, indent: &str) {
+    xml.push_str(&format!("{indent}<location>\n"));
+    for axis in axes {
+        let value = location.get(&axis.tag).copied().unwrap_or(axis.default_value);
+        let design = variable::piecewise(&design_map(axis), value);
+        xml.push_str(&format!(
+            "{indent}  <dimension name=\"{}\" xvalue=\"{}\"/>\n",
+            escape(axis_name(axis)),
+            number(design)
+        ));
+    }
+    xml.push_str(&format!("{indent}</location>\n"));
+}
+
+fn write_axis(xml: &mut String, axis: &Axis) {
+    xml.push_str(&format!(
+        "    <axis tag=\"{}\" name=\"{}\" minimum=\"{}\" maximum=\"{}\" default=\"{}\"",
+        escape(&axis.tag),
+        escape(axis_name(axis)),
+        number(axis.min_value),
+        number(axis.max_value),
+        number(axis.default_value)
+    ));
+    if axis.hidden {
+        xml.push_str(" hidden=\"1\"");
+    }
+    if axis.map.is_empty() && axis.labels.is_empty() {
+        xml.push_str("/>\n");
+        return;
+    }
+    xml.push_str(">\n");
+    if !axis.map.is_empty() {
+        for (input, output) in design_map(axis) {
+            xml.push_str(&format!("      <map input=\"{}\" output=\"{}\"/>\n", number(input), number(output)));
+        }
+    }
+    // STAT 取值名，默认值上的名称可省略（与原生导出的 STAT 一致）
+    if !axis.labels.is_empty() {
+        xml.push_str("      <labels>\n");
+        for label in &axis.labels {
+            xml.push_str(&format!(
+                "        <label uservalue=\"{}\" name=\"{}\"",
+                number(label.value),
+                escape(&label.name)
+            ));
+            if label.value == axis.default_value {
+                xml.push_str(" elidable=\"true\"");
+            }
+            xml.push_str("/>\n");
+        }
+        xml.push_str("      </labels>\n");
+    }
+    xml.push_str("    </axis>\n");
+}
+
+// 文件名只保留字母、数字与 - _ .
+fn file_stem(name: &str) -> String {
+    name.chars().filter(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.')).collect()
+}
+
+fn unique_file_name(stem: &str, used: &mut HashSet<String>) -> String {
+    let mut name = format!("{stem}.ufo");
+    let mut n = 1;
+    while !used.insert(name.to_lowercase()) {
+        name = format!("{stem}-{n}.ufo");
+        n += 1;
+    }
+    name
+}
+
+// 母版的样式名：与某个实例位置相同时使用实例名，否则按轴坐标命名
+fn master_style(data: &VariableFontData, location: &BTreeMap<String, f64>) -> String {
+    let value =
+        |axis: &Axis, location: &BTreeMap<String, f64>| location.get(&axis.tag).copied().unwrap_or(axis.default_value);
+    let instance = data
+        .instances
+        .iter()
+        .find(|instance| data.axes.iter().all(|axis| value(axis, &instance.coordinates) == value(axis, location)));
+    match instance {
+        Some(instance) if !instance.name.is_empty() => instance.name.clone(),
+        _ => data
+            .axes
+            .iter()
+            .filter(|axis| value(axis, location) != axis.default_value)
+            .map(|axis| format!("{}{}", axis.tag.trim(), number(value(axis, location))))
+            .collect::<Vec<_>>()
+            .join(" "),
+    }
+}
+
+// 各母版的字形名与码位取自默认母版，保证各 UFO 的字形一一对应
+fn master_font(data: &VariableFontData, glyphs: &[GlyphData], style: &str) -> FontData {
+    let mut font = data.font.clone();
+    font.info.style_name = style.to_string();
+    font.glyphs = data
+        .font
+        .glyphs
+        .iter()
+        .zip(glyphs)
+        .map(|(default, glyph)| GlyphData { name: default.name.clone(), unicode: default.unicode, ..glyph.clone() })
+        .collect();
+    font
+}
+
+// 多母版工程导出为 designspace 5.0 文档，并在同一目录下为每个母版写出一个 UFO，供 fontmake 等工具构建；
+// 所有母版与文档都写好后才替换已有的文件，中途出错时原有的导出保持不变
+pub fn write(data: &VariableFontData, path: &Path) -> Result<(), String> {
+    variable::validate(data)?;
+    let mut names = HashSet::new();
+    for axis in &data.axes {
+        if !names.insert(axis_name(axis)) {
+            return Err(format!("duplicate axis name: {}", axis_name(axis)));
+        }
+    }
+    let directory = path.parent().unwrap_or(Path::new("."));
+    let stem = path.file_stem().map(|s| file_stem(&s.to_string_lossy())).unwrap_or_default();
+    let info = &data.font.info;
+    let family = if info.family_name.is_empty() { "Untitled" } else { info.family_name.as_str() };
+    let family_stem = if stem.is_empty() { file_stem(family) } else { stem };
+
+    // 默认母版即工程本身，位于各轴的默认值
+    let default_style = if info.style_name.is_empty() { "Regular" } else { info.style_name.as_str() };
+    let mut sources = vec![(BTreeMap::new(), default_style.to_string(), &data.font.glyphs)];
+    for master in &data.masters {
+        sources.push((master.location.clone(), master_style(data, &master.location), &master.glyphs));
+    }
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<designspace format=\"5.0\">\n  <axes>\n");
+    for axis in &data.axes {
+        write_axis(&mut xml, axis);
+    }
+    xml.push_str("  </axes>\n  <sources>\n");
+    let mut used = HashSet::new();
+    let mut staged = Vec::with_capacity(sources.len());
+    for (i, (location, style, glyphs)) in sources.iter().enumerate() {
+        let style_stem = file_stem(style);
+        let style_stem = if style_stem.is_empty() { format!("Master{i}") } else { style_stem };
+        let file_name = unique_file_name(&format!("{family_stem}-{style_stem}"), &mut used);
+        staged.push(ufo::stage(&master_font(data, glyphs, style), &directory.join(&file_name))?);
+        xml.push_str(&format!(
+            "    <source filename=\"{}\" name=\"{} {}\" familyname=\"{}\" stylename=\"{}\">\n",
+            escape(&file_name),
+            escape(family),
+            escape(style),
+            escape(family),
+            escape(style)
+        ));
+        write_location(&mut xml, &data.axes, location, "      ");
+        xml.push_str("    </source>\n");
+    }
+    xml.push_str("  </sources>\n");
+
+    if !data.instances.is_empty() {
+        xml.push_str("  <instances>\n");
+        let mut used = HashSet::new();
+        for instance in &data.instances {
+            xml.push_str(&format!(
+                "    <instance name=\"{} {}\" familyname=\"{}\" stylename=\"{}\"",
+                escape(family),
+                escape(&instance.name),
+                escape(family),
+                escape(&instance.name)
+            ));
+            let post_script_name = if instance.post_script_name.is_empty() {
+                format!("{}-{}", file_stem(family), file_stem(&instance.name))
+            } else {
+                instance.post_script_name.clone()
+            };
+            if !instance.post_script_name.is_empty() {
+                xml.push_str(&format!(" postscriptfontname=\"{}\"", escape(&post_script_name)));
+            }
+            let file_name = unique_file_name(&format!("instances/{}", file_stem(&post_script_name)), &mut used);
+            xml.push_str(&format!(" filename=\"{}\">\n", escape(&file_name)));
+            write_location(&mut xml, &data.axes, &instance.coordinates, "      ");
+            xml.push_str("    </instance>\n");
+        }
+        xml.push_str("  </instances>\n");
+    }
+    xml.push_str("</designspace>\n");
+    let name = path.file_name().ok_or_else(|| format!("invalid designspace path {}", path.display()))?;
+    let temp = path.with_file_name(format!(".{}.tmp", name.to_string_lossy()));
+    fs::write(&temp, xml).map_err(|err| format!("failed to write {}: {err}", temp.display()))?;
+    for master in &staged {
+        if let Err(err) = master.commit() {
+            let _ = fs::remove_file(&temp);
+            return Err(err);
+        }
+    }
+    fs::rename(&temp, path).map_err(|err| format!("failed to write {}: {err}", path.display()))
+}
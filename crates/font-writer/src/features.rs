@@ -0,0 +1,382 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::tables::layout::context::{ChainContext, SequenceLookup};
+use crate::tables::layout::gsub::{Gsub, Substitution};
+use crate::tables::layout::{Feature, LangSys, Lookup, Script};
+use crate::FontData;
+
+// 未声明语言系统时的默认值
+const DEFAULT_LANGUAGE_SYSTEMS: [(&str, &str); 2] = [("DFLT", "dflt"), ("latn", "dflt")];
+
+pub type LanguageSystemTag = ([u8; 4], [u8; 4]);
+
+// 特性标签与查找序号
+type FeatureRecord = ([u8; 4], Vec<u16>);
+
+// 对应 .fea 中的 languagesystem，language 为 dflt 时写为脚本的默认语言系统
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct LanguageSystem {
+    pub script: String,
+    pub language: String,
+}
+
+// 具名字形类别（.fea 中的 @class），glyphs 为 font.glyphs 中的序号
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct GlyphClass {
+    pub name: String,
+    pub glyphs: Vec<usize>,
+}
+
+// 规则中的一个位置：单个字形，或 classes 中的类别序号
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum GlyphRef {
+    Glyph { glyph: usize },
+    Class { class: usize },
+}
+
+// 上下文规则中的输入位置，lookup 为匹配后在该位置应用的查找序号
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChainInput {
+    pub glyphs: GlyphRef,
+    #[serde(default)]
+    pub lookup: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum SubstitutionRule {
+    // sub a by b; 类别替换为等长的类别时按位置一一对应，也可以整个类别替换为同一个字形
+    Single {
+        input: GlyphRef,
+        replacement: GlyphRef,
+    },
+    // sub f_i by f i;
+    Multiple {
+        input: usize,
+        replacement: Vec<usize>,
+    },
+    // sub f i by f_i; 组件为类别时展开为所有组合
+    Ligature {
+        components: Vec<GlyphRef>,
+        ligature: usize,
+    },
+    // sub x a' lookup L b; backtrack 按书写顺序排列，输入位置都不指定查找时相当于 ignore sub
+    Chain {
+        #[serde(default)]
+        backtrack: Vec<GlyphRef>,
+        input: Vec<ChainInput>,
+        #[serde(default)]
+        lookahead: Vec<GlyphRef>,
+    },
+}
+
+// 一个查找中的规则类型必须相同，按顺序匹配
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SubstitutionLookup {
+    pub name: String,
+    pub rules: Vec<SubstitutionRule>,
+}
+
+// 只在指定脚本与语言下追加的查找，language 为 dflt 时对该脚本的所有语言生效
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct FeatureLanguage {
+    pub script: String,
+    pub language: String,
+    pub lookups: Vec<usize>,
+}
+
+// 特性（liga、calt、locl、ss01 等），lookups 对所有语言系统生效
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct FeatureDefinition {
+    pub tag: String,
+    pub lookups: Vec<usize>,
+    pub languages: Vec<FeatureLanguage>,
+}
+
+// 字形替换特性，相当于 .fea 的一个子集：查找按 lookups 中的顺序写入 GSUB
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Features {
+    pub language_systems: Vec<LanguageSystem>,
+    pub classes: Vec<GlyphClass>,
+    pub lookups: Vec<SubstitutionLookup>,
+    pub features: Vec<FeatureDefinition>,
+}
+
+// 脚本、语言与特性标签：1 到 4 个可打印 ASCII 字符，不足时补空格
+pub fn tag(value: &str) -> Result<[u8; 4], String> {
+    if value.is_empty() || value.len() > 4 || !value.bytes().all(|b| (0x20..=0x7E).contains(&b)) {
+        return Err(format!("invalid OpenType tag: {value:?}"));
+    }
+    let mut tag = [b' '; 4];
+    tag[..value.len()].copy_from_slice(value.as_bytes());
+    Ok(tag)
+}
+
+// 声明的语言系统，去掉重复项，未声明时使用默认值
+pub fn language_systems(features: &Features) -> Result<Vec<LanguageSystemTag>, String> {
+    let mut systems = Vec::new();
+    let declared: Vec<(&str, &str)> = if features.language_systems.is_empty() {
+        DEFAULT_LANGUAGE_SYSTEMS.to_vec()
+    } else {
+        features.language_systems.iter().map(|s| (s.script.as_str(), s.language.as_str())).collect()
+    };
+    for (script, language) in declared {
+        let system = (tag(script)?, tag(language)?);
+        if !systems.contains(&system) {
+            systems.push(system);
+        }
+    }
+    Ok(systems)
+}
+
+// 按语言系统生成脚本列表，dflt 语言写为默认语言系统
+pub fn scripts(systems: &[LanguageSystemTag], mut lang_sys: impl FnMut(&LanguageSystemTag) -> LangSys) -> Vec<Script> {
+    let mut scripts: BTreeMap<[u8; 4], Script> = BTreeMap::new();
+    for system in systems {
+        let (script, language) = *system;
+        let entry =
+            scripts.entry(script).or_insert(Script { tag: script, default_lang_sys: None, lang_systems: Vec::new() });
+        if &language == b"dflt" {
+            entry.default_lang_sys = Some(lang_sys(system));
+        } else {
+            entry.lang_systems.push((language, lang_sys(system)));
+        }
+    }
+    scripts.into_values().collect()
+}
+
+struct Compiler<'a> {
+    font: &'a FontData,
+    shift: usize,
+}
+
+impl Compiler<'_> {
+    fn glyph(&self, index: usize) -> Result<u16, String> {
+        if index >= self.font.glyphs.len() {
+            return Err(format!("refers to missing glyph {index}"));
+        }
+        Ok((index + self.shift) as u16)
+    }
+
+    fn glyphs(&self, glyphs: &GlyphRef) -> Result<Vec<u16>, String> {
+        match *glyphs {
+            GlyphRef::Glyph { glyph } => Ok(vec![self.glyph(glyph)?]),
+            GlyphRef::Class { class } => {
+                let class =
+                    self.font.features.classes.get(class).ok_or_else(|| format!("refers to missing class {class}"))?;
+                if class.glyphs.is_empty() {
+                    return Err(format!("class {:?} is empty", class.name));
+                }
+                class
+                    .glyphs
+                    .iter()
+                    .map(|&g| self.glyph(g).map_err(|err| format!("class {:?} {err}", class.name)))
+                    .collect()
+            }
+        }
+    }
+
+    // Coverage 需要有序且不重复
+    fn coverage(&self, glyphs: &GlyphRef) -> Result<Vec<u16>, String> {
+        Ok(self.glyphs(glyphs)?.into_iter().collect::<BTreeSet<u16>>().into_iter().collect())
+    }
+
+    fn single(&self, rules: &[SubstitutionRule]) -> Result<Vec<Substitution>, String> {
+        let mut map = BTreeMap::new();
+        for rule in rules {
+            let SubstitutionRule::Single { input, replacement } = rule else { unreachable!() };
+            let (input, replacement) = (self.glyphs(input)?, self.glyphs(replacement)?);
+            let pairs: Vec<(u16, u16)> = match replacement.len() {
+                1 => input.iter().map(|&g| (g, replacement[0])).collect(),
+                n if n == input.len() => input.into_iter().zip(replacement).collect(),
+                n => return Err(format!("cannot substitute {} glyphs by {n} glyphs", input.len())),
+            };
+            for (from, to) in pairs {
+                if map.insert(from, to).is_some_and(|old| old != to) {
+                    return Err(format!("glyph {} is substituted twice", from as usize - self.shift));
+                }
+            }
+        }
+        Ok(vec![Substitution::Single(map)])
+    }
+
+    fn multiple(&self, rules: &[SubstitutionRule]) -> Result<Vec<Substitution>, String> {
+        let mut map = BTreeMap::new();
+        for rule in rules {
+            let SubstitutionRule::Multiple { input, replacement } = rule else { unreachable!() };
+            if replacement.is_empty() {
+                return Err(format!("glyph {input} is substituted by an empty sequence"));
+            }
+            let sequence = replacement.iter().map(|&g| self.glyph(g)).collect::<Result<Vec<u16>, String>>()?;
+            if map.insert(self.glyph(*input)?, sequence).is_some() {
+                return Err(format!("glyph {input} is substituted twice"));
+            }
+        }
+        Ok(vec![Substitution::Multiple(map)])
+    }
+
+    fn ligature(&self, rules: &[SubstitutionRule]) -> Result<Vec<Substitution>, String> {
+        let mut map: BTreeMap<u16, Vec<(Vec<u16>, u16)>> = BTreeMap::new();
+        for rule in rules {
+            let SubstitutionRule::Ligature { components, ligature } = rule else { unreachable!() };
+            if components.is_empty() {
+                return Err(String::from("ligature needs at least one component"));
+            }
+            let ligature = self.glyph(*ligature)?;
+            let mut sequences: Vec<Vec<u16>> = vec![Vec::new()];
+            for component in components {
+                let glyphs = self.glyphs(component)?;
+                sequences = sequences
+                    .iter()
+                    .flat_map(|sequence| {
+                        glyphs.iter().map(move |&g| sequence.iter().copied().chain([g]).collect::<Vec<u16>>())
+                    })
+                    .collect();
+            }
+            for sequence in sequences {
+                let entries = map.entry(sequence[0]).or_default();
+                let rest = sequence[1..].to_vec();
+                // 相同的组件序列只保留先定义的连字
+                if !entries.iter().any(|(components, _)| *components == rest) {
+                    entries.push((rest, ligature));
+                }
+            }
+        }
+        // 组件较多的连字优先匹配
+        for entries in map.values_mut() {
+            entries.sort_by_key(|(components, _)| std::cmp::Reverse(components.len()));
+        }
+        Ok(vec![Substitution::Ligature(map)])
+    }
+
+    // 每条规则写为一个格式 3 子表，按规则顺序匹配
+    fn chain(&self, rules: &[SubstitutionRule]) -> Result<Vec<Substitution>, String> {
+        let lookup_count = self.font.features.lookups.len();
+        rules
+            .iter()
+            .map(|rule| {
+                let SubstitutionRule::Chain { backtrack, input, lookahead } = rule else { unreachable!() };
+                if input.is_empty() {
+                    return Err(String::from("chained rule needs at least one input glyph"));
+                }
+                let mut lookups = Vec::new();
+                for (i, position) in input.iter().enumerate() {
+                    if let Some(lookup) = position.lookup {
+                        if lookup >= lookup_count {
+                            return Err(format!("chained rule refers to missing lookup {lookup}"));
+                        }
+                        lookups.push(SequenceLookup { sequence_index: i as u16, lookup_index: lookup as u16 });
+                    }
+                }
+                Ok(Substitution::Context(ChainContext::Coverages {
+                    backtrack: backtrack.iter().rev().map(|g| self.coverage(g)).collect::<Result<_, String>>()?,
+                    input: input.iter().map(|p| self.coverage(&p.glyphs)).collect::<Result<_, String>>()?,
+                    lookahead: lookahead.iter().map(|g| self.coverage(g)).collect::<Result<_, String>>()?,
+                    lookups,
+                }))
+            })
+            .collect()
+    }
+
+    fn lookup(&self, lookup: &SubstitutionLookup) -> Result<Lookup<Substitution>, String> {
+        let Some(first) = lookup.rules.first() else {
+            return Err(String::from("lookup has no rules"));
+        };
+        if lookup.rules.iter().any(|rule| std::mem::discriminant(rule) != std::mem::discriminant(first)) {
+            return Err(String::from("lookup mixes different rule types"));
+        }
+        let subtables = match first {
+            SubstitutionRule::Single { .. } => self.single(&lookup.rules)?,
+            SubstitutionRule::Multiple { .. } => self.multiple(&lookup.rules)?,
+            SubstitutionRule::Ligature { .. } => self.ligature(&lookup.rules)?,
+            SubstitutionRule::Chain { .. } => self.chain(&lookup.rules)?,
+        };
+        Ok(Lookup { flag: 0, mark_filtering_set: None, subtables })
+    }
+}
+
+// 各语言系统下每个特性使用的查找，相同标签与查找的特性记录共用
+fn feature_lookups(
+    features: &Features,
+    systems: &[LanguageSystemTag],
+) -> Result<BTreeMap<LanguageSystemTag, Vec<FeatureRecord>>, String> {
+    let lookup_index = |index: usize, feature: &str| {
+        if index >= features.lookups.len() {
+            return Err(format!("feature {feature:?} refers to missing lookup {index}"));
+        }
+        Ok(index as u16)
+    };
+    let mut result: BTreeMap<LanguageSystemTag, Vec<FeatureRecord>> = BTreeMap::new();
+    for feature in &features.features {
+        let feature_tag = tag(&feature.tag)?;
+        let mut languages = Vec::with_capacity(feature.languages.len());
+        for language in &feature.languages {
+            let system = (tag(&language.script)?, tag(&language.language)?);
+            let declared = systems.iter().any(|s| s.0 == system.0 && (s.1 == system.1 || &system.1 == b"dflt"));
+            if !declared {
+                return Err(format!(
+                    "feature {:?} uses undeclared language system {} {}",
+                    feature.tag, language.script, language.language
+                ));
+            }
+            languages.push((system, language));
+        }
+        for system in systems {
+            let mut lookups = BTreeSet::new();
+            for &index in &feature.lookups {
+                lookups.insert(lookup_index(index, &feature.tag)?);
+            }
+            for (target, language) in &languages {
+                if target.0 == system.0 && (target.1 == system.1 || &target.1 == b"dflt") {
+                    for &index in &language.lookups {
+                        lookups.insert(lookup_index(index, &feature.tag)?);
+                    }
+                }
+            }
+            if !lookups.is_empty() {
+                result.entry(*system).or_default().push((feature_tag, lookups.into_iter().collect()));
+            }
+        }
+    }
+    Ok(result)
+}
+
+// 按工程的特性定义生成 GSUB，没有特性时返回 None
+pub fn gsub(font: &FontData) -> Result<Option<Gsub>, String> {
+    let features = &font.features;
+    if features.features.is_empty() {
+        return Ok(None);
+    }
+    let compiler = Compiler { font, shift: font.notdef_shift() };
+    let lookups = features
+        .lookups
+        .iter()
+        .enumerate()
+        .map(|(i, lookup)| compiler.lookup(lookup).map_err(|err| format!("lookup {i} ({:?}): {err}", lookup.name)))
+        .collect::<Result<Vec<_>, String>>()?;
+
+    let systems = language_systems(features)?;
+    let by_system = feature_lookups(features, &systems)?;
+    // 特性列表按标签排序
+    let records: BTreeSet<FeatureRecord> = by_system.values().flatten().cloned().collect();
+    let indices: BTreeMap<&FeatureRecord, u16> = records.iter().enumerate().map(|(i, r)| (r, i as u16)).collect();
+    let scripts = scripts(&systems, |system| {
+        let mut features: Vec<u16> =
+            by_system.get(system).into_iter().flatten().map(|record| indices[record]).collect();
+        features.sort_unstable();
+        LangSys { required_feature: None, features }
+    });
+    let features =
+        records.iter().map(|(tag, lookups)| Feature { tag: *tag, params: None, lookups: lookups.clone() }).collect();
+    Ok(Some(Gsub { scripts, features, lookups }))
+}
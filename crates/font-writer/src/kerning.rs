@@ -0,0 +1,425 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use serde::{Deserialize, Serialize};
+
+use crate::binary::Reader;
+use crate::sfnt::Sfnt;
+use crate::tables::cmap;
+use crate::tables::layout::gpos::{Gpos, Positioning, ValueRecord};
+use crate::tables::layout::{Feature, LangSys, Lookup};
+use crate::{features, woff, FontData};
+
+// 单个 PairPos 子表的大致上限，超过时拆分为多个子表，避免 Offset16 溢出
+const MAX_SUBTABLE_SIZE: usize = 60000;
+
+// 字偶距分组：glyphs 为 font.glyphs 中的序号，同一字形在同一侧只能属于一个分组
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct KerningClass {
+    pub name: String,
+    pub glyphs: Vec<usize>,
+}
+
+// 字偶距的一侧：单个字形，或 leftClasses/rightClasses 中的分组序号
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum KerningSide {
+    Glyph { glyph: usize },
+    Class { class: usize },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KerningPair {
+    pub left: KerningSide,
+    pub right: KerningSide,
+    pub value: i16,
+}
+
+// 字偶距：优先级为 字形-字形 > 字形-分组 > 分组-字形 > 分组-分组
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Kerning {
+    pub left_classes: Vec<KerningClass>,
+    pub right_classes: Vec<KerningClass>,
+    pub pairs: Vec<KerningPair>,
+}
+
+impl Kerning {
+    pub fn is_empty(&self) -> bool {
+        self.pairs.is_empty()
+    }
+}
+
+// 分组转换为字形 ID 列表，检查序号是否有效以及字形是否重复归类
+fn class_glyphs(classes: &[KerningClass], side: &str, font: &FontData, shift: usize) -> Result<Vec<Vec<u16>>, String> {
+    let mut assigned: HashMap<usize, &str> = HashMap::new();
+    classes
+        .iter()
+        .map(|class| {
+            class
+                .glyphs
+                .iter()
+                .map(|&index| {
+                    if index >= font.glyphs.len() {
+                        return Err(format!("{side} kerning class {:?} refers to missing glyph {index}", class.name));
+                    }
+                    if let Some(other) = assigned.insert(index, &class.name) {
+                        return Err(format!(
+                            "glyph {index} belongs to both {side} kerning classes {other:?} and {:?}",
+                            class.name
+                        ));
+                    }
+                    Ok((index + shift) as u16)
+                })
+                .collect()
+        })
+        .collect()
+}
+
+// 字形对按第一个字形拆分为多个格式 1 子表
+fn pair_subtables(pairs: &BTreeMap<u16, BTreeMap<u16, i16>>) -> Vec<Positioning> {
+    let mut subtables = Vec::new();
+    let mut current: BTreeMap<u16, BTreeMap<u16, (ValueRecord, ValueRecord)>> = BTreeMap::new();
+    let mut size = 0;
+    for (&first, seconds) in pairs {
+        // PairSet、覆盖表与偏移量
+        let set_size = 2 + 4 * seconds.len() + 4;
+        if !current.is_empty() && size + set_size > MAX_SUBTABLE_SIZE {
+            subtables.push(Positioning::Pair(std::mem::take(&mut current)));
+            size = 0;
+        }
+        let set = seconds
+            .iter()
+            .map(|(&second, &value)| {
+                (second, (ValueRecord { x_advance: value, ..Default::default() }, ValueRecord::default()))
+            })
+            .collect();
+        current.insert(first, set);
+        size += set_size;
+    }
+    if !current.is_empty() {
+        subtables.push(Positioning::Pair(current));
+    }
+    subtables
+}
+
+// 分组对按第一侧的分组拆分为多个格式 2 子表，第二侧的分组定义在各子表中共用
+fn class_subtables(left: &[Vec<u16>], right: &[Vec<u16>], values: &BTreeMap<(usize, usize), i16>) -> Vec<Positioning> {
+    let used_left: Vec<usize> = values.keys().map(|&(l, _)| l).collect::<BTreeSet<_>>().into_iter().collect();
+    let used_right: Vec<usize> = values.keys().map(|&(_, r)| r).collect::<BTreeSet<_>>().into_iter().collect();
+    let right_class: HashMap<usize, u16> = used_right.iter().enumerate().map(|(i, &r)| (r, i as u16 + 1)).collect();
+    let classes2: BTreeMap<u16, u16> =
+        used_right.iter().flat_map(|&r| right[r].iter().map(|&g| (g, right_class[&r])).collect::<Vec<_>>()).collect();
+    let columns = used_right.len() + 1;
+    let rows_per_subtable = (MAX_SUBTABLE_SIZE / (2 * columns)).max(1);
+
+    used_left
+        .chunks(rows_per_subtable)
+        .map(|chunk| {
+            let mut classes1 = BTreeMap::new();
+            let mut rows = vec![vec![(ValueRecord::default(), ValueRecord::default()); columns]];
+            for (i, &l) in chunk.iter().enumerate() {
+                let mut row = vec![(ValueRecord::default(), ValueRecord::default()); columns];
+                for (&r, &class) in &right_class {
+                    if let Some(&value) = values.get(&(l, r)) {
+                        row[class as usize].0.x_advance = value;
+                    }
+                }
+                rows.push(row);
+                classes1.extend(left[l].iter().map(|&g| (g, i as u16 + 1)));
+            }
+            let coverage: Vec<u16> = classes1.keys().copied().collect();
+            Positioning::PairClass { coverage, classes1, classes2: classes2.clone(), values: rows }
+        })
+        .collect()
+}
+
+// 生成字偶距查找：字形对与展开后的字形-分组对写为格式 1，分组对写为格式 2 并放在后面，
+// 这样格式 1 中的例外优先匹配
+pub fn lookup(font: &FontData) -> Result<Option<Lookup<Positioning>>, String> {
+    let kerning = &font.kerning;
+    if kerning.is_empty() {
+        return Ok(None);
+    }
+    let shift = font.notdef_shift();
+    let left = class_glyphs(&kerning.left_classes, "left", font, shift)?;
+    let right = class_glyphs(&kerning.right_classes, "right", font, shift)?;
+    let glyph = |index: usize| {
+        if index >= font.glyphs.len() {
+            return Err(format!("kerning pair refers to missing glyph {index}"));
+        }
+        Ok((index + shift) as u16)
+    };
+    let class = |classes: &[Vec<u16>], index: usize, side: &str| {
+        classes.get(index).cloned().ok_or_else(|| format!("kerning pair refers to missing {side} class {index}"))
+    };
+
+    // 按优先级从低到高写入，后写入的覆盖先写入的
+    let mut pairs: BTreeMap<u16, BTreeMap<u16, i16>> = BTreeMap::new();
+    let mut class_pairs: BTreeMap<(usize, usize), i16> = BTreeMap::new();
+    for rank in 0..3 {
+        for pair in &kerning.pairs {
+            match (pair.left, pair.right, rank) {
+                (KerningSide::Class { class: l }, KerningSide::Class { class: r }, 0) => {
+                    class(&left, l, "left")?;
+                    class(&right, r, "right")?;
+                    class_pairs.insert((l, r), pair.value);
+                }
+                (KerningSide::Class { class: l }, KerningSide::Glyph { glyph: g }, 0) => {
+                    let second = glyph(g)?;
+                    for first in class(&left, l, "left")? {
+                        pairs.entry(first).or_default().insert(second, pair.value);
+                    }
+                }
+                (KerningSide::Glyph { glyph: g }, KerningSide::Class { class: r }, 1) => {
+                    let first = glyph(g)?;
+                    for second in class(&right, r, "right")? {
+                        pairs.entry(first).or_default().insert(second, pair.value);
+                    }
+                }
+                (KerningSide::Glyph { glyph: a }, KerningSide::Glyph { glyph: b }, 2) => {
+                    pairs.entry(glyph(a)?).or_default().insert(glyph(b)?, pair.value);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let mut subtables = pair_subtables(&pairs);
+    subtables.extend(class_subtables(&left, &right, &class_pairs));
+    Ok(Some(Lookup { flag: 0, mark_filtering_set: None, subtables }))
+}
+
+// 只包含 kern 特性的 GPOS，注册在特性定义声明的所有语言系统下
+pub fn gpos(font: &FontData) -> Result<Option<Gpos>, String> {
+    let Some(lookup) = lookup(font)? else {
+        return Ok(None);
+    };
+    let systems = features::language_systems(&font.features)?;
+    let scripts = features::scripts(&systems, |_| LangSys { required_feature: None, features: vec![0] });
+    Ok(Some(Gpos {
+        scripts,
+        features: vec![Feature { tag: *b"kern", params: None, lookups: vec![0] }],
+        lookups: vec![lookup],
+    }))
+}
+
+// 从字体中读取的字偶距，字形为字体中的字形 ID
+#[derive(Default)]
+struct FontKerning {
+    pairs: BTreeMap<(u16, u16), i16>,
+    left_classes: Vec<Vec<u16>>,
+    right_classes: Vec<Vec<u16>>,
+    class_pairs: BTreeMap<(usize, usize), i16>,
+}
+
+impl FontKerning {
+    // 同一查找中先出现的子表优先；格式 2 子表覆盖的第一个字形不再匹配后面的子表
+    fn read_gpos(&mut self, gpos: &Gpos) {
+        let lookups: BTreeSet<u16> =
+            gpos.features.iter().filter(|f| &f.tag == b"kern").flat_map(|f| f.lookups.iter().copied()).collect();
+        let mut left_assigned = BTreeSet::new();
+        let mut right_assigned: HashMap<Vec<u16>, usize> = HashMap::new();
+        for lookup in lookups.iter().filter_map(|&i| gpos.lookups.get(i as usize)) {
+            let mut claimed_pairs = BTreeSet::new();
+            let mut claimed_firsts = BTreeSet::new();
+            for subtable in &lookup.subtables {
+                match subtable {
+                    Positioning::Pair(map) => {
+                        for (&first, seconds) in map.iter().filter(|(first, _)| !claimed_firsts.contains(*first)) {
+                            for (&second, (value, _)) in seconds {
+                                // 多个查找的值相加，超出 i16 时取边界值
+                                if claimed_pairs.insert((first, second)) {
+                                    let total = self.pairs.entry((first, second)).or_default();
+                                    *total = total.saturating_add(value.x_advance);
+                                }
+                            }
+                        }
+                    }
+                    Positioning::PairClass { coverage, classes1, classes2, values } => {
+                        // 第一侧的类别 0 为覆盖表中未分类的字形；第二侧的类别 0 包含所有其他字形，无法表示为分组
+                        let mut rows: Vec<Vec<u16>> = vec![Vec::new(); values.len()];
+                        for &g in
+                            coverage.iter().filter(|g| !claimed_firsts.contains(*g) && !left_assigned.contains(*g))
+                        {
+                            if let Some(row) = rows.get_mut(classes1.get(&g).copied().unwrap_or(0) as usize) {
+                                row.push(g);
+                            }
+                        }
+                        let columns = values.first().map_or(0, Vec::len);
+                        let mut cols: Vec<Vec<u16>> = vec![Vec::new(); columns];
+                        for (&g, &c) in classes2 {
+                            if let Some(col) = cols.get_mut(c as usize) {
+                                col.push(g);
+                            }
+                        }
+                        let col_classes: Vec<Option<usize>> = cols
+                            .into_iter()
+                            .enumerate()
+                            .map(|(c, glyphs)| {
+                                if c == 0 || glyphs.is_empty() {
+                                    return None;
+                                }
+                                // 相同的第二侧分组只保留一份
+                                Some(*right_assigned.entry(glyphs.clone()).or_insert_with(|| {
+                                    self.right_classes.push(glyphs);
+                                    self.right_classes.len() - 1
+                                }))
+                            })
+                            .collect();
+                        for (row, glyphs) in rows.into_iter().enumerate().filter(|(_, g)| !g.is_empty()) {
+                            let left = self.left_classes.len();
+                            for (c, right) in col_classes.iter().enumerate() {
+                                let value = values[row][c].0.x_advance;
+                                if let (Some(right), true) = (right, value != 0) {
+                                    self.class_pairs.insert((left, *right), value);
+                                }
+                            }
+                            claimed_firsts.extend(glyphs.iter().copied());
+                            left_assigned.extend(glyphs.iter().copied());
+                            self.left_classes.push(glyphs);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    // 不同子表的第二侧分组可能有交集，而工程中每个字形只能属于一个分组：
+    // 按字形所属分组的组合拆分为互不相交的分组，分组字偶距复制到拆分出的每个分组。
+    // 第一侧分组只属于一个子表，它引用的第二侧分组互不相交，拆分后不会出现冲突的值
+    fn split_right_classes(&mut self) {
+        let mut membership: BTreeMap<u16, Vec<usize>> = BTreeMap::new();
+        for (class, glyphs) in self.right_classes.iter().enumerate() {
+            for &glyph in glyphs {
+                membership.entry(glyph).or_default().push(class);
+            }
+        }
+        if membership.values().all(|classes| classes.len() == 1) {
+            return;
+        }
+        let mut parts: BTreeMap<Vec<usize>, Vec<u16>> = BTreeMap::new();
+        for (glyph, classes) in membership {
+            parts.entry(classes).or_default().push(glyph);
+        }
+        let mut split: Vec<Vec<usize>> = vec![Vec::new(); self.right_classes.len()];
+        self.right_classes.clear();
+        for (classes, glyphs) in parts {
+            for class in classes {
+                split[class].push(self.right_classes.len());
+            }
+            self.right_classes.push(glyphs);
+        }
+        self.class_pairs = std::mem::take(&mut self.class_pairs)
+            .into_iter()
+            .flat_map(|((left, right), value)| split[right].iter().map(move |&right| ((left, right), value)))
+            .collect();
+    }
+
+    // 旧式 kern 表（版本 0）中的格式 0 水平字偶距
+    fn read_kern(&mut self, data: &[u8]) -> Result<(), String> {
+        let mut r = Reader::new(data);
+        if r.u16()? != 0 {
+            return Ok(());
+        }
+        for _ in 0..r.u16()? {
+            let start = r.pos();
+            r.skip(2)?;
+            let length = r.u16()? as usize;
+            let coverage = r.u16()?;
+            // 格式 0、水平方向、非最小值、非交叉方向
+            if coverage >> 8 == 0 && coverage & 0x0007 == 0x0001 {
+                let count = r.u16()?;
+                r.skip(6)?;
+                for _ in 0..count {
+                    let (left, right, value) = (r.u16()?, r.u16()?, r.i16()?);
+                    self.pairs.entry((left, right)).or_insert(value);
+                }
+            }
+            r.set_pos(start + length);
+        }
+        Ok(())
+    }
+}
+
+// 从已有字体（TTF/OTF/WOFF）导入字偶距，按 cmap 中的码位对应到工程中的字形
+pub fn import(data: &[u8], font: &FontData) -> Result<Kerning, String> {
+    let sfnt_data = if woff::is_woff(data) { woff::decode(data)?.sfnt } else { data.to_vec() };
+    let sfnt = Sfnt::parse(&sfnt_data)?;
+    let mapping = cmap::parse(sfnt.table(b"cmap").ok_or("missing 'cmap' table")?)?;
+    let by_unicode: HashMap<u32, usize> =
+        font.glyphs.iter().enumerate().filter(|(_, g)| g.unicode != 0).rev().map(|(i, g)| (g.unicode, i)).collect();
+    let mut glyph_index: HashMap<u16, usize> = HashMap::new();
+    for (cp, gid) in &mapping {
+        if let Some(&index) = by_unicode.get(cp) {
+            glyph_index.entry(*gid).or_insert(index);
+        }
+    }
+
+    let mut source = FontKerning::default();
+    if let Some(gpos) = sfnt.table(b"GPOS") {
+        source.read_gpos(&Gpos::parse(gpos).map_err(|err| format!("failed to read GPOS: {err}"))?);
+        source.split_right_classes();
+    }
+    if source.pairs.is_empty() && source.class_pairs.is_empty() {
+        if let Some(kern) = sfnt.table(b"kern") {
+            source.read_kern(kern).map_err(|err| format!("failed to read kern: {err}"))?;
+        }
+    }
+
+    let name = |index: usize| {
+        let glyph = &font.glyphs[index];
+        if glyph.name.is_empty() {
+            format!("uni{:04X}", glyph.unicode)
+        } else {
+            glyph.name.clone()
+        }
+    };
+    // 分组映射到工程字形，以第一个字形命名，字形都不在工程中的分组删除
+    let classes = |source: &[Vec<u16>], prefix: &str| -> (Vec<KerningClass>, Vec<Option<usize>>) {
+        let mut classes: Vec<KerningClass> = Vec::new();
+        let mut names = BTreeSet::new();
+        let indices = source
+            .iter()
+            .map(|glyphs| {
+                let glyphs: Vec<usize> = glyphs.iter().filter_map(|g| glyph_index.get(g).copied()).collect();
+                let first = *glyphs.first()?;
+                let base = format!("{prefix}.{}", name(first));
+                let mut class_name = base.clone();
+                let mut n = 1;
+                while !names.insert(class_name.clone()) {
+                    n += 1;
+                    class_name = format!("{base}.{n}");
+                }
+                classes.push(KerningClass { name: class_name, glyphs });
+                Some(classes.len() - 1)
+            })
+            .collect();
+        (classes, indices)
+    };
+    let (left_classes, left_map) = classes(&source.left_classes, "public.kern1");
+    let (right_classes, right_map) = classes(&source.right_classes, "public.kern2");
+
+    let mut pairs = Vec::new();
+    for (&(l, r), &value) in &source.class_pairs {
+        if let (Some(l), Some(r)) = (left_map[l], right_map[r]) {
+            pairs.push(KerningPair {
+                left: KerningSide::Class { class: l },
+                right: KerningSide::Class { class: r },
+                value,
+            });
+        }
+    }
+    for (&(a, b), &value) in &source.pairs {
+        if let (Some(&a), Some(&b)) = (glyph_index.get(&a), glyph_index.get(&b)) {
+            pairs.push(KerningPair {
+                left: KerningSide::Glyph { glyph: a },
+                right: KerningSide::Glyph { glyph: b },
+                value,
+            });
+        }
+    }
+    Ok(Kerning { left_classes, right_classes, pairs })
+}
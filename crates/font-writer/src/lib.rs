@@ -0,0 +1,475 @@
+use std::collections::{BTreeSet, HashMap};
+
+use serde::{Deserialize, Serialize};
+
+pub mod autohint;
+pub mod binary;
+pub mod bitmap;
+pub mod color;
+pub mod composite;
+pub mod designspace;
+pub mod features;
+pub mod kerning;
+pub mod outline;
+pub mod quadratic;
+pub mod sfnt;
+pub mod subset;
+pub mod tables;
+pub mod ufo;
+pub mod validate;
+pub mod variable;
+pub mod vertical;
+pub mod woff;
+pub mod woff2;
+mod xml;
+
+use color::ColorFontData;
+use composite::GlyphComponent;
+use features::Features;
+use kerning::Kerning;
+use outline::Contours;
+use tables::cff::{self, charstring, subroutinize};
+use tables::name::LocalizedNames;
+use tables::{cmap, gasp, glyf, head, hhea, hmtx, maxp, name, os2, post, GlyphMetrics};
+use variable::VariableFontData;
+use vertical::VerticalLayout;
+use woff::{WoffMetadata, WoffOptions};
+
+// 字体信息，字段与前端 IOption 保持一致
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct FontInfo {
+    pub family_name: String,
+    pub style_name: String,
+    pub full_name: String,
+    pub post_script_name: String,
+    pub designer: String,
+    #[serde(rename = "designerURL")]
+    pub designer_url: String,
+    pub manufacturer: String,
+    #[serde(rename = "manufacturerURL")]
+    pub manufacturer_url: String,
+    pub license: String,
+    #[serde(rename = "licenseURL")]
+    pub license_url: String,
+    pub version: String,
+    pub description: String,
+    pub copyright: String,
+    pub trademark: String,
+    pub units_per_em: u16,
+    pub ascender: i16,
+    pub descender: i16,
+    pub line_gap: i16,
+    pub cap_height: Option<i16>,
+    pub x_height: Option<i16>,
+    pub italic_angle: f64,
+    pub weight_class: u16,
+    pub width_class: u16,
+    pub vendor_id: String,
+    // 秒级 Unix 时间戳
+    pub created_timestamp: Option<i64>,
+    pub modified_timestamp: Option<i64>,
+    // 多语言名称：nameID → 语言标签（en-US、zh-CN、zh-TW、ja-JP）→ 字符串
+    pub localized_names: LocalizedNames,
+}
+
+impl Default for FontInfo {
+    fn default() -> Self {
+        FontInfo {
+            family_name: String::from("Untitled"),
+            style_name: String::from("Regular"),
+            full_name: String::new(),
+            post_script_name: String::new(),
+            designer: String::new(),
+            designer_url: String::new(),
+            manufacturer: String::new(),
+            manufacturer_url: String::new(),
+            license: String::new(),
+            license_url: String::new(),
+            version: String::from("Version 1.000"),
+            description: String::new(),
+            copyright: String::new(),
+            trademark: String::new(),
+            units_per_em: 1000,
+            ascender: 800,
+            descender: -200,
+            line_gap: 0,
+            cap_height: None,
+            x_height: None,
+            italic_angle: 0.0,
+            weight_class: 400,
+            width_class: 5,
+            vendor_id: String::from("NONE"),
+            created_timestamp: None,
+            modified_timestamp: None,
+            localized_names: LocalizedNames::new(),
+        }
+    }
+}
+
+impl FontInfo {
+    pub fn full_name(&self) -> String {
+        if !self.full_name.is_empty() {
+            return self.full_name.clone();
+        }
+        if self.style_name.is_empty() || self.style_name == "Regular" {
+            self.family_name.clone()
+        } else {
+            format!("{} {}", self.family_name, self.style_name)
+        }
+    }
+
+    pub fn post_script_name(&self) -> String {
+        let raw = if self.post_script_name.is_empty() {
+            let style = if self.style_name.is_empty() { "Regular" } else { &self.style_name };
+            format!("{}-{}", self.family_name.replace(' ', ""), style.replace(' ', ""))
+        } else {
+            self.post_script_name.clone()
+        };
+        name::sanitize_post_script_name(&raw)
+    }
+
+    pub fn is_bold(&self) -> bool {
+        self.weight_class >= 700 || self.style_name.to_lowercase().contains("bold")
+    }
+
+    pub fn is_italic(&self) -> bool {
+        let style = self.style_name.to_lowercase();
+        self.italic_angle != 0.0 || style.contains("italic") || style.contains("oblique")
+    }
+}
+
+// 单个字形：unicode 为 0 表示不映射字符
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct GlyphData {
+    pub name: String,
+    pub unicode: u32,
+    pub advance_width: f64,
+    pub contours: Contours,
+    // 引用其他字形的组件，与自身轮廓一起组成字形
+    pub components: Vec<GlyphComponent>,
+}
+
+// 变体序列（如日文异体字的 IVS）：glyph 为 font.glyphs 中的序号，缺省时为默认变体（使用基本字符的字形）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct VariationSequence {
+    pub unicode: u32,
+    pub selector: u32,
+    pub glyph: Option<usize>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct FontData {
+    pub info: FontInfo,
+    pub glyphs: Vec<GlyphData>,
+    pub variation_sequences: Vec<VariationSequence>,
+    pub kerning: Kerning,
+    pub features: Features,
+    // 竖排度量与竖排替换字形，为 None 时不写入竖排表
+    pub vertical: Option<VerticalLayout>,
+}
+
+impl FontData {
+    // 自动插入 .notdef 时，工程中的字形序号转换为字形 ID 需要后移一位
+    pub fn notdef_shift(&self) -> usize {
+        usize::from(self.glyphs.first().map_or(true, |g| g.name != ".notdef"))
+    }
+
+    // 保证 0 号字形为 .notdef，缺失时插入一个空字形
+    pub fn glyphs_with_notdef(&self) -> Vec<GlyphData> {
+        let mut glyphs = Vec::with_capacity(self.glyphs.len() + 1);
+        let has_notdef = self.glyphs.first().is_some_and(|g| g.name == ".notdef");
+        if !has_notdef {
+            glyphs.push(GlyphData {
+                name: String::from(".notdef"),
+                unicode: 0,
+                advance_width: self.info.units_per_em as f64,
+                contours: Vec::new(),
+                components: Vec::new(),
+            });
+        }
+        glyphs.extend(self.glyphs.iter().cloned());
+        glyphs
+    }
+}
+
+pub fn advance_width(value: f64) -> u16 {
+    value.round().clamp(0.0, u16::MAX as f64) as u16
+}
+
+// 轮廓格式：TrueType（glyf，二次曲线）或 CFF（三次曲线）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Outlines {
+    #[default]
+    TrueType,
+    Cff,
+}
+
+// 输出的文件封装：原始 sfnt（TTF/OTF）、WOFF 或 WOFF2
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Flavor {
+    #[default]
+    Sfnt,
+    Woff,
+    Woff2,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ExportOptions {
+    pub outlines: Outlines,
+    // CFF 轮廓是否提取全局/局部子程序
+    pub subroutinize: bool,
+    // TrueType 轮廓是否自动生成提示指令（fpgm、prep、cvt 与字形程序）
+    pub hinting: bool,
+    pub flavor: Flavor,
+    // WOFF 元数据，未指定时由字体信息生成
+    pub woff_metadata: Option<WoffMetadata>,
+    pub woff_private_data: Option<Vec<u8>>,
+    // 组件展开为普通轮廓，TrueType 轮廓不再写成复合字形；去除重叠由调用方在展开后的轮廓上完成
+    pub flatten_components: bool,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        ExportOptions {
+            outlines: Outlines::TrueType,
+            subroutinize: true,
+            hinting: false,
+            flavor: Flavor::Sfnt,
+            woff_metadata: None,
+            woff_private_data: None,
+            flatten_components: false,
+        }
+    }
+}
+
+pub(crate) fn prepare_glyphs(font: &FontData) -> Result<Vec<GlyphData>, String> {
+    let upm = font.info.units_per_em;
+    if !(16..=16384).contains(&upm) {
+        return Err(format!("invalid unitsPerEm: {upm}"));
+    }
+    let glyphs = font.glyphs_with_notdef();
+    if glyphs.len() > u16::MAX as usize {
+        return Err(format!("too many glyphs: {}", glyphs.len()));
+    }
+    Ok(glyphs)
+}
+
+pub fn build_font(font: &FontData, options: &ExportOptions) -> Result<Vec<u8>, String> {
+    let (version, tables) = outline_tables(font, options)?;
+    wrap_flavor(&font.info, sfnt::assemble(version, tables), options)
+}
+
+// 按轮廓格式生成字体的基本表，返回 (sfnt 版本, 表)
+pub(crate) fn outline_tables(font: &FontData, options: &ExportOptions) -> Result<(u32, Vec<sfnt::Table>), String> {
+    let font = &*vertical::expand(font)?;
+    match options.outlines {
+        Outlines::TrueType => {
+            let glyphs = prepare_glyphs(font)?;
+            let shift = glyphs.len() - font.glyphs.len();
+            let (simple_glyphs, composites) = if options.flatten_components {
+                let glyphs = composite::flatten(&glyphs, shift)?;
+                (glyphs.iter().map(|g| glyf::SimpleGlyph::from_contours(&g.contours)).collect(), Vec::new())
+            } else {
+                composite::truetype_glyphs(&glyphs, shift)?
+            };
+            let tables = truetype_tables(font, &glyphs, &simple_glyphs, &composites, options.hinting)?;
+            Ok((sfnt::TRUETYPE, tables))
+        }
+        Outlines::Cff => Ok((sfnt::CFF, cff_tables(font, options.subroutinize)?)),
+    }
+}
+
+// 可变字体目前只支持 TrueType 轮廓（gvar）
+pub fn build_variable_font(font: &VariableFontData, options: &ExportOptions) -> Result<Vec<u8>, String> {
+    if options.outlines != Outlines::TrueType {
+        return Err(String::from("variable fonts require TrueType outlines"));
+    }
+    let data = variable::build_ttf(font)?;
+    wrap_flavor(&font.font.info, data, options)
+}
+
+// 彩色字体：COLR/CPAL 与 TrueType 或 CFF 轮廓组合
+pub fn build_color_font(font: &ColorFontData, options: &ExportOptions) -> Result<Vec<u8>, String> {
+    let (version, tables) = color::build_tables(font, options)?;
+    wrap_flavor(&font.font.info, sfnt::assemble(version, tables), options)
+}
+
+// 对已有字体文件（sfnt 或 WOFF）按码位做子集化，输出为指定的封装格式
+pub fn subset_font(data: &[u8], codepoints: &BTreeSet<u32>, flavor: Flavor) -> Result<Vec<u8>, String> {
+    let sfnt_data = if woff::is_woff(data) { woff::decode(data)?.sfnt } else { data.to_vec() };
+    let subset = subset::subset(&sfnt_data, codepoints)?;
+    match flavor {
+        Flavor::Sfnt => Ok(subset),
+        Flavor::Woff => woff::encode(&subset, &WoffOptions::default()),
+        Flavor::Woff2 => woff2::encode(&subset),
+    }
+}
+
+// 先按工程生成完整字体再做子集化，保证版式表中的字形引用与完整字体一致
+pub fn subset_project(font: &FontData, codepoints: &BTreeSet<u32>, options: &ExportOptions) -> Result<Vec<u8>, String> {
+    let (version, tables) = outline_tables(font, options)?;
+    let subset = subset::subset(&sfnt::assemble(version, tables), codepoints)?;
+    wrap_flavor(&font.info, subset, options)
+}
+
+fn wrap_flavor(info: &FontInfo, data: Vec<u8>, options: &ExportOptions) -> Result<Vec<u8>, String> {
+    match options.flavor {
+        Flavor::Sfnt => Ok(data),
+        Flavor::Woff => {
+            let metadata = options.woff_metadata.clone().unwrap_or_else(|| WoffMetadata::from_info(info));
+            let woff_options = WoffOptions { metadata: Some(metadata), private_data: options.woff_private_data.clone() };
+            woff::encode(&data, &woff_options)
+        }
+        Flavor::Woff2 => woff2::encode(&data),
+    }
+}
+
+// 生成 TrueType 轮廓的字体文件
+pub fn build_ttf(font: &FontData) -> Result<Vec<u8>, String> {
+    let (version, tables) = outline_tables(font, &ExportOptions::default())?;
+    Ok(sfnt::assemble(version, tables))
+}
+
+// TrueType 字体的基本表，glyphs 为含 .notdef 的字形，simple_glyphs 与之一一对应；
+// composites 中有值的字形写成复合字形，对应的 simple_glyphs 为展开后的点
+pub(crate) fn truetype_tables(
+    font: &FontData,
+    glyphs: &[GlyphData],
+    simple_glyphs: &[glyf::SimpleGlyph],
+    composites: &[Option<glyf::CompositeGlyph>],
+    hinting: bool,
+) -> Result<Vec<sfnt::Table>, String> {
+    let info = &font.info;
+    let metrics: Vec<GlyphMetrics> = glyphs
+        .iter()
+        .zip(simple_glyphs)
+        .map(|(g, s)| GlyphMetrics { advance_width: advance_width(g.advance_width), bbox: s.bbox })
+        .collect();
+    let mapping = cmap::mapping(glyphs);
+    let variations = cmap::variation_mappings(font)?;
+    let names = post::production_names(glyphs);
+
+    let hints = hinting.then(|| autohint::autohint(glyphs, simple_glyphs, info.units_per_em));
+    let glyph_data: Vec<Vec<u8>> = simple_glyphs
+        .iter()
+        .enumerate()
+        .map(|(gid, g)| match composites.get(gid).and_then(Option::as_ref) {
+            Some(composite) => composite.encode(g.bbox),
+            None => g.encode(hints.as_ref().map_or(&[][..], |hints| &hints.glyph_programs[gid])),
+        })
+        .collect();
+    let (glyf_data, loca_data, index_to_loc_format) = glyf::build_with_loca(&glyph_data);
+    let (hmtx_data, number_of_h_metrics) = hmtx::build(&metrics);
+    let limits = hints.as_ref().map(|hints| hints.limits).unwrap_or_default();
+
+    let mut tables = vec![
+        sfnt::Table::new(b"head", head::build(info, &metrics, index_to_loc_format)),
+        sfnt::Table::new(b"hhea", hhea::build(info, &metrics, number_of_h_metrics)),
+        sfnt::Table::new(b"maxp", maxp::build_truetype(simple_glyphs, composites, &limits)),
+        sfnt::Table::new(b"OS/2", os2::build(info, &metrics, &mapping)),
+        sfnt::Table::new(b"hmtx", hmtx_data),
+        sfnt::Table::new(b"cmap", cmap::build(&mapping, &variations)?),
+        sfnt::Table::new(b"loca", loca_data),
+        sfnt::Table::new(b"glyf", glyf_data),
+        sfnt::Table::new(b"name", name::build(info)?),
+        sfnt::Table::new(b"post", post::build(info, &names)),
+    ];
+    if let Some(hints) = hints {
+        tables.push(sfnt::Table::new(b"fpgm", hints.fpgm));
+        tables.push(sfnt::Table::new(b"prep", hints.prep));
+        tables.push(sfnt::Table::new(b"cvt ", hints.cvt));
+        tables.push(sfnt::Table::new(b"gasp", gasp::hinted()));
+    }
+    tables.extend(vertical::tables(font, glyphs, &metrics, false)?);
+    tables.extend(layout_tables(font)?);
+    Ok(tables)
+}
+
+// OpenType 布局表，TrueType 与 CFF 轮廓共用
+fn layout_tables(font: &FontData) -> Result<Vec<sfnt::Table>, String> {
+    let mut tables = Vec::new();
+    if let Some(gsub) = features::gsub(font)? {
+        tables.push(sfnt::Table::new(b"GSUB", gsub.build().map_err(|err| format!("failed to build GSUB: {err}"))?));
+    }
+    if let Some(gpos) = kerning::gpos(font)? {
+        tables.push(sfnt::Table::new(b"GPOS", gpos.build().map_err(|err| format!("failed to build GPOS: {err}"))?));
+    }
+    Ok(tables)
+}
+
+// 生成 CFF 轮廓的 OpenType 字体文件
+pub fn build_otf(font: &FontData, subroutinize: bool) -> Result<Vec<u8>, String> {
+    let options = ExportOptions { outlines: Outlines::Cff, subroutinize, ..Default::default() };
+    let (version, tables) = outline_tables(font, &options)?;
+    Ok(sfnt::assemble(version, tables))
+}
+
+fn cff_tables(font: &FontData, subroutinize: bool) -> Result<Vec<sfnt::Table>, String> {
+    let info = &font.info;
+    let glyphs = prepare_glyphs(font)?;
+    // CFF 没有复合字形，组件一律展开
+    let glyphs = composite::flatten(&glyphs, glyphs.len() - font.glyphs.len())?;
+
+    // 出现最多的步进宽度作为 defaultWidthX，同时作为 nominalWidthX
+    let widths: Vec<u16> = glyphs.iter().map(|g| advance_width(g.advance_width)).collect();
+    let mut counts: HashMap<u16, usize> = HashMap::new();
+    for &w in &widths {
+        *counts.entry(w).or_default() += 1;
+    }
+    let default_width = counts.into_iter().max_by_key(|&(w, n)| (n, std::cmp::Reverse(w))).map_or(0, |(w, _)| w) as i32;
+
+    let charstrings: Vec<charstring::Charstring> = glyphs
+        .iter()
+        .zip(&widths)
+        .map(|(g, &w)| {
+            let width = (w as i32 != default_width).then_some(w as i32 - default_width);
+            charstring::encode(&g.contours, width)
+        })
+        .collect();
+    let metrics: Vec<GlyphMetrics> = widths
+        .iter()
+        .zip(&charstrings)
+        .map(|(&advance_width, c)| GlyphMetrics { advance_width, bbox: c.bbox })
+        .collect();
+    let mapping = cmap::mapping(&glyphs);
+    let variations = cmap::variation_mappings(font)?;
+    let names = post::production_names(&glyphs);
+
+    let charstrings = if subroutinize {
+        subroutinize::subroutinize(&charstrings)
+    } else {
+        subroutinize::without_subroutines(&charstrings)
+    };
+    let cff_data = cff::build(cff::CffFont {
+        info,
+        post_script_name: info.post_script_name(),
+        glyph_names: &names,
+        bbox: tables::font_bbox(&metrics),
+        default_width,
+        nominal_width: default_width,
+        charstrings,
+    });
+    let (hmtx_data, number_of_h_metrics) = hmtx::build(&metrics);
+
+    let mut tables = vec![
+        sfnt::Table::new(b"CFF ", cff_data),
+        sfnt::Table::new(b"head", head::build(info, &metrics, 0)),
+        sfnt::Table::new(b"hhea", hhea::build(info, &metrics, number_of_h_metrics)),
+        sfnt::Table::new(b"maxp", maxp::build_cff(glyphs.len() as u16)),
+        sfnt::Table::new(b"OS/2", os2::build(info, &metrics, &mapping)),
+        sfnt::Table::new(b"hmtx", hmtx_data),
+        sfnt::Table::new(b"cmap", cmap::build(&mapping, &variations)?),
+        sfnt::Table::new(b"name", name::build(info)?),
+        sfnt::Table::new(b"post", post::build_without_names(info)),
+    ];
+    tables.extend(vertical::tables(font, &glyphs, &metrics, true)?);
+    tables.extend(layout_tables(font)?);
+    Ok(tables)
+}
@@ -0,0 +1,165 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Point {
+    pub x: f64,
+    pub y: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum Segment {
+    #[serde(rename = "LINE")]
+    Line { start: Point, end: Point },
+
+    #[serde(rename = "QUADRATIC_BEZIER")]
+    Quadratic { start: Point, control: Point, end: Point },
+
+    #[serde(rename = "CUBIC_BEZIER")]
+    Cubic { start: Point, control1: Point, control2: Point, end: Point },
+}
+
+impl Segment {
+    pub fn start(&self) -> Point {
+        match self {
+            Segment::Line { start, .. } => *start,
+            Segment::Quadratic { start, .. } => *start,
+            Segment::Cubic { start, .. } => *start,
+        }
+    }
+
+    pub fn end(&self) -> Point {
+        match self {
+            Segment::Line { end, .. } => *end,
+            Segment::Quadratic { end, .. } => *end,
+            Segment::Cubic { end, .. } => *end,
+        }
+    }
+}
+
+pub type Contour = Vec<Segment>;
+pub type Contours = Vec<Contour>;
+
+// 2x3 仿射矩阵：x' = a*x + c*y + e，y' = b*x + d*y + f
+// JSON 中以 [a, b, c, d, e, f] 数组表示，与 SVG matrix()、canvas setTransform 以及组件的 transform 顺序一致
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(from = "[f64; 6]", into = "[f64; 6]")]
+pub struct Matrix {
+    pub a: f64,
+    pub b: f64,
+    pub c: f64,
+    pub d: f64,
+    pub e: f64,
+    pub f: f64,
+}
+
+impl From<[f64; 6]> for Matrix {
+    fn from(m: [f64; 6]) -> Self {
+        Matrix::new(m[0], m[1], m[2], m[3], m[4], m[5])
+    }
+}
+
+impl From<Matrix> for [f64; 6] {
+    fn from(m: Matrix) -> Self {
+        [m.a, m.b, m.c, m.d, m.e, m.f]
+    }
+}
+
+impl Matrix {
+    pub const IDENTITY: Matrix = Matrix { a: 1.0, b: 0.0, c: 0.0, d: 1.0, e: 0.0, f: 0.0 };
+
+    pub fn new(a: f64, b: f64, c: f64, d: f64, e: f64, f: f64) -> Matrix {
+        Matrix { a, b, c, d, e, f }
+    }
+
+    pub fn translate(tx: f64, ty: f64) -> Matrix {
+        Matrix::new(1.0, 0.0, 0.0, 1.0, tx, ty)
+    }
+
+    pub fn scale(sx: f64, sy: f64) -> Matrix {
+        Matrix::new(sx, 0.0, 0.0, sy, 0.0, 0.0)
+    }
+
+    // 角度制，正方向与 SVG 一致
+    pub fn rotate(degrees: f64) -> Matrix {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        Matrix::new(cos, sin, -sin, cos, 0.0, 0.0)
+    }
+
+    pub fn skew_x(degrees: f64) -> Matrix {
+        Matrix::new(1.0, 0.0, degrees.to_radians().tan(), 1.0, 0.0, 0.0)
+    }
+
+    pub fn skew_y(degrees: f64) -> Matrix {
+        Matrix::new(1.0, degrees.to_radians().tan(), 0.0, 1.0, 0.0, 0.0)
+    }
+
+    // self * other：先应用 other，再应用 self
+    pub fn multiply(&self, other: &Matrix) -> Matrix {
+        Matrix {
+            a: self.a * other.a + self.c * other.b,
+            b: self.b * other.a + self.d * other.b,
+            c: self.a * other.c + self.c * other.d,
+            d: self.b * other.c + self.d * other.d,
+            e: self.a * other.e + self.c * other.f + self.e,
+            f: self.b * other.e + self.d * other.f + self.f,
+        }
+    }
+
+    pub fn determinant(&self) -> f64 {
+        self.a * self.d - self.b * self.c
+    }
+
+    pub fn apply(&self, p: Point) -> Point {
+        Point { x: self.a * p.x + self.c * p.y + self.e, y: self.b * p.x + self.d * p.y + self.f }
+    }
+}
+
+// 仿射变换下贝塞尔曲线的控制点与曲线本身同步变换，因此直接变换每个点即可
+pub fn transform_segment(segment: &Segment, m: &Matrix) -> Segment {
+    match segment {
+        Segment::Line { start, end } => Segment::Line { start: m.apply(*start), end: m.apply(*end) },
+        Segment::Quadratic { start, control, end } => {
+            Segment::Quadratic { start: m.apply(*start), control: m.apply(*control), end: m.apply(*end) }
+        }
+        Segment::Cubic { start, control1, control2, end } => Segment::Cubic {
+            start: m.apply(*start),
+            control1: m.apply(*control1),
+            control2: m.apply(*control2),
+            end: m.apply(*end),
+        },
+    }
+}
+
+pub fn transform_contour(contour: &Contour, m: &Matrix) -> Contour {
+    contour.iter().map(|segment| transform_segment(segment, m)).collect()
+}
+
+pub fn transform_contours(contours: &Contours, m: &Matrix) -> Contours {
+    contours.iter().map(|contour| transform_contour(contour, m)).collect()
+}
+
+// 反转轮廓方向
+pub fn reverse_contour(contour: &Contour) -> Contour {
+    contour
+        .iter()
+        .rev()
+        .map(|segment| match *segment {
+            Segment::Line { start, end } => Segment::Line { start: end, end: start },
+            Segment::Quadratic { start, control, end } => Segment::Quadratic { start: end, control, end: start },
+            Segment::Cubic { start, control1, control2, end } => {
+                Segment::Cubic { start: end, control1: control2, control2: control1, end: start }
+            }
+        })
+        .collect()
+}
+
+// 放置组件的轮廓：镜像变换会反转轮廓方向，再反转一次保持与其他轮廓的环绕方向一致
+pub fn place_contour(contour: &Contour, m: &Matrix) -> Contour {
+    let placed = transform_contour(contour, m);
+    if m.determinant() < 0.0 {
+        reverse_contour(&placed)
+    } else {
+        placed
+    }
+}
@@ -0,0 +1,59 @@
+use crate::outline::Point;
+
+// 单段二次曲线近似三次曲线的最大误差系数（中点近似法）：√3 / 36
+const ERROR_FACTOR: f64 = 0.048_112_522_432_468_82;
+
+// 同一段曲线最多拆分的段数
+const MAX_SPLITS: usize = 32;
+
+fn lerp(a: Point, b: Point, t: f64) -> Point {
+    Point { x: a.x + (b.x - a.x) * t, y: a.y + (b.y - a.y) * t }
+}
+
+// 三次曲线的 blossom 值 B(u, v, w)，用于截取参数区间上的子曲线
+fn blossom(p: &[Point; 4], u: f64, v: f64, w: f64) -> Point {
+    let a = [lerp(p[0], p[1], u), lerp(p[1], p[2], u), lerp(p[2], p[3], u)];
+    let b = [lerp(a[0], a[1], v), lerp(a[1], a[2], v)];
+    lerp(b[0], b[1], w)
+}
+
+// 三次差分 |P3 - 3P2 + 3P1 - P0|，均分为 n 段后每段按 1/n³ 缩小
+fn third_difference(p: &[Point; 4]) -> f64 {
+    let x = p[3].x - 3.0 * p[2].x + 3.0 * p[1].x - p[0].x;
+    let y = p[3].y - 3.0 * p[2].y + 3.0 * p[1].y - p[0].y;
+    (x * x + y * y).sqrt()
+}
+
+// 满足误差要求所需的最少分段数
+pub fn split_count(p: &[Point; 4], tolerance: f64) -> usize {
+    let error = ERROR_FACTOR * third_difference(p);
+    if error <= tolerance {
+        return 1;
+    }
+    let n = (error / tolerance).cbrt().ceil() as usize;
+    n.clamp(1, MAX_SPLITS)
+}
+
+// 将三次曲线均分为 n 段，每段用一条二次曲线近似，返回 (控制点, 终点) 序列
+pub fn cubic_to_quadratics(p: &[Point; 4], n: usize) -> Vec<(Point, Point)> {
+    let n = n.max(1);
+    let mut out = Vec::with_capacity(n);
+    for i in 0..n {
+        let t0 = i as f64 / n as f64;
+        let t1 = (i + 1) as f64 / n as f64;
+        let q0 = blossom(p, t0, t0, t0);
+        let q1 = blossom(p, t0, t0, t1);
+        let q2 = blossom(p, t0, t1, t1);
+        let q3 = if i + 1 == n { p[3] } else { blossom(p, t1, t1, t1) };
+        let control = Point {
+            x: (3.0 * (q1.x + q2.x) - (q0.x + q3.x)) / 4.0,
+            y: (3.0 * (q1.y + q2.y) - (q0.y + q3.y)) / 4.0,
+        };
+        out.push((control, q3));
+    }
+    out
+}
+
+pub fn cubic_to_quadratic(p: &[Point; 4], tolerance: f64) -> Vec<(Point, Point)> {
+    cubic_to_quadratics(p, split_count(p, tolerance))
+}
@@ -0,0 +1,111 @@
+use crate::binary::{checksum, Reader, Writer};
+
+// sfntVersion：TrueType 轮廓为 0x00010000，CFF 轮廓为 'OTTO'
+pub const TRUETYPE: u32 = 0x0001_0000;
+pub const CFF: u32 = 0x4F54_544F;
+
+// head.checkSumAdjustment 的计算常量
+pub const CHECKSUM_MAGIC: u32 = 0xB1B0_AFBA;
+
+#[derive(Debug, Clone)]
+pub struct Table {
+    pub tag: [u8; 4],
+    pub data: Vec<u8>,
+}
+
+impl Table {
+    pub fn new(tag: &[u8; 4], data: Vec<u8>) -> Self {
+        Table { tag: *tag, data }
+    }
+}
+
+// 解析后的 sfnt 字体：版本号与各表数据
+#[derive(Debug, Clone)]
+pub struct Sfnt {
+    pub version: u32,
+    pub tables: Vec<Table>,
+}
+
+impl Sfnt {
+    pub fn parse(data: &[u8]) -> Result<Self, String> {
+        let mut r = Reader::new(data);
+        let version = r.u32()?;
+        if version != TRUETYPE && version != CFF && &version.to_be_bytes() != b"true" {
+            return Err(format!("unsupported sfnt version: 0x{version:08X}"));
+        }
+        let num_tables = r.u16()?;
+        r.skip(6)?;
+        let mut tables = Vec::with_capacity(num_tables as usize);
+        for _ in 0..num_tables {
+            let tag = r.tag()?;
+            let _checksum = r.u32()?;
+            let offset = r.u32()? as usize;
+            let length = r.u32()? as usize;
+            let data = Reader::at(data, offset).bytes(length).map_err(|_| {
+                format!("table '{}' is out of bounds", String::from_utf8_lossy(&tag))
+            })?;
+            tables.push(Table { tag, data: data.to_vec() });
+        }
+        Ok(Sfnt { version, tables })
+    }
+
+    pub fn table(&self, tag: &[u8; 4]) -> Option<&[u8]> {
+        self.tables.iter().find(|t| &t.tag == tag).map(|t| t.data.as_slice())
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        assemble(self.version, self.tables)
+    }
+}
+
+// 二分查找头参数（searchRange, entrySelector, rangeShift），unit 为单条记录的字节数
+pub fn search_params(count: u16, unit: u16) -> (u16, u16, u16) {
+    if count == 0 {
+        return (0, 0, 0);
+    }
+    let entry_selector = 15 - count.leading_zeros() as u16;
+    let search_range = (1u16 << entry_selector) * unit;
+    let range_shift = count * unit - search_range;
+    (search_range, entry_selector, range_shift)
+}
+
+// 组装完整的字体文件：表目录按标签排序，各表 4 字节对齐，最后回填 head.checkSumAdjustment
+pub fn assemble(sfnt_version: u32, mut tables: Vec<Table>) -> Vec<u8> {
+    tables.sort_by_key(|t| t.tag);
+    // head 表的校验和按 checkSumAdjustment 为 0 计算
+    for table in tables.iter_mut().filter(|t| &t.tag == b"head" && t.data.len() >= 12) {
+        table.data[8..12].fill(0);
+    }
+    let num_tables = tables.len() as u16;
+    let (search_range, entry_selector, range_shift) = search_params(num_tables, 16);
+
+    let mut w = Writer::new();
+    w.u32(sfnt_version);
+    w.u16(num_tables);
+    w.u16(search_range);
+    w.u16(entry_selector);
+    w.u16(range_shift);
+
+    let mut offset = 12 + 16 * tables.len();
+    let mut head_offset = None;
+    for table in &tables {
+        if &table.tag == b"head" {
+            head_offset = Some(offset);
+        }
+        w.tag(&table.tag);
+        w.u32(checksum(&table.data));
+        w.u32(offset as u32);
+        w.u32(table.data.len() as u32);
+        offset += (table.data.len() + 3) & !3;
+    }
+    for table in &tables {
+        w.bytes(&table.data);
+        w.align(4);
+    }
+
+    if let Some(head_offset) = head_offset {
+        let adjustment = CHECKSUM_MAGIC.wrapping_sub(checksum(w.as_bytes()));
+        w.set_u32(head_offset + 8, adjustment);
+    }
+    w.into_bytes()
+}
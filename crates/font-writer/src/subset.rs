@@ -0,0 +1,268 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::binary::{Reader, Writer};
+use crate::sfnt::{self, Sfnt, Table};
+use crate::tables::layout::gdef::Gdef;
+use crate::tables::layout::gpos::Gpos;
+use crate::tables::layout::gsub::Gsub;
+use crate::tables::{cff, cmap, glyf, hhea, hmtx, os2, post, BBox, GlyphMetrics};
+
+// 与字形无关、原样保留的表；可变字体、彩色字体等其余表在子集中删除
+const COPIED: [&[u8; 4]; 5] = [b"name", b"cvt ", b"fpgm", b"prep", b"gasp"];
+
+fn table<'a>(font: &'a Sfnt, tag: &[u8; 4]) -> Result<&'a [u8], String> {
+    font.table(tag).ok_or_else(|| format!("missing '{}' table", String::from_utf8_lossy(tag)))
+}
+
+// 复制表数据并修改其中一个 u16 字段
+fn patch_u16(data: &[u8], offset: usize, value: u16) -> Result<Vec<u8>, String> {
+    if data.len() < offset + 2 {
+        return Err(format!("table is too short to patch offset {offset}"));
+    }
+    let mut data = data.to_vec();
+    data[offset..offset + 2].copy_from_slice(&value.to_be_bytes());
+    Ok(data)
+}
+
+// GSUB 闭包：反复应用所有替换查找，直到字形集合不再增加
+fn gsub_closure(gsub: &Gsub, glyphs: &mut BTreeSet<u16>) {
+    loop {
+        let mut output = BTreeSet::new();
+        for subtable in gsub.lookups.iter().flat_map(|l| &l.subtables) {
+            subtable.closure(glyphs, &mut output);
+        }
+        let before = glyphs.len();
+        glyphs.extend(output);
+        if glyphs.len() == before {
+            break;
+        }
+    }
+}
+
+// 复合字形闭包：加入所有组件字形（组件本身也可能是复合字形）
+fn composite_closure(glyphs: &[&[u8]], kept: &mut BTreeSet<u16>) -> Result<(), String> {
+    let mut pending: Vec<u16> = kept.iter().copied().collect();
+    while let Some(gid) = pending.pop() {
+        let data = glyphs.get(gid as usize).copied().unwrap_or_default();
+        for position in glyf::component_positions(data)? {
+            let component = Reader::at(data, position).u16()?;
+            if kept.insert(component) {
+                pending.push(component);
+            }
+        }
+    }
+    Ok(())
+}
+
+// 子集化后的 hmtx/vmtx 与对应表头；给出保留字形的包围盒时同时更新 hhea 的步进与侧边距极值
+fn subset_metrics(
+    font: &Sfnt,
+    header_tag: &[u8; 4],
+    metrics_tag: &[u8; 4],
+    num_glyphs: u16,
+    glyph_ids: &[u16],
+    bboxes: Option<&[Option<BBox>]>,
+    tables: &mut Vec<Table>,
+) -> Result<(), String> {
+    let (Some(header), Some(metrics)) = (font.table(header_tag), font.table(metrics_tag)) else {
+        return Ok(());
+    };
+    let num_metrics = Reader::at(header, 34).u16()?;
+    let metrics = hmtx::parse(metrics, num_metrics, num_glyphs)?;
+    let kept: Vec<(u16, i16)> =
+        glyph_ids.iter().map(|&gid| metrics.get(gid as usize).copied().unwrap_or_default()).collect();
+    let (data, count) = hmtx::build_raw(&kept);
+    let mut header = patch_u16(header, 34, count)?;
+    if let Some(bboxes) = bboxes {
+        let metrics: Vec<GlyphMetrics> =
+            kept.iter().zip(bboxes).map(|(&(advance_width, _), &bbox)| GlyphMetrics { advance_width, bbox }).collect();
+        let (advance_width_max, min_lsb, min_rsb, x_max_extent) = hhea::extents(&metrics);
+        for (i, value) in
+            [advance_width_max, min_lsb as u16, min_rsb as u16, x_max_extent as u16].into_iter().enumerate()
+        {
+            header = patch_u16(&header, 10 + 2 * i, value)?;
+        }
+    }
+    tables.push(Table::new(header_tag, header));
+    tables.push(Table::new(metrics_tag, data));
+    Ok(())
+}
+
+fn subset_vorg(data: &[u8], glyph_map: &[Option<u16>]) -> Result<Vec<u8>, String> {
+    let mut r = Reader::new(data);
+    let version = r.u32()?;
+    let default_y = r.i16()?;
+    let count = r.u16()?;
+    let mut records = Vec::new();
+    for _ in 0..count {
+        let (gid, y) = (r.u16()?, r.i16()?);
+        if let Some(new_gid) = glyph_map.get(gid as usize).copied().flatten() {
+            records.push((new_gid, y));
+        }
+    }
+    let mut w = Writer::new();
+    w.u32(version);
+    w.i16(default_y);
+    w.u16(records.len() as u16);
+    for (gid, y) in records {
+        w.u16(gid);
+        w.i16(y);
+    }
+    Ok(w.into_bytes())
+}
+
+fn subset_os2(data: &[u8], map: &BTreeMap<u32, u16>) -> Result<Vec<u8>, String> {
+    let first_char = map.keys().next().map_or(0, |&cp| cp.min(0xFFFF) as u16);
+    let last_char = map.keys().next_back().map_or(0, |&cp| cp.min(0xFFFF) as u16);
+    let mut data = patch_u16(data, 64, first_char)?;
+    data = patch_u16(&data, 66, last_char)?;
+    for (i, range) in os2::unicode_ranges(map).into_iter().enumerate() {
+        data[42 + 4 * i..46 + 4 * i].copy_from_slice(&range.to_be_bytes());
+    }
+    // 版本 1 起才有 ulCodePageRange
+    if Reader::new(&data).u16()? >= 1 && data.len() >= 86 {
+        for (i, range) in os2::code_page_ranges(map).into_iter().enumerate() {
+            data[78 + 4 * i..82 + 4 * i].copy_from_slice(&range.to_be_bytes());
+        }
+    }
+    Ok(data)
+}
+
+fn layout_error(tag: &str) -> impl Fn(String) -> String + '_ {
+    move |err| format!("failed to subset {tag}: {err}")
+}
+
+// 按码位集合对 sfnt 字体（TrueType 或 CFF 轮廓）做子集化，保留 .notdef、映射到的字形、
+// GSUB 可能替换出的字形以及复合字形的组件，字形 ID 按原顺序重新编号
+pub fn subset(data: &[u8], codepoints: &BTreeSet<u32>) -> Result<Vec<u8>, String> {
+    let font = Sfnt::parse(data)?;
+    if font.table(b"CFF2").is_some() {
+        return Err(String::from("CFF2 fonts are not supported"));
+    }
+    let num_glyphs = Reader::at(table(&font, b"maxp")?, 4).u16()?;
+    let mapping = cmap::parse(table(&font, b"cmap")?)?;
+    // 基本字符被保留的变体序列一并保留
+    let mut variations = cmap::parse_variations(table(&font, b"cmap")?)?;
+    variations.retain(|v| codepoints.contains(&v.unicode) && v.glyph.map_or(true, |gid| gid < num_glyphs));
+
+    let mut kept: BTreeSet<u16> = BTreeSet::from([0]);
+    kept.extend(codepoints.iter().filter_map(|cp| mapping.get(cp)).copied());
+    kept.extend(variations.iter().filter_map(|v| v.glyph));
+    let gsub = font.table(b"GSUB").map(Gsub::parse).transpose().map_err(layout_error("GSUB"))?;
+    if let Some(gsub) = &gsub {
+        gsub_closure(gsub, &mut kept);
+    }
+    let head = table(&font, b"head")?;
+    let index_to_loc_format = Reader::at(head, 50).i16()?;
+    let glyphs = match (font.table(b"glyf"), font.table(b"loca")) {
+        (Some(glyf_data), Some(loca)) => Some(glyf::split_with_loca(glyf_data, loca, index_to_loc_format, num_glyphs)?),
+        _ => None,
+    };
+    if let Some(glyphs) = &glyphs {
+        composite_closure(glyphs, &mut kept)?;
+    }
+    kept.retain(|&gid| gid < num_glyphs);
+
+    let glyph_ids: Vec<u16> = kept.iter().copied().collect();
+    let mut glyph_map: Vec<Option<u16>> = vec![None; num_glyphs as usize];
+    for (new_gid, &gid) in glyph_ids.iter().enumerate() {
+        glyph_map[gid as usize] = Some(new_gid as u16);
+    }
+    let mut new_mapping = BTreeMap::new();
+    for cp in codepoints {
+        if let Some(&gid) = mapping.get(cp) {
+            let new_gid =
+                glyph_map.get(gid as usize).copied().flatten().ok_or_else(|| {
+                    format!("cmap maps U+{cp:04X} to glyph {gid}, but the font has {num_glyphs} glyphs")
+                })?;
+            new_mapping.insert(*cp, new_gid);
+        }
+    }
+    for variation in &mut variations {
+        variation.glyph = variation.glyph.and_then(|gid| glyph_map.get(gid as usize).copied().flatten());
+    }
+
+    let mut tables = Vec::new();
+    let mut head = head.to_vec();
+    // 保留字形的包围盒，用于重新计算 head 与 hhea 中的极值
+    let mut bboxes = None;
+    if let Some(glyphs) = &glyphs {
+        // 复合字形的组件 ID 改为新编号
+        let subset_glyphs = glyph_ids
+            .iter()
+            .map(|&gid| {
+                let mut data = glyphs.get(gid as usize).copied().unwrap_or_default().to_vec();
+                for position in glyf::component_positions(&data)? {
+                    let component = Reader::at(&data, position).u16()?;
+                    let new_component = glyph_map
+                        .get(component as usize)
+                        .copied()
+                        .flatten()
+                        .ok_or_else(|| format!("composite glyph {gid} references missing glyph {component}"))?;
+                    data[position..position + 2].copy_from_slice(&new_component.to_be_bytes());
+                }
+                Ok(data)
+            })
+            .collect::<Result<Vec<Vec<u8>>, String>>()?;
+        bboxes = Some(
+            subset_glyphs
+                .iter()
+                .map(|data| {
+                    if data.len() < 10 {
+                        return Ok(None);
+                    }
+                    let mut r = Reader::at(data, 2);
+                    Ok(Some(BBox { x_min: r.i16()?, y_min: r.i16()?, x_max: r.i16()?, y_max: r.i16()? }))
+                })
+                .collect::<Result<Vec<Option<BBox>>, String>>()?,
+        );
+        let (glyf_data, loca, index_to_loc_format) = glyf::build_with_loca(&subset_glyphs);
+        head = patch_u16(&head, 50, index_to_loc_format as u16)?;
+        tables.push(Table::new(b"glyf", glyf_data));
+        tables.push(Table::new(b"loca", loca));
+    }
+    if let Some(cff_data) = font.table(b"CFF ") {
+        let cff_subset = cff::subset::subset(cff_data, &glyph_ids)?;
+        // charstring 无法完整解释时保留原字体的极值
+        bboxes = bboxes.or(cff_subset.bounds);
+        tables.push(Table::new(b"CFF ", cff_subset.data));
+    }
+    if let Some(bboxes) = &bboxes {
+        let bbox = bboxes.iter().flatten().copied().reduce(BBox::union);
+        let bbox = bbox.unwrap_or(BBox { x_min: 0, y_min: 0, x_max: 0, y_max: 0 });
+        for (i, value) in [bbox.x_min, bbox.y_min, bbox.x_max, bbox.y_max].into_iter().enumerate() {
+            head = patch_u16(&head, 36 + 2 * i, value as u16)?;
+        }
+    }
+    tables.push(Table::new(b"head", head));
+    tables.push(Table::new(b"maxp", patch_u16(table(&font, b"maxp")?, 4, glyph_ids.len() as u16)?));
+    tables.push(Table::new(b"cmap", cmap::build(&new_mapping, &variations)?));
+    subset_metrics(&font, b"hhea", b"hmtx", num_glyphs, &glyph_ids, bboxes.as_deref(), &mut tables)?;
+    subset_metrics(&font, b"vhea", b"vmtx", num_glyphs, &glyph_ids, None, &mut tables)?;
+    if let Some(os2_data) = font.table(b"OS/2") {
+        tables.push(Table::new(b"OS/2", subset_os2(os2_data, &new_mapping)?));
+    }
+    if let Some(post_data) = font.table(b"post") {
+        tables.push(Table::new(b"post", post::subset(post_data, &glyph_ids)?));
+    }
+    if let Some(vorg) = font.table(b"VORG") {
+        tables.push(Table::new(b"VORG", subset_vorg(vorg, &glyph_map)?));
+    }
+    if let Some(gsub) = gsub {
+        tables.push(Table::new(b"GSUB", gsub.subset(&glyph_map).build().map_err(layout_error("GSUB"))?));
+    }
+    if let Some(gpos) = font.table(b"GPOS") {
+        let gpos = Gpos::parse(gpos).map_err(layout_error("GPOS"))?;
+        tables.push(Table::new(b"GPOS", gpos.subset(&glyph_map).build().map_err(layout_error("GPOS"))?));
+    }
+    if let Some(gdef) = font.table(b"GDEF") {
+        let gdef = Gdef::parse(gdef).map_err(layout_error("GDEF"))?;
+        tables.push(Table::new(b"GDEF", gdef.subset(&glyph_map).build().map_err(layout_error("GDEF"))?));
+    }
+    for tag in COPIED {
+        if let Some(data) = font.table(tag) {
+            tables.push(Table::new(tag, data.to_vec()));
+        }
+    }
+    Ok(sfnt::assemble(font.version, tables))
+}
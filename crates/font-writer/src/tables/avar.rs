@@ -0,0 +1,26 @@
+use crate::binary::Writer;
+
+// 每个轴的 (归一化输入, 归一化输出) 映射，没有映射的轴写入恒等映射
+pub fn build(segments: &[Vec<(f64, f64)>]) -> Vec<u8> {
+    let mut w = Writer::new();
+    w.u16(1);
+    w.u16(0);
+    w.u16(0);
+    w.u16(segments.len() as u16);
+    for map in segments {
+        if map.is_empty() {
+            w.u16(3);
+            for v in [-1.0, 0.0, 1.0] {
+                w.f2dot14(v);
+                w.f2dot14(v);
+            }
+            continue;
+        }
+        w.u16(map.len() as u16);
+        for &(from, to) in map {
+            w.f2dot14(from);
+            w.f2dot14(to);
+        }
+    }
+    w.into_bytes()
+}
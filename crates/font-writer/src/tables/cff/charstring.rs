@@ -0,0 +1,211 @@
+use crate::outline::{Contour, Point, Segment};
+use crate::tables::BBox;
+
+// Type 2 charstring 操作符
+pub const RLINETO: u8 = 5;
+pub const HLINETO: u8 = 6;
+pub const VLINETO: u8 = 7;
+pub const RRCURVETO: u8 = 8;
+pub const CALLSUBR: u8 = 10;
+pub const RETURN: u8 = 11;
+pub const ENDCHAR: u8 = 14;
+pub const RMOVETO: u8 = 21;
+pub const CALLGSUBR: u8 = 29;
+pub const VHCURVETO: u8 = 30;
+pub const HVCURVETO: u8 = 31;
+
+// Type 2 charstring 中的整数操作数
+pub fn push_number(out: &mut Vec<u8>, v: i32) {
+    match v {
+        -107..=107 => out.push((v + 139) as u8),
+        108..=1131 => {
+            let v = v - 108;
+            out.push(((v >> 8) + 247) as u8);
+            out.push((v & 0xFF) as u8);
+        }
+        -1131..=-108 => {
+            let v = -v - 108;
+            out.push(((v >> 8) + 251) as u8);
+            out.push((v & 0xFF) as u8);
+        }
+        _ => {
+            let v = v.clamp(i16::MIN as i32, i16::MAX as i32) as i16;
+            out.push(28);
+            out.extend_from_slice(&v.to_be_bytes());
+        }
+    }
+}
+
+fn token(operands: &[i32], operator: u8) -> Vec<u8> {
+    let mut out = Vec::with_capacity(operands.len() * 2 + 1);
+    for &v in operands {
+        push_number(&mut out, v);
+    }
+    out.push(operator);
+    out
+}
+
+// 一个字形的 charstring，按“操作数 + 操作符”切分为记号，便于子程序化时匹配重复片段
+#[derive(Debug, Clone, Default)]
+pub struct Charstring {
+    pub tokens: Vec<Vec<u8>>,
+    pub bbox: Option<BBox>,
+}
+
+impl Charstring {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.tokens.concat()
+    }
+}
+
+pub(super) struct Bounds {
+    x_min: f64,
+    y_min: f64,
+    x_max: f64,
+    y_max: f64,
+}
+
+impl Bounds {
+    pub(super) fn new() -> Self {
+        Bounds { x_min: f64::MAX, y_min: f64::MAX, x_max: f64::MIN, y_max: f64::MIN }
+    }
+
+    pub(super) fn add(&mut self, x: f64, y: f64) {
+        self.x_min = self.x_min.min(x);
+        self.y_min = self.y_min.min(y);
+        self.x_max = self.x_max.max(x);
+        self.y_max = self.y_max.max(y);
+    }
+
+    // 三次曲线的精确包围盒：端点加上导数为零处的极值点
+    pub(super) fn add_cubic(&mut self, p: [(f64, f64); 4]) {
+        self.add(p[3].0, p[3].1);
+        for axis in 0..2 {
+            let v: Vec<f64> = p.iter().map(|q| if axis == 0 { q.0 } else { q.1 }).collect();
+            let a = -v[0] + 3.0 * v[1] - 3.0 * v[2] + v[3];
+            let b = 2.0 * (v[0] - 2.0 * v[1] + v[2]);
+            let c = v[1] - v[0];
+            let mut roots = Vec::new();
+            if a.abs() < 1e-12 {
+                if b.abs() > 1e-12 {
+                    roots.push(-c / b);
+                }
+            } else {
+                let d = b * b - 4.0 * a * c;
+                if d >= 0.0 {
+                    let s = d.sqrt();
+                    roots.push((-b + s) / (2.0 * a));
+                    roots.push((-b - s) / (2.0 * a));
+                }
+            }
+            for t in roots.into_iter().filter(|t| *t > 0.0 && *t < 1.0) {
+                let mt = 1.0 - t;
+                let value = mt * mt * mt * v[0] + 3.0 * mt * mt * t * v[1] + 3.0 * mt * t * t * v[2] + t * t * t * v[3];
+                if axis == 0 {
+                    self.add(value, p[0].1);
+                } else {
+                    self.add(p[0].0, value);
+                }
+            }
+        }
+    }
+
+    pub(super) fn to_bbox(&self) -> Option<BBox> {
+        if self.x_min > self.x_max {
+            return None;
+        }
+        Some(BBox {
+            x_min: self.x_min.floor() as i16,
+            y_min: self.y_min.floor() as i16,
+            x_max: self.x_max.ceil() as i16,
+            y_max: self.y_max.ceil() as i16,
+        })
+    }
+}
+
+fn round(p: Point) -> (i32, i32) {
+    (p.x.round() as i32, p.y.round() as i32)
+}
+
+// 将轮廓编码为无 hint 的 Type 2 charstring，width 为相对 nominalWidthX 的宽度（None 表示使用 defaultWidthX）
+pub fn encode(contours: &[Contour], width: Option<i32>) -> Charstring {
+    let mut tokens: Vec<Vec<u8>> = Vec::new();
+    let mut bounds = Bounds::new();
+    let mut pos = (0, 0);
+    let mut width = width;
+
+    for contour in contours.iter().filter(|c| !c.is_empty()) {
+        let start = round(contour[0].start());
+        let mut operands = Vec::with_capacity(3);
+        if let Some(w) = width.take() {
+            operands.push(w);
+        }
+        operands.push(start.0 - pos.0);
+        operands.push(start.1 - pos.1);
+        tokens.push(token(&operands, RMOVETO));
+        pos = start;
+        bounds.add(start.0 as f64, start.1 as f64);
+
+        for (i, segment) in contour.iter().enumerate() {
+            // 回到起点的最后一条直线由 closepath 隐式完成
+            let is_last = i + 1 == contour.len();
+            match segment {
+                Segment::Line { end, .. } => {
+                    let end = round(*end);
+                    let (dx, dy) = (end.0 - pos.0, end.1 - pos.1);
+                    if (dx == 0 && dy == 0) || (is_last && end == start) {
+                        continue;
+                    }
+                    if dy == 0 {
+                        tokens.push(token(&[dx], HLINETO));
+                    } else if dx == 0 {
+                        tokens.push(token(&[dy], VLINETO));
+                    } else {
+                        tokens.push(token(&[dx, dy], RLINETO));
+                    }
+                    pos = end;
+                    bounds.add(end.0 as f64, end.1 as f64);
+                }
+                Segment::Quadratic { start: p0, control, end } => {
+                    // 二次曲线精确升阶为三次曲线
+                    let c1 = Point { x: p0.x + 2.0 / 3.0 * (control.x - p0.x), y: p0.y + 2.0 / 3.0 * (control.y - p0.y) };
+                    let c2 = Point { x: end.x + 2.0 / 3.0 * (control.x - end.x), y: end.y + 2.0 / 3.0 * (control.y - end.y) };
+                    curve(&mut tokens, &mut bounds, &mut pos, round(c1), round(c2), round(*end));
+                }
+                Segment::Cubic { control1, control2, end, .. } => {
+                    curve(&mut tokens, &mut bounds, &mut pos, round(*control1), round(*control2), round(*end));
+                }
+            }
+        }
+    }
+
+    let mut operands = Vec::new();
+    if let Some(w) = width {
+        operands.push(w);
+    }
+    tokens.push(token(&operands, ENDCHAR));
+    Charstring { tokens, bbox: bounds.to_bbox() }
+}
+
+fn curve(
+    tokens: &mut Vec<Vec<u8>>,
+    bounds: &mut Bounds,
+    pos: &mut (i32, i32),
+    c1: (i32, i32),
+    c2: (i32, i32),
+    end: (i32, i32),
+) {
+    let (dx1, dy1) = (c1.0 - pos.0, c1.1 - pos.1);
+    let (dx2, dy2) = (c2.0 - c1.0, c2.1 - c1.1);
+    let (dx3, dy3) = (end.0 - c2.0, end.1 - c2.1);
+    if dy1 == 0 && dx3 == 0 {
+        tokens.push(token(&[dx1, dx2, dy2, dy3], HVCURVETO));
+    } else if dx1 == 0 && dy3 == 0 {
+        tokens.push(token(&[dy1, dx2, dy2, dx3], VHCURVETO));
+    } else {
+        tokens.push(token(&[dx1, dy1, dx2, dy2, dx3, dy3], RRCURVETO));
+    }
+    let f = |p: (i32, i32)| (p.0 as f64, p.1 as f64);
+    bounds.add_cubic([f(*pos), f(c1), f(c2), f(end)]);
+    *pos = end;
+}
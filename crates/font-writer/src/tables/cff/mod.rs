@@ -0,0 +1,282 @@
+pub mod charstring;
+pub mod subroutinize;
+pub mod subset;
+
+use crate::binary::Writer;
+use crate::FontInfo;
+
+use super::BBox;
+use subroutinize::Subroutinized;
+
+// 自定义字符串的 SID 从标准字符串之后开始
+const STANDARD_STRINGS: u16 = 391;
+
+// Top DICT / Private DICT 操作符
+const OP_VERSION: u16 = 0;
+const OP_NOTICE: u16 = 1;
+const OP_FULL_NAME: u16 = 2;
+const OP_FAMILY_NAME: u16 = 3;
+const OP_WEIGHT: u16 = 4;
+const OP_FONT_BBOX: u16 = 5;
+const OP_CHARSET: u16 = 15;
+const OP_CHAR_STRINGS: u16 = 17;
+const OP_PRIVATE: u16 = 18;
+const OP_SUBRS: u16 = 19;
+const OP_DEFAULT_WIDTH_X: u16 = 20;
+const OP_NOMINAL_WIDTH_X: u16 = 21;
+const OP_ITALIC_ANGLE: u16 = 0x0C02;
+const OP_UNDERLINE_POSITION: u16 = 0x0C03;
+const OP_UNDERLINE_THICKNESS: u16 = 0x0C04;
+const OP_FONT_MATRIX: u16 = 0x0C07;
+
+// INDEX 结构：count, offSize, offset 数组（从 1 开始），数据
+pub fn index(items: &[Vec<u8>]) -> Vec<u8> {
+    let mut w = Writer::new();
+    w.u16(items.len() as u16);
+    if items.is_empty() {
+        return w.into_bytes();
+    }
+    let total: usize = items.iter().map(Vec::len).sum::<usize>() + 1;
+    let off_size: u8 = match total {
+        0..=0xFF => 1,
+        0x100..=0xFFFF => 2,
+        0x1_0000..=0xFF_FFFF => 3,
+        _ => 4,
+    };
+    w.u8(off_size);
+    let mut offset = 1usize;
+    let write_offset = |w: &mut Writer, v: usize| match off_size {
+        1 => w.u8(v as u8),
+        2 => w.u16(v as u16),
+        3 => w.u24(v as u32),
+        _ => w.u32(v as u32),
+    };
+    write_offset(&mut w, offset);
+    for item in items {
+        offset += item.len();
+        write_offset(&mut w, offset);
+    }
+    for item in items {
+        w.bytes(item);
+    }
+    w.into_bytes()
+}
+
+#[derive(Debug, Default)]
+struct Dict {
+    data: Vec<u8>,
+}
+
+impl Dict {
+    fn int(&mut self, v: i32) {
+        match v {
+            -107..=107 => self.data.push((v + 139) as u8),
+            108..=1131 => {
+                let v = v - 108;
+                self.data.push(((v >> 8) + 247) as u8);
+                self.data.push((v & 0xFF) as u8);
+            }
+            -1131..=-108 => {
+                let v = -v - 108;
+                self.data.push(((v >> 8) + 251) as u8);
+                self.data.push((v & 0xFF) as u8);
+            }
+            -32768..=32767 => {
+                self.data.push(28);
+                self.data.extend_from_slice(&(v as i16).to_be_bytes());
+            }
+            _ => self.offset(v),
+        }
+    }
+
+    // 固定 5 字节的整数，用于先占位后回填的偏移量
+    fn offset(&mut self, v: i32) {
+        self.data.push(29);
+        self.data.extend_from_slice(&v.to_be_bytes());
+    }
+
+    // 实数：BCD 编码，每个半字节一个字符
+    fn real(&mut self, v: f64) {
+        let text = format!("{v}");
+        let text = if text.contains('e') { format!("{v:e}") } else { text };
+        let mut nibbles: Vec<u8> = Vec::new();
+        let mut chars = text.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '0'..='9' => nibbles.push(c as u8 - b'0'),
+                '.' => nibbles.push(0xA),
+                '-' => nibbles.push(0xE),
+                'e' | 'E' => {
+                    if chars.peek() == Some(&'-') {
+                        chars.next();
+                        nibbles.push(0xC);
+                    } else {
+                        nibbles.push(0xB);
+                    }
+                }
+                _ => {}
+            }
+        }
+        nibbles.push(0xF);
+        if nibbles.len() % 2 == 1 {
+            nibbles.push(0xF);
+        }
+        self.data.push(30);
+        for pair in nibbles.chunks(2) {
+            self.data.push((pair[0] << 4) | pair[1]);
+        }
+    }
+
+    fn op(&mut self, op: u16) {
+        if op > 0xFF {
+            self.data.push((op >> 8) as u8);
+        }
+        self.data.push((op & 0xFF) as u8);
+    }
+}
+
+struct Strings {
+    items: Vec<Vec<u8>>,
+}
+
+impl Strings {
+    fn sid(&mut self, value: &str) -> i32 {
+        self.items.push(value.as_bytes().to_vec());
+        (STANDARD_STRINGS as usize + self.items.len() - 1) as i32
+    }
+}
+
+// charset：格式 0 逐个列出 SID，格式 2 按连续区间记录，取较短者
+fn charset(sids: &[u16]) -> Vec<u8> {
+    let mut format0 = Writer::new();
+    format0.u8(0);
+    for &sid in sids {
+        format0.u16(sid);
+    }
+    let mut format2 = Writer::new();
+    format2.u8(2);
+    let mut i = 0;
+    while i < sids.len() {
+        let mut j = i + 1;
+        while j < sids.len() && sids[j] == sids[j - 1] + 1 && j - i <= 0xFFFF {
+            j += 1;
+        }
+        format2.u16(sids[i]);
+        format2.u16((j - i - 1) as u16);
+        i = j;
+    }
+    if format2.len() < format0.len() {
+        format2.into_bytes()
+    } else {
+        format0.into_bytes()
+    }
+}
+
+pub struct CffFont<'a> {
+    pub info: &'a FontInfo,
+    pub post_script_name: String,
+    // 与字形顺序一致的字形名，0 号为 .notdef
+    pub glyph_names: &'a [String],
+    pub bbox: BBox,
+    pub default_width: i32,
+    pub nominal_width: i32,
+    pub charstrings: Subroutinized,
+}
+
+pub fn build(font: CffFont) -> Vec<u8> {
+    let info = font.info;
+    let mut strings = Strings { items: Vec::new() };
+    let weight = if info.is_bold() { "Bold" } else { "Regular" };
+
+    let mut top = Dict::default();
+    let version = strings.sid(&info.version);
+    top.int(version);
+    top.op(OP_VERSION);
+    if !info.copyright.is_empty() {
+        let notice = strings.sid(&info.copyright);
+        top.int(notice);
+        top.op(OP_NOTICE);
+    }
+    let full_name = strings.sid(&info.full_name());
+    top.int(full_name);
+    top.op(OP_FULL_NAME);
+    let family_name = strings.sid(&info.family_name);
+    top.int(family_name);
+    top.op(OP_FAMILY_NAME);
+    let weight = strings.sid(weight);
+    top.int(weight);
+    top.op(OP_WEIGHT);
+    if info.italic_angle != 0.0 {
+        top.real(info.italic_angle);
+        top.op(OP_ITALIC_ANGLE);
+    }
+    let upm = info.units_per_em as f64;
+    top.int((-upm * 0.1).round() as i32);
+    top.op(OP_UNDERLINE_POSITION);
+    top.int((upm * 0.05).round() as i32);
+    top.op(OP_UNDERLINE_THICKNESS);
+    if info.units_per_em != 1000 {
+        for v in [1.0 / upm, 0.0, 0.0, 1.0 / upm, 0.0, 0.0] {
+            top.real(v);
+        }
+        top.op(OP_FONT_MATRIX);
+    }
+    for v in [font.bbox.x_min, font.bbox.y_min, font.bbox.x_max, font.bbox.y_max] {
+        top.int(v as i32);
+    }
+    top.op(OP_FONT_BBOX);
+
+    let sids: Vec<u16> = font.glyph_names.iter().skip(1).map(|name| strings.sid(name) as u16).collect();
+
+    let mut private = Dict::default();
+    private.int(font.default_width);
+    private.op(OP_DEFAULT_WIDTH_X);
+    private.int(font.nominal_width);
+    private.op(OP_NOMINAL_WIDTH_X);
+    if !font.charstrings.local_subrs.is_empty() {
+        // Subrs 偏移相对于 Private DICT 起点，局部子程序紧跟在 Private DICT 之后
+        let len = private.data.len() as i32 + 6;
+        private.offset(len);
+        private.op(OP_SUBRS);
+    }
+
+    let name_index = index(&[font.post_script_name.into_bytes()]);
+    let string_index = index(&strings.items);
+    let global_subr_index = index(&font.charstrings.global_subrs);
+    let charset = charset(&sids);
+    let char_strings_index = index(&font.charstrings.charstrings);
+    let local_subr_index = index(&font.charstrings.local_subrs);
+
+    // Top DICT 中的偏移量固定占 5 字节，先计算 Top DICT INDEX 的长度再回填
+    let offsets_len = 5 + 1 + 5 + 1 + 5 + 5 + 1;
+    let top_len = top.data.len() + offsets_len;
+    let top_index_len = index(&[vec![0; top_len]]).len();
+    let charset_offset = 4 + name_index.len() + top_index_len + string_index.len() + global_subr_index.len();
+    let char_strings_offset = charset_offset + charset.len();
+    let private_offset = char_strings_offset + char_strings_index.len();
+
+    top.offset(charset_offset as i32);
+    top.op(OP_CHARSET);
+    top.offset(char_strings_offset as i32);
+    top.op(OP_CHAR_STRINGS);
+    top.offset(private.data.len() as i32);
+    top.offset(private_offset as i32);
+    top.op(OP_PRIVATE);
+    let top_index = index(&[top.data]);
+
+    let mut w = Writer::new();
+    // major, minor, hdrSize, offSize
+    w.u8(1);
+    w.u8(0);
+    w.u8(4);
+    w.u8(4);
+    w.bytes(&name_index);
+    w.bytes(&top_index);
+    w.bytes(&string_index);
+    w.bytes(&global_subr_index);
+    w.bytes(&charset);
+    w.bytes(&char_strings_index);
+    w.bytes(&private.data);
+    w.bytes(&local_subr_index);
+    w.into_bytes()
+}
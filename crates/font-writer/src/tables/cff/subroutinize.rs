@@ -0,0 +1,232 @@
+use std::collections::HashMap;
+
+use super::charstring::{push_number, Charstring, CALLGSUBR, CALLSUBR, RETURN};
+
+// 子程序最长包含的记号数
+const MAX_TOKENS: usize = 32;
+
+// 每个 INDEX 最多容纳的子程序数量
+const MAX_SUBRS: usize = 65535;
+
+// 调用一次子程序的估算字节数（索引操作数 + callsubr）
+const CALL_COST: i64 = 3;
+
+#[derive(Debug, Clone, Default)]
+pub struct Subroutinized {
+    pub charstrings: Vec<Vec<u8>>,
+    pub global_subrs: Vec<Vec<u8>>,
+    pub local_subrs: Vec<Vec<u8>>,
+}
+
+// 子程序索引偏置，取决于子程序数量
+pub fn bias(count: usize) -> i32 {
+    if count < 1240 {
+        107
+    } else if count < 33900 {
+        1131
+    } else {
+        32768
+    }
+}
+
+fn number_len(v: i32) -> usize {
+    let mut out = Vec::new();
+    push_number(&mut out, v);
+    out.len()
+}
+
+pub fn without_subroutines(glyphs: &[Charstring]) -> Subroutinized {
+    Subroutinized { charstrings: glyphs.iter().map(Charstring::to_bytes).collect(), ..Default::default() }
+}
+
+struct Candidate {
+    len: usize,
+    bytes: i64,
+    // (字形序号, 起始记号位置)
+    occurrences: Vec<(u32, u32)>,
+}
+
+impl Candidate {
+    fn savings(&self, count: usize) -> i64 {
+        count as i64 * (self.bytes - CALL_COST) - (self.bytes + 1 + CALL_COST)
+    }
+}
+
+// 逐级延长重复片段，只记录“闭合”的片段（延长一个记号后出现次数减少）
+fn collect_candidates(
+    seqs: &[Vec<u32>],
+    token_len: &[i64],
+    occurrences: Vec<(u32, u32)>,
+    len: usize,
+    bytes: i64,
+    out: &mut Vec<Candidate>,
+) {
+    let mut children: HashMap<u32, Vec<(u32, u32)>> = HashMap::new();
+    if len < MAX_TOKENS {
+        for &(g, p) in &occurrences {
+            let seq = &seqs[g as usize];
+            let next = p as usize + len;
+            // 最后一个记号为 endchar，不放入子程序
+            if next + 1 < seq.len() {
+                children.entry(seq[next]).or_default().push((g, p));
+            }
+        }
+    }
+    let closed = !children.values().any(|c| c.len() == occurrences.len());
+    for (token, child) in children {
+        if child.len() >= 2 {
+            collect_candidates(seqs, token_len, child, len + 1, bytes + token_len[token as usize], out);
+        }
+    }
+    if closed && len >= 2 {
+        let candidate = Candidate { len, bytes, occurrences };
+        if candidate.savings(candidate.occurrences.len()) > 0 {
+            out.push(candidate);
+        }
+    }
+}
+
+// 贪心地选择节省字节最多的重复片段作为子程序，按调用次数交替放入全局和局部子程序
+pub fn subroutinize(glyphs: &[Charstring]) -> Subroutinized {
+    let mut ids: HashMap<&[u8], u32> = HashMap::new();
+    let mut token_len: Vec<i64> = Vec::new();
+    let mut tokens: Vec<&[u8]> = Vec::new();
+    let seqs: Vec<Vec<u32>> = glyphs
+        .iter()
+        .map(|glyph| {
+            glyph
+                .tokens
+                .iter()
+                .map(|t| {
+                    *ids.entry(t.as_slice()).or_insert_with(|| {
+                        token_len.push(t.len() as i64);
+                        tokens.push(t.as_slice());
+                        (token_len.len() - 1) as u32
+                    })
+                })
+                .collect()
+        })
+        .collect();
+
+    // 第一个记号可能带有宽度，最后一个记号为 endchar，均不参与匹配
+    let mut roots: HashMap<u32, Vec<(u32, u32)>> = HashMap::new();
+    for (g, seq) in seqs.iter().enumerate() {
+        let end = seq.len().saturating_sub(1);
+        for (p, &token) in seq.iter().enumerate().take(end).skip(1) {
+            roots.entry(token).or_default().push((g as u32, p as u32));
+        }
+    }
+    let mut candidates = Vec::new();
+    for (token, occurrences) in roots {
+        if occurrences.len() >= 2 {
+            collect_candidates(&seqs, &token_len, occurrences, 1, token_len[token as usize], &mut candidates);
+        }
+    }
+    candidates.sort_by(|a, b| {
+        b.savings(b.occurrences.len()).cmp(&a.savings(a.occurrences.len())).then(b.len.cmp(&a.len))
+    });
+
+    // 选择互不重叠的出现位置
+    let mut used: Vec<Vec<bool>> = seqs.iter().map(|s| vec![false; s.len()]).collect();
+    let mut calls: Vec<Vec<(u32, usize, usize)>> = vec![Vec::new(); seqs.len()];
+    let mut subrs: Vec<(Vec<u8>, usize)> = Vec::new();
+    for candidate in &mut candidates {
+        if subrs.len() >= MAX_SUBRS * 2 {
+            break;
+        }
+        candidate.occurrences.sort_unstable();
+        let mut valid = Vec::new();
+        let mut last: Option<(u32, usize)> = None;
+        for &(g, p) in &candidate.occurrences {
+            let p = p as usize;
+            if let Some((lg, end)) = last {
+                if lg == g && p < end {
+                    continue;
+                }
+            }
+            if used[g as usize][p..p + candidate.len].iter().any(|u| *u) {
+                continue;
+            }
+            valid.push((g, p));
+            last = Some((g, p + candidate.len));
+        }
+        if valid.len() < 2 || candidate.savings(valid.len()) <= 0 {
+            continue;
+        }
+        let subr_id = subrs.len() as u32;
+        let (g0, p0) = valid[0];
+        let seq = &seqs[g0 as usize];
+        let mut body: Vec<u8> = seq[p0..p0 + candidate.len].iter().flat_map(|&t| tokens[t as usize].iter().copied()).collect();
+        body.push(RETURN);
+        subrs.push((body, valid.len()));
+        for (g, p) in valid {
+            used[g as usize][p..p + candidate.len].iter_mut().for_each(|u| *u = true);
+            calls[g as usize].push((subr_id, p, candidate.len));
+        }
+    }
+
+    // 调用次数多的子程序优先分配到编码最短的索引，全局与局部交替分配
+    let mut order: Vec<usize> = (0..subrs.len()).collect();
+    order.sort_by(|&a, &b| subrs[b].1.cmp(&subrs[a].1));
+    let global_count = order.len().div_ceil(2);
+    let local_count = order.len() / 2;
+    let slots = |count: usize| -> Vec<usize> {
+        let bias = bias(count);
+        let mut slots: Vec<usize> = (0..count).collect();
+        slots.sort_by_key(|&i| number_len(i as i32 - bias));
+        slots
+    };
+    let global_slots = slots(global_count);
+    let local_slots = slots(local_count);
+    let mut global_subrs = vec![Vec::new(); global_count];
+    let mut local_subrs = vec![Vec::new(); local_count];
+    // 子程序编号 -> (是否全局, 索引)
+    let mut placement = vec![(true, 0usize); subrs.len()];
+    for (rank, &subr) in order.iter().enumerate() {
+        if rank % 2 == 0 {
+            let index = global_slots[rank / 2];
+            placement[subr] = (true, index);
+            global_subrs[index] = std::mem::take(&mut subrs[subr].0);
+        } else {
+            let index = local_slots[rank / 2];
+            placement[subr] = (false, index);
+            local_subrs[index] = std::mem::take(&mut subrs[subr].0);
+        }
+    }
+    let global_bias = bias(global_count);
+    let local_bias = bias(local_count);
+
+    let charstrings = seqs
+        .iter()
+        .zip(calls.iter_mut())
+        .map(|(seq, calls)| {
+            calls.sort_by_key(|c| c.1);
+            let mut out = Vec::new();
+            let mut calls = calls.iter().peekable();
+            let mut p = 0;
+            while p < seq.len() {
+                match calls.peek() {
+                    Some(&&(subr, start, len)) if start == p => {
+                        let (global, index) = placement[subr as usize];
+                        if global {
+                            push_number(&mut out, index as i32 - global_bias);
+                            out.push(CALLGSUBR);
+                        } else {
+                            push_number(&mut out, index as i32 - local_bias);
+                            out.push(CALLSUBR);
+                        }
+                        calls.next();
+                        p += len;
+                    }
+                    _ => {
+                        out.extend_from_slice(tokens[seq[p] as usize]);
+                        p += 1;
+                    }
+                }
+            }
+            out
+        })
+        .collect();
+
+    Subroutinized { charstrings, global_subrs, local_subrs }
+}
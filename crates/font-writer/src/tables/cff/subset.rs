@@ -0,0 +1,661 @@
+use std::collections::HashMap;
+
+use crate::binary::{Reader, Writer};
+use crate::tables::BBox;
+
+use super::charstring::{
+    Bounds, CALLGSUBR, CALLSUBR, ENDCHAR, HLINETO, HVCURVETO, RETURN, RLINETO, RMOVETO, RRCURVETO, VHCURVETO, VLINETO,
+};
+use super::subroutinize::bias;
+use super::{
+    charset, index, Dict, OP_CHARSET, OP_CHAR_STRINGS, OP_FAMILY_NAME, OP_FULL_NAME, OP_NOTICE, OP_PRIVATE, OP_SUBRS,
+    OP_VERSION, OP_WEIGHT, STANDARD_STRINGS,
+};
+
+const OP_ENCODING: u16 = 16;
+const OP_FD_ARRAY: u16 = 0x0C24;
+const OP_FD_SELECT: u16 = 0x0C25;
+const OP_COPYRIGHT: u16 = 0x0C00;
+const OP_POST_SCRIPT: u16 = 0x0C15;
+const OP_BASE_FONT_NAME: u16 = 0x0C16;
+const OP_ROS: u16 = 0x0C1E;
+const OP_FONT_NAME: u16 = 0x0C26;
+
+// 操作数为 SID 的 DICT 条目
+const SID_OPS: [u16; 9] = [
+    OP_VERSION,
+    OP_NOTICE,
+    OP_FULL_NAME,
+    OP_FAMILY_NAME,
+    OP_WEIGHT,
+    OP_COPYRIGHT,
+    OP_POST_SCRIPT,
+    OP_BASE_FONT_NAME,
+    OP_FONT_NAME,
+];
+
+// 解释 charstring 时用到的其余 Type 2 操作符
+const HSTEM: u8 = 1;
+const VSTEM: u8 = 3;
+const VMOVETO: u8 = 4;
+const HSTEMHM: u8 = 18;
+const HINTMASK: u8 = 19;
+const CNTRMASK: u8 = 20;
+const HMOVETO: u8 = 22;
+const VSTEMHM: u8 = 23;
+const RCURVELINE: u8 = 24;
+const RLINECURVE: u8 = 25;
+const VVCURVETO: u8 = 26;
+const HHCURVETO: u8 = 27;
+// 以 12 开头的双字节操作符
+const DOTSECTION: u8 = 0;
+const HFLEX: u8 = 34;
+const FLEX: u8 = 35;
+const HFLEX1: u8 = 36;
+const FLEX1: u8 = 37;
+
+// 子程序最大嵌套深度
+const MAX_CALL_DEPTH: usize = 10;
+
+fn read_index(data: &[u8], pos: usize) -> Result<(Vec<&[u8]>, usize), String> {
+    let mut r = Reader::at(data, pos);
+    let count = r.u16()? as usize;
+    if count == 0 {
+        return Ok((Vec::new(), r.pos()));
+    }
+    let off_size = r.u8()?;
+    let mut offsets = Vec::with_capacity(count + 1);
+    for _ in 0..=count {
+        offsets.push(match off_size {
+            1 => r.u8()? as usize,
+            2 => r.u16()? as usize,
+            3 => r.u24()? as usize,
+            4 => r.u32()? as usize,
+            _ => return Err(format!("invalid CFF INDEX offSize {off_size}")),
+        });
+    }
+    // 偏移量从 1 开始，相对于数据区起点的前一个字节
+    let base = r.pos() - 1;
+    let mut items = Vec::with_capacity(count);
+    for pair in offsets.windows(2) {
+        let item = data.get(base + pair[0]..base + pair[1]).ok_or("CFF INDEX is out of bounds")?;
+        items.push(item);
+    }
+    Ok((items, base + offsets[count]))
+}
+
+// DICT 条目：操作数保留原始字节，整数值另存以便读取偏移量
+struct Entry {
+    op: u16,
+    operands: Vec<u8>,
+    values: Vec<i32>,
+}
+
+fn parse_dict(data: &[u8]) -> Result<Vec<Entry>, String> {
+    let mut r = Reader::new(data);
+    let mut entries = Vec::new();
+    let mut start = 0;
+    let mut values = Vec::new();
+    while r.remaining() > 0 {
+        let pos = r.pos();
+        let b0 = r.u8()?;
+        match b0 {
+            0..=21 => {
+                let op = if b0 == 12 { 0x0C00 | r.u8()? as u16 } else { b0 as u16 };
+                entries.push(Entry { op, operands: data[start..pos].to_vec(), values: std::mem::take(&mut values) });
+                start = r.pos();
+            }
+            28 => values.push(r.i16()? as i32),
+            29 => values.push(r.i32()?),
+            30 => {
+                // 实数只保留原始字节，值记为 0
+                while r.u8()? & 0x0F != 0x0F {}
+                values.push(0);
+            }
+            32..=246 => values.push(b0 as i32 - 139),
+            247..=250 => values.push((b0 as i32 - 247) * 256 + r.u8()? as i32 + 108),
+            251..=254 => values.push(-(b0 as i32 - 251) * 256 - r.u8()? as i32 - 108),
+            _ => return Err(format!("invalid CFF DICT byte {b0}")),
+        }
+    }
+    Ok(entries)
+}
+
+fn dict_value(entries: &[Entry], op: u16) -> Option<&[i32]> {
+    entries.iter().find(|e| e.op == op).map(|e| e.values.as_slice())
+}
+
+// 只保留用到的自定义字符串，SID 按首次使用的顺序重新编号
+struct StringSubset<'a> {
+    strings: &'a [&'a [u8]],
+    items: Vec<Vec<u8>>,
+    sids: HashMap<u16, u16>,
+}
+
+impl StringSubset<'_> {
+    fn sid(&mut self, sid: i32) -> Result<i32, String> {
+        let sid = u16::try_from(sid).map_err(|_| format!("invalid SID {sid}"))?;
+        if sid < STANDARD_STRINGS {
+            return Ok(sid as i32);
+        }
+        if let Some(&new_sid) = self.sids.get(&sid) {
+            return Ok(new_sid as i32);
+        }
+        let value = self.strings.get((sid - STANDARD_STRINGS) as usize).ok_or_else(|| format!("SID {sid} is out of range"))?;
+        let new_sid = STANDARD_STRINGS + self.items.len() as u16;
+        self.items.push(value.to_vec());
+        self.sids.insert(sid, new_sid);
+        Ok(new_sid as i32)
+    }
+}
+
+// 复制除 skip 之外的条目，SID 操作数改为新编号
+fn copy_dict(entries: &[Entry], skip: &[u16], strings: &mut StringSubset) -> Result<Dict, String> {
+    let mut dict = Dict::default();
+    for entry in entries.iter().filter(|e| !skip.contains(&e.op)) {
+        match (entry.op, entry.values.as_slice()) {
+            (op, &[sid]) if SID_OPS.contains(&op) => {
+                let sid = strings.sid(sid)?;
+                dict.int(sid);
+            }
+            (OP_ROS, &[registry, ordering, supplement]) => {
+                let registry = strings.sid(registry)?;
+                dict.int(registry);
+                let ordering = strings.sid(ordering)?;
+                dict.int(ordering);
+                dict.int(supplement);
+            }
+            _ => dict.data.extend_from_slice(&entry.operands),
+        }
+        dict.op(entry.op);
+    }
+    Ok(dict)
+}
+
+// 预定义 charset（ISOAdobe）下 SID 与字形 ID 相同
+fn read_charset(data: &[u8], offset: i32, num_glyphs: usize) -> Result<Vec<u16>, String> {
+    let mut sids = vec![0u16];
+    match offset {
+        0 => sids.extend(1..num_glyphs as u16),
+        1 | 2 => return Err(String::from("expert charsets are not supported")),
+        _ => {
+            let mut r = Reader::at(data, offset as usize);
+            let format = r.u8()?;
+            while sids.len() < num_glyphs {
+                match format {
+                    0 => sids.push(r.u16()?),
+                    1 | 2 => {
+                        let first = r.u16()?;
+                        let left = if format == 1 { r.u8()? as u16 } else { r.u16()? };
+                        sids.extend((0..=left).map(|i| first.wrapping_add(i)));
+                    }
+                    _ => return Err(format!("unknown CFF charset format {format}")),
+                }
+            }
+            sids.truncate(num_glyphs);
+        }
+    }
+    Ok(sids)
+}
+
+fn read_fd_select(data: &[u8], offset: usize, num_glyphs: usize) -> Result<Vec<u8>, String> {
+    let mut r = Reader::at(data, offset);
+    let format = r.u8()?;
+    match format {
+        0 => Ok(r.bytes(num_glyphs)?.to_vec()),
+        3 => {
+            let count = r.u16()?;
+            let mut fds = vec![0u8; num_glyphs];
+            let mut first = r.u16()? as usize;
+            for _ in 0..count {
+                let fd = r.u8()?;
+                let next = r.u16()? as usize;
+                for slot in fds.iter_mut().take(next).skip(first) {
+                    *slot = fd;
+                }
+                first = next;
+            }
+            Ok(fds)
+        }
+        _ => Err(format!("unknown FDSelect format {format}")),
+    }
+}
+
+// FDSelect 格式 3：按连续相同的 FD 分段
+fn fd_select(fds: &[u8]) -> Vec<u8> {
+    let mut ranges: Vec<(u16, u8)> = Vec::new();
+    for (gid, &fd) in fds.iter().enumerate() {
+        if ranges.last().map_or(true, |r| r.1 != fd) {
+            ranges.push((gid as u16, fd));
+        }
+    }
+    let mut w = Writer::new();
+    w.u8(3);
+    w.u16(ranges.len() as u16);
+    for (first, fd) in ranges {
+        w.u16(first);
+        w.u8(fd);
+    }
+    w.u16(fds.len() as u16);
+    w.into_bytes()
+}
+
+struct Private<'a> {
+    entries: Vec<Entry>,
+    subrs: Vec<&'a [u8]>,
+}
+
+fn read_private<'a>(data: &'a [u8], entries: &[Entry]) -> Result<Private<'a>, String> {
+    let Some(&[size, offset]) = dict_value(entries, OP_PRIVATE) else {
+        return Ok(Private { entries: Vec::new(), subrs: Vec::new() });
+    };
+    let (size, offset) = (size as usize, offset as usize);
+    let private = data.get(offset..offset + size).ok_or("CFF Private DICT is out of bounds")?;
+    let entries = parse_dict(private)?;
+    let subrs = match dict_value(&entries, OP_SUBRS) {
+        Some(&[subrs]) => read_index(data, offset + subrs as usize)?.0,
+        _ => Vec::new(),
+    };
+    Ok(Private { entries, subrs })
+}
+
+// 解释 charstring：标记用到的子程序并计算轮廓的包围盒；遇到无法解析的调用或不支持的操作符时返回错误
+struct SubrScanner<'a> {
+    global: &'a [&'a [u8]],
+    local: &'a [&'a [u8]],
+    used_global: &'a mut [bool],
+    used_local: &'a mut [bool],
+    stack: Vec<f64>,
+    stems: usize,
+    x: f64,
+    y: f64,
+    // 只有 moveto 之后画出线段时才计入起点，单独的 moveto 不影响包围盒
+    pending: Option<(f64, f64)>,
+    bounds: Bounds,
+}
+
+// 取栈顶的 N 个操作数，其前可能是字宽
+fn last<const N: usize>(stack: &[f64]) -> Result<[f64; N], String> {
+    let start = stack.len().checked_sub(N).ok_or("charstring stack underflow")?;
+    Ok(stack[start..].try_into().unwrap())
+}
+
+impl SubrScanner<'_> {
+    fn move_to(&mut self, dx: f64, dy: f64) {
+        self.x += dx;
+        self.y += dy;
+        self.pending = Some((self.x, self.y));
+    }
+
+    fn start_segment(&mut self) {
+        if let Some((x, y)) = self.pending.take() {
+            self.bounds.add(x, y);
+        }
+    }
+
+    fn line_to(&mut self, dx: f64, dy: f64) {
+        self.start_segment();
+        self.x += dx;
+        self.y += dy;
+        self.bounds.add(self.x, self.y);
+    }
+
+    fn curve_to(&mut self, d: [f64; 6]) {
+        self.start_segment();
+        let p0 = (self.x, self.y);
+        let p1 = (p0.0 + d[0], p0.1 + d[1]);
+        let p2 = (p1.0 + d[2], p1.1 + d[3]);
+        let p3 = (p2.0 + d[4], p2.1 + d[5]);
+        self.bounds.add_cubic([p0, p1, p2, p3]);
+        (self.x, self.y) = p3;
+    }
+
+    fn curves(&mut self, operands: &[f64]) {
+        for c in operands.chunks_exact(6) {
+            self.curve_to([c[0], c[1], c[2], c[3], c[4], c[5]]);
+        }
+    }
+
+    fn lines(&mut self, operands: &[f64]) {
+        for c in operands.chunks_exact(2) {
+            self.line_to(c[0], c[1]);
+        }
+    }
+
+    // hvcurveto 与 vhcurveto：切线方向交替，最后一段可以多一个操作数
+    fn alternating_curves(&mut self, s: &[f64], mut horizontal: bool) {
+        let count = s.len() / 4;
+        for (i, c) in s.chunks_exact(4).enumerate() {
+            let extra = if i + 1 == count && s.len() % 4 == 1 { s[s.len() - 1] } else { 0.0 };
+            if horizontal {
+                self.curve_to([c[0], 0.0, c[1], c[2], extra, c[3]]);
+            } else {
+                self.curve_to([0.0, c[0], c[1], c[2], c[3], extra]);
+            }
+            horizontal = !horizontal;
+        }
+    }
+
+    fn flex(&mut self, op: u8, s: &[f64]) -> Result<(), String> {
+        match op {
+            FLEX => {
+                let s: [f64; 13] = last(s)?;
+                self.curves(&s[..12]);
+            }
+            HFLEX => {
+                let [dx1, dx2, dy2, dx3, dx4, dx5, dx6] = last(s)?;
+                self.curve_to([dx1, 0.0, dx2, dy2, dx3, 0.0]);
+                self.curve_to([dx4, 0.0, dx5, -dy2, dx6, 0.0]);
+            }
+            HFLEX1 => {
+                let [dx1, dy1, dx2, dy2, dx3, dx4, dx5, dy5, dx6] = last(s)?;
+                self.curve_to([dx1, dy1, dx2, dy2, dx3, 0.0]);
+                self.curve_to([dx4, 0.0, dx5, dy5, dx6, -(dy1 + dy2 + dy5)]);
+            }
+            _ => {
+                let s: [f64; 11] = last(s)?;
+                let dx: f64 = s[..10].iter().step_by(2).sum();
+                let dy: f64 = s[1..10].iter().step_by(2).sum();
+                let (dx6, dy6) = if dx.abs() > dy.abs() { (s[10], -dy) } else { (-dx, s[10]) };
+                self.curve_to([s[0], s[1], s[2], s[3], s[4], s[5]]);
+                self.curve_to([s[6], s[7], s[8], s[9], dx6, dy6]);
+            }
+        }
+        Ok(())
+    }
+
+    // 返回 true 表示遇到 endchar
+    fn run(&mut self, charstring: &[u8], depth: usize) -> Result<bool, String> {
+        if depth > MAX_CALL_DEPTH {
+            return Err(String::from("subroutine nesting is too deep"));
+        }
+        let mut r = Reader::new(charstring);
+        while r.remaining() > 0 {
+            let b0 = r.u8()?;
+            match b0 {
+                28 => self.stack.push(r.i16()? as f64),
+                32..=246 => self.stack.push(b0 as f64 - 139.0),
+                247..=250 => self.stack.push(((b0 as i32 - 247) * 256 + r.u8()? as i32 + 108) as f64),
+                251..=254 => self.stack.push((-(b0 as i32 - 251) * 256 - r.u8()? as i32 - 108) as f64),
+                255 => self.stack.push(r.i32()? as f64 / 65536.0),
+                CALLSUBR | CALLGSUBR => {
+                    let index = self.stack.pop().ok_or("subroutine call without index")? as i32;
+                    let (subrs, used) = if b0 == CALLSUBR {
+                        (self.local, &mut *self.used_local)
+                    } else {
+                        (self.global, &mut *self.used_global)
+                    };
+                    let i = (index + bias(subrs.len())) as usize;
+                    let subr = *subrs.get(i).ok_or_else(|| format!("subroutine {index} is out of range"))?;
+                    used[i] = true;
+                    if self.run(subr, depth + 1)? {
+                        return Ok(true);
+                    }
+                }
+                RETURN => return Ok(false),
+                _ => {
+                    let s = std::mem::take(&mut self.stack);
+                    match b0 {
+                        HSTEM | VSTEM | HSTEMHM | VSTEMHM => self.stems += s.len() / 2,
+                        HINTMASK | CNTRMASK => {
+                            // 前面的操作数为隐含的 vstem
+                            self.stems += s.len() / 2;
+                            r.skip(self.stems.div_ceil(8))?;
+                        }
+                        RMOVETO => {
+                            let [dx, dy] = last(&s)?;
+                            self.move_to(dx, dy);
+                        }
+                        HMOVETO => self.move_to(last::<1>(&s)?[0], 0.0),
+                        VMOVETO => self.move_to(0.0, last::<1>(&s)?[0]),
+                        RLINETO => self.lines(&s),
+                        HLINETO | VLINETO => {
+                            for (i, &d) in s.iter().enumerate() {
+                                if (i % 2 == 0) == (b0 == HLINETO) {
+                                    self.line_to(d, 0.0);
+                                } else {
+                                    self.line_to(0.0, d);
+                                }
+                            }
+                        }
+                        RRCURVETO => self.curves(&s),
+                        RCURVELINE => {
+                            let split = s.len().saturating_sub(2);
+                            self.curves(&s[..split]);
+                            self.lines(&s[split..]);
+                        }
+                        RLINECURVE => {
+                            let split = s.len().saturating_sub(6);
+                            self.lines(&s[..split]);
+                            self.curves(&s[split..]);
+                        }
+                        HHCURVETO | VVCURVETO => {
+                            let (d1, rest) = if s.len() % 2 == 1 { (s[0], &s[1..]) } else { (0.0, &s[..]) };
+                            for (i, c) in rest.chunks_exact(4).enumerate() {
+                                let d1 = if i == 0 { d1 } else { 0.0 };
+                                if b0 == HHCURVETO {
+                                    self.curve_to([c[0], d1, c[1], c[2], c[3], 0.0]);
+                                } else {
+                                    self.curve_to([d1, c[0], c[1], c[2], 0.0, c[3]]);
+                                }
+                            }
+                        }
+                        HVCURVETO | VHCURVETO => self.alternating_curves(&s, b0 == HVCURVETO),
+                        ENDCHAR => {
+                            // 4 或 5 个操作数时为 seac 组合字形
+                            if s.len() >= 4 {
+                                return Err(String::from("seac accented characters are not supported"));
+                            }
+                            return Ok(true);
+                        }
+                        12 => match r.u8()? {
+                            DOTSECTION => {}
+                            op @ (FLEX | HFLEX | HFLEX1 | FLEX1) => self.flex(op, &s)?,
+                            op => return Err(format!("unsupported charstring operator 12 {op}")),
+                        },
+                        _ => {}
+                    }
+                }
+            }
+        }
+        Ok(false)
+    }
+}
+
+// 未用到的子程序替换为只含 return 的空子程序，保持编号不变
+fn prune(subrs: &[&[u8]], used: &[bool]) -> Vec<Vec<u8>> {
+    subrs.iter().zip(used).map(|(subr, &used)| if used { subr.to_vec() } else { vec![RETURN] }).collect()
+}
+
+// 按 glyph_ids（旧字形 ID，按新顺序排列）生成子集 CFF，只支持 CFF 1
+// CharStrings INDEX 中的字形数量，用于校验 maxp.numGlyphs
+pub fn num_glyphs(data: &[u8]) -> Result<usize, String> {
+    let header_size = Reader::at(data, 2).u8()? as usize;
+    let (_, pos) = read_index(data, header_size)?;
+    let (top_dicts, _) = read_index(data, pos)?;
+    let top = parse_dict(top_dicts.first().ok_or("CFF has no Top DICT")?)?;
+    let Some(&[char_strings_offset]) = dict_value(&top, OP_CHAR_STRINGS) else {
+        return Err(String::from("CFF has no CharStrings"));
+    };
+    Ok(read_index(data, char_strings_offset as usize)?.0.len())
+}
+
+// 子集 CFF 与保留字形（按新顺序）的包围盒；charstring 无法完整解释时 bounds 为 None
+pub struct CffSubset {
+    pub data: Vec<u8>,
+    pub bounds: Option<Vec<Option<BBox>>>,
+}
+
+pub fn subset(data: &[u8], glyph_ids: &[u16]) -> Result<CffSubset, String> {
+    let mut r = Reader::new(data);
+    let major = r.u8()?;
+    if major != 1 {
+        return Err(format!("unsupported CFF version {major}"));
+    }
+    r.skip(1)?;
+    let header_size = r.u8()? as usize;
+    let (names, pos) = read_index(data, header_size)?;
+    let (top_dicts, pos) = read_index(data, pos)?;
+    let (strings, pos) = read_index(data, pos)?;
+    let (global_subrs, _) = read_index(data, pos)?;
+    let top = parse_dict(top_dicts.first().ok_or("CFF has no Top DICT")?)?;
+
+    let Some(&[char_strings_offset]) = dict_value(&top, OP_CHAR_STRINGS) else {
+        return Err(String::from("CFF has no CharStrings"));
+    };
+    let (char_strings, _) = read_index(data, char_strings_offset as usize)?;
+    let num_glyphs = char_strings.len();
+    let charset_offset = dict_value(&top, OP_CHARSET).and_then(|v| v.first().copied()).unwrap_or(0);
+    let sids = read_charset(data, charset_offset, num_glyphs)?;
+
+    // CID 字体有 FDArray 与 FDSelect，每个 Font DICT 有自己的 Private DICT 与局部子程序
+    let cid = dict_value(&top, OP_FD_ARRAY).is_some();
+    let (font_dicts, fds, privates) = if cid {
+        let Some(&[fd_array]) = dict_value(&top, OP_FD_ARRAY) else {
+            return Err(String::from("invalid FDArray"));
+        };
+        let Some(&[fd_select]) = dict_value(&top, OP_FD_SELECT) else {
+            return Err(String::from("CID-keyed CFF has no FDSelect"));
+        };
+        let font_dicts =
+            read_index(data, fd_array as usize)?.0.into_iter().map(parse_dict).collect::<Result<Vec<_>, String>>()?;
+        let fds = read_fd_select(data, fd_select as usize, num_glyphs)?;
+        let privates = font_dicts.iter().map(|d| read_private(data, d)).collect::<Result<Vec<_>, String>>()?;
+        (font_dicts, fds, privates)
+    } else {
+        (Vec::new(), vec![0; num_glyphs], vec![read_private(data, &top)?])
+    };
+
+    let charstring = |gid: u16| char_strings.get(gid as usize).copied().ok_or(format!("glyph {gid} is out of range"));
+    let fd_of = |gid: u16| fds.get(gid as usize).copied().unwrap_or(0) as usize;
+
+    // 标记保留字形用到的子程序并计算包围盒，扫描失败时保留全部子程序
+    let mut used_global = vec![false; global_subrs.len()];
+    let mut used_local: Vec<Vec<bool>> = privates.iter().map(|p| vec![false; p.subrs.len()]).collect();
+    let mut bounds = Some(Vec::with_capacity(glyph_ids.len()));
+    for &gid in glyph_ids {
+        let fd = fd_of(gid);
+        let private = privates.get(fd).ok_or_else(|| format!("glyph {gid} refers to missing FD {fd}"))?;
+        let mut scanner = SubrScanner {
+            global: &global_subrs,
+            local: &private.subrs,
+            used_global: &mut used_global,
+            used_local: &mut used_local[fd],
+            stack: Vec::new(),
+            stems: 0,
+            x: 0.0,
+            y: 0.0,
+            pending: None,
+            bounds: Bounds::new(),
+        };
+        if scanner.run(charstring(gid)?, 0).is_err() {
+            bounds = None;
+            break;
+        }
+        if let Some(bounds) = &mut bounds {
+            bounds.push(scanner.bounds.to_bbox());
+        }
+    }
+    if bounds.is_none() {
+        used_global.fill(true);
+        used_local.iter_mut().for_each(|used| used.fill(true));
+    }
+
+    let name_index = index(&names.iter().map(|n| n.to_vec()).collect::<Vec<_>>());
+    let global_subr_index = index(&prune(&global_subrs, &used_global));
+    let mut strings = StringSubset { strings: &strings, items: Vec::new(), sids: HashMap::new() };
+    let new_sids = glyph_ids
+        .iter()
+        .skip(1)
+        .map(|&gid| strings.sid(sids[gid as usize] as i32).map(|sid| sid as u16))
+        .collect::<Result<Vec<u16>, String>>()?;
+    let charset = charset(&new_sids);
+    let fd_select = cid.then(|| fd_select(&glyph_ids.iter().map(|&gid| fd_of(gid) as u8).collect::<Vec<_>>()));
+    let char_strings_index =
+        index(&glyph_ids.iter().map(|&gid| charstring(gid).map(<[u8]>::to_vec)).collect::<Result<Vec<_>, String>>()?);
+
+    // Private DICT 与其后的局部子程序，Subrs 偏移相对于 Private DICT 起点
+    let private_blocks: Vec<(Vec<u8>, Vec<u8>)> = privates
+        .iter()
+        .zip(&used_local)
+        .map(|(private, used)| {
+            let mut dict = copy_dict(&private.entries, &[OP_SUBRS], &mut strings)?;
+            let subrs = if private.subrs.is_empty() { Vec::new() } else { index(&prune(&private.subrs, used)) };
+            if !subrs.is_empty() {
+                let len = dict.data.len() as i32 + 6;
+                dict.offset(len);
+                dict.op(OP_SUBRS);
+            }
+            Ok((dict.data, subrs))
+        })
+        .collect::<Result<_, String>>()?;
+
+    // Top DICT 与 Font DICT 中的偏移量固定占 5 字节，长度可以预先算出
+    let skip = [OP_CHARSET, OP_ENCODING, OP_CHAR_STRINGS, OP_PRIVATE, OP_FD_ARRAY, OP_FD_SELECT];
+    let top_base = copy_dict(&top, &skip, &mut strings)?;
+    let font_dict_base =
+        font_dicts.iter().map(|d| copy_dict(d, &[OP_PRIVATE], &mut strings)).collect::<Result<Vec<Dict>, String>>()?;
+    let string_index = index(&strings.items);
+    let top_len = top_base.data.len() + if cid { 4 * 5 + 1 + 1 + 2 + 2 } else { 4 * 5 + 1 + 1 + 1 };
+    let top_index_len = index(&[vec![0; top_len]]).len();
+    let charset_offset = header_size + name_index.len() + top_index_len + string_index.len() + global_subr_index.len();
+    let fd_select_offset = charset_offset + charset.len();
+    let char_strings_offset = fd_select_offset + fd_select.as_ref().map_or(0, Vec::len);
+    let fd_array_len = if cid {
+        index(&font_dict_base.iter().map(|d| vec![0; d.data.len() + 11]).collect::<Vec<_>>()).len()
+    } else {
+        0
+    };
+    let mut private_offset = char_strings_offset + char_strings_index.len() + fd_array_len;
+    let mut private_offsets = Vec::with_capacity(private_blocks.len());
+    for (dict, subrs) in &private_blocks {
+        private_offsets.push(private_offset);
+        private_offset += dict.len() + subrs.len();
+    }
+
+    let mut top = top_base;
+    top.offset(charset_offset as i32);
+    top.op(OP_CHARSET);
+    top.offset(char_strings_offset as i32);
+    top.op(OP_CHAR_STRINGS);
+    let fd_array_index = if cid {
+        top.offset(fd_select_offset as i32);
+        top.op(OP_FD_SELECT);
+        top.offset((char_strings_offset + char_strings_index.len()) as i32);
+        top.op(OP_FD_ARRAY);
+        let font_dicts: Vec<Vec<u8>> = font_dict_base
+            .into_iter()
+            .zip(private_blocks.iter().zip(&private_offsets))
+            .map(|(mut dict, ((private, _), &offset))| {
+                dict.offset(private.len() as i32);
+                dict.offset(offset as i32);
+                dict.op(OP_PRIVATE);
+                dict.data
+            })
+            .collect();
+        index(&font_dicts)
+    } else {
+        top.offset(private_blocks[0].0.len() as i32);
+        top.offset(private_offsets[0] as i32);
+        top.op(OP_PRIVATE);
+        Vec::new()
+    };
+
+    let mut w = Writer::new();
+    w.bytes(&data[..header_size]);
+    w.bytes(&name_index);
+    w.bytes(&index(&[top.data]));
+    w.bytes(&string_index);
+    w.bytes(&global_subr_index);
+    w.bytes(&charset);
+    if let Some(fd_select) = &fd_select {
+        w.bytes(fd_select);
+    }
+    w.bytes(&char_strings_index);
+    w.bytes(&fd_array_index);
+    for (dict, subrs) in &private_blocks {
+        w.bytes(dict);
+        w.bytes(subrs);
+    }
+    Ok(CffSubset { data: w.into_bytes(), bounds })
+}
@@ -0,0 +1,429 @@
+use std::collections::BTreeMap;
+
+use crate::binary::{Reader, Writer};
+use crate::sfnt::search_params;
+use crate::{FontData, GlyphData};
+
+// Unicode 码位 -> 字形索引，同一码位只取第一个字形
+pub fn mapping(glyphs: &[GlyphData]) -> BTreeMap<u32, u16> {
+    let mut map = BTreeMap::new();
+    for (gid, glyph) in glyphs.iter().enumerate() {
+        if glyph.unicode != 0 && glyph.unicode <= 0x10FFFF {
+            map.entry(glyph.unicode).or_insert(gid as u16);
+        }
+    }
+    map
+}
+
+struct Segment {
+    start: u16,
+    end: u16,
+    // None 表示使用 idDelta，Some 为 glyphIdArray 中的字形序列
+    glyphs: Option<Vec<u16>>,
+    delta: u16,
+}
+
+// 按码位连续且字形索引连续划分 idDelta 段；码位连续但字形跳跃的多段在更省空间时合并为 glyphIdArray 段
+fn segments(map: &BTreeMap<u32, u16>) -> Vec<Segment> {
+    let entries: Vec<(u16, u16)> =
+        map.iter().filter(|(&cp, _)| cp < 0xFFFF).map(|(&cp, &gid)| (cp as u16, gid)).collect();
+
+    let mut segments = Vec::new();
+    let mut i = 0;
+    while i < entries.len() {
+        // 码位连续的一块
+        let mut j = i + 1;
+        while j < entries.len() && entries[j].0 == entries[j - 1].0 + 1 {
+            j += 1;
+        }
+        let block = &entries[i..j];
+        let mut runs: Vec<&[(u16, u16)]> = Vec::new();
+        let mut k = 0;
+        while k < block.len() {
+            let mut l = k + 1;
+            while l < block.len() && block[l].1 == block[l - 1].1.wrapping_add(1) {
+                l += 1;
+            }
+            runs.push(&block[k..l]);
+            k = l;
+        }
+        // 每个独立段占 8 字节，合并后每个码位占 2 字节
+        if runs.len() > 1 && 8 + 2 * block.len() < 8 * runs.len() {
+            segments.push(Segment {
+                start: block[0].0,
+                end: block[block.len() - 1].0,
+                glyphs: Some(block.iter().map(|e| e.1).collect()),
+                delta: 0,
+            });
+        } else {
+            for run in runs {
+                segments.push(Segment {
+                    start: run[0].0,
+                    end: run[run.len() - 1].0,
+                    glyphs: None,
+                    delta: run[0].1.wrapping_sub(run[0].0),
+                });
+            }
+        }
+        i = j;
+    }
+    segments.push(Segment { start: 0xFFFF, end: 0xFFFF, glyphs: None, delta: 1 });
+    segments
+}
+
+// format 4 子表，只覆盖基本多文种平面；长度超出 16 位时返回 None
+pub fn format4(map: &BTreeMap<u32, u16>) -> Option<Vec<u8>> {
+    let segments = segments(map);
+    let seg_count = segments.len();
+    let array_len: usize = segments.iter().filter_map(|s| s.glyphs.as_ref()).map(Vec::len).sum();
+    let length = 16 + 8 * seg_count + 2 * array_len;
+    if length > 0xFFFF {
+        return None;
+    }
+    let (search_range, entry_selector, range_shift) = search_params(seg_count as u16, 2);
+
+    let mut w = Writer::new();
+    w.u16(4);
+    w.u16(length as u16);
+    w.u16(0);
+    w.u16((seg_count * 2) as u16);
+    w.u16(search_range);
+    w.u16(entry_selector);
+    w.u16(range_shift);
+    for s in &segments {
+        w.u16(s.end);
+    }
+    w.u16(0);
+    for s in &segments {
+        w.u16(s.start);
+    }
+    for s in &segments {
+        w.u16(s.delta);
+    }
+    // idRangeOffset 为从该字段自身位置到 glyphIdArray 对应项的字节偏移
+    let mut array_index = 0;
+    for (i, s) in segments.iter().enumerate() {
+        match &s.glyphs {
+            Some(glyphs) => {
+                w.u16((2 * (seg_count - i) + 2 * array_index) as u16);
+                array_index += glyphs.len();
+            }
+            None => w.u16(0),
+        }
+    }
+    for glyphs in segments.iter().filter_map(|s| s.glyphs.as_ref()) {
+        for &gid in glyphs {
+            w.u16(gid);
+        }
+    }
+    Some(w.into_bytes())
+}
+
+// format 12 子表：码位连续且字形索引连续的区间为一组，覆盖全部平面
+pub fn format12(map: &BTreeMap<u32, u16>) -> Vec<u8> {
+    let mut groups: Vec<(u32, u32, u32)> = Vec::new();
+    for (&cp, &gid) in map {
+        match groups.last_mut() {
+            Some(group) if group.1 + 1 == cp && group.2 + (cp - group.0) == gid as u32 => group.1 = cp,
+            _ => groups.push((cp, cp, gid as u32)),
+        }
+    }
+    let mut w = Writer::new();
+    w.u16(12);
+    w.u16(0);
+    w.u32((16 + 12 * groups.len()) as u32);
+    w.u32(0);
+    w.u32(groups.len() as u32);
+    for (start, end, start_gid) in groups {
+        w.u32(start);
+        w.u32(end);
+        w.u32(start_gid);
+    }
+    w.into_bytes()
+}
+
+// 变体序列：glyph 为 None 时为默认变体，使用基本字符在 cmap 中的字形
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct VariationMapping {
+    pub selector: u32,
+    pub unicode: u32,
+    pub glyph: Option<u16>,
+}
+
+// 变体选择符：VS1–VS16、VS17–VS256 以及蒙古文自由变体选择符
+pub fn is_variation_selector(cp: u32) -> bool {
+    matches!(cp, 0x180B..=0x180D | 0x180F | 0xFE00..=0xFE0F | 0xE0100..=0xE01EF)
+}
+
+// 工程中的变体序列转换为字形 ID，自动插入 .notdef 时序号整体后移
+pub fn variation_mappings(font: &FontData) -> Result<Vec<VariationMapping>, String> {
+    let shift = font.notdef_shift();
+    let mut mappings = Vec::with_capacity(font.variation_sequences.len());
+    for sequence in &font.variation_sequences {
+        let name = format!("U+{:04X} U+{:04X}", sequence.unicode, sequence.selector);
+        if !is_variation_selector(sequence.selector) {
+            return Err(format!("variation sequence {name}: U+{:04X} is not a variation selector", sequence.selector));
+        }
+        if sequence.unicode == 0 || sequence.unicode > 0x10FFFF {
+            return Err(format!("variation sequence {name}: invalid base character"));
+        }
+        let glyph = match sequence.glyph {
+            Some(index) if index >= font.glyphs.len() => {
+                return Err(format!("variation sequence {name} refers to missing glyph {index}"));
+            }
+            Some(index) => Some((index + shift) as u16),
+            None => None,
+        };
+        mappings.push(VariationMapping { selector: sequence.selector, unicode: sequence.unicode, glyph });
+    }
+    mappings.sort();
+    mappings.dedup_by_key(|m| (m.selector, m.unicode));
+    Ok(mappings)
+}
+
+// 每个变体选择符下的默认变体码位与非默认变体映射
+type SelectorRecords = (Vec<u32>, Vec<(u32, u16)>);
+
+// format 14 子表：按变体选择符分组，默认变体写入 DefaultUVS 区间，其余写入 NonDefaultUVS 映射
+pub fn format14(mappings: &[VariationMapping]) -> Vec<u8> {
+    let mut selectors: BTreeMap<u32, SelectorRecords> = BTreeMap::new();
+    for mapping in mappings {
+        let (defaults, glyphs) = selectors.entry(mapping.selector).or_default();
+        match mapping.glyph {
+            Some(gid) => glyphs.push((mapping.unicode, gid)),
+            None => defaults.push(mapping.unicode),
+        }
+    }
+    let mut body = Writer::new();
+    let mut records = Vec::with_capacity(selectors.len());
+    let header_len = 10 + 11 * selectors.len();
+    for (&selector, (defaults, glyphs)) in &selectors {
+        let default_offset = if defaults.is_empty() {
+            0
+        } else {
+            let mut ranges: Vec<(u32, u8)> = Vec::new();
+            for &cp in defaults {
+                match ranges.last_mut() {
+                    Some(range) if range.0 + range.1 as u32 + 1 == cp && range.1 < 255 => range.1 += 1,
+                    _ => ranges.push((cp, 0)),
+                }
+            }
+            let offset = header_len + body.len();
+            body.u32(ranges.len() as u32);
+            for (start, additional) in ranges {
+                body.u24(start);
+                body.u8(additional);
+            }
+            offset
+        };
+        let glyph_offset = if glyphs.is_empty() {
+            0
+        } else {
+            let offset = header_len + body.len();
+            body.u32(glyphs.len() as u32);
+            for &(cp, gid) in glyphs {
+                body.u24(cp);
+                body.u16(gid);
+            }
+            offset
+        };
+        records.push((selector, default_offset, glyph_offset));
+    }
+    let mut w = Writer::new();
+    w.u16(14);
+    w.u32((header_len + body.len()) as u32);
+    w.u32(records.len() as u32);
+    for (selector, default_offset, glyph_offset) in records {
+        w.u24(selector);
+        w.u32(default_offset as u32);
+        w.u32(glyph_offset as u32);
+    }
+    w.bytes(body.as_bytes());
+    w.into_bytes()
+}
+
+// 总是写入 format 4（BMP），有补充平面字符时加上 format 12，有变体序列时加上 format 14；
+// BMP 映射过于零散、format 4 超出 64KB 时只写入空的 format 4，完整映射由 format 12 提供
+pub fn build(map: &BTreeMap<u32, u16>, variations: &[VariationMapping]) -> Result<Vec<u8>, String> {
+    let bmp = format4(map);
+    let full = bmp.is_none() || map.keys().any(|&cp| cp > 0xFFFF);
+    let bmp = match bmp {
+        Some(bmp) => bmp,
+        None => format4(&BTreeMap::new()).ok_or("failed to build empty cmap format 4 subtable")?,
+    };
+    let mut subtables: Vec<Vec<u8>> = vec![bmp];
+    // (platformID, encodingID, 子表序号)，按平台与编码排序
+    let mut records = vec![(0u16, 3u16, 0usize), (3, 1, 0)];
+    if full {
+        subtables.push(format12(map));
+        records.push((0, 4, subtables.len() - 1));
+        records.push((3, 10, subtables.len() - 1));
+    }
+    if !variations.is_empty() {
+        subtables.push(format14(variations));
+        records.push((0, 5, subtables.len() - 1));
+    }
+    records.sort();
+
+    let mut offsets = Vec::with_capacity(subtables.len());
+    let mut offset = 4 + 8 * records.len();
+    for subtable in &subtables {
+        offsets.push(offset);
+        offset += subtable.len();
+    }
+    let mut w = Writer::new();
+    w.u16(0);
+    w.u16(records.len() as u16);
+    for (platform_id, encoding_id, subtable) in records {
+        w.u16(platform_id);
+        w.u16(encoding_id);
+        w.u32(offsets[subtable] as u32);
+    }
+    for subtable in &subtables {
+        w.bytes(subtable);
+    }
+    Ok(w.into_bytes())
+}
+
+fn parse_subtable(data: &[u8], map: &mut BTreeMap<u32, u16>) -> Result<(), String> {
+    let mut r = Reader::new(data);
+    let format = r.u16()?;
+    match format {
+        0 => {
+            r.skip(4)?;
+            for (cp, &gid) in r.bytes(256)?.iter().enumerate() {
+                if gid != 0 {
+                    map.entry(cp as u32).or_insert(gid as u16);
+                }
+            }
+        }
+        4 => {
+            r.skip(4)?;
+            let seg_count = r.u16()? as usize / 2;
+            let ends = 14;
+            let starts = ends + 2 * seg_count + 2;
+            let deltas = starts + 2 * seg_count;
+            let range_offsets = deltas + 2 * seg_count;
+            for i in 0..seg_count {
+                let end = Reader::at(data, ends + 2 * i).u16()?;
+                let start = Reader::at(data, starts + 2 * i).u16()?;
+                let delta = Reader::at(data, deltas + 2 * i).u16()?;
+                let range_offset = Reader::at(data, range_offsets + 2 * i).u16()? as usize;
+                for cp in start..=end.max(start) {
+                    if cp == 0xFFFF {
+                        break;
+                    }
+                    let gid = if range_offset == 0 {
+                        cp.wrapping_add(delta)
+                    } else {
+                        let pos = range_offsets + 2 * i + range_offset + 2 * (cp - start) as usize;
+                        match Reader::at(data, pos).u16()? {
+                            0 => 0,
+                            gid => gid.wrapping_add(delta),
+                        }
+                    };
+                    if gid != 0 {
+                        map.entry(cp as u32).or_insert(gid);
+                    }
+                }
+            }
+        }
+        6 => {
+            r.skip(4)?;
+            let first = r.u16()? as u32;
+            let count = r.u16()? as u32;
+            for cp in first..first + count {
+                let gid = r.u16()?;
+                if gid != 0 {
+                    map.entry(cp).or_insert(gid);
+                }
+            }
+        }
+        12 => {
+            r.skip(10)?;
+            let groups = r.u32()?;
+            for _ in 0..groups {
+                let (start, end, start_gid) = (r.u32()?, r.u32()?, r.u32()?);
+                for cp in start..=end.min(0x10FFFF) {
+                    // 字形索引超出 u16 后整组余下的码位都无效
+                    let Some(gid) = start_gid.checked_add(cp - start).filter(|&gid| gid <= u16::MAX as u32) else {
+                        break;
+                    };
+                    if gid != 0 {
+                        map.entry(cp).or_insert(gid as u16);
+                    }
+                }
+            }
+        }
+        // 其余格式（如 14 号变体序列）不含基本映射
+        _ => {}
+    }
+    Ok(())
+}
+
+// 读取 cmap 中所有 Unicode 子表的映射，完整覆盖 Unicode 的子表优先
+pub fn parse(data: &[u8]) -> Result<BTreeMap<u32, u16>, String> {
+    let mut r = Reader::new(data);
+    r.skip(2)?;
+    let count = r.u16()?;
+    let mut records = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let (platform_id, encoding_id, offset) = (r.u16()?, r.u16()?, r.u32()? as usize);
+        let unicode = matches!((platform_id, encoding_id), (0, 0..=4 | 6) | (3, 1 | 10));
+        if unicode {
+            // Unicode 完整平面（0,4 / 0,6 / 3,10）排在前面
+            let full = matches!((platform_id, encoding_id), (0, 4 | 6) | (3, 10));
+            records.push((!full, offset));
+        }
+    }
+    records.sort();
+    let mut map = BTreeMap::new();
+    for (_, offset) in records {
+        let subtable = data.get(offset..).ok_or("cmap subtable is out of bounds")?;
+        parse_subtable(subtable, &mut map)?;
+    }
+    Ok(map)
+}
+
+// 读取 format 14 子表中的变体序列
+pub fn parse_variations(data: &[u8]) -> Result<Vec<VariationMapping>, String> {
+    let mut r = Reader::new(data);
+    r.skip(2)?;
+    let count = r.u16()?;
+    let mut mappings = Vec::new();
+    for _ in 0..count {
+        let (platform_id, encoding_id, offset) = (r.u16()?, r.u16()?, r.u32()? as usize);
+        if (platform_id, encoding_id) != (0, 5) {
+            continue;
+        }
+        let subtable = data.get(offset..).ok_or("cmap subtable is out of bounds")?;
+        let mut s = Reader::new(subtable);
+        if s.u16()? != 14 {
+            continue;
+        }
+        s.skip(4)?;
+        for _ in 0..s.u32()? {
+            let selector = s.u24()?;
+            let default_offset = s.u32()? as usize;
+            let glyph_offset = s.u32()? as usize;
+            if default_offset != 0 {
+                let mut d = Reader::at(subtable, default_offset);
+                for _ in 0..d.u32()? {
+                    let (start, additional) = (d.u24()?, d.u8()?);
+                    for unicode in start..=start + additional as u32 {
+                        mappings.push(VariationMapping { selector, unicode, glyph: None });
+                    }
+                }
+            }
+            if glyph_offset != 0 {
+                let mut g = Reader::at(subtable, glyph_offset);
+                for _ in 0..g.u32()? {
+                    let (unicode, gid) = (g.u24()?, g.u16()?);
+                    mappings.push(VariationMapping { selector, unicode, glyph: Some(gid) });
+                }
+            }
+        }
+        break;
+    }
+    Ok(mappings)
+}
@@ -0,0 +1,213 @@
+use std::collections::HashMap;
+
+use crate::binary::Writer;
+
+// 调色板索引 0xFFFF 表示使用文本的前景色
+pub const FOREGROUND: u16 = 0xFFFF;
+
+const MAX_LAYERS: usize = 255;
+
+#[derive(Debug, Clone)]
+pub struct ColorStop {
+    pub offset: f64,
+    pub palette_index: u16,
+    pub alpha: f64,
+}
+
+// extend：0 = pad，1 = repeat，2 = reflect
+#[derive(Debug, Clone)]
+pub struct ColorLine {
+    pub extend: u8,
+    pub stops: Vec<ColorStop>,
+}
+
+// COLRv1 的绘制节点，坐标为字体单位
+#[derive(Debug, Clone)]
+pub enum Paint {
+    Layers(Vec<Paint>),
+    Solid { palette_index: u16, alpha: f64 },
+    LinearGradient { color_line: ColorLine, points: [(i16, i16); 3] },
+    RadialGradient { color_line: ColorLine, start: (i16, i16), start_radius: u16, end: (i16, i16), end_radius: u16 },
+    Glyph { glyph_id: u16, paint: Box<Paint> },
+    // [xx, yx, xy, yy, dx, dy]
+    Transform { matrix: [f64; 6], paint: Box<Paint> },
+}
+
+// COLRv0 的基础字形：由 (图层字形, 调色板索引) 依次叠加
+#[derive(Debug, Clone)]
+pub struct LayerGlyph {
+    pub glyph_id: u16,
+    pub layers: Vec<(u16, u16)>,
+}
+
+fn color_line(line: &ColorLine) -> Vec<u8> {
+    let mut stops = line.stops.clone();
+    stops.sort_by(|a, b| a.offset.total_cmp(&b.offset));
+    let mut w = Writer::new();
+    w.u8(line.extend);
+    w.u16(stops.len() as u16);
+    for stop in &stops {
+        w.f2dot14(stop.offset);
+        w.u16(stop.palette_index);
+        w.f2dot14(stop.alpha);
+    }
+    w.into_bytes()
+}
+
+// 每个绘制节点连同其子表一起序列化，子表紧跟在节点之后，Offset24 相对于节点起点
+struct PaintWriter {
+    layers: Vec<Vec<u8>>,
+}
+
+impl PaintWriter {
+    fn paint(&mut self, paint: &Paint) -> Vec<u8> {
+        let mut w = Writer::new();
+        match paint {
+            Paint::Layers(children) if children.len() > MAX_LAYERS => {
+                // 图层数超过 255 时分组嵌套
+                let groups: Vec<Paint> = children.chunks(MAX_LAYERS).map(|chunk| Paint::Layers(chunk.to_vec())).collect();
+                return self.paint(&Paint::Layers(groups));
+            }
+            Paint::Layers(children) => {
+                let blobs: Vec<Vec<u8>> = children.iter().map(|child| self.paint(child)).collect();
+                // 相同的图层序列只在 LayerList 中出现一次
+                let existing = if blobs.is_empty() {
+                    None
+                } else {
+                    self.layers.windows(blobs.len()).position(|window| window == blobs.as_slice())
+                };
+                let first = match existing {
+                    Some(first) => first,
+                    None => {
+                        self.layers.extend(blobs.iter().cloned());
+                        self.layers.len() - blobs.len()
+                    }
+                };
+                w.u8(1);
+                w.u8(blobs.len() as u8);
+                w.u32(first as u32);
+            }
+            Paint::Solid { palette_index, alpha } => {
+                w.u8(2);
+                w.u16(*palette_index);
+                w.f2dot14(*alpha);
+            }
+            Paint::LinearGradient { color_line: line, points } => {
+                w.u8(4);
+                w.u24(16);
+                for &(x, y) in points {
+                    w.i16(x);
+                    w.i16(y);
+                }
+                w.bytes(&color_line(line));
+            }
+            Paint::RadialGradient { color_line: line, start, start_radius, end, end_radius } => {
+                w.u8(6);
+                w.u24(16);
+                w.i16(start.0);
+                w.i16(start.1);
+                w.u16(*start_radius);
+                w.i16(end.0);
+                w.i16(end.1);
+                w.u16(*end_radius);
+                w.bytes(&color_line(line));
+            }
+            Paint::Glyph { glyph_id, paint } => {
+                w.u8(10);
+                w.u24(6);
+                w.u16(*glyph_id);
+                let child = self.paint(paint);
+                w.bytes(&child);
+            }
+            Paint::Transform { matrix, paint } => {
+                w.u8(12);
+                w.u24(31);
+                w.u24(7);
+                for &v in matrix {
+                    w.fixed(v);
+                }
+                let child = self.paint(paint);
+                w.bytes(&child);
+            }
+        }
+        w.into_bytes()
+    }
+}
+
+// 由 uint32 计数、Offset32 数组与去重后的数据组成的列表
+fn offset_list(header: impl Fn(&mut Writer, usize, u32), blobs: &[Vec<u8>], record_size: usize) -> Vec<u8> {
+    let mut w = Writer::new();
+    w.u32(blobs.len() as u32);
+    let mut data = Writer::new();
+    let mut offsets: HashMap<&[u8], u32> = HashMap::new();
+    let start = 4 + blobs.len() * record_size;
+    for (i, blob) in blobs.iter().enumerate() {
+        let offset = *offsets.entry(blob.as_slice()).or_insert_with(|| {
+            let offset = (start + data.len()) as u32;
+            data.bytes(blob);
+            offset
+        });
+        header(&mut w, i, offset);
+    }
+    w.bytes(data.as_bytes());
+    w.into_bytes()
+}
+
+// v1 为空时生成 COLR 0，否则生成同时包含 v0 记录的 COLR 1
+pub fn build(v0: &[LayerGlyph], v1: &[(u16, Paint)]) -> Vec<u8> {
+    let mut v0: Vec<&LayerGlyph> = v0.iter().collect();
+    v0.sort_by_key(|g| g.glyph_id);
+    let mut v1: Vec<&(u16, Paint)> = v1.iter().collect();
+    v1.sort_by_key(|(glyph_id, _)| *glyph_id);
+
+    let mut writer = PaintWriter { layers: Vec::new() };
+    let base_paints: Vec<Vec<u8>> = v1.iter().map(|(_, paint)| writer.paint(paint)).collect();
+    let base_glyph_list = offset_list(
+        |w, i, offset| {
+            w.u16(v1[i].0);
+            w.u32(offset);
+        },
+        &base_paints,
+        6,
+    );
+    let layer_list = offset_list(|w, _, offset| w.u32(offset), &writer.layers, 4);
+
+    let header_size = if v1.is_empty() { 14 } else { 34 };
+    let num_layer_records: usize = v0.iter().map(|g| g.layers.len()).sum();
+    let base_records_offset = header_size;
+    let layer_records_offset = base_records_offset + 6 * v0.len();
+    let base_glyph_list_offset = layer_records_offset + 4 * num_layer_records;
+    let layer_list_offset = base_glyph_list_offset + base_glyph_list.len();
+
+    let mut w = Writer::new();
+    w.u16(if v1.is_empty() { 0 } else { 1 });
+    w.u16(v0.len() as u16);
+    w.u32(if v0.is_empty() { 0 } else { base_records_offset as u32 });
+    w.u32(if v0.is_empty() { 0 } else { layer_records_offset as u32 });
+    w.u16(num_layer_records as u16);
+    if !v1.is_empty() {
+        w.u32(base_glyph_list_offset as u32);
+        w.u32(if writer.layers.is_empty() { 0 } else { layer_list_offset as u32 });
+        w.u32(0);
+        w.u32(0);
+        w.u32(0);
+    }
+    let mut first_layer = 0;
+    for glyph in &v0 {
+        w.u16(glyph.glyph_id);
+        w.u16(first_layer as u16);
+        w.u16(glyph.layers.len() as u16);
+        first_layer += glyph.layers.len();
+    }
+    for (glyph_id, palette_index) in v0.iter().flat_map(|g| &g.layers) {
+        w.u16(*glyph_id);
+        w.u16(*palette_index);
+    }
+    if !v1.is_empty() {
+        w.bytes(&base_glyph_list);
+        if !writer.layers.is_empty() {
+            w.bytes(&layer_list);
+        }
+    }
+    w.into_bytes()
+}
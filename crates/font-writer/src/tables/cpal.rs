@@ -0,0 +1,19 @@
+use crate::binary::Writer;
+
+// 只含一个调色板的 CPAL 0，颜色为 RGBA，写入时转为 BGRA
+pub fn build(colors: &[[u8; 4]]) -> Vec<u8> {
+    let mut w = Writer::new();
+    w.u16(0);
+    w.u16(colors.len() as u16);
+    w.u16(1);
+    w.u16(colors.len() as u16);
+    w.u32(14);
+    w.u16(0);
+    for &[r, g, b, a] in colors {
+        w.u8(b);
+        w.u8(g);
+        w.u8(r);
+        w.u8(a);
+    }
+    w.into_bytes()
+}
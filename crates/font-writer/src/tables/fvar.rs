@@ -0,0 +1,47 @@
+use crate::binary::Writer;
+use crate::variable::Axis;
+
+const HIDDEN_AXIS: u16 = 0x0001;
+
+// 命名实例：坐标为各轴的用户坐标，按 fvar 中轴的顺序排列
+#[derive(Debug, Clone)]
+pub struct FvarInstance {
+    pub subfamily_name_id: u16,
+    pub post_script_name_id: Option<u16>,
+    pub coordinates: Vec<f64>,
+}
+
+pub fn build(axes: &[Axis], axis_name_ids: &[u16], instances: &[FvarInstance]) -> Vec<u8> {
+    // 只要有一个实例带 PostScript 名称，所有实例记录都包含 postScriptNameID 字段
+    let with_post_script_name = instances.iter().any(|i| i.post_script_name_id.is_some());
+    let instance_size = 4 + 4 * axes.len() + if with_post_script_name { 2 } else { 0 };
+
+    let mut w = Writer::new();
+    w.u16(1);
+    w.u16(0);
+    w.u16(16);
+    w.u16(2);
+    w.u16(axes.len() as u16);
+    w.u16(20);
+    w.u16(instances.len() as u16);
+    w.u16(instance_size as u16);
+    for (axis, &name_id) in axes.iter().zip(axis_name_ids) {
+        w.tag(&axis.tag_bytes());
+        w.fixed(axis.min_value);
+        w.fixed(axis.default_value);
+        w.fixed(axis.max_value);
+        w.u16(if axis.hidden { HIDDEN_AXIS } else { 0 });
+        w.u16(name_id);
+    }
+    for instance in instances {
+        w.u16(instance.subfamily_name_id);
+        w.u16(0);
+        for &value in &instance.coordinates {
+            w.fixed(value);
+        }
+        if with_post_script_name {
+            w.u16(instance.post_script_name_id.unwrap_or(0xFFFF));
+        }
+    }
+    w.into_bytes()
+}
@@ -0,0 +1,23 @@
+use crate::binary::Writer;
+
+pub const GRIDFIT: u16 = 0x0001;
+pub const DOGRAY: u16 = 0x0002;
+pub const SYMMETRIC_GRIDFIT: u16 = 0x0004;
+pub const SYMMETRIC_SMOOTHING: u16 = 0x0008;
+
+// 版本 1，ranges 为 (rangeMaxPPEM, rangeGaspBehavior)，按 ppem 升序，最后一项应为 0xFFFF
+pub fn build(ranges: &[(u16, u16)]) -> Vec<u8> {
+    let mut w = Writer::new();
+    w.u16(1);
+    w.u16(ranges.len() as u16);
+    for &(max_ppem, behavior) in ranges {
+        w.u16(max_ppem);
+        w.u16(behavior);
+    }
+    w.into_bytes()
+}
+
+// 有提示指令的字体：小字号只做灰度平滑，其余字号同时应用网格对齐
+pub fn hinted() -> Vec<u8> {
+    build(&[(8, DOGRAY | SYMMETRIC_SMOOTHING), (0xFFFF, GRIDFIT | DOGRAY | SYMMETRIC_GRIDFIT | SYMMETRIC_SMOOTHING)])
+}
@@ -0,0 +1,449 @@
+use crate::binary::{Reader, Writer};
+use crate::outline::{reverse_contour, Contour, Point, Segment};
+use crate::quadratic;
+
+use super::BBox;
+
+// 三次曲线转二次曲线的允许误差（字体单位）
+pub const CURVE_TOLERANCE: f64 = 1.0;
+
+const ON_CURVE: u8 = 0x01;
+const X_SHORT: u8 = 0x02;
+const Y_SHORT: u8 = 0x04;
+const REPEAT: u8 = 0x08;
+const X_SAME_OR_POSITIVE: u8 = 0x10;
+const Y_SAME_OR_POSITIVE: u8 = 0x20;
+
+// 复合字形组件标志
+const ARG_1_AND_2_ARE_WORDS: u16 = 0x0001;
+const ARGS_ARE_XY_VALUES: u16 = 0x0002;
+const ROUND_XY_TO_GRID: u16 = 0x0004;
+const WE_HAVE_A_SCALE: u16 = 0x0008;
+const MORE_COMPONENTS: u16 = 0x0020;
+const WE_HAVE_AN_X_AND_Y_SCALE: u16 = 0x0040;
+const WE_HAVE_A_TWO_BY_TWO: u16 = 0x0080;
+const UNSCALED_COMPONENT_OFFSET: u16 = 0x1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GlyphPoint {
+    pub x: i16,
+    pub y: i16,
+    pub on_curve: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SimpleGlyph {
+    pub contours: Vec<Vec<GlyphPoint>>,
+    pub bbox: Option<BBox>,
+}
+
+// 复合字形的组件：matrix 为 [a, b, c, d]（已按 F2Dot14 取整），偏移量不随矩阵缩放
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Component {
+    pub glyph_id: u16,
+    pub matrix: [f64; 4],
+    pub dx: i16,
+    pub dy: i16,
+}
+
+// depth 为组件嵌套层数，只引用简单字形时为 1
+#[derive(Debug, Clone, Default)]
+pub struct CompositeGlyph {
+    pub components: Vec<Component>,
+    pub depth: u16,
+}
+
+fn round(v: f64) -> i16 {
+    v.round().clamp(i16::MIN as f64, i16::MAX as f64) as i16
+}
+
+fn glyph_point(p: Point, on_curve: bool) -> GlyphPoint {
+    GlyphPoint { x: round(p.x), y: round(p.y), on_curve }
+}
+
+// 将一条 Segment 轮廓转换为 TrueType 点序列（首点为起点，终点与起点重合时省略）
+// 工程中的轮廓按 PostScript 约定外轮廓逆时针，TrueType 约定外轮廓顺时针，转换时反转方向
+fn contour_points(contour: &Contour) -> Vec<GlyphPoint> {
+    let contour = &reverse_contour(contour);
+    let Some(first) = contour.first() else {
+        return Vec::new();
+    };
+    let mut points = vec![glyph_point(first.start(), true)];
+    for segment in contour {
+        match segment {
+            Segment::Line { end, .. } => points.push(glyph_point(*end, true)),
+            Segment::Quadratic { control, end, .. } => {
+                points.push(glyph_point(*control, false));
+                points.push(glyph_point(*end, true));
+            }
+            Segment::Cubic { start, control1, control2, end } => {
+                let cubic = [*start, *control1, *control2, *end];
+                for (control, end) in quadratic::cubic_to_quadratic(&cubic, CURVE_TOLERANCE) {
+                    points.push(glyph_point(control, false));
+                    points.push(glyph_point(end, true));
+                }
+            }
+        }
+    }
+    simplify(points)
+}
+
+// 去除重复点、退化的控制点以及可由相邻控制点中点推出的落点
+fn simplify(mut points: Vec<GlyphPoint>) -> Vec<GlyphPoint> {
+    while points.len() > 1 {
+        let n = points.len();
+        let redundant = (0..n).find(|&i| {
+            let prev = points[(i + n - 1) % n];
+            let p = points[i];
+            let next = points[(i + 1) % n];
+            let same_as_prev = p.x == prev.x && p.y == prev.y;
+            let same_as_next = p.x == next.x && p.y == next.y;
+            if p.on_curve {
+                (same_as_prev && prev.on_curve)
+                    || (!prev.on_curve
+                        && !next.on_curve
+                        && 2 * p.x as i32 == prev.x as i32 + next.x as i32
+                        && 2 * p.y as i32 == prev.y as i32 + next.y as i32)
+            } else {
+                (same_as_prev && prev.on_curve) || (same_as_next && next.on_curve)
+            }
+        });
+        match redundant {
+            Some(i) => {
+                points.remove(i);
+            }
+            None => break,
+        }
+    }
+    points
+}
+
+fn segment_kind(segment: &Segment) -> &'static str {
+    match segment {
+        Segment::Line { .. } => "line",
+        Segment::Quadratic { .. } => "quadratic",
+        Segment::Cubic { .. } => "cubic",
+    }
+}
+
+// 检查母版轮廓与默认母版的结构（轮廓数、线段数与线段类型）是否一致
+pub fn check_compatible(default: &[Contour], contours: &[Contour]) -> Result<(), String> {
+    if contours.len() != default.len() {
+        return Err(format!("{} contours, default has {}", contours.len(), default.len()));
+    }
+    for (c, (contour, base)) in contours.iter().zip(default).enumerate() {
+        if contour.len() != base.len() {
+            return Err(format!("contour {c} has {} segments, default has {}", contour.len(), base.len()));
+        }
+        for (s, (segment, base_segment)) in contour.iter().zip(base).enumerate() {
+            if segment_kind(segment) != segment_kind(base_segment) {
+                return Err(format!(
+                    "segment {s} of contour {c} is {}, default has {}",
+                    segment_kind(segment),
+                    segment_kind(base_segment)
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+// 每条三次曲线在所有母版中所需的最大拆分段数
+fn compatible_splits(masters: &[&[Contour]]) -> Vec<Vec<usize>> {
+    let mut splits: Vec<Vec<usize>> = masters[0].iter().map(|contour| vec![1; contour.len()]).collect();
+    for contours in masters {
+        for (contour, splits) in contours.iter().zip(&mut splits) {
+            for (segment, n) in contour.iter().zip(splits.iter_mut()) {
+                if let Segment::Cubic { start, control1, control2, end } = segment {
+                    *n = (*n).max(quadratic::split_count(&[*start, *control1, *control2, *end], CURVE_TOLERANCE));
+                }
+            }
+        }
+    }
+    splits
+}
+
+// 按给定的拆分段数生成点序列，不做简化，保证各母版点数一致
+fn compatible_points(contour: &Contour, splits: &[usize]) -> Vec<GlyphPoint> {
+    let Some(first) = contour.first() else {
+        return Vec::new();
+    };
+    let mut points = vec![glyph_point(first.start(), true)];
+    for (segment, &n) in contour.iter().zip(splits) {
+        match segment {
+            Segment::Line { end, .. } => points.push(glyph_point(*end, true)),
+            Segment::Quadratic { control, end, .. } => {
+                points.push(glyph_point(*control, false));
+                points.push(glyph_point(*end, true));
+            }
+            Segment::Cubic { start, control1, control2, end } => {
+                for (control, end) in quadratic::cubic_to_quadratics(&[*start, *control1, *control2, *end], n) {
+                    points.push(glyph_point(control, false));
+                    points.push(glyph_point(end, true));
+                }
+            }
+        }
+    }
+    points
+}
+
+fn points_bbox(contours: &[Vec<GlyphPoint>]) -> Option<BBox> {
+    contours.iter().flatten().fold(None, |bbox: Option<BBox>, p| {
+        let point = BBox { x_min: p.x, y_min: p.y, x_max: p.x, y_max: p.y };
+        Some(bbox.map_or(point, |b| b.union(point)))
+    })
+}
+
+// 可变字体各母版的字形，首个为默认母版；调用前需先用 check_compatible 检查结构
+pub fn compatible_glyphs(masters: &[&[Contour]]) -> Vec<SimpleGlyph> {
+    if masters.is_empty() {
+        return Vec::new();
+    }
+    // 与 contour_points 一致，先反转为 TrueType 的轮廓方向
+    let reversed: Vec<Vec<Contour>> =
+        masters.iter().map(|contours| contours.iter().map(reverse_contour).collect()).collect();
+    let masters: Vec<&[Contour]> = reversed.iter().map(Vec::as_slice).collect();
+    let splits = compatible_splits(&masters);
+    let mut point_lists: Vec<Vec<Vec<GlyphPoint>>> = masters
+        .iter()
+        .map(|contours| contours.iter().zip(&splits).map(|(contour, n)| compatible_points(contour, n)).collect())
+        .collect();
+    // 所有母版中终点都与起点重合时才省略终点
+    for c in 0..splits.len() {
+        let closed = point_lists.iter().all(|contours| {
+            let points = &contours[c];
+            points.len() > 1 && points[0] == points[points.len() - 1]
+        });
+        if closed {
+            for contours in &mut point_lists {
+                contours[c].pop();
+            }
+        }
+    }
+    // 与 from_contours 一致，丢弃在所有母版中都不足 2 个点的退化轮廓
+    let keep: Vec<bool> = (0..splits.len()).map(|c| point_lists.iter().any(|contours| contours[c].len() >= 2)).collect();
+    point_lists
+        .into_iter()
+        .map(|contours| {
+            let contours: Vec<Vec<GlyphPoint>> =
+                contours.into_iter().zip(&keep).filter(|(_, &keep)| keep).map(|(points, _)| points).collect();
+            let bbox = points_bbox(&contours);
+            SimpleGlyph { contours, bbox }
+        })
+        .collect()
+}
+
+impl SimpleGlyph {
+    pub fn from_contours(contours: &[Contour]) -> Self {
+        let contours: Vec<Vec<GlyphPoint>> =
+            contours.iter().map(contour_points).filter(|points| points.len() >= 2).collect();
+        SimpleGlyph::from_points(contours)
+    }
+
+    pub fn from_points(contours: Vec<Vec<GlyphPoint>>) -> Self {
+        let bbox = points_bbox(&contours);
+        SimpleGlyph { contours, bbox }
+    }
+
+    pub fn num_points(&self) -> usize {
+        self.contours.iter().map(Vec::len).sum()
+    }
+
+    // 编码为 glyf 表中的简单字形，空字形返回空数据；指令长度超出 u16 时不写指令
+    pub fn encode(&self, instructions: &[u8]) -> Vec<u8> {
+        let Some(bbox) = self.bbox else {
+            return Vec::new();
+        };
+        let instructions = if instructions.len() > u16::MAX as usize { &[][..] } else { instructions };
+        let mut w = Writer::new();
+        w.i16(self.contours.len() as i16);
+        w.i16(bbox.x_min);
+        w.i16(bbox.y_min);
+        w.i16(bbox.x_max);
+        w.i16(bbox.y_max);
+        let mut end = 0usize;
+        for contour in &self.contours {
+            end += contour.len();
+            w.u16((end - 1) as u16);
+        }
+        w.u16(instructions.len() as u16);
+        w.bytes(instructions);
+
+        let mut flags: Vec<u8> = Vec::with_capacity(end);
+        let mut xs = Writer::new();
+        let mut ys = Writer::new();
+        let (mut last_x, mut last_y) = (0i32, 0i32);
+        for p in self.contours.iter().flatten() {
+            let mut flag = if p.on_curve { ON_CURVE } else { 0 };
+            let dx = p.x as i32 - last_x;
+            let dy = p.y as i32 - last_y;
+            last_x = p.x as i32;
+            last_y = p.y as i32;
+            if dx == 0 {
+                flag |= X_SAME_OR_POSITIVE;
+            } else if dx.abs() < 256 {
+                flag |= X_SHORT;
+                if dx > 0 {
+                    flag |= X_SAME_OR_POSITIVE;
+                }
+                xs.u8(dx.unsigned_abs() as u8);
+            } else {
+                xs.i16(dx as i16);
+            }
+            if dy == 0 {
+                flag |= Y_SAME_OR_POSITIVE;
+            } else if dy.abs() < 256 {
+                flag |= Y_SHORT;
+                if dy > 0 {
+                    flag |= Y_SAME_OR_POSITIVE;
+                }
+                ys.u8(dy.unsigned_abs() as u8);
+            } else {
+                ys.i16(dy as i16);
+            }
+            flags.push(flag);
+        }
+
+        // 连续相同的标志用 REPEAT 压缩
+        let mut i = 0;
+        while i < flags.len() {
+            let flag = flags[i];
+            let mut run = 1;
+            while i + run < flags.len() && flags[i + run] == flag && run < 256 {
+                run += 1;
+            }
+            if run > 1 {
+                w.u8(flag | REPEAT);
+                w.u8((run - 1) as u8);
+            } else {
+                w.u8(flag);
+            }
+            i += run;
+        }
+        w.bytes(xs.as_bytes());
+        w.bytes(ys.as_bytes());
+        w.into_bytes()
+    }
+}
+
+impl CompositeGlyph {
+    // 编码为 glyf 表中的复合字形，bbox 为展开后各点的包围盒
+    pub fn encode(&self, bbox: Option<BBox>) -> Vec<u8> {
+        let Some(bbox) = bbox else {
+            return Vec::new();
+        };
+        let mut w = Writer::new();
+        w.i16(-1);
+        w.i16(bbox.x_min);
+        w.i16(bbox.y_min);
+        w.i16(bbox.x_max);
+        w.i16(bbox.y_max);
+        for (i, component) in self.components.iter().enumerate() {
+            let [a, b, c, d] = component.matrix;
+            let words = !(i8::MIN as i16..=i8::MAX as i16).contains(&component.dx)
+                || !(i8::MIN as i16..=i8::MAX as i16).contains(&component.dy);
+            let mut flags = ARGS_ARE_XY_VALUES | ROUND_XY_TO_GRID | UNSCALED_COMPONENT_OFFSET;
+            if words {
+                flags |= ARG_1_AND_2_ARE_WORDS;
+            }
+            if i + 1 < self.components.len() {
+                flags |= MORE_COMPONENTS;
+            }
+            let scale: Vec<f64> = if b != 0.0 || c != 0.0 {
+                flags |= WE_HAVE_A_TWO_BY_TWO;
+                vec![a, b, c, d]
+            } else if a != d {
+                flags |= WE_HAVE_AN_X_AND_Y_SCALE;
+                vec![a, d]
+            } else if a != 1.0 {
+                flags |= WE_HAVE_A_SCALE;
+                vec![a]
+            } else {
+                Vec::new()
+            };
+            w.u16(flags);
+            w.u16(component.glyph_id);
+            if words {
+                w.i16(component.dx);
+                w.i16(component.dy);
+            } else {
+                w.i8(component.dx as i8);
+                w.i8(component.dy as i8);
+            }
+            scale.into_iter().for_each(|v| w.f2dot14(v));
+        }
+        w.into_bytes()
+    }
+}
+
+// 由各字形的二进制数据生成 glyf 与 loca，返回 (glyf, loca, indexToLocFormat)
+pub fn build_with_loca(glyphs: &[Vec<u8>]) -> (Vec<u8>, Vec<u8>, i16) {
+    let mut glyf = Writer::new();
+    let mut offsets = Vec::with_capacity(glyphs.len() + 1);
+    for data in glyphs {
+        offsets.push(glyf.len());
+        glyf.bytes(data);
+        glyf.align(2);
+    }
+    offsets.push(glyf.len());
+
+    // 总长度不超过 0x1FFFE 时使用短格式（偏移量除以 2）
+    let short = glyf.len() <= 0x1FFFE;
+    let mut loca = Writer::new();
+    for offset in offsets {
+        if short {
+            loca.u16((offset / 2) as u16);
+        } else {
+            loca.u32(offset as u32);
+        }
+    }
+    (glyf.into_bytes(), loca.into_bytes(), if short { 0 } else { 1 })
+}
+
+pub fn build(glyphs: &[SimpleGlyph]) -> (Vec<u8>, Vec<u8>, i16) {
+    let data: Vec<Vec<u8>> = glyphs.iter().map(|g| g.encode(&[])).collect();
+    build_with_loca(&data)
+}
+
+// 按 loca 切分 glyf，返回各字形的二进制数据
+pub fn split_with_loca<'a>(
+    glyf: &'a [u8],
+    loca: &[u8],
+    index_to_loc_format: i16,
+    num_glyphs: u16,
+) -> Result<Vec<&'a [u8]>, String> {
+    let mut r = Reader::new(loca);
+    let offsets = (0..=num_glyphs)
+        .map(|_| if index_to_loc_format == 0 { r.u16().map(|v| 2 * v as usize) } else { r.u32().map(|v| v as usize) })
+        .collect::<Result<Vec<usize>, String>>()?;
+    offsets
+        .windows(2)
+        .enumerate()
+        .map(|(gid, pair)| glyf.get(pair[0]..pair[1].max(pair[0])).ok_or(format!("glyph {gid} is out of bounds")))
+        .collect()
+}
+
+// 复合字形中各组件字形 ID 的字节位置，简单字形返回空列表
+pub fn component_positions(glyph: &[u8]) -> Result<Vec<usize>, String> {
+    if glyph.is_empty() || Reader::new(glyph).i16()? >= 0 {
+        return Ok(Vec::new());
+    }
+    let mut r = Reader::at(glyph, 10);
+    let mut positions = Vec::new();
+    loop {
+        let flags = r.u16()?;
+        positions.push(r.pos());
+        r.skip(2)?;
+        r.skip(if flags & ARG_1_AND_2_ARE_WORDS != 0 { 4 } else { 2 })?;
+        if flags & WE_HAVE_A_SCALE != 0 {
+            r.skip(2)?;
+        } else if flags & WE_HAVE_AN_X_AND_Y_SCALE != 0 {
+            r.skip(4)?;
+        } else if flags & WE_HAVE_A_TWO_BY_TWO != 0 {
+            r.skip(8)?;
+        }
+        if flags & MORE_COMPONENTS == 0 {
+            return Ok(positions);
+        }
+    }
+}
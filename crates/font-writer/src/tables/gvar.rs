@@ -0,0 +1,181 @@
+use std::collections::HashMap;
+
+use crate::binary::{f2dot14, Writer};
+use crate::variable::Region;
+
+const SHARED_POINT_NUMBERS: u16 = 0x8000;
+const EMBEDDED_PEAK_TUPLE: u16 = 0x8000;
+const INTERMEDIATE_REGION: u16 = 0x4000;
+
+const DELTAS_ARE_ZERO: u8 = 0x80;
+const DELTAS_ARE_WORDS: u8 = 0x40;
+const MAX_RUN: usize = 64;
+
+// 单个区域上字形所有点（含 4 个幻影点）的 x/y 增量
+#[derive(Debug, Clone)]
+pub struct TupleVariation {
+    pub region: Region,
+    pub deltas: Vec<(i16, i16)>,
+}
+
+impl TupleVariation {
+    fn peak(&self) -> Vec<i16> {
+        self.region.iter().map(|r| f2dot14(r[1])).collect()
+    }
+
+    // 起止坐标与峰值推导出的默认区间不同时需要写入中间区域
+    fn intermediate(&self) -> Option<(Vec<i16>, Vec<i16>)> {
+        let start: Vec<i16> = self.region.iter().map(|r| f2dot14(r[0])).collect();
+        let end: Vec<i16> = self.region.iter().map(|r| f2dot14(r[2])).collect();
+        let implied = self
+            .peak()
+            .iter()
+            .zip(start.iter().zip(&end))
+            .all(|(&peak, (&s, &e))| s == peak.min(0) && e == peak.max(0));
+        (!implied).then_some((start, end))
+    }
+}
+
+fn is_byte(v: i16) -> bool {
+    (-128..=127).contains(&v)
+}
+
+// 打包增量：连续的 0、单字节与双字节数值分别成段，每段最多 64 个
+pub fn pack_deltas(deltas: &[i16], w: &mut Writer) {
+    let n = deltas.len();
+    let mut pos = 0;
+    while pos < n {
+        let start = pos;
+        if deltas[pos] == 0 {
+            while pos < n && deltas[pos] == 0 {
+                pos += 1;
+            }
+            let mut run = pos - start;
+            while run > 0 {
+                let chunk = run.min(MAX_RUN);
+                w.u8(DELTAS_ARE_ZERO | (chunk - 1) as u8);
+                run -= chunk;
+            }
+            continue;
+        }
+        let words = !is_byte(deltas[pos]);
+        while pos < n {
+            let value = deltas[pos];
+            let next = deltas.get(pos + 1).copied();
+            if words {
+                // 字段内单个小数值仍按双字节存储，连续两个则另起一段
+                if value == 0 || (is_byte(value) && next.is_some_and(is_byte)) {
+                    break;
+                }
+            } else if !is_byte(value) || (value == 0 && next == Some(0)) {
+                // 单字节段内的单个 0 直接存储，连续的 0 另起一段
+                break;
+            }
+            pos += 1;
+        }
+        for chunk in deltas[start..pos].chunks(MAX_RUN) {
+            if words {
+                w.u8(DELTAS_ARE_WORDS | (chunk.len() - 1) as u8);
+                for &v in chunk {
+                    w.i16(v);
+                }
+            } else {
+                w.u8((chunk.len() - 1) as u8);
+                for &v in chunk {
+                    w.i8(v as i8);
+                }
+            }
+        }
+    }
+}
+
+// 单个字形的 GlyphVariationData，所有元组共用“全部点”的共享点编号
+fn glyph_variation_data(variations: &[TupleVariation], shared_tuples: &HashMap<Vec<i16>, u16>) -> Vec<u8> {
+    if variations.is_empty() {
+        return Vec::new();
+    }
+    let mut headers = Writer::new();
+    let mut serialized = Writer::new();
+    serialized.u8(0);
+    for variation in variations {
+        let mut data = Writer::new();
+        let xs: Vec<i16> = variation.deltas.iter().map(|d| d.0).collect();
+        let ys: Vec<i16> = variation.deltas.iter().map(|d| d.1).collect();
+        pack_deltas(&xs, &mut data);
+        pack_deltas(&ys, &mut data);
+
+        let peak = variation.peak();
+        let intermediate = variation.intermediate();
+        let mut tuple_index = match shared_tuples.get(&peak) {
+            Some(&index) => index,
+            None => EMBEDDED_PEAK_TUPLE,
+        };
+        if intermediate.is_some() {
+            tuple_index |= INTERMEDIATE_REGION;
+        }
+        headers.u16(data.len() as u16);
+        headers.u16(tuple_index);
+        if tuple_index & EMBEDDED_PEAK_TUPLE != 0 {
+            peak.iter().for_each(|&v| headers.i16(v));
+        }
+        if let Some((start, end)) = intermediate {
+            start.iter().chain(&end).for_each(|&v| headers.i16(v));
+        }
+        serialized.bytes(data.as_bytes());
+    }
+
+    let mut w = Writer::new();
+    w.u16(SHARED_POINT_NUMBERS | variations.len() as u16);
+    w.u16((4 + headers.len()) as u16);
+    w.bytes(headers.as_bytes());
+    w.bytes(serialized.as_bytes());
+    w.align(2);
+    w.into_bytes()
+}
+
+// glyphs 为每个字形的元组变化列表，峰值坐标按使用次数排序后放入共享元组
+pub fn build(axis_count: usize, glyphs: &[Vec<TupleVariation>]) -> Vec<u8> {
+    let mut counts: HashMap<Vec<i16>, usize> = HashMap::new();
+    for variation in glyphs.iter().flatten() {
+        *counts.entry(variation.peak()).or_default() += 1;
+    }
+    let mut peaks: Vec<(Vec<i16>, usize)> = counts.into_iter().collect();
+    peaks.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    peaks.truncate(0x0FFF);
+    let shared_tuples: HashMap<Vec<i16>, u16> =
+        peaks.iter().enumerate().map(|(i, (peak, _))| (peak.clone(), i as u16)).collect();
+
+    let data: Vec<Vec<u8>> = glyphs.iter().map(|g| glyph_variation_data(g, &shared_tuples)).collect();
+    let total: usize = data.iter().map(Vec::len).sum();
+    let long_offsets = total > 0x1FFFE;
+
+    let offsets_size = (glyphs.len() + 1) * if long_offsets { 4 } else { 2 };
+    let shared_tuples_offset = 20 + offsets_size;
+    let data_offset = shared_tuples_offset + peaks.len() * axis_count * 2;
+
+    let mut w = Writer::new();
+    w.u16(1);
+    w.u16(0);
+    w.u16(axis_count as u16);
+    w.u16(peaks.len() as u16);
+    w.u32(shared_tuples_offset as u32);
+    w.u16(glyphs.len() as u16);
+    w.u16(if long_offsets { 1 } else { 0 });
+    w.u32(data_offset as u32);
+    let mut offset = 0;
+    for glyph in data.iter().map(Vec::len).chain(std::iter::once(0)) {
+        if long_offsets {
+            w.u32(offset as u32);
+        } else {
+            w.u16((offset / 2) as u16);
+        }
+        offset += glyph;
+    }
+    for (peak, _) in &peaks {
+        peak.iter().for_each(|&v| w.i16(v));
+    }
+    for glyph in &data {
+        w.bytes(glyph);
+    }
+    w.into_bytes()
+}
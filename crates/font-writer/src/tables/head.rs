@@ -0,0 +1,81 @@
+use crate::binary::Writer;
+use crate::FontInfo;
+
+use super::{font_bbox, GlyphMetrics};
+
+// 1904-01-01 与 1970-01-01 之间相差的秒数
+const MAC_EPOCH_OFFSET: i64 = 2_082_844_800;
+
+// 从 "Version 1.002" 之类的版本字符串中解析 fontRevision
+pub fn font_revision(version: &str) -> f64 {
+    let start = version.find(|c: char| c.is_ascii_digit());
+    let Some(start) = start else {
+        return 1.0;
+    };
+    let number: String = version[start..].chars().take_while(|c| c.is_ascii_digit() || *c == '.').collect();
+    let mut parts = number.split('.');
+    let major = parts.next().unwrap_or("1");
+    let minor = parts.next().unwrap_or("0");
+    format!("{major}.{minor}").trim_end_matches('.').parse().unwrap_or(1.0)
+}
+
+// 未指定时间戳时优先使用 SOURCE_DATE_EPOCH，保证同样的输入构建出同样的字体
+fn now() -> i64 {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        use std::time::{SystemTime, UNIX_EPOCH};
+        if let Some(epoch) = std::env::var("SOURCE_DATE_EPOCH").ok().and_then(|v| v.trim().parse().ok()) {
+            return epoch;
+        }
+        SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs() as i64).unwrap_or(0)
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        0
+    }
+}
+
+// 秒级 Unix 时间戳转换为 LONGDATETIME
+pub fn long_date_time(timestamp: Option<i64>) -> i64 {
+    timestamp.unwrap_or_else(now) + MAC_EPOCH_OFFSET
+}
+
+pub fn mac_style(info: &FontInfo) -> u16 {
+    let mut style = 0;
+    if info.is_bold() {
+        style |= 1;
+    }
+    if info.is_italic() {
+        style |= 2;
+    }
+    style
+}
+
+pub fn build(info: &FontInfo, metrics: &[GlyphMetrics], index_to_loc_format: i16) -> Vec<u8> {
+    let bbox = font_bbox(metrics);
+    let created = long_date_time(info.created_timestamp);
+    let modified = info.modified_timestamp.map_or(created, |t| long_date_time(Some(t)));
+
+    let mut w = Writer::new();
+    w.u16(1);
+    w.u16(0);
+    w.fixed(font_revision(&info.version));
+    // checkSumAdjustment 在组装字体时回填
+    w.u32(0);
+    w.u32(0x5F0F_3CF5);
+    // 基线在 y=0，左侧基准点在 x=0，指令可依赖 ppem 为整数
+    w.u16(0x000B);
+    w.u16(info.units_per_em);
+    w.i64(created);
+    w.i64(modified);
+    w.i16(bbox.x_min);
+    w.i16(bbox.y_min);
+    w.i16(bbox.x_max);
+    w.i16(bbox.y_max);
+    w.u16(mac_style(info));
+    w.u16(8);
+    w.i16(2);
+    w.i16(index_to_loc_format);
+    w.i16(0);
+    w.into_bytes()
+}
@@ -0,0 +1,56 @@
+use crate::binary::Writer;
+use crate::FontInfo;
+
+use super::GlyphMetrics;
+
+// advanceWidthMax、minLeftSideBearing、minRightSideBearing 与 xMaxExtent
+pub fn extents(metrics: &[GlyphMetrics]) -> (u16, i16, i16, i16) {
+    let advance_width_max = metrics.iter().map(|m| m.advance_width).max().unwrap_or(0);
+    // 侧边距极值只统计有轮廓的字形
+    let mut min_lsb = i16::MAX;
+    let mut min_rsb = i16::MAX;
+    let mut x_max_extent = i16::MIN;
+    for m in metrics {
+        if let Some(bbox) = m.bbox {
+            let rsb = m.advance_width as i32 - bbox.x_max as i32;
+            min_lsb = min_lsb.min(bbox.x_min);
+            min_rsb = min_rsb.min(rsb.clamp(i16::MIN as i32, i16::MAX as i32) as i16);
+            x_max_extent = x_max_extent.max(bbox.x_max);
+        }
+    }
+    if x_max_extent == i16::MIN {
+        (min_lsb, min_rsb, x_max_extent) = (0, 0, 0);
+    }
+    (advance_width_max, min_lsb, min_rsb, x_max_extent)
+}
+
+pub fn build(info: &FontInfo, metrics: &[GlyphMetrics], number_of_h_metrics: u16) -> Vec<u8> {
+    let (advance_width_max, min_lsb, min_rsb, x_max_extent) = extents(metrics);
+
+    let mut w = Writer::new();
+    w.u16(1);
+    w.u16(0);
+    w.i16(info.ascender);
+    w.i16(info.descender);
+    w.i16(info.line_gap);
+    w.u16(advance_width_max);
+    w.i16(min_lsb);
+    w.i16(min_rsb);
+    w.i16(x_max_extent);
+    // 斜体时按 italicAngle 计算光标斜率
+    if info.italic_angle != 0.0 {
+        let run = -(info.italic_angle.to_radians().tan() * 1000.0).round() as i16;
+        w.i16(1000);
+        w.i16(run);
+    } else {
+        w.i16(1);
+        w.i16(0);
+    }
+    w.i16(0);
+    for _ in 0..4 {
+        w.i16(0);
+    }
+    w.i16(0);
+    w.u16(number_of_h_metrics);
+    w.into_bytes()
+}
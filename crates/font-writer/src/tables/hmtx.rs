@@ -0,0 +1,40 @@
+use crate::binary::{Reader, Writer};
+
+use super::GlyphMetrics;
+
+// 返回 (hmtx, numberOfHMetrics)：末尾连续相同的步进宽度只保留一条完整记录
+pub fn build(metrics: &[GlyphMetrics]) -> (Vec<u8>, u16) {
+    let metrics: Vec<(u16, i16)> = metrics.iter().map(|m| (m.advance_width, m.left_side_bearing())).collect();
+    build_raw(&metrics)
+}
+
+// 由 (步进, 侧边距) 生成 hmtx 或 vmtx，返回数据与完整记录数
+pub fn build_raw(metrics: &[(u16, i16)]) -> (Vec<u8>, u16) {
+    let mut count = metrics.len();
+    while count > 1 && metrics[count - 1].0 == metrics[count - 2].0 {
+        count -= 1;
+    }
+    let mut w = Writer::new();
+    for (i, &(advance, bearing)) in metrics.iter().enumerate() {
+        if i < count {
+            w.u16(advance);
+        }
+        w.i16(bearing);
+    }
+    (w.into_bytes(), count as u16)
+}
+
+// 读取 hmtx 或 vmtx，省略的步进沿用最后一条完整记录
+pub fn parse(data: &[u8], num_metrics: u16, num_glyphs: u16) -> Result<Vec<(u16, i16)>, String> {
+    let mut r = Reader::new(data);
+    let mut metrics = Vec::with_capacity(num_glyphs as usize);
+    let mut advance = 0;
+    for i in 0..num_glyphs {
+        if i < num_metrics {
+            advance = r.u16()?;
+        }
+        // 部分字体省略末尾的侧边距，按 0 处理
+        metrics.push((advance, r.i16().unwrap_or(0)));
+    }
+    Ok(metrics)
+}
@@ -0,0 +1,82 @@
+use crate::binary::Writer;
+use crate::variable::Region;
+
+const LONG_WORDS: u16 = 0x8000;
+
+// 每列增量所需的字节数：0 表示整列为 0 可省略
+fn column_size(column: impl Iterator<Item = i32>) -> usize {
+    column.fold(0, |size, v| {
+        let needed = if v == 0 {
+            0
+        } else if (-128..=127).contains(&v) {
+            1
+        } else if (-32768..=32767).contains(&v) {
+            2
+        } else {
+            4
+        };
+        size.max(needed)
+    })
+}
+
+// 只含一个 ItemVariationData 的 ItemVariationStore，rows[i] 为第 i 项在各区域上的增量
+pub fn item_variation_store(axis_count: usize, regions: &[Region], rows: &[Vec<i32>]) -> Vec<u8> {
+    let mut columns: Vec<(usize, usize)> = (0..regions.len())
+        .map(|r| (r, column_size(rows.iter().map(|row| row[r]))))
+        .filter(|&(_, size)| size > 0)
+        .collect();
+    // 宽列排在前面
+    columns.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    let long_words = columns.iter().any(|&(_, size)| size == 4);
+    let word_size = if long_words { 4 } else { 2 };
+    let word_count = columns.iter().filter(|&&(_, size)| size >= word_size).count();
+
+    let region_list_offset = 12;
+    let region_list_size = 4 + regions.len() * axis_count * 6;
+    let mut w = Writer::new();
+    w.u16(1);
+    w.u32(region_list_offset as u32);
+    w.u16(1);
+    w.u32((region_list_offset + region_list_size) as u32);
+
+    w.u16(axis_count as u16);
+    w.u16(regions.len() as u16);
+    for region in regions {
+        for tent in region {
+            w.f2dot14(tent[0]);
+            w.f2dot14(tent[1]);
+            w.f2dot14(tent[2]);
+        }
+    }
+
+    w.u16(rows.len() as u16);
+    w.u16(word_count as u16 | if long_words { LONG_WORDS } else { 0 });
+    w.u16(columns.len() as u16);
+    for &(region, _) in &columns {
+        w.u16(region as u16);
+    }
+    for row in rows {
+        for (i, &(region, _)) in columns.iter().enumerate() {
+            let value = row[region];
+            match (i < word_count, long_words) {
+                (true, true) => w.i32(value),
+                (true, false) | (false, true) => w.i16(value as i16),
+                (false, false) => w.i8(value as i8),
+            }
+        }
+    }
+    w.into_bytes()
+}
+
+// 步进宽度增量按字形序号直接索引，不写入 DeltaSetIndexMap
+pub fn build(axis_count: usize, regions: &[Region], advance_deltas: &[Vec<i32>]) -> Vec<u8> {
+    let mut w = Writer::new();
+    w.u16(1);
+    w.u16(0);
+    w.u32(20);
+    w.u32(0);
+    w.u32(0);
+    w.u32(0);
+    w.bytes(&item_variation_store(axis_count, regions, advance_deltas));
+    w.into_bytes()
+}
@@ -0,0 +1,292 @@
+use std::collections::BTreeMap;
+
+use crate::binary::Reader;
+
+use super::{
+    class_def, coverage, read_class_def, read_coverage, remap_classes, remap_glyph, remap_glyphs, sub, GlyphMap, Packer,
+};
+
+// 在输入序列第 sequence_index 个字形处应用查找 lookup_index
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SequenceLookup {
+    pub sequence_index: u16,
+    pub lookup_index: u16,
+}
+
+// 一条规则：backtrack 按由近及远的顺序排列，input 不含首个字形（或类别），按字形或类别解释由所在子表决定
+#[derive(Debug, Clone, Default)]
+pub struct ChainRule {
+    pub backtrack: Vec<u16>,
+    pub input: Vec<u16>,
+    pub lookahead: Vec<u16>,
+    pub lookups: Vec<SequenceLookup>,
+}
+
+// 上下文与链式上下文子表（GSUB 5/6、GPOS 7/8），解析后统一按链式上下文写出
+#[derive(Debug, Clone)]
+pub enum ChainContext {
+    // 格式 1：按首个字形分组的规则
+    Glyphs(BTreeMap<u16, Vec<ChainRule>>),
+    // 格式 2：按首个字形类别分组的规则
+    Classes {
+        coverage: Vec<u16>,
+        backtrack_classes: BTreeMap<u16, u16>,
+        input_classes: BTreeMap<u16, u16>,
+        lookahead_classes: BTreeMap<u16, u16>,
+        rules: BTreeMap<u16, Vec<ChainRule>>,
+    },
+    // 格式 3：每个位置一个 Coverage，backtrack 同样由近及远
+    Coverages {
+        backtrack: Vec<Vec<u16>>,
+        input: Vec<Vec<u16>>,
+        lookahead: Vec<Vec<u16>>,
+        lookups: Vec<SequenceLookup>,
+    },
+}
+
+fn read_glyphs(r: &mut Reader, count: usize) -> Result<Vec<u16>, String> {
+    (0..count).map(|_| r.u16()).collect()
+}
+
+fn read_lookups(r: &mut Reader) -> Result<Vec<SequenceLookup>, String> {
+    let count = r.u16()?;
+    (0..count).map(|_| Ok(SequenceLookup { sequence_index: r.u16()?, lookup_index: r.u16()? })).collect()
+}
+
+fn read_coverages(data: &[u8], r: &mut Reader) -> Result<Vec<Vec<u16>>, String> {
+    let count = r.u16()?;
+    (0..count).map(|_| read_coverage(sub(data, r.u16()? as usize)?)).collect()
+}
+
+// 规则集：chained 为 false 时是非链式上下文规则（无 backtrack 与 lookahead，输入数与查找数在前）
+fn read_rule_sets(data: &[u8], r: &mut Reader, chained: bool) -> Result<Vec<Option<Vec<ChainRule>>>, String> {
+    let count = r.u16()?;
+    let mut sets = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let offset = r.u16()? as usize;
+        if offset == 0 {
+            sets.push(None);
+            continue;
+        }
+        let set = sub(data, offset)?;
+        let mut s = Reader::new(set);
+        let mut rules = Vec::new();
+        for _ in 0..s.u16()? {
+            let mut rule = Reader::new(sub(set, s.u16()? as usize)?);
+            rules.push(if chained {
+                let backtrack_count = rule.u16()? as usize;
+                let backtrack = read_glyphs(&mut rule, backtrack_count)?;
+                let input_count = rule.u16()? as usize;
+                let input = read_glyphs(&mut rule, input_count.saturating_sub(1))?;
+                let lookahead_count = rule.u16()? as usize;
+                let lookahead = read_glyphs(&mut rule, lookahead_count)?;
+                ChainRule { backtrack, input, lookahead, lookups: read_lookups(&mut rule)? }
+            } else {
+                let input_count = rule.u16()? as usize;
+                let lookup_count = rule.u16()?;
+                let input = read_glyphs(&mut rule, input_count.saturating_sub(1))?;
+                let lookups = (0..lookup_count)
+                    .map(|_| Ok(SequenceLookup { sequence_index: rule.u16()?, lookup_index: rule.u16()? }))
+                    .collect::<Result<Vec<_>, String>>()?;
+                ChainRule { input, lookups, ..ChainRule::default() }
+            });
+        }
+        sets.push(Some(rules));
+    }
+    Ok(sets)
+}
+
+fn write_rule_sets(p: &mut Packer, sets: &[Option<&Vec<ChainRule>>]) -> Result<(), String> {
+    p.u16(sets.len() as u16);
+    for set in sets {
+        let Some(rules) = set else {
+            p.u16(0);
+            continue;
+        };
+        let mut s = Packer::new();
+        s.u16(rules.len() as u16);
+        for rule in rules.iter() {
+            let mut w = Packer::new();
+            w.u16(rule.backtrack.len() as u16);
+            rule.backtrack.iter().for_each(|&g| w.u16(g));
+            w.u16(rule.input.len() as u16 + 1);
+            rule.input.iter().for_each(|&g| w.u16(g));
+            w.u16(rule.lookahead.len() as u16);
+            rule.lookahead.iter().for_each(|&g| w.u16(g));
+            write_lookups(&mut w, &rule.lookups);
+            s.offset(w.finish()?);
+        }
+        p.offset(s.finish()?);
+    }
+    Ok(())
+}
+
+fn write_lookups(p: &mut Packer, lookups: &[SequenceLookup]) {
+    p.u16(lookups.len() as u16);
+    for lookup in lookups {
+        p.u16(lookup.sequence_index);
+        p.u16(lookup.lookup_index);
+    }
+}
+
+fn remap_lookups(lookups: &[SequenceLookup], map: &[Option<u16>]) -> Vec<SequenceLookup> {
+    lookups
+        .iter()
+        .filter_map(|l| {
+            let lookup_index = map.get(l.lookup_index as usize).copied().flatten()?;
+            Some(SequenceLookup { sequence_index: l.sequence_index, lookup_index })
+        })
+        .collect()
+}
+
+impl ChainContext {
+    // chained 为 false 时按非链式上下文子表（GSUB 5、GPOS 7）解析
+    pub fn parse(data: &[u8], chained: bool) -> Result<Self, String> {
+        let mut r = Reader::new(data);
+        let format = r.u16()?;
+        match format {
+            1 => {
+                let coverage = read_coverage(sub(data, r.u16()? as usize)?)?;
+                let sets = read_rule_sets(data, &mut r, chained)?;
+                let rules = coverage.into_iter().zip(sets).filter_map(|(g, set)| set.map(|set| (g, set))).collect();
+                Ok(ChainContext::Glyphs(rules))
+            }
+            2 => {
+                let coverage = read_coverage(sub(data, r.u16()? as usize)?)?;
+                let (backtrack_classes, lookahead_classes);
+                let input_classes;
+                if chained {
+                    backtrack_classes = read_class_def(sub(data, r.u16()? as usize)?)?;
+                    input_classes = read_class_def(sub(data, r.u16()? as usize)?)?;
+                    lookahead_classes = read_class_def(sub(data, r.u16()? as usize)?)?;
+                } else {
+                    input_classes = read_class_def(sub(data, r.u16()? as usize)?)?;
+                    backtrack_classes = BTreeMap::new();
+                    lookahead_classes = BTreeMap::new();
+                }
+                let rules = read_rule_sets(data, &mut r, chained)?
+                    .into_iter()
+                    .enumerate()
+                    .filter_map(|(class, set)| set.map(|set| (class as u16, set)))
+                    .collect();
+                Ok(ChainContext::Classes { coverage, backtrack_classes, input_classes, lookahead_classes, rules })
+            }
+            3 if chained => {
+                let backtrack = read_coverages(data, &mut r)?;
+                let input = read_coverages(data, &mut r)?;
+                let lookahead = read_coverages(data, &mut r)?;
+                Ok(ChainContext::Coverages { backtrack, input, lookahead, lookups: read_lookups(&mut r)? })
+            }
+            3 => {
+                let input_count = r.u16()?;
+                let lookup_count = r.u16()?;
+                let input =
+                    (0..input_count)
+                        .map(|_| read_coverage(sub(data, r.u16()? as usize)?))
+                        .collect::<Result<Vec<_>, String>>()?;
+                let lookups = (0..lookup_count)
+                    .map(|_| Ok(SequenceLookup { sequence_index: r.u16()?, lookup_index: r.u16()? }))
+                    .collect::<Result<Vec<_>, String>>()?;
+                Ok(ChainContext::Coverages { backtrack: Vec::new(), input, lookahead: Vec::new(), lookups })
+            }
+            _ => Err(format!("unknown context format {format}")),
+        }
+    }
+
+    pub fn write(&self) -> Result<Vec<u8>, String> {
+        let mut p = Packer::new();
+        match self {
+            ChainContext::Glyphs(rules) => {
+                p.u16(1);
+                p.offset(coverage(rules.keys().copied()));
+                let sets: Vec<Option<&Vec<ChainRule>>> = rules.values().map(Some).collect();
+                write_rule_sets(&mut p, &sets)?;
+            }
+            ChainContext::Classes { coverage: glyphs, backtrack_classes, input_classes, lookahead_classes, rules } => {
+                p.u16(2);
+                p.offset(coverage(glyphs.iter().copied()));
+                p.offset(class_def(backtrack_classes));
+                p.offset(class_def(input_classes));
+                p.offset(class_def(lookahead_classes));
+                let count = rules.keys().next_back().map_or(0, |&c| c as usize + 1);
+                let sets: Vec<Option<&Vec<ChainRule>>> = (0..count).map(|c| rules.get(&(c as u16))).collect();
+                write_rule_sets(&mut p, &sets)?;
+            }
+            ChainContext::Coverages { backtrack, input, lookahead, lookups } => {
+                p.u16(3);
+                for coverages in [backtrack, input, lookahead] {
+                    p.u16(coverages.len() as u16);
+                    for glyphs in coverages {
+                        p.offset(coverage(glyphs.iter().copied()));
+                    }
+                }
+                write_lookups(&mut p, lookups);
+            }
+        }
+        p.finish()
+    }
+
+    // 规则中有字形未保留时整条规则删除；类别定义中删除未保留的字形，类别编号不变
+    pub fn subset(&self, glyphs: &GlyphMap, lookups: &[Option<u16>]) -> Option<Self> {
+        let remap_rule = |rule: &ChainRule| {
+            Some(ChainRule {
+                backtrack: remap_glyphs(&rule.backtrack, glyphs)?,
+                input: remap_glyphs(&rule.input, glyphs)?,
+                lookahead: remap_glyphs(&rule.lookahead, glyphs)?,
+                lookups: remap_lookups(&rule.lookups, lookups),
+            })
+        };
+        match self {
+            ChainContext::Glyphs(rules) => {
+                let rules: BTreeMap<u16, Vec<ChainRule>> = rules
+                    .iter()
+                    .filter_map(|(&g, rules)| {
+                        let rules: Vec<ChainRule> = rules.iter().filter_map(remap_rule).collect();
+                        Some((remap_glyph(g, glyphs)?, rules)).filter(|(_, rules)| !rules.is_empty())
+                    })
+                    .collect();
+                (!rules.is_empty()).then_some(ChainContext::Glyphs(rules))
+            }
+            ChainContext::Classes { coverage, backtrack_classes, input_classes, lookahead_classes, rules } => {
+                let coverage: Vec<u16> = coverage.iter().filter_map(|&g| remap_glyph(g, glyphs)).collect();
+                if coverage.is_empty() {
+                    return None;
+                }
+                let rules = rules
+                    .iter()
+                    .map(|(&class, rules)| {
+                        let rules = rules
+                            .iter()
+                            .map(|rule| ChainRule { lookups: remap_lookups(&rule.lookups, lookups), ..rule.clone() })
+                            .collect();
+                        (class, rules)
+                    })
+                    .collect();
+                Some(ChainContext::Classes {
+                    coverage,
+                    backtrack_classes: remap_classes(backtrack_classes, glyphs),
+                    input_classes: remap_classes(input_classes, glyphs),
+                    lookahead_classes: remap_classes(lookahead_classes, glyphs),
+                    rules,
+                })
+            }
+            ChainContext::Coverages { backtrack, input, lookahead, lookups: records } => {
+                let remap = |coverages: &Vec<Vec<u16>>| {
+                    coverages
+                        .iter()
+                        .map(|c| {
+                            let c: Vec<u16> = c.iter().filter_map(|&g| remap_glyph(g, glyphs)).collect();
+                            (!c.is_empty()).then_some(c)
+                        })
+                        .collect::<Option<Vec<Vec<u16>>>>()
+                };
+                Some(ChainContext::Coverages {
+                    backtrack: remap(backtrack)?,
+                    input: remap(input)?,
+                    lookahead: remap(lookahead)?,
+                    lookups: remap_lookups(records, lookups),
+                })
+            }
+        }
+    }
+}
@@ -0,0 +1,174 @@
+use std::collections::BTreeMap;
+
+use crate::binary::{Reader, Writer};
+
+use super::{
+    class_def, coverage, read_class_def, read_coverage, remap_classes, remap_glyph, remap_map, sub, GlyphMap, Packer,
+};
+
+// 字形类别：1 基字形，2 连字，3 标记，4 组件
+pub const BASE: u16 = 1;
+pub const LIGATURE: u16 = 2;
+pub const MARK: u16 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Caret {
+    Coordinate(i16),
+    Point(u16),
+}
+
+// GDEF 1.0/1.2，ItemVariationStore 与设备表不保留
+#[derive(Debug, Clone, Default)]
+pub struct Gdef {
+    pub glyph_classes: BTreeMap<u16, u16>,
+    pub attach_points: BTreeMap<u16, Vec<u16>>,
+    pub ligature_carets: BTreeMap<u16, Vec<Caret>>,
+    pub mark_attach_classes: BTreeMap<u16, u16>,
+    pub mark_glyph_sets: Vec<Vec<u16>>,
+}
+
+fn read_optional<T: Default>(
+    data: &[u8],
+    offset: usize,
+    read: impl FnOnce(&[u8]) -> Result<T, String>,
+) -> Result<T, String> {
+    if offset == 0 {
+        return Ok(T::default());
+    }
+    read(sub(data, offset)?)
+}
+
+fn read_glyph_lists<T>(
+    data: &[u8],
+    mut read: impl FnMut(&[u8], &mut Reader) -> Result<T, String>,
+) -> Result<BTreeMap<u16, Vec<T>>, String> {
+    let mut r = Reader::new(data);
+    let glyphs = read_coverage(sub(data, r.u16()? as usize)?)?;
+    let count = r.u16()? as usize;
+    let mut map = BTreeMap::new();
+    for &glyph in glyphs.iter().take(count) {
+        let list = sub(data, r.u16()? as usize)?;
+        let mut l = Reader::new(list);
+        let items = (0..l.u16()?).map(|_| read(list, &mut l)).collect::<Result<Vec<T>, String>>()?;
+        map.insert(glyph, items);
+    }
+    Ok(map)
+}
+
+fn write_optional(p: &mut Packer, data: Option<Vec<u8>>) {
+    match data {
+        Some(data) => p.offset(data),
+        None => p.u16(0),
+    }
+}
+
+impl Gdef {
+    pub fn parse(data: &[u8]) -> Result<Self, String> {
+        let mut r = Reader::new(data);
+        let (major, minor) = (r.u16()?, r.u16()?);
+        if major != 1 {
+            return Err(format!("unsupported GDEF version {major}.{minor}"));
+        }
+        let glyph_classes = read_optional(data, r.u16()? as usize, read_class_def)?;
+        let attach_points = read_optional(data, r.u16()? as usize, |d| read_glyph_lists(d, |_, r| r.u16()))?;
+        let ligature_carets = read_optional(data, r.u16()? as usize, |d| {
+            read_glyph_lists(d, |list, r| {
+                let mut caret = Reader::new(sub(list, r.u16()? as usize)?);
+                let format = caret.u16()?;
+                Ok(if format == 2 { Caret::Point(caret.u16()?) } else { Caret::Coordinate(caret.i16()?) })
+            })
+        })?;
+        let mark_attach_classes = read_optional(data, r.u16()? as usize, read_class_def)?;
+        let mark_glyph_sets = if minor >= 2 {
+            read_optional(data, r.u16()? as usize, |sets| {
+                let mut s = Reader::new(sets);
+                s.skip(2)?;
+                let count = s.u16()?;
+                (0..count).map(|_| read_coverage(sub(sets, s.u32()? as usize)?)).collect()
+            })?
+        } else {
+            Vec::new()
+        };
+        Ok(Gdef { glyph_classes, attach_points, ligature_carets, mark_attach_classes, mark_glyph_sets })
+    }
+
+    pub fn build(&self) -> Result<Vec<u8>, String> {
+        let mut p = Packer::new();
+        p.u16(1);
+        p.u16(if self.mark_glyph_sets.is_empty() { 0 } else { 2 });
+        write_optional(&mut p, (!self.glyph_classes.is_empty()).then(|| class_def(&self.glyph_classes)));
+
+        let attach_list = (!self.attach_points.is_empty()).then(|| {
+            let mut list = Packer::new();
+            list.offset(coverage(self.attach_points.keys().copied()));
+            list.u16(self.attach_points.len() as u16);
+            for points in self.attach_points.values() {
+                let mut w = Writer::new();
+                w.u16(points.len() as u16);
+                points.iter().for_each(|&point| w.u16(point));
+                list.offset(w.into_bytes());
+            }
+            list.finish()
+        });
+        write_optional(&mut p, attach_list.transpose()?);
+
+        let caret_list = (!self.ligature_carets.is_empty()).then(|| {
+            let mut list = Packer::new();
+            list.offset(coverage(self.ligature_carets.keys().copied()));
+            list.u16(self.ligature_carets.len() as u16);
+            for carets in self.ligature_carets.values() {
+                let mut glyph = Packer::new();
+                glyph.u16(carets.len() as u16);
+                for caret in carets {
+                    let mut w = Writer::new();
+                    match *caret {
+                        Caret::Coordinate(x) => {
+                            w.u16(1);
+                            w.i16(x);
+                        }
+                        Caret::Point(point) => {
+                            w.u16(2);
+                            w.u16(point);
+                        }
+                    }
+                    glyph.offset(w.into_bytes());
+                }
+                list.offset(glyph.finish()?);
+            }
+            list.finish()
+        });
+        write_optional(&mut p, caret_list.transpose()?);
+        write_optional(&mut p, (!self.mark_attach_classes.is_empty()).then(|| class_def(&self.mark_attach_classes)));
+
+        if !self.mark_glyph_sets.is_empty() {
+            // MarkGlyphSets 使用 Offset32，直接在内部写好各 Coverage
+            let mut sets = Writer::new();
+            sets.u16(1);
+            sets.u16(self.mark_glyph_sets.len() as u16);
+            let mut offset = 4 + 4 * self.mark_glyph_sets.len();
+            let coverages: Vec<Vec<u8>> = self.mark_glyph_sets.iter().map(|s| coverage(s.iter().copied())).collect();
+            for c in &coverages {
+                sets.u32(offset as u32);
+                offset += c.len();
+            }
+            coverages.iter().for_each(|c| sets.bytes(c));
+            p.offset(sets.into_bytes());
+        }
+        p.finish()
+    }
+
+    // 标记字形集合保留原有序号，以免 LookupFlag 中的引用失效
+    pub fn subset(&self, glyphs: &GlyphMap) -> Self {
+        Gdef {
+            glyph_classes: remap_classes(&self.glyph_classes, glyphs),
+            attach_points: remap_map(&self.attach_points, glyphs),
+            ligature_carets: remap_map(&self.ligature_carets, glyphs),
+            mark_attach_classes: remap_classes(&self.mark_attach_classes, glyphs),
+            mark_glyph_sets: self
+                .mark_glyph_sets
+                .iter()
+                .map(|set| set.iter().filter_map(|&g| remap_glyph(g, glyphs)).collect())
+                .collect(),
+        }
+    }
+}
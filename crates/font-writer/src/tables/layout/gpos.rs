@@ -0,0 +1,449 @@
+use std::collections::BTreeMap;
+
+use crate::binary::{Reader, Writer};
+
+use super::context::ChainContext;
+use super::{
+    class_def, coverage, read_class_def, read_coverage, remap_classes, remap_glyph, remap_map, sub, GlyphMap,
+    LayoutTable, Packer, Subtable,
+};
+
+pub type Gpos = LayoutTable<Positioning>;
+
+const X_PLACEMENT: u16 = 0x0001;
+const Y_PLACEMENT: u16 = 0x0002;
+const X_ADVANCE: u16 = 0x0004;
+const Y_ADVANCE: u16 = 0x0008;
+
+// ValueRecord 中的设备表（0x0010~0x0080）在解析时丢弃
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ValueRecord {
+    pub x_placement: i16,
+    pub y_placement: i16,
+    pub x_advance: i16,
+    pub y_advance: i16,
+}
+
+impl ValueRecord {
+    fn read(r: &mut Reader, format: u16) -> Result<Self, String> {
+        let mut value = ValueRecord::default();
+        if format & X_PLACEMENT != 0 {
+            value.x_placement = r.i16()?;
+        }
+        if format & Y_PLACEMENT != 0 {
+            value.y_placement = r.i16()?;
+        }
+        if format & X_ADVANCE != 0 {
+            value.x_advance = r.i16()?;
+        }
+        if format & Y_ADVANCE != 0 {
+            value.y_advance = r.i16()?;
+        }
+        r.skip(2 * (format & 0x00F0).count_ones() as usize)?;
+        Ok(value)
+    }
+
+    fn format(&self) -> u16 {
+        let mut format = 0;
+        for (v, bit) in [
+            (self.x_placement, X_PLACEMENT),
+            (self.y_placement, Y_PLACEMENT),
+            (self.x_advance, X_ADVANCE),
+            (self.y_advance, Y_ADVANCE),
+        ] {
+            if v != 0 {
+                format |= bit;
+            }
+        }
+        format
+    }
+
+    fn write(&self, w: &mut Writer, format: u16) {
+        for (v, bit) in [
+            (self.x_placement, X_PLACEMENT),
+            (self.y_placement, Y_PLACEMENT),
+            (self.x_advance, X_ADVANCE),
+            (self.y_advance, Y_ADVANCE),
+        ] {
+            if format & bit != 0 {
+                w.i16(v);
+            }
+        }
+    }
+}
+
+fn value_format<'a>(values: impl IntoIterator<Item = &'a ValueRecord>) -> u16 {
+    values.into_iter().fold(0, |format, v| format | v.format())
+}
+
+// 锚点：point 为 TrueType 轮廓点序号（格式 2），格式 3 的设备表丢弃
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Anchor {
+    pub x: i16,
+    pub y: i16,
+    pub point: Option<u16>,
+}
+
+impl Anchor {
+    fn read(data: &[u8]) -> Result<Self, String> {
+        let mut r = Reader::new(data);
+        let format = r.u16()?;
+        let (x, y) = (r.i16()?, r.i16()?);
+        let point = if format == 2 { Some(r.u16()?) } else { None };
+        Ok(Anchor { x, y, point })
+    }
+
+    fn read_optional(data: &[u8], offset: u16) -> Result<Option<Self>, String> {
+        if offset == 0 {
+            return Ok(None);
+        }
+        Anchor::read(sub(data, offset as usize)?).map(Some)
+    }
+
+    fn write(&self) -> Vec<u8> {
+        let mut w = Writer::new();
+        w.u16(if self.point.is_some() { 2 } else { 1 });
+        w.i16(self.x);
+        w.i16(self.y);
+        if let Some(point) = self.point {
+            w.u16(point);
+        }
+        w.into_bytes()
+    }
+}
+
+fn write_optional_anchor(p: &mut Packer, anchor: &Option<Anchor>) {
+    match anchor {
+        Some(anchor) => p.offset(anchor.write()),
+        None => p.u16(0),
+    }
+}
+
+// MarkBase 与 MarkMark 共用：marks 为标记字形 → (类别, 锚点)，bases 为基字形 → 各类别的锚点
+#[derive(Debug, Clone, Default)]
+pub struct MarkAttachment {
+    pub class_count: u16,
+    pub marks: BTreeMap<u16, (u16, Anchor)>,
+    pub bases: BTreeMap<u16, Vec<Option<Anchor>>>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MarkLigatureAttachment {
+    pub class_count: u16,
+    pub marks: BTreeMap<u16, (u16, Anchor)>,
+    // 连字字形 → 各组件 → 各类别的锚点
+    pub ligatures: BTreeMap<u16, Vec<Vec<Option<Anchor>>>>,
+}
+
+// GPOS 子表，扩展子表（类型 9）在解析时展开
+#[derive(Debug, Clone)]
+pub enum Positioning {
+    Single(BTreeMap<u16, ValueRecord>),
+    // 格式 1：第一个字形 → 第二个字形 → 两个字形各自的调整
+    Pair(BTreeMap<u16, BTreeMap<u16, (ValueRecord, ValueRecord)>>),
+    // 格式 2：values[第一个类别][第二个类别]，类别 0 为未分类的字形
+    PairClass {
+        coverage: Vec<u16>,
+        classes1: BTreeMap<u16, u16>,
+        classes2: BTreeMap<u16, u16>,
+        values: Vec<Vec<(ValueRecord, ValueRecord)>>,
+    },
+    // 字形 → (入口锚点, 出口锚点)
+    Cursive(BTreeMap<u16, (Option<Anchor>, Option<Anchor>)>),
+    MarkBase(MarkAttachment),
+    MarkLigature(MarkLigatureAttachment),
+    MarkMark(MarkAttachment),
+    Context(ChainContext),
+}
+
+fn read_marks(data: &[u8], glyphs: &[u16]) -> Result<BTreeMap<u16, (u16, Anchor)>, String> {
+    let mut r = Reader::new(data);
+    let count = r.u16()? as usize;
+    let mut marks = BTreeMap::new();
+    for &glyph in glyphs.iter().take(count) {
+        let class = r.u16()?;
+        marks.insert(glyph, (class, Anchor::read(sub(data, r.u16()? as usize)?)?));
+    }
+    Ok(marks)
+}
+
+fn write_marks(marks: &BTreeMap<u16, (u16, Anchor)>) -> Result<Vec<u8>, String> {
+    let mut p = Packer::new();
+    p.u16(marks.len() as u16);
+    for (class, anchor) in marks.values() {
+        p.u16(*class);
+        p.offset(anchor.write());
+    }
+    p.finish()
+}
+
+// BaseArray 与 ComponentRecord 数组格式相同：count 行，每行 class_count 个锚点偏移
+fn read_anchor_rows(data: &[u8], class_count: u16) -> Result<Vec<Vec<Option<Anchor>>>, String> {
+    let mut r = Reader::new(data);
+    let count = r.u16()?;
+    (0..count).map(|_| (0..class_count).map(|_| Anchor::read_optional(data, r.u16()?)).collect()).collect()
+}
+
+fn write_anchor_rows<'a>(rows: impl ExactSizeIterator<Item = &'a Vec<Option<Anchor>>>) -> Result<Vec<u8>, String> {
+    let mut p = Packer::new();
+    p.u16(rows.len() as u16);
+    for row in rows {
+        row.iter().for_each(|anchor| write_optional_anchor(&mut p, anchor));
+    }
+    p.finish()
+}
+
+fn read_mark_attachment(data: &[u8], r: &mut Reader) -> Result<MarkAttachment, String> {
+    let mark_glyphs = read_coverage(sub(data, r.u16()? as usize)?)?;
+    let base_glyphs = read_coverage(sub(data, r.u16()? as usize)?)?;
+    let class_count = r.u16()?;
+    let marks = read_marks(sub(data, r.u16()? as usize)?, &mark_glyphs)?;
+    let rows = read_anchor_rows(sub(data, r.u16()? as usize)?, class_count)?;
+    Ok(MarkAttachment { class_count, marks, bases: base_glyphs.into_iter().zip(rows).collect() })
+}
+
+impl MarkAttachment {
+    fn write(&self) -> Result<Vec<u8>, String> {
+        let mut p = Packer::new();
+        p.u16(1);
+        p.offset(coverage(self.marks.keys().copied()));
+        p.offset(coverage(self.bases.keys().copied()));
+        p.u16(self.class_count);
+        p.offset(write_marks(&self.marks)?);
+        p.offset(write_anchor_rows(self.bases.values())?);
+        p.finish()
+    }
+
+    fn subset(&self, glyphs: &GlyphMap) -> Option<Self> {
+        let marks = remap_map(&self.marks, glyphs);
+        let bases = remap_map(&self.bases, glyphs);
+        (!marks.is_empty() && !bases.is_empty()).then_some(MarkAttachment {
+            class_count: self.class_count,
+            marks,
+            bases,
+        })
+    }
+}
+
+impl Subtable for Positioning {
+    const EXTENSION: u16 = 9;
+
+    fn parse(lookup_type: u16, data: &[u8]) -> Result<Option<Self>, String> {
+        let mut r = Reader::new(data);
+        let format = r.u16()?;
+        Ok(Some(match lookup_type {
+            1 => {
+                let glyphs = read_coverage(sub(data, r.u16()? as usize)?)?;
+                let value_format = r.u16()?;
+                match format {
+                    1 => {
+                        let value = ValueRecord::read(&mut r, value_format)?;
+                        Positioning::Single(glyphs.into_iter().map(|g| (g, value)).collect())
+                    }
+                    2 => {
+                        let count = r.u16()? as usize;
+                        let mut map = BTreeMap::new();
+                        for &g in glyphs.iter().take(count) {
+                            map.insert(g, ValueRecord::read(&mut r, value_format)?);
+                        }
+                        Positioning::Single(map)
+                    }
+                    _ => return Err(format!("unknown single positioning format {format}")),
+                }
+            }
+            2 => {
+                let glyphs = read_coverage(sub(data, r.u16()? as usize)?)?;
+                let (format1, format2) = (r.u16()?, r.u16()?);
+                match format {
+                    1 => {
+                        let count = r.u16()? as usize;
+                        let mut map = BTreeMap::new();
+                        for &first in glyphs.iter().take(count) {
+                            let mut s = Reader::new(sub(data, r.u16()? as usize)?);
+                            let mut pairs = BTreeMap::new();
+                            for _ in 0..s.u16()? {
+                                let second = s.u16()?;
+                                let value1 = ValueRecord::read(&mut s, format1)?;
+                                pairs.insert(second, (value1, ValueRecord::read(&mut s, format2)?));
+                            }
+                            map.insert(first, pairs);
+                        }
+                        Positioning::Pair(map)
+                    }
+                    2 => {
+                        let classes1 = read_class_def(sub(data, r.u16()? as usize)?)?;
+                        let classes2 = read_class_def(sub(data, r.u16()? as usize)?)?;
+                        let (count1, count2) = (r.u16()?, r.u16()?);
+                        let values = (0..count1)
+                            .map(|_| {
+                                (0..count2)
+                                    .map(|_| {
+                                        Ok((ValueRecord::read(&mut r, format1)?, ValueRecord::read(&mut r, format2)?))
+                                    })
+                                    .collect()
+                            })
+                            .collect::<Result<Vec<_>, String>>()?;
+                        Positioning::PairClass { coverage: glyphs, classes1, classes2, values }
+                    }
+                    _ => return Err(format!("unknown pair positioning format {format}")),
+                }
+            }
+            3 => {
+                let glyphs = read_coverage(sub(data, r.u16()? as usize)?)?;
+                let count = r.u16()? as usize;
+                let mut map = BTreeMap::new();
+                for &g in glyphs.iter().take(count) {
+                    let entry = Anchor::read_optional(data, r.u16()?)?;
+                    map.insert(g, (entry, Anchor::read_optional(data, r.u16()?)?));
+                }
+                Positioning::Cursive(map)
+            }
+            4 => Positioning::MarkBase(read_mark_attachment(data, &mut r)?),
+            5 => {
+                let mark_glyphs = read_coverage(sub(data, r.u16()? as usize)?)?;
+                let ligature_glyphs = read_coverage(sub(data, r.u16()? as usize)?)?;
+                let class_count = r.u16()?;
+                let marks = read_marks(sub(data, r.u16()? as usize)?, &mark_glyphs)?;
+                let array = sub(data, r.u16()? as usize)?;
+                let mut a = Reader::new(array);
+                let count = a.u16()? as usize;
+                let mut ligatures = BTreeMap::new();
+                for &glyph in ligature_glyphs.iter().take(count) {
+                    ligatures.insert(glyph, read_anchor_rows(sub(array, a.u16()? as usize)?, class_count)?);
+                }
+                Positioning::MarkLigature(MarkLigatureAttachment { class_count, marks, ligatures })
+            }
+            6 => Positioning::MarkMark(read_mark_attachment(data, &mut r)?),
+            7 | 8 => Positioning::Context(ChainContext::parse(data, lookup_type == 8)?),
+            _ => return Ok(None),
+        }))
+    }
+
+    fn lookup_type(&self) -> u16 {
+        match self {
+            Positioning::Single(_) => 1,
+            Positioning::Pair(_) | Positioning::PairClass { .. } => 2,
+            Positioning::Cursive(_) => 3,
+            Positioning::MarkBase(_) => 4,
+            Positioning::MarkLigature(_) => 5,
+            Positioning::MarkMark(_) => 6,
+            Positioning::Context(_) => 8,
+        }
+    }
+
+    fn write(&self) -> Result<Vec<u8>, String> {
+        let mut p = Packer::new();
+        match self {
+            Positioning::Single(map) => {
+                let format = value_format(map.values());
+                let first = map.values().next().copied().unwrap_or_default();
+                let uniform = map.values().all(|v| *v == first);
+                p.u16(if uniform { 1 } else { 2 });
+                p.offset(coverage(map.keys().copied()));
+                p.u16(format);
+                if uniform {
+                    first.write(&mut p, format);
+                } else {
+                    p.u16(map.len() as u16);
+                    map.values().for_each(|v| v.write(&mut p, format));
+                }
+            }
+            Positioning::Pair(map) => {
+                let format1 = value_format(map.values().flat_map(|pairs| pairs.values().map(|v| &v.0)));
+                let format2 = value_format(map.values().flat_map(|pairs| pairs.values().map(|v| &v.1)));
+                p.u16(1);
+                p.offset(coverage(map.keys().copied()));
+                p.u16(format1);
+                p.u16(format2);
+                p.u16(map.len() as u16);
+                for pairs in map.values() {
+                    let mut s = Writer::new();
+                    s.u16(pairs.len() as u16);
+                    for (&second, (value1, value2)) in pairs {
+                        s.u16(second);
+                        value1.write(&mut s, format1);
+                        value2.write(&mut s, format2);
+                    }
+                    p.offset(s.into_bytes());
+                }
+            }
+            Positioning::PairClass { coverage: glyphs, classes1, classes2, values } => {
+                let format1 = value_format(values.iter().flatten().map(|v| &v.0));
+                let format2 = value_format(values.iter().flatten().map(|v| &v.1));
+                p.u16(2);
+                p.offset(coverage(glyphs.iter().copied()));
+                p.u16(format1);
+                p.u16(format2);
+                p.offset(class_def(classes1));
+                p.offset(class_def(classes2));
+                p.u16(values.len() as u16);
+                p.u16(values.first().map_or(0, Vec::len) as u16);
+                for (value1, value2) in values.iter().flatten() {
+                    value1.write(&mut p, format1);
+                    value2.write(&mut p, format2);
+                }
+            }
+            Positioning::Cursive(map) => {
+                p.u16(1);
+                p.offset(coverage(map.keys().copied()));
+                p.u16(map.len() as u16);
+                for (entry, exit) in map.values() {
+                    write_optional_anchor(&mut p, entry);
+                    write_optional_anchor(&mut p, exit);
+                }
+            }
+            Positioning::MarkBase(attachment) | Positioning::MarkMark(attachment) => return attachment.write(),
+            Positioning::MarkLigature(attachment) => {
+                p.u16(1);
+                p.offset(coverage(attachment.marks.keys().copied()));
+                p.offset(coverage(attachment.ligatures.keys().copied()));
+                p.u16(attachment.class_count);
+                p.offset(write_marks(&attachment.marks)?);
+                let mut array = Packer::new();
+                array.u16(attachment.ligatures.len() as u16);
+                for components in attachment.ligatures.values() {
+                    array.offset(write_anchor_rows(components.iter())?);
+                }
+                p.offset(array.finish()?);
+            }
+            Positioning::Context(context) => return context.write(),
+        }
+        p.finish()
+    }
+
+    fn subset(&self, glyphs: &GlyphMap, lookups: &[Option<u16>]) -> Option<Self> {
+        match self {
+            Positioning::Single(map) => Some(remap_map(map, glyphs)).filter(|m| !m.is_empty()).map(Positioning::Single),
+            Positioning::Pair(map) => {
+                let map: BTreeMap<u16, BTreeMap<u16, (ValueRecord, ValueRecord)>> = map
+                    .iter()
+                    .filter_map(|(&first, pairs)| Some((remap_glyph(first, glyphs)?, remap_map(pairs, glyphs))))
+                    .filter(|(_, pairs)| !pairs.is_empty())
+                    .collect();
+                (!map.is_empty()).then_some(Positioning::Pair(map))
+            }
+            Positioning::PairClass { coverage, classes1, classes2, values } => {
+                let coverage: Vec<u16> = coverage.iter().filter_map(|&g| remap_glyph(g, glyphs)).collect();
+                (!coverage.is_empty()).then(|| Positioning::PairClass {
+                    coverage,
+                    classes1: remap_classes(classes1, glyphs),
+                    classes2: remap_classes(classes2, glyphs),
+                    values: values.clone(),
+                })
+            }
+            Positioning::Cursive(map) => {
+                Some(remap_map(map, glyphs)).filter(|m| !m.is_empty()).map(Positioning::Cursive)
+            }
+            Positioning::MarkBase(attachment) => attachment.subset(glyphs).map(Positioning::MarkBase),
+            Positioning::MarkMark(attachment) => attachment.subset(glyphs).map(Positioning::MarkMark),
+            Positioning::MarkLigature(attachment) => {
+                let marks = remap_map(&attachment.marks, glyphs);
+                let ligatures = remap_map(&attachment.ligatures, glyphs);
+                let class_count = attachment.class_count;
+                (!marks.is_empty() && !ligatures.is_empty())
+                    .then_some(Positioning::MarkLigature(MarkLigatureAttachment { class_count, marks, ligatures }))
+            }
+            Positioning::Context(context) => context.subset(glyphs, lookups).map(Positioning::Context),
+        }
+    }
+}
@@ -0,0 +1,287 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::binary::Reader;
+
+use super::context::ChainContext;
+use super::{coverage, read_coverage, remap_glyph, remap_glyphs, sub, GlyphMap, LayoutTable, Packer, Subtable};
+
+pub type Gsub = LayoutTable<Substitution>;
+
+// GSUB 子表，扩展子表（类型 7）在解析时展开
+#[derive(Debug, Clone)]
+pub enum Substitution {
+    Single(BTreeMap<u16, u16>),
+    Multiple(BTreeMap<u16, Vec<u16>>),
+    Alternate(BTreeMap<u16, Vec<u16>>),
+    // 首个字形 → (其余组件, 连字字形)，按匹配优先级排列
+    Ligature(BTreeMap<u16, Vec<(Vec<u16>, u16)>>),
+    Context(ChainContext),
+    // substitutes 与 coverage 一一对应
+    ReverseChain { coverage: Vec<u16>, backtrack: Vec<Vec<u16>>, lookahead: Vec<Vec<u16>>, substitutes: Vec<u16> },
+}
+
+fn read_sequences(data: &[u8], r: &mut Reader) -> Result<BTreeMap<u16, Vec<u16>>, String> {
+    let glyphs = read_coverage(sub(data, r.u16()? as usize)?)?;
+    let count = r.u16()? as usize;
+    let mut map = BTreeMap::new();
+    for &glyph in glyphs.iter().take(count) {
+        let mut s = Reader::new(sub(data, r.u16()? as usize)?);
+        let len = s.u16()?;
+        map.insert(glyph, (0..len).map(|_| s.u16()).collect::<Result<Vec<u16>, String>>()?);
+    }
+    Ok(map)
+}
+
+fn write_sequences(format: u16, map: &BTreeMap<u16, Vec<u16>>) -> Result<Vec<u8>, String> {
+    let mut p = Packer::new();
+    p.u16(format);
+    p.offset(coverage(map.keys().copied()));
+    p.u16(map.len() as u16);
+    for glyphs in map.values() {
+        let mut s = Packer::new();
+        s.u16(glyphs.len() as u16);
+        glyphs.iter().for_each(|&g| s.u16(g));
+        p.offset(s.finish()?);
+    }
+    p.finish()
+}
+
+fn remap_sequences(map: &BTreeMap<u16, Vec<u16>>, glyphs: &GlyphMap, partial: bool) -> BTreeMap<u16, Vec<u16>> {
+    map.iter()
+        .filter_map(|(&g, outputs)| {
+            let outputs = if partial {
+                outputs.iter().filter_map(|&o| remap_glyph(o, glyphs)).collect()
+            } else {
+                remap_glyphs(outputs, glyphs)?
+            };
+            Some((remap_glyph(g, glyphs)?, outputs)).filter(|(_, o)| !o.is_empty())
+        })
+        .collect()
+}
+
+impl Substitution {
+    // 输入字形都在集合中时可能产生的输出字形，用于子集化时的字形闭包
+    pub fn closure(&self, glyphs: &BTreeSet<u16>, output: &mut BTreeSet<u16>) {
+        match self {
+            Substitution::Single(map) => {
+                output.extend(map.iter().filter(|(g, _)| glyphs.contains(g)).map(|(_, &s)| s));
+            }
+            Substitution::Multiple(map) | Substitution::Alternate(map) => {
+                for (g, outputs) in map {
+                    if glyphs.contains(g) {
+                        output.extend(outputs);
+                    }
+                }
+            }
+            Substitution::Ligature(map) => {
+                for ligatures in map.iter().filter(|(g, _)| glyphs.contains(g)).map(|(_, l)| l) {
+                    for (components, ligature) in ligatures {
+                        if components.iter().all(|c| glyphs.contains(c)) {
+                            output.insert(*ligature);
+                        }
+                    }
+                }
+            }
+            Substitution::ReverseChain { coverage, substitutes, .. } => {
+                for (g, s) in coverage.iter().zip(substitutes) {
+                    if glyphs.contains(g) {
+                        output.insert(*s);
+                    }
+                }
+            }
+            Substitution::Context(_) => {}
+        }
+    }
+}
+
+impl Subtable for Substitution {
+    const EXTENSION: u16 = 7;
+
+    fn parse(lookup_type: u16, data: &[u8]) -> Result<Option<Self>, String> {
+        let mut r = Reader::new(data);
+        Ok(Some(match lookup_type {
+            1 => {
+                let format = r.u16()?;
+                let glyphs = read_coverage(sub(data, r.u16()? as usize)?)?;
+                match format {
+                    1 => {
+                        let delta = r.i16()?;
+                        Substitution::Single(glyphs.into_iter().map(|g| (g, g.wrapping_add(delta as u16))).collect())
+                    }
+                    2 => {
+                        let count = r.u16()? as usize;
+                        let mut map = BTreeMap::new();
+                        for &g in glyphs.iter().take(count) {
+                            map.insert(g, r.u16()?);
+                        }
+                        Substitution::Single(map)
+                    }
+                    _ => return Err(format!("unknown single substitution format {format}")),
+                }
+            }
+            2 | 3 => {
+                r.skip(2)?;
+                let map = read_sequences(data, &mut r)?;
+                if lookup_type == 2 {
+                    Substitution::Multiple(map)
+                } else {
+                    Substitution::Alternate(map)
+                }
+            }
+            4 => {
+                r.skip(2)?;
+                let glyphs = read_coverage(sub(data, r.u16()? as usize)?)?;
+                let count = r.u16()? as usize;
+                let mut map = BTreeMap::new();
+                for &glyph in glyphs.iter().take(count) {
+                    let set = sub(data, r.u16()? as usize)?;
+                    let mut s = Reader::new(set);
+                    let mut ligatures = Vec::new();
+                    for _ in 0..s.u16()? {
+                        let mut l = Reader::new(sub(set, s.u16()? as usize)?);
+                        let ligature = l.u16()?;
+                        let count = l.u16()?.saturating_sub(1);
+                        let components = (0..count).map(|_| l.u16()).collect::<Result<Vec<u16>, String>>()?;
+                        ligatures.push((components, ligature));
+                    }
+                    map.insert(glyph, ligatures);
+                }
+                Substitution::Ligature(map)
+            }
+            5 | 6 => Substitution::Context(ChainContext::parse(data, lookup_type == 6)?),
+            8 => {
+                r.skip(2)?;
+                let coverage = read_coverage(sub(data, r.u16()? as usize)?)?;
+                let mut coverages = || -> Result<Vec<Vec<u16>>, String> {
+                    let count = r.u16()?;
+                    (0..count).map(|_| read_coverage(sub(data, r.u16()? as usize)?)).collect()
+                };
+                let backtrack = coverages()?;
+                let lookahead = coverages()?;
+                let count = r.u16()?;
+                let substitutes = (0..count).map(|_| r.u16()).collect::<Result<Vec<u16>, String>>()?;
+                Substitution::ReverseChain { coverage, backtrack, lookahead, substitutes }
+            }
+            _ => return Ok(None),
+        }))
+    }
+
+    fn lookup_type(&self) -> u16 {
+        match self {
+            Substitution::Single(_) => 1,
+            Substitution::Multiple(_) => 2,
+            Substitution::Alternate(_) => 3,
+            Substitution::Ligature(_) => 4,
+            Substitution::Context(_) => 6,
+            Substitution::ReverseChain { .. } => 8,
+        }
+    }
+
+    fn write(&self) -> Result<Vec<u8>, String> {
+        match self {
+            Substitution::Single(map) => {
+                let mut p = Packer::new();
+                let deltas: BTreeSet<u16> = map.iter().map(|(&g, &s)| s.wrapping_sub(g)).collect();
+                p.u16(if deltas.len() == 1 { 1 } else { 2 });
+                p.offset(coverage(map.keys().copied()));
+                match deltas.iter().next() {
+                    Some(&delta) if deltas.len() == 1 => p.u16(delta),
+                    _ => {
+                        p.u16(map.len() as u16);
+                        map.values().for_each(|&s| p.u16(s));
+                    }
+                }
+                p.finish()
+            }
+            Substitution::Multiple(map) | Substitution::Alternate(map) => write_sequences(1, map),
+            Substitution::Ligature(map) => {
+                let mut p = Packer::new();
+                p.u16(1);
+                p.offset(coverage(map.keys().copied()));
+                p.u16(map.len() as u16);
+                for ligatures in map.values() {
+                    let mut s = Packer::new();
+                    s.u16(ligatures.len() as u16);
+                    for (components, ligature) in ligatures {
+                        let mut l = Packer::new();
+                        l.u16(*ligature);
+                        l.u16(components.len() as u16 + 1);
+                        components.iter().for_each(|&c| l.u16(c));
+                        s.offset(l.finish()?);
+                    }
+                    p.offset(s.finish()?);
+                }
+                p.finish()
+            }
+            Substitution::Context(context) => context.write(),
+            Substitution::ReverseChain { coverage: glyphs, backtrack, lookahead, substitutes } => {
+                let mut p = Packer::new();
+                p.u16(1);
+                p.offset(coverage(glyphs.iter().copied()));
+                for coverages in [backtrack, lookahead] {
+                    p.u16(coverages.len() as u16);
+                    for c in coverages {
+                        p.offset(coverage(c.iter().copied()));
+                    }
+                }
+                p.u16(substitutes.len() as u16);
+                substitutes.iter().for_each(|&s| p.u16(s));
+                p.finish()
+            }
+        }
+    }
+
+    fn subset(&self, glyphs: &GlyphMap, lookups: &[Option<u16>]) -> Option<Self> {
+        let subtable = match self {
+            Substitution::Single(map) => Substitution::Single(
+                map.iter().filter_map(|(&g, &s)| Some((remap_glyph(g, glyphs)?, remap_glyph(s, glyphs)?))).collect(),
+            ),
+            Substitution::Multiple(map) => Substitution::Multiple(remap_sequences(map, glyphs, false)),
+            Substitution::Alternate(map) => Substitution::Alternate(remap_sequences(map, glyphs, true)),
+            Substitution::Ligature(map) => Substitution::Ligature(
+                map.iter()
+                    .filter_map(|(&g, ligatures)| {
+                        let ligatures: Vec<(Vec<u16>, u16)> = ligatures
+                            .iter()
+                            .filter_map(|(c, l)| Some((remap_glyphs(c, glyphs)?, remap_glyph(*l, glyphs)?)))
+                            .collect();
+                        Some((remap_glyph(g, glyphs)?, ligatures)).filter(|(_, l)| !l.is_empty())
+                    })
+                    .collect(),
+            ),
+            Substitution::Context(context) => return context.subset(glyphs, lookups).map(Substitution::Context),
+            Substitution::ReverseChain { coverage, backtrack, lookahead, substitutes } => {
+                let remap = |coverages: &Vec<Vec<u16>>| {
+                    coverages
+                        .iter()
+                        .map(|c| {
+                            let c: Vec<u16> = c.iter().filter_map(|&g| remap_glyph(g, glyphs)).collect();
+                            (!c.is_empty()).then_some(c)
+                        })
+                        .collect::<Option<Vec<Vec<u16>>>>()
+                };
+                let pairs: Vec<(u16, u16)> = coverage
+                    .iter()
+                    .zip(substitutes)
+                    .filter_map(|(&g, &s)| Some((remap_glyph(g, glyphs)?, remap_glyph(s, glyphs)?)))
+                    .collect();
+                if pairs.is_empty() {
+                    return None;
+                }
+                Substitution::ReverseChain {
+                    coverage: pairs.iter().map(|p| p.0).collect(),
+                    backtrack: remap(backtrack)?,
+                    lookahead: remap(lookahead)?,
+                    substitutes: pairs.iter().map(|p| p.1).collect(),
+                }
+            }
+        };
+        let empty = match &subtable {
+            Substitution::Single(map) => map.is_empty(),
+            Substitution::Multiple(map) | Substitution::Alternate(map) => map.is_empty(),
+            Substitution::Ligature(map) => map.is_empty(),
+            _ => false,
+        };
+        (!empty).then_some(subtable)
+    }
+}
@@ -0,0 +1,569 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::ops::{Deref, DerefMut};
+
+use crate::binary::{Reader, Writer};
+
+pub mod context;
+pub mod gdef;
+pub mod gpos;
+pub mod gsub;
+
+// 子集化时旧字形 ID → 新字形 ID，未保留的为 None
+pub type GlyphMap = [Option<u16>];
+
+// 带 Offset16 子表的表：子表依次写在表头之后，偏移量相对于表头起点，相同的子表只写一次
+#[derive(Debug, Default)]
+pub struct Packer {
+    w: Writer,
+    links: Vec<(usize, Vec<u8>)>,
+}
+
+impl Deref for Packer {
+    type Target = Writer;
+
+    fn deref(&self) -> &Writer {
+        &self.w
+    }
+}
+
+impl DerefMut for Packer {
+    fn deref_mut(&mut self) -> &mut Writer {
+        &mut self.w
+    }
+}
+
+impl Packer {
+    pub fn new() -> Self {
+        Packer::default()
+    }
+
+    // 子表需自成一体（其内部偏移量相对于子表自身）
+    pub fn offset(&mut self, child: Vec<u8>) {
+        self.links.push((self.w.len(), child));
+        self.w.u16(0);
+    }
+
+    pub fn finish(self) -> Result<Vec<u8>, String> {
+        let Packer { mut w, links } = self;
+        let mut placed: Vec<(&[u8], usize)> = Vec::new();
+        let mut patches = Vec::with_capacity(links.len());
+        for (pos, child) in &links {
+            let offset = match placed.iter().find(|(data, _)| *data == child.as_slice()) {
+                Some(&(_, offset)) => offset,
+                None => {
+                    let offset = w.len();
+                    w.bytes(child);
+                    placed.push((child, offset));
+                    offset
+                }
+            };
+            if offset > u16::MAX as usize {
+                return Err(String::from("Offset16 overflow"));
+            }
+            patches.push((*pos, offset as u16));
+        }
+        for (pos, offset) in patches {
+            w.set_u16(pos, offset);
+        }
+        Ok(w.into_bytes())
+    }
+}
+
+// 从表起点按偏移量取子表数据
+pub fn sub(data: &[u8], offset: usize) -> Result<&[u8], String> {
+    data.get(offset..).ok_or_else(|| format!("offset {offset} is out of bounds"))
+}
+
+// Coverage：格式 1 逐个列出字形，格式 2 按连续区间记录，取较短者
+pub fn coverage(glyphs: impl IntoIterator<Item = u16>) -> Vec<u8> {
+    let glyphs: Vec<u16> = glyphs.into_iter().collect::<BTreeSet<u16>>().into_iter().collect();
+    let mut ranges: Vec<(u16, u16)> = Vec::new();
+    for &g in &glyphs {
+        match ranges.last_mut() {
+            Some(range) if range.1 + 1 == g => range.1 = g,
+            _ => ranges.push((g, g)),
+        }
+    }
+    let mut w = Writer::new();
+    if 6 * ranges.len() < 2 * glyphs.len() {
+        w.u16(2);
+        w.u16(ranges.len() as u16);
+        let mut index = 0;
+        for (start, end) in ranges {
+            w.u16(start);
+            w.u16(end);
+            w.u16(index);
+            index += end - start + 1;
+        }
+    } else {
+        w.u16(1);
+        w.u16(glyphs.len() as u16);
+        glyphs.iter().for_each(|&g| w.u16(g));
+    }
+    w.into_bytes()
+}
+
+// 按 Coverage 索引顺序返回字形
+pub fn read_coverage(data: &[u8]) -> Result<Vec<u16>, String> {
+    let mut r = Reader::new(data);
+    let format = r.u16()?;
+    let count = r.u16()? as usize;
+    let mut glyphs = Vec::new();
+    match format {
+        1 => {
+            for _ in 0..count {
+                glyphs.push(r.u16()?);
+            }
+        }
+        2 => {
+            for _ in 0..count {
+                let (start, end, _) = (r.u16()?, r.u16()?, r.u16()?);
+                glyphs.extend(start..=end.max(start));
+            }
+        }
+        _ => return Err(format!("unknown coverage format {format}")),
+    }
+    Ok(glyphs)
+}
+
+// ClassDef：class 0 不写入；格式 1 为连续字形区间的类别数组，格式 2 为同类别区间，取较短者
+pub fn class_def(classes: &BTreeMap<u16, u16>) -> Vec<u8> {
+    let entries: Vec<(u16, u16)> = classes.iter().filter(|(_, &c)| c != 0).map(|(&g, &c)| (g, c)).collect();
+    let mut ranges: Vec<(u16, u16, u16)> = Vec::new();
+    for &(g, c) in &entries {
+        match ranges.last_mut() {
+            Some(range) if range.1 + 1 == g && range.2 == c => range.1 = g,
+            _ => ranges.push((g, g, c)),
+        }
+    }
+    let span = match (entries.first(), entries.last()) {
+        (Some(first), Some(last)) => (last.0 - first.0) as usize + 1,
+        _ => 0,
+    };
+    let mut w = Writer::new();
+    if span > 0 && 2 * span <= 6 * ranges.len() - 2 {
+        let start = entries[0].0;
+        w.u16(1);
+        w.u16(start);
+        w.u16(span as u16);
+        for g in start..start + span as u16 {
+            w.u16(classes.get(&g).copied().unwrap_or(0));
+        }
+    } else {
+        w.u16(2);
+        w.u16(ranges.len() as u16);
+        for (start, end, class) in ranges {
+            w.u16(start);
+            w.u16(end);
+            w.u16(class);
+        }
+    }
+    w.into_bytes()
+}
+
+pub fn read_class_def(data: &[u8]) -> Result<BTreeMap<u16, u16>, String> {
+    let mut r = Reader::new(data);
+    let format = r.u16()?;
+    let mut classes = BTreeMap::new();
+    match format {
+        1 => {
+            let start = r.u16()?;
+            let count = r.u16()?;
+            for i in 0..count {
+                let class = r.u16()?;
+                if class != 0 {
+                    classes.insert(start.wrapping_add(i), class);
+                }
+            }
+        }
+        2 => {
+            let count = r.u16()?;
+            for _ in 0..count {
+                let (start, end, class) = (r.u16()?, r.u16()?, r.u16()?);
+                if class != 0 {
+                    for g in start..=end.max(start) {
+                        classes.insert(g, class);
+                    }
+                }
+            }
+        }
+        _ => return Err(format!("unknown class definition format {format}")),
+    }
+    Ok(classes)
+}
+
+// 子集化时保留的字形按新 ID 重新编号
+pub fn remap_glyphs(glyphs: &[u16], map: &GlyphMap) -> Option<Vec<u16>> {
+    glyphs.iter().map(|&g| map.get(g as usize).copied().flatten()).collect()
+}
+
+pub fn remap_glyph(glyph: u16, map: &GlyphMap) -> Option<u16> {
+    map.get(glyph as usize).copied().flatten()
+}
+
+pub fn remap_map<T: Clone>(map: &BTreeMap<u16, T>, glyphs: &GlyphMap) -> BTreeMap<u16, T> {
+    map.iter().filter_map(|(&g, v)| Some((remap_glyph(g, glyphs)?, v.clone()))).collect()
+}
+
+pub fn remap_classes(classes: &BTreeMap<u16, u16>, map: &GlyphMap) -> BTreeMap<u16, u16> {
+    classes.iter().filter_map(|(&g, &c)| remap_glyph(g, map).map(|g| (g, c))).collect()
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct LangSys {
+    pub required_feature: Option<u16>,
+    pub features: Vec<u16>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Script {
+    pub tag: [u8; 4],
+    pub default_lang_sys: Option<LangSys>,
+    pub lang_systems: Vec<([u8; 4], LangSys)>,
+}
+
+// params 为自成一体的 FeatureParams 数据（size、ssXX、cvXX）
+#[derive(Debug, Clone)]
+pub struct Feature {
+    pub tag: [u8; 4],
+    pub params: Option<Vec<u8>>,
+    pub lookups: Vec<u16>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Lookup<S> {
+    pub flag: u16,
+    pub mark_filtering_set: Option<u16>,
+    pub subtables: Vec<S>,
+}
+
+pub const USE_MARK_FILTERING_SET: u16 = 0x0010;
+
+// GSUB 与 GPOS 各类子表的解析、序列化与子集化
+pub trait Subtable: Sized + Clone {
+    // 扩展查找的类型：GSUB 为 7，GPOS 为 9
+    const EXTENSION: u16;
+
+    // 不支持的查找类型返回 None
+    fn parse(lookup_type: u16, data: &[u8]) -> Result<Option<Self>, String>;
+
+    fn lookup_type(&self) -> u16;
+
+    fn write(&self) -> Result<Vec<u8>, String>;
+
+    // lookups 为旧查找序号 → 新序号，子表不再包含任何字形时返回 None
+    fn subset(&self, glyphs: &GlyphMap, lookups: &[Option<u16>]) -> Option<Self>;
+}
+
+// GSUB/GPOS 的公共结构：脚本列表、特性列表与查找列表
+#[derive(Debug, Clone)]
+pub struct LayoutTable<S> {
+    pub scripts: Vec<Script>,
+    pub features: Vec<Feature>,
+    pub lookups: Vec<Lookup<S>>,
+}
+
+impl<S> Default for LayoutTable<S> {
+    fn default() -> Self {
+        LayoutTable { scripts: Vec::new(), features: Vec::new(), lookups: Vec::new() }
+    }
+}
+
+fn read_lang_sys(data: &[u8]) -> Result<LangSys, String> {
+    let mut r = Reader::new(data);
+    r.skip(2)?;
+    let required = r.u16()?;
+    let count = r.u16()?;
+    let features = (0..count).map(|_| r.u16()).collect::<Result<Vec<u16>, String>>()?;
+    Ok(LangSys { required_feature: (required != 0xFFFF).then_some(required), features })
+}
+
+fn write_lang_sys(lang_sys: &LangSys) -> Vec<u8> {
+    let mut w = Writer::new();
+    w.u16(0);
+    w.u16(lang_sys.required_feature.unwrap_or(0xFFFF));
+    w.u16(lang_sys.features.len() as u16);
+    lang_sys.features.iter().for_each(|&f| w.u16(f));
+    w.into_bytes()
+}
+
+// FeatureParams 没有子表偏移，按特性类型确定长度后原样保留
+fn read_feature_params(tag: &[u8; 4], data: &[u8]) -> Option<Vec<u8>> {
+    let len = match tag {
+        b"size" => 10,
+        [b's', b's', ..] => 4,
+        [b'c', b'v', ..] => 14 + 3 * Reader::at(data, 12).u16().ok()? as usize,
+        _ => return None,
+    };
+    data.get(..len).map(<[u8]>::to_vec)
+}
+
+impl<S: Subtable> LayoutTable<S> {
+    pub fn parse(data: &[u8]) -> Result<Self, String> {
+        let mut r = Reader::new(data);
+        let major = r.u16()?;
+        if major != 1 {
+            return Err(format!("unsupported layout table version {major}"));
+        }
+        r.skip(2)?;
+        let script_list = sub(data, r.u16()? as usize)?;
+        let feature_list = sub(data, r.u16()? as usize)?;
+        let lookup_list = sub(data, r.u16()? as usize)?;
+
+        let mut scripts = Vec::new();
+        let mut r = Reader::new(script_list);
+        for _ in 0..r.u16()? {
+            let tag = r.tag()?;
+            let script = sub(script_list, r.u16()? as usize)?;
+            let mut s = Reader::new(script);
+            let default_offset = s.u16()? as usize;
+            let default_lang_sys =
+                if default_offset == 0 { None } else { Some(read_lang_sys(sub(script, default_offset)?)?) };
+            let mut lang_systems = Vec::new();
+            for _ in 0..s.u16()? {
+                let lang_tag = s.tag()?;
+                lang_systems.push((lang_tag, read_lang_sys(sub(script, s.u16()? as usize)?)?));
+            }
+            scripts.push(Script { tag, default_lang_sys, lang_systems });
+        }
+
+        let mut features = Vec::new();
+        let mut r = Reader::new(feature_list);
+        for _ in 0..r.u16()? {
+            let tag = r.tag()?;
+            let feature = sub(feature_list, r.u16()? as usize)?;
+            let mut f = Reader::new(feature);
+            let params_offset = f.u16()? as usize;
+            let params =
+                if params_offset == 0 { None } else { read_feature_params(&tag, sub(feature, params_offset)?) };
+            let lookups = (0..f.u16()?).map(|_| f.u16()).collect::<Result<Vec<u16>, String>>()?;
+            features.push(Feature { tag, params, lookups });
+        }
+
+        let mut lookups = Vec::new();
+        let mut r = Reader::new(lookup_list);
+        for _ in 0..r.u16()? {
+            let lookup = sub(lookup_list, r.u16()? as usize)?;
+            let mut l = Reader::new(lookup);
+            let lookup_type = l.u16()?;
+            let flag = l.u16()?;
+            let count = l.u16()?;
+            let mut subtables = Vec::with_capacity(count as usize);
+            for _ in 0..count {
+                let mut data = sub(lookup, l.u16()? as usize)?;
+                let mut subtable_type = lookup_type;
+                // 扩展子表：取出实际的查找类型与子表
+                if lookup_type == S::EXTENSION {
+                    let mut e = Reader::new(data);
+                    e.skip(2)?;
+                    subtable_type = e.u16()?;
+                    data = sub(data, e.u32()? as usize)?;
+                }
+                if let Some(subtable) = S::parse(subtable_type, data)? {
+                    subtables.push(subtable);
+                }
+            }
+            let mark_filtering_set = if flag & USE_MARK_FILTERING_SET != 0 { Some(l.u16()?) } else { None };
+            lookups.push(Lookup { flag, mark_filtering_set, subtables });
+        }
+        Ok(LayoutTable { scripts, features, lookups })
+    }
+
+    fn script_list(&self) -> Result<Vec<u8>, String> {
+        let mut scripts: Vec<&Script> = self.scripts.iter().collect();
+        scripts.sort_by_key(|s| s.tag);
+        let mut p = Packer::new();
+        p.u16(scripts.len() as u16);
+        for script in scripts {
+            p.tag(&script.tag);
+            let mut s = Packer::new();
+            match &script.default_lang_sys {
+                Some(lang_sys) => s.offset(write_lang_sys(lang_sys)),
+                None => s.u16(0),
+            }
+            let mut lang_systems: Vec<&([u8; 4], LangSys)> = script.lang_systems.iter().collect();
+            lang_systems.sort_by_key(|(tag, _)| *tag);
+            s.u16(lang_systems.len() as u16);
+            for (tag, lang_sys) in lang_systems {
+                s.tag(tag);
+                s.offset(write_lang_sys(lang_sys));
+            }
+            p.offset(s.finish()?);
+        }
+        p.finish()
+    }
+
+    fn feature_list(&self) -> Result<Vec<u8>, String> {
+        let mut p = Packer::new();
+        p.u16(self.features.len() as u16);
+        for feature in &self.features {
+            p.tag(&feature.tag);
+            let mut f = Packer::new();
+            match &feature.params {
+                Some(params) => f.offset(params.clone()),
+                None => f.u16(0),
+            }
+            f.u16(feature.lookups.len() as u16);
+            feature.lookups.iter().for_each(|&l| f.u16(l));
+            p.offset(f.finish()?);
+        }
+        p.finish()
+    }
+
+    // 先尝试直接引用子表，Offset16 溢出时所有查找改用扩展子表（Offset32）
+    fn lookup_list(&self) -> Result<Vec<u8>, String> {
+        let subtables: Vec<Vec<Vec<u8>>> = self
+            .lookups
+            .iter()
+            .map(|lookup| lookup.subtables.iter().map(S::write).collect::<Result<Vec<_>, String>>())
+            .collect::<Result<_, String>>()?;
+        self.write_lookup_list(&subtables, false).or_else(|_| self.write_lookup_list(&subtables, true))
+    }
+
+    fn write_lookup_list(&self, subtables: &[Vec<Vec<u8>>], extension: bool) -> Result<Vec<u8>, String> {
+        let header_size = |lookup: &Lookup<S>| {
+            6 + 2 * lookup.subtables.len() + if lookup.mark_filtering_set.is_some() { 2 } else { 0 }
+        };
+        let mut w = Writer::new();
+        w.u16(self.lookups.len() as u16);
+        let mut offset = 2 + 2 * self.lookups.len();
+        let mut lookup_offsets = Vec::with_capacity(self.lookups.len());
+        for lookup in &self.lookups {
+            lookup_offsets.push(offset);
+            offset += header_size(lookup);
+        }
+        // 扩展子表紧跟在所有查找表之后，实际子表放在最后
+        let extension_start = offset;
+        let extension_count: usize = self.lookups.iter().map(|l| l.subtables.len()).sum();
+        let data_start = if extension { extension_start + 8 * extension_count } else { extension_start };
+        for &lookup_offset in &lookup_offsets {
+            if lookup_offset > u16::MAX as usize {
+                return Err(String::from("Offset16 overflow in lookup list"));
+            }
+            w.u16(lookup_offset as u16);
+        }
+
+        let mut data = Writer::new();
+        let mut placed: Vec<(&[u8], usize)> = Vec::new();
+        let mut extensions = Writer::new();
+        let mut extension_index = 0;
+        for ((lookup, blobs), &lookup_offset) in self.lookups.iter().zip(subtables).zip(&lookup_offsets) {
+            let lookup_type = lookup.subtables.first().map_or(0, S::lookup_type);
+            w.u16(if extension { S::EXTENSION } else { lookup_type });
+            w.u16(lookup.flag);
+            w.u16(blobs.len() as u16);
+            for blob in blobs {
+                let position = match placed.iter().find(|(d, _)| *d == blob.as_slice()) {
+                    Some(&(_, position)) => position,
+                    None => {
+                        let position = data_start + data.len();
+                        data.bytes(blob);
+                        placed.push((blob, position));
+                        position
+                    }
+                };
+                let target = if extension {
+                    let extension_offset = extension_start + 8 * extension_index;
+                    extension_index += 1;
+                    extensions.u16(1);
+                    extensions.u16(lookup_type);
+                    extensions.u32((position - extension_offset) as u32);
+                    extension_offset
+                } else {
+                    position
+                };
+                let relative = target - lookup_offset;
+                if relative > u16::MAX as usize {
+                    return Err(String::from("Offset16 overflow in lookup"));
+                }
+                w.u16(relative as u16);
+            }
+            if let Some(set) = lookup.mark_filtering_set {
+                w.u16(set);
+            }
+        }
+        w.bytes(extensions.as_bytes());
+        w.bytes(data.as_bytes());
+        Ok(w.into_bytes())
+    }
+
+    // 版本 1.0 的 GSUB/GPOS，查找列表放在最后以免较小的列表偏移量溢出
+    pub fn build(&self) -> Result<Vec<u8>, String> {
+        let script_list = self.script_list()?;
+        let feature_list = self.feature_list()?;
+        let lookup_list = self.lookup_list()?;
+        let feature_offset = 10 + script_list.len();
+        let lookup_offset = feature_offset + feature_list.len();
+        if lookup_offset > u16::MAX as usize {
+            return Err(String::from("Offset16 overflow in layout header"));
+        }
+        let mut w = Writer::new();
+        w.u16(1);
+        w.u16(0);
+        w.u16(10);
+        w.u16(feature_offset as u16);
+        w.u16(lookup_offset as u16);
+        w.bytes(&script_list);
+        w.bytes(&feature_list);
+        w.bytes(&lookup_list);
+        Ok(w.into_bytes())
+    }
+
+    // 子集化：子表按字形映射裁剪，空查找删除并重新编号，特性与脚本随之更新
+    pub fn subset(&self, glyphs: &GlyphMap) -> Self {
+        // 先按字形裁剪确定哪些查找仍然有效，上下文查找中对已删除查找的引用随后去掉
+        let identity: Vec<Option<u16>> = (0..self.lookups.len()).map(|i| Some(i as u16)).collect();
+        let kept: Vec<bool> =
+            self.lookups.iter().map(|l| l.subtables.iter().any(|s| s.subset(glyphs, &identity).is_some())).collect();
+        let mut lookup_map = Vec::with_capacity(self.lookups.len());
+        let mut next = 0u16;
+        for &keep in &kept {
+            lookup_map.push(keep.then(|| {
+                next += 1;
+                next - 1
+            }));
+        }
+        let lookups: Vec<Lookup<S>> = self
+            .lookups
+            .iter()
+            .zip(&kept)
+            .filter(|(_, &keep)| keep)
+            .map(|(lookup, _)| Lookup {
+                flag: lookup.flag,
+                mark_filtering_set: lookup.mark_filtering_set,
+                subtables: lookup.subtables.iter().filter_map(|s| s.subset(glyphs, &lookup_map)).collect(),
+            })
+            .collect();
+
+        let mut feature_map = Vec::with_capacity(self.features.len());
+        let mut features = Vec::new();
+        for feature in &self.features {
+            let lookups: Vec<u16> =
+                feature.lookups.iter().filter_map(|&l| lookup_map.get(l as usize).copied().flatten()).collect();
+            if lookups.is_empty() && feature.params.is_none() {
+                feature_map.push(None);
+                continue;
+            }
+            feature_map.push(Some(features.len() as u16));
+            features.push(Feature { tag: feature.tag, params: feature.params.clone(), lookups });
+        }
+        let remap_lang_sys = |lang_sys: &LangSys| LangSys {
+            required_feature: lang_sys.required_feature.and_then(|f| feature_map.get(f as usize).copied().flatten()),
+            features: lang_sys
+                .features
+                .iter()
+                .filter_map(|&f| feature_map.get(f as usize).copied().flatten())
+                .collect(),
+        };
+        let scripts = self
+            .scripts
+            .iter()
+            .map(|script| Script {
+                tag: script.tag,
+                default_lang_sys: script.default_lang_sys.as_ref().map(remap_lang_sys),
+                lang_systems: script.lang_systems.iter().map(|(tag, l)| (*tag, remap_lang_sys(l))).collect(),
+            })
+            .collect();
+        LayoutTable { scripts, features, lookups }
+    }
+}
@@ -0,0 +1,66 @@
+use crate::binary::Writer;
+
+use super::glyf::{CompositeGlyph, SimpleGlyph};
+
+// CFF 轮廓使用 0.5 版本，只包含字形数量
+pub fn build_cff(num_glyphs: u16) -> Vec<u8> {
+    let mut w = Writer::new();
+    w.u32(0x0000_5000);
+    w.u16(num_glyphs);
+    w.into_bytes()
+}
+
+// 提示指令相关的上限，未生成提示时全部为 0
+#[derive(Debug, Clone, Copy, Default)]
+pub struct InstructionLimits {
+    pub max_function_defs: u16,
+    pub max_stack_elements: u16,
+    pub max_size_of_instructions: u16,
+}
+
+// composites 中有值的字形为复合字形，glyphs 中对应项为展开后的点，只计入复合字形的统计
+pub fn build_truetype(
+    glyphs: &[SimpleGlyph],
+    composites: &[Option<CompositeGlyph>],
+    limits: &InstructionLimits,
+) -> Vec<u8> {
+    let composite = |gid: usize| composites.get(gid).and_then(Option::as_ref);
+    let (mut max_points, mut max_contours) = (0, 0);
+    let (mut max_composite_points, mut max_composite_contours) = (0, 0);
+    let (mut max_component_elements, mut max_component_depth) = (0, 0);
+    for (gid, g) in glyphs.iter().enumerate() {
+        match composite(gid) {
+            Some(c) => {
+                max_composite_points = max_composite_points.max(g.num_points());
+                max_composite_contours = max_composite_contours.max(g.contours.len());
+                max_component_elements = max_component_elements.max(c.components.len());
+                max_component_depth = max_component_depth.max(c.depth);
+            }
+            None => {
+                max_points = max_points.max(g.num_points());
+                max_contours = max_contours.max(g.contours.len());
+            }
+        }
+    }
+
+    let mut w = Writer::new();
+    w.u32(0x0001_0000);
+    w.u16(glyphs.len() as u16);
+    w.u16(max_points as u16);
+    w.u16(max_contours as u16);
+    w.u16(max_composite_points as u16);
+    w.u16(max_composite_contours as u16);
+    // maxZones
+    w.u16(2);
+    // maxTwilightPoints, maxStorage
+    w.u16(0);
+    w.u16(0);
+    w.u16(limits.max_function_defs);
+    // maxInstructionDefs
+    w.u16(0);
+    w.u16(limits.max_stack_elements);
+    w.u16(limits.max_size_of_instructions);
+    w.u16(max_component_elements as u16);
+    w.u16(max_component_depth);
+    w.into_bytes()
+}
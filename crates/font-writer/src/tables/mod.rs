@@ -0,0 +1,61 @@
+pub mod avar;
+pub mod cff;
+pub mod cmap;
+pub mod colr;
+pub mod cpal;
+pub mod fvar;
+pub mod gasp;
+pub mod glyf;
+pub mod gvar;
+pub mod head;
+pub mod hhea;
+pub mod hmtx;
+pub mod hvar;
+pub mod layout;
+pub mod maxp;
+pub mod name;
+pub mod os2;
+pub mod post;
+pub mod stat;
+pub mod svg;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BBox {
+    pub x_min: i16,
+    pub y_min: i16,
+    pub x_max: i16,
+    pub y_max: i16,
+}
+
+impl BBox {
+    pub fn union(self, other: BBox) -> BBox {
+        BBox {
+            x_min: self.x_min.min(other.x_min),
+            y_min: self.y_min.min(other.y_min),
+            x_max: self.x_max.max(other.x_max),
+            y_max: self.y_max.max(other.y_max),
+        }
+    }
+}
+
+// 各表共用的字形度量，空字形没有包围盒
+#[derive(Debug, Clone, Copy)]
+pub struct GlyphMetrics {
+    pub advance_width: u16,
+    pub bbox: Option<BBox>,
+}
+
+impl GlyphMetrics {
+    pub fn left_side_bearing(&self) -> i16 {
+        self.bbox.map_or(0, |b| b.x_min)
+    }
+}
+
+// 所有字形的整体包围盒
+pub fn font_bbox(metrics: &[GlyphMetrics]) -> BBox {
+    metrics
+        .iter()
+        .filter_map(|m| m.bbox)
+        .reduce(BBox::union)
+        .unwrap_or(BBox { x_min: 0, y_min: 0, x_max: 0, y_max: 0 })
+}
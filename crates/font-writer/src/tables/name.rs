@@ -0,0 +1,251 @@
+use std::collections::BTreeMap;
+
+use encoding_rs::{Encoding, BIG5, GBK, MACINTOSH, SHIFT_JIS};
+
+use crate::binary::Writer;
+use crate::FontInfo;
+
+// nameID → 语言标签 → 字符串
+pub type LocalizedNames = BTreeMap<u16, BTreeMap<String, String>>;
+
+// 必须提供英文记录的 nameID：家族名、子家族名、全名与 PostScript 名称
+const REQUIRED_NAME_IDS: [u16; 4] = [1, 2, 4, 6];
+
+// 支持的语言：标签、Windows 语言 ID，以及 Mac 平台的语言 ID 与编码 ID
+pub struct Language {
+    pub tag: &'static str,
+    pub windows_id: u16,
+    pub mac_id: u16,
+    pub mac_encoding_id: u16,
+}
+
+pub const LANGUAGES: [Language; 4] = [
+    Language { tag: "en-US", windows_id: 0x0409, mac_id: 0, mac_encoding_id: 0 },
+    Language { tag: "zh-CN", windows_id: 0x0804, mac_id: 33, mac_encoding_id: 25 },
+    Language { tag: "zh-TW", windows_id: 0x0404, mac_id: 19, mac_encoding_id: 2 },
+    Language { tag: "ja-JP", windows_id: 0x0411, mac_id: 11, mac_encoding_id: 1 },
+];
+
+const ENGLISH: &Language = &LANGUAGES[0];
+
+#[derive(Debug, Clone)]
+pub struct NameRecord {
+    pub platform_id: u16,
+    pub encoding_id: u16,
+    pub language_id: u16,
+    pub name_id: u16,
+    pub data: Vec<u8>,
+}
+
+// PostScript 名称只允许可打印 ASCII（不含空格和 [](){}<>/%），最长 63 个字符
+pub fn sanitize_post_script_name(name: &str) -> String {
+    let mut out = String::new();
+    for c in name.chars() {
+        if c.is_ascii_graphic() && !"[](){}<>/%".contains(c) {
+            out.push(c);
+        } else if !c.is_ascii() {
+            out.push_str(&(c as u32).to_string());
+        }
+    }
+    if out.is_empty() {
+        out.push_str("Untitled");
+    }
+    out.truncate(63);
+    out
+}
+
+fn is_ribbi(style: &str) -> bool {
+    matches!(style, "Regular" | "Bold" | "Italic" | "Bold Italic")
+}
+
+// 字体的 (nameID, 字符串) 列表，空字符串不写入
+pub fn name_strings(info: &FontInfo) -> Vec<(u16, String)> {
+    let style = if info.style_name.is_empty() { "Regular" } else { info.style_name.as_str() };
+    let post_script_name = info.post_script_name();
+    let mut names = vec![(0, info.copyright.clone())];
+    // 非 Regular/Bold/Italic/Bold Italic 的样式名放入家族名，并写入 16/17 号典型家族名
+    if is_ribbi(style) {
+        names.push((1, info.family_name.clone()));
+        names.push((2, style.to_string()));
+    } else {
+        names.push((1, format!("{} {}", info.family_name, style)));
+        names.push((2, String::from("Regular")));
+    }
+    names.push((3, format!("{};{};{}", info.version, info.vendor_id, post_script_name)));
+    names.push((4, info.full_name()));
+    names.push((5, info.version.clone()));
+    names.push((6, post_script_name));
+    names.push((7, info.trademark.clone()));
+    names.push((8, info.manufacturer.clone()));
+    names.push((9, info.designer.clone()));
+    names.push((10, info.description.clone()));
+    names.push((11, info.manufacturer_url.clone()));
+    names.push((12, info.designer_url.clone()));
+    names.push((13, info.license.clone()));
+    names.push((14, info.license_url.clone()));
+    if !is_ribbi(style) {
+        names.push((16, info.family_name.clone()));
+        names.push((17, style.to_string()));
+    }
+    names.retain(|(_, value)| !value.is_empty());
+    names
+}
+
+fn utf16be(value: &str) -> Vec<u8> {
+    value.encode_utf16().flat_map(|u| u.to_be_bytes()).collect()
+}
+
+// GB2312（EUC-CN）是 GBK 的子集，双字节的首尾字节都在 0xA1..=0xFE 之间
+fn is_gb2312(bytes: &[u8]) -> bool {
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] < 0x80 {
+            i += 1;
+        } else if (0xA1..=0xF7).contains(&bytes[i]) && bytes.get(i + 1).is_some_and(|b| (0xA1..=0xFE).contains(b)) {
+            i += 2;
+        } else {
+            return false;
+        }
+    }
+    true
+}
+
+// Mac 平台按语言使用 Mac Roman、GB2312、Big5 或 Shift-JIS，包含无法编码的字符时返回 None
+fn mac_bytes(value: &str, language: &Language) -> Option<Vec<u8>> {
+    if value.is_ascii() {
+        return Some(value.as_bytes().to_vec());
+    }
+    let encoding: &'static Encoding = match language.mac_encoding_id {
+        25 => GBK,
+        2 => BIG5,
+        1 => SHIFT_JIS,
+        _ => MACINTOSH,
+    };
+    let (bytes, _, had_errors) = encoding.encode(value);
+    if had_errors || (language.mac_encoding_id == 25 && !is_gb2312(&bytes)) {
+        return None;
+    }
+    Some(bytes.into_owned())
+}
+
+// Windows 平台使用 UTF-16BE；能以 Mac 传统编码表示的字符串另外写入 Mac 记录
+pub fn localized_records(name_id: u16, language: &Language, value: &str) -> Vec<NameRecord> {
+    let mut records = Vec::new();
+    if let Some(data) = mac_bytes(value, language) {
+        records.push(NameRecord {
+            platform_id: 1,
+            encoding_id: language.mac_encoding_id,
+            language_id: language.mac_id,
+            name_id,
+            data,
+        });
+    }
+    records.push(NameRecord {
+        platform_id: 3,
+        encoding_id: 1,
+        language_id: language.windows_id,
+        name_id,
+        data: utf16be(value),
+    });
+    records
+}
+
+pub fn string_records(name_id: u16, value: &str) -> Vec<NameRecord> {
+    localized_records(name_id, ENGLISH, value)
+}
+
+pub fn language(tag: &str) -> Result<&'static Language, String> {
+    LANGUAGES.iter().find(|l| l.tag.eq_ignore_ascii_case(tag)).ok_or_else(|| {
+        let supported: Vec<&str> = LANGUAGES.iter().map(|l| l.tag).collect();
+        format!("unsupported name language {tag:?}, expected one of {}", supported.join(", "))
+    })
+}
+
+// PostScript 名称：1 到 63 个可打印 ASCII 字符，不含空格和 [](){}<>/%
+pub fn check_post_script_name(name: &str) -> Result<(), String> {
+    if name.is_empty() || name.len() > 63 {
+        return Err(format!("PostScript name {name:?} must be 1 to 63 characters long"));
+    }
+    if let Some(c) = name.chars().find(|&c| !c.is_ascii_graphic() || "[](){}<>/%".contains(c)) {
+        return Err(format!("PostScript name {name:?} contains invalid character {c:?}"));
+    }
+    Ok(())
+}
+
+// 字体信息生成的英文名称与 localizedNames 合并，英文条目覆盖默认值，空字符串删除对应记录
+pub fn localized_strings(info: &FontInfo) -> Result<Vec<(u16, &'static Language, String)>, String> {
+    let mut names: BTreeMap<(u16, usize), String> =
+        name_strings(info).into_iter().map(|(name_id, value)| ((name_id, 0), value)).collect();
+    for (&name_id, values) in &info.localized_names {
+        for (tag, value) in values {
+            let language = language(tag)?;
+            let index = LANGUAGES.iter().position(|l| l.tag == language.tag).unwrap_or(0);
+            if value.is_empty() {
+                names.remove(&(name_id, index));
+            } else {
+                names.insert((name_id, index), value.clone());
+            }
+        }
+    }
+
+    for name_id in REQUIRED_NAME_IDS {
+        if !names.contains_key(&(name_id, 0)) {
+            return Err(format!("missing required name ID {name_id} ({})", ENGLISH.tag));
+        }
+    }
+    for ((name_id, index), value) in &names {
+        if *name_id == 6 {
+            if *index != 0 {
+                return Err(format!("PostScript name (name ID 6) cannot be localized to {}", LANGUAGES[*index].tag));
+            }
+            check_post_script_name(value)?;
+        }
+        if value.encode_utf16().count() * 2 > u16::MAX as usize {
+            return Err(format!("name ID {name_id} ({}) is too long", LANGUAGES[*index].tag));
+        }
+    }
+    Ok(names.into_iter().map(|((name_id, index), value)| (name_id, &LANGUAGES[index], value)).collect())
+}
+
+pub fn records(info: &FontInfo) -> Result<Vec<NameRecord>, String> {
+    Ok(localized_strings(info)?
+        .into_iter()
+        .flat_map(|(name_id, language, value)| localized_records(name_id, language, &value))
+        .collect())
+}
+
+// format 0 的 name 表，记录排序后写入，相同的字符串数据共享存储
+pub fn build_from_records(mut records: Vec<NameRecord>) -> Vec<u8> {
+    records.sort_by_key(|r| (r.platform_id, r.encoding_id, r.language_id, r.name_id));
+    let mut storage: Vec<u8> = Vec::new();
+    let mut offsets = Vec::with_capacity(records.len());
+    for record in &records {
+        let offset = match storage.windows(record.data.len().max(1)).position(|w| w == record.data.as_slice()) {
+            Some(offset) if !record.data.is_empty() => offset,
+            _ => {
+                storage.extend_from_slice(&record.data);
+                storage.len() - record.data.len()
+            }
+        };
+        offsets.push(offset);
+    }
+
+    let mut w = Writer::new();
+    w.u16(0);
+    w.u16(records.len() as u16);
+    w.u16((6 + 12 * records.len()) as u16);
+    for (record, offset) in records.iter().zip(offsets) {
+        w.u16(record.platform_id);
+        w.u16(record.encoding_id);
+        w.u16(record.language_id);
+        w.u16(record.name_id);
+        w.u16(record.data.len() as u16);
+        w.u16(offset as u16);
+    }
+    w.bytes(&storage);
+    w.into_bytes()
+}
+
+pub fn build(info: &FontInfo) -> Result<Vec<u8>, String> {
+    Ok(build_from_records(records(info)?))
+}
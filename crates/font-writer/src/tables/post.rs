@@ -0,0 +1,124 @@
+use std::collections::HashSet;
+
+use crate::binary::{Reader, Writer};
+use crate::{FontInfo, GlyphData};
+
+// 合法的字形名：不超过 63 个字符，只含 A-Z a-z 0-9 . _，且不以数字开头
+fn is_valid_glyph_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 63
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '_')
+        && !name.starts_with(|c: char| c.is_ascii_digit())
+        && (name == ".notdef" || !name.starts_with('.'))
+}
+
+// 生成唯一的产品字形名，不合法的名称改用 uniXXXX / uXXXXX / glyphN
+pub fn production_names(glyphs: &[GlyphData]) -> Vec<String> {
+    let mut used: HashSet<String> = HashSet::new();
+    let mut names = Vec::with_capacity(glyphs.len());
+    for (gid, glyph) in glyphs.iter().enumerate() {
+        let base = if gid == 0 {
+            String::from(".notdef")
+        } else if is_valid_glyph_name(&glyph.name) && glyph.name != ".notdef" {
+            glyph.name.clone()
+        } else if glyph.unicode == 0 {
+            format!("glyph{gid}")
+        } else if glyph.unicode <= 0xFFFF {
+            format!("uni{:04X}", glyph.unicode)
+        } else {
+            format!("u{:X}", glyph.unicode)
+        };
+        let mut name = base.clone();
+        let mut suffix = 1;
+        while used.contains(&name) {
+            name = format!("{base}.{suffix}");
+            suffix += 1;
+        }
+        used.insert(name.clone());
+        names.push(name);
+    }
+    names
+}
+
+fn header(w: &mut Writer, version: u32, info: &FontInfo) {
+    let upm = info.units_per_em as f64;
+    w.u32(version);
+    w.fixed(info.italic_angle);
+    // underlinePosition, underlineThickness
+    w.i16((-upm * 0.1).round() as i16);
+    w.i16((upm * 0.05).round() as i16);
+    // isFixedPitch, minMemType42, maxMemType42, minMemType1, maxMemType1
+    w.u32(0);
+    for _ in 0..4 {
+        w.u32(0);
+    }
+}
+
+// 3.0 版本不含字形名，CFF 轮廓的字形名保存在 charset 中
+pub fn build_without_names(info: &FontInfo) -> Vec<u8> {
+    let mut w = Writer::new();
+    header(&mut w, 0x0003_0000, info);
+    w.into_bytes()
+}
+
+// 2.0 版本：.notdef 使用标准 Macintosh 字形名序号 0，其余字形名写入自定义名称列表
+pub fn build(info: &FontInfo, names: &[String]) -> Vec<u8> {
+    let mut w = Writer::new();
+    header(&mut w, 0x0002_0000, info);
+    w.u16(names.len() as u16);
+    let mut custom = Vec::new();
+    for name in names {
+        if name == ".notdef" {
+            w.u16(0);
+        } else {
+            w.u16((258 + custom.len()) as u16);
+            custom.push(name);
+        }
+    }
+    for name in custom {
+        w.u8(name.len() as u8);
+        w.bytes(name.as_bytes());
+    }
+    w.into_bytes()
+}
+
+// 子集化：2.0 版本按 glyph_ids（旧字形 ID，按新顺序排列）保留字形名，其他版本改为不含字形名的 3.0
+pub fn subset(data: &[u8], glyph_ids: &[u16]) -> Result<Vec<u8>, String> {
+    let header = data.get(..32).ok_or("post table is too short")?;
+    let mut w = Writer::new();
+    if Reader::new(data).u32()? != 0x0002_0000 {
+        w.u32(0x0003_0000);
+        w.bytes(&header[4..]);
+        return Ok(w.into_bytes());
+    }
+    let mut r = Reader::at(data, 32);
+    let num_glyphs = r.u16()?;
+    let indices = (0..num_glyphs).map(|_| r.u16()).collect::<Result<Vec<u16>, String>>()?;
+    let mut strings = Vec::new();
+    while r.remaining() > 0 {
+        let len = r.u8()? as usize;
+        strings.push(r.bytes(len)?);
+    }
+
+    w.bytes(header);
+    w.u16(glyph_ids.len() as u16);
+    let mut custom: Vec<&[u8]> = Vec::new();
+    for &gid in glyph_ids {
+        match indices.get(gid as usize).copied().unwrap_or(0) {
+            index @ 0..=257 => w.u16(index),
+            index => {
+                let name = strings.get(index as usize - 258).copied().unwrap_or_default();
+                let position = custom.iter().position(|&n| n == name).unwrap_or_else(|| {
+                    custom.push(name);
+                    custom.len() - 1
+                });
+                w.u16((258 + position) as u16);
+            }
+        }
+    }
+    for name in custom {
+        w.u8(name.len() as u8);
+        w.bytes(name);
+    }
+    Ok(w.into_bytes())
+}
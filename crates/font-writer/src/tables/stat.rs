@@ -0,0 +1,48 @@
+use crate::binary::Writer;
+use crate::variable::Axis;
+
+const ELIDABLE_AXIS_VALUE_NAME: u16 = 0x0002;
+
+// format 1 的轴取值记录
+#[derive(Debug, Clone)]
+pub struct AxisValue {
+    pub axis_index: u16,
+    pub value: f64,
+    pub name_id: u16,
+    pub elidable: bool,
+}
+
+// STAT 1.1，省略全部可省略名称时回退到 nameID 2
+pub fn build(axes: &[Axis], axis_name_ids: &[u16], values: &[AxisValue]) -> Vec<u8> {
+    let header_size = 20;
+    let design_axes_offset = header_size;
+    let axis_values_offset = design_axes_offset + 8 * axes.len();
+
+    let mut w = Writer::new();
+    w.u16(1);
+    w.u16(1);
+    w.u16(8);
+    w.u16(axes.len() as u16);
+    w.u32(if axes.is_empty() { 0 } else { design_axes_offset as u32 });
+    w.u16(values.len() as u16);
+    w.u32(if values.is_empty() { 0 } else { axis_values_offset as u32 });
+    w.u16(2);
+    for (ordering, (axis, &name_id)) in axes.iter().zip(axis_name_ids).enumerate() {
+        w.tag(&axis.tag_bytes());
+        w.u16(name_id);
+        w.u16(ordering as u16);
+    }
+
+    // 偏移量数组之后依次是各个 12 字节的 format 1 记录
+    for i in 0..values.len() {
+        w.u16((2 * values.len() + 12 * i) as u16);
+    }
+    for value in values {
+        w.u16(1);
+        w.u16(value.axis_index);
+        w.u16(if value.elidable { ELIDABLE_AXIS_VALUE_NAME } else { 0 });
+        w.u16(value.name_id);
+        w.fixed(value.value);
+    }
+    w.into_bytes()
+}
@@ -0,0 +1,55 @@
+use std::collections::HashMap;
+use std::io::Write;
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+use crate::binary::Writer;
+
+// 一个 SVG 文档及其包含的字形 ID 范围（含两端），文档中每个字形都有 id="glyph{ID}" 的元素
+pub struct SvgDocument {
+    pub start: u16,
+    pub end: u16,
+    pub data: String,
+}
+
+fn gzip(data: &[u8]) -> Result<Vec<u8>, String> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+    encoder.write_all(data).map_err(|err| format!("gzip compression failed: {err}"))?;
+    encoder.finish().map_err(|err| format!("gzip compression failed: {err}"))
+}
+
+// SVG 表版本 0，documents 按字形 ID 升序且范围不重叠；内容相同的文档只写一次
+pub fn build(documents: &[SvgDocument], compress: bool) -> Result<Vec<u8>, String> {
+    let mut data = Vec::new();
+    let mut offsets: HashMap<&str, (u32, u32)> = HashMap::new();
+    let list_header = 2 + 12 * documents.len();
+    let mut records = Vec::with_capacity(documents.len());
+    for document in documents {
+        let (offset, length) = match offsets.get(document.data.as_str()) {
+            Some(&entry) => entry,
+            None => {
+                let bytes = if compress { gzip(document.data.as_bytes())? } else { document.data.as_bytes().to_vec() };
+                let entry = ((list_header + data.len()) as u32, bytes.len() as u32);
+                data.extend_from_slice(&bytes);
+                offsets.insert(&document.data, entry);
+                entry
+            }
+        };
+        records.push((document.start, document.end, offset, length));
+    }
+
+    let mut w = Writer::new();
+    w.u16(0);
+    w.u32(10);
+    w.u32(0);
+    w.u16(records.len() as u16);
+    for (start, end, offset, length) in records {
+        w.u16(start);
+        w.u16(end);
+        w.u32(offset);
+        w.u32(length);
+    }
+    w.bytes(&data);
+    Ok(w.into_bytes())
+}
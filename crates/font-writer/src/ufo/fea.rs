@@ -0,0 +1,752 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::features::{
+    ChainInput, FeatureDefinition, FeatureLanguage, Features, GlyphClass, GlyphRef, LanguageSystem, SubstitutionLookup,
+    SubstitutionRule,
+};
+
+// 未声明语言系统时写出编译时使用的默认值，其他工具读取时得到相同的结果
+const DEFAULT_LANGUAGE_SYSTEMS: [(&str, &str); 2] = [("DFLT", "dflt"), ("latn", "dflt")];
+
+// .fea 中查找与类别的名称：字母、数字、下划线与点，不以数字开头，最长 63 个字符
+pub fn identifier(name: &str) -> String {
+    let mut id: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '_' || c == '.' { c } else { '_' })
+        .take(63)
+        .collect();
+    if id.is_empty() || id.starts_with(|c: char| c.is_ascii_digit() || c == '.') {
+        id.insert(0, '_');
+        id.truncate(63);
+    }
+    id
+}
+
+// 逐个转换为合法且不重复的名称，重名时追加序号
+pub fn identifiers<'a>(names: impl Iterator<Item = &'a str>, fallback: &str) -> Vec<String> {
+    let mut used = HashSet::new();
+    names
+        .enumerate()
+        .map(|(i, name)| {
+            let base = if name.is_empty() { format!("{fallback}{i}") } else { identifier(name) };
+            let mut id = base.clone();
+            let mut n = 1;
+            while !used.insert(id.clone()) {
+                id = format!("{}_{n}", &base[..base.len().min(58)]);
+                n += 1;
+            }
+            id
+        })
+        .collect()
+}
+
+struct Writer<'a> {
+    features: &'a Features,
+    glyph_names: &'a [String],
+    class_names: &'a [String],
+    lookup_names: &'a [String],
+}
+
+impl Writer<'_> {
+    fn glyph(&self, glyph: usize) -> Result<&str, String> {
+        self.glyph_names
+            .get(glyph)
+            .map(String::as_str)
+            .ok_or_else(|| format!("features refer to missing glyph {glyph}"))
+    }
+
+    fn glyph_ref(&self, glyphs: &GlyphRef) -> Result<String, String> {
+        match *glyphs {
+            GlyphRef::Glyph { glyph } => Ok(self.glyph(glyph)?.to_string()),
+            GlyphRef::Class { class } => self
+                .class_names
+                .get(class)
+                .map(|name| format!("@{name}"))
+                .ok_or_else(|| format!("features refer to missing class {class}")),
+        }
+    }
+
+    fn sequence(&self, glyphs: &[GlyphRef]) -> Result<Vec<String>, String> {
+        glyphs.iter().map(|g| self.glyph_ref(g)).collect()
+    }
+
+    fn lookup_name(&self, lookup: usize) -> Result<&str, String> {
+        self.lookup_names
+            .get(lookup)
+            .map(String::as_str)
+            .ok_or_else(|| format!("features refer to missing lookup {lookup}"))
+    }
+
+    fn rule(&self, rule: &SubstitutionRule) -> Result<String, String> {
+        Ok(match rule {
+            SubstitutionRule::Single { input, replacement } => {
+                format!("sub {} by {};", self.glyph_ref(input)?, self.glyph_ref(replacement)?)
+            }
+            SubstitutionRule::Multiple { input, replacement } => {
+                let replacement: Vec<&str> = replacement.iter().map(|&g| self.glyph(g)).collect::<Result<_, _>>()?;
+                format!("sub {} by {};", self.glyph(*input)?, replacement.join(" "))
+            }
+            SubstitutionRule::Ligature { components, ligature } => {
+                format!("sub {} by {};", self.sequence(components)?.join(" "), self.glyph(*ligature)?)
+            }
+            SubstitutionRule::Chain { backtrack, input, lookahead } => {
+                let mut items = self.sequence(backtrack)?;
+                for position in input {
+                    let mut item = format!("{}'", self.glyph_ref(&position.glyphs)?);
+                    if let Some(lookup) = position.lookup {
+                        item.push_str(&format!(" lookup {}", self.lookup_name(lookup)?));
+                    }
+                    items.push(item);
+                }
+                items.extend(self.sequence(lookahead)?);
+                // 输入位置都不指定查找时相当于 ignore sub
+                let keyword = if input.iter().all(|p| p.lookup.is_none()) { "ignore sub" } else { "sub" };
+                format!("{keyword} {};", items.join(" "))
+            }
+        })
+    }
+
+    // .fea 要求查找先定义后引用，链式规则引用的查找提前写出
+    fn lookup(&self, index: usize, written: &mut Vec<bool>, fea: &mut String) -> Result<(), String> {
+        if written[index] {
+            return Ok(());
+        }
+        written[index] = true;
+        let lookup = &self.features.lookups[index];
+        for rule in &lookup.rules {
+            if let SubstitutionRule::Chain { input, .. } = rule {
+                for nested in input.iter().filter_map(|p| p.lookup) {
+                    self.lookup_name(nested)?;
+                    self.lookup(nested, written, fea)?;
+                }
+            }
+        }
+        let name = &self.lookup_names[index];
+        fea.push_str(&format!("lookup {name} {{\n"));
+        for rule in &lookup.rules {
+            fea.push_str(&format!("    {}\n", self.rule(rule)?));
+        }
+        fea.push_str(&format!("}} {name};\n\n"));
+        Ok(())
+    }
+
+    fn feature(
+        &self,
+        feature: &FeatureDefinition,
+        systems: &[(String, String)],
+        fea: &mut String,
+    ) -> Result<(), String> {
+        fea.push_str(&format!("feature {} {{\n", feature.tag));
+        for &lookup in &feature.lookups {
+            fea.push_str(&format!("    lookup {};\n", self.lookup_name(lookup)?));
+        }
+        // 按脚本分组；脚本默认语言的查找对该脚本的所有语言生效，因此其余语言都要写出 language 语句
+        let mut scripts: Vec<&str> = Vec::new();
+        for language in &feature.languages {
+            if !scripts.contains(&language.script.as_str()) {
+                scripts.push(&language.script);
+            }
+        }
+        for script in scripts {
+            fea.push_str(&format!("    script {script};\n"));
+            let lookups = |language: &str| -> Vec<usize> {
+                feature
+                    .languages
+                    .iter()
+                    .filter(|l| l.script == script && l.language == language)
+                    .flat_map(|l| l.lookups.iter().copied())
+                    .collect()
+            };
+            for lookup in lookups("dflt") {
+                fea.push_str(&format!("    lookup {};\n", self.lookup_name(lookup)?));
+            }
+            let mut languages: Vec<&str> = systems
+                .iter()
+                .filter(|(s, l)| s == script && l != "dflt")
+                .map(|(_, l)| l.as_str())
+                .chain(
+                    feature
+                        .languages
+                        .iter()
+                        .filter(|l| l.script == script && l.language != "dflt")
+                        .map(|l| l.language.as_str()),
+                )
+                .collect();
+            let mut seen = HashSet::new();
+            languages.retain(|l| seen.insert(*l));
+            for language in languages {
+                fea.push_str(&format!("    language {language};\n"));
+                for lookup in lookups(language) {
+                    fea.push_str(&format!("    lookup {};\n", self.lookup_name(lookup)?));
+                }
+            }
+        }
+        fea.push_str(&format!("}} {};\n\n", feature.tag));
+        Ok(())
+    }
+}
+
+pub fn is_empty(features: &Features) -> bool {
+    features.classes.is_empty() && features.lookups.is_empty() && features.features.is_empty()
+}
+
+// 生成 features.fea，名称均已转换为合法的标识符
+pub fn write(
+    features: &Features,
+    glyph_names: &[String],
+    class_names: &[String],
+    lookup_names: &[String],
+) -> Result<String, String> {
+    if is_empty(features) {
+        return Ok(String::new());
+    }
+    let writer = Writer { features, glyph_names, class_names, lookup_names };
+    let mut fea = String::new();
+    let systems: Vec<(String, String)> = if features.language_systems.is_empty() {
+        DEFAULT_LANGUAGE_SYSTEMS.iter().map(|&(s, l)| (s.to_string(), l.to_string())).collect()
+    } else {
+        features.language_systems.iter().map(|s| (s.script.clone(), s.language.clone())).collect()
+    };
+    for (script, language) in &systems {
+        fea.push_str(&format!("languagesystem {script} {language};\n"));
+    }
+    fea.push('\n');
+    for (class, name) in features.classes.iter().zip(class_names) {
+        let glyphs: Vec<&str> = class.glyphs.iter().map(|&g| writer.glyph(g)).collect::<Result<_, _>>()?;
+        fea.push_str(&format!("@{name} = [{}];\n", glyphs.join(" ")));
+    }
+    if !features.classes.is_empty() {
+        fea.push('\n');
+    }
+    let mut written = vec![false; features.lookups.len()];
+    for index in 0..features.lookups.len() {
+        writer.lookup(index, &mut written, &mut fea)?;
+    }
+    for feature in &features.features {
+        writer.feature(feature, &systems, &mut fea)?;
+    }
+    Ok(fea)
+}
+
+// 被上下文规则引用的查找必须先定义，.fea 中的顺序可能与工程不同；
+// 读回后按名称列表恢复原顺序并同步所有查找序号，列表之外的查找保持原有次序排在最后
+pub fn reorder_lookups(features: &mut Features, order: &[&str]) {
+    let position: HashMap<&str, usize> = order.iter().enumerate().map(|(i, &name)| (name, i)).collect();
+    let mut lookups: Vec<(usize, SubstitutionLookup)> =
+        std::mem::take(&mut features.lookups).into_iter().enumerate().collect();
+    lookups.sort_by_key(|(_, lookup)| position.get(lookup.name.as_str()).copied().unwrap_or(usize::MAX));
+    let mut index = vec![0; lookups.len()];
+    for (new, (old, _)) in lookups.iter().enumerate() {
+        index[*old] = new;
+    }
+    features.lookups = lookups.into_iter().map(|(_, lookup)| lookup).collect();
+    let remap = |lookups: &mut Vec<usize>| lookups.iter_mut().for_each(|lookup| *lookup = index[*lookup]);
+    for feature in &mut features.features {
+        remap(&mut feature.lookups);
+        feature.languages.iter_mut().for_each(|language| remap(&mut language.lookups));
+    }
+    for lookup in &mut features.lookups {
+        for rule in &mut lookup.rules {
+            if let SubstitutionRule::Chain { input, .. } = rule {
+                input
+                    .iter_mut()
+                    .filter_map(|position| position.lookup.as_mut())
+                    .for_each(|lookup| *lookup = index[*lookup]);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Class(String),
+    String(String),
+    Symbol(char),
+}
+
+const SYMBOLS: &str = ";{}[]'=,()<>";
+
+fn tokenize(text: &str) -> Result<Vec<(Token, usize)>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    let mut line = 1;
+    while let Some(c) = chars.next() {
+        match c {
+            '\n' => line += 1,
+            c if c.is_whitespace() => {}
+            '#' => while chars.next_if(|&c| c != '\n').is_some() {},
+            '"' => {
+                let mut s = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some(c) => {
+                            line += usize::from(c == '\n');
+                            s.push(c);
+                        }
+                        None => return Err(format!("features.fea line {line}: unterminated string")),
+                    }
+                }
+                tokens.push((Token::String(s), line));
+            }
+            c if SYMBOLS.contains(c) => tokens.push((Token::Symbol(c), line)),
+            c => {
+                let mut word = String::from(c);
+                while let Some(c) =
+                    chars.next_if(|&c| !c.is_whitespace() && !SYMBOLS.contains(c) && c != '#' && c != '"')
+                {
+                    word.push(c);
+                }
+                let token = match word.strip_prefix('@') {
+                    Some(name) => Token::Class(name.to_string()),
+                    // 转义的名称（\sub）去掉反斜杠
+                    None => Token::Word(word.strip_prefix('\\').map(str::to_string).unwrap_or(word)),
+                };
+                tokens.push((token, line));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+// 规则中的一个位置：字形、是否标记（'）以及在该位置应用的查找
+struct Item {
+    glyphs: GlyphRef,
+    marked: bool,
+    lookup: Option<usize>,
+}
+
+struct Parser<'a> {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+    glyphs: &'a HashMap<String, usize>,
+    features: Features,
+    classes: HashMap<String, usize>,
+    lookups: HashMap<String, usize>,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(t, _)| t)
+    }
+
+    fn line(&self) -> usize {
+        self.tokens.get(self.pos).or(self.tokens.last()).map_or(0, |&(_, line)| line)
+    }
+
+    fn error(&self, message: &str) -> String {
+        format!("features.fea line {}: {message}", self.line())
+    }
+
+    fn next(&mut self) -> Result<Token, String> {
+        let token =
+            self.tokens.get(self.pos).map(|(t, _)| t.clone()).ok_or_else(|| self.error("unexpected end of file"))?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn is_word(&self, word: &str) -> bool {
+        matches!(self.peek(), Some(Token::Word(w)) if w == word)
+    }
+
+    fn is_symbol(&self, symbol: char) -> bool {
+        self.peek() == Some(&Token::Symbol(symbol))
+    }
+
+    fn word(&mut self) -> Result<String, String> {
+        match self.next()? {
+            Token::Word(w) => Ok(w),
+            token => Err(self.error(&format!("expected a name, found {token:?}"))),
+        }
+    }
+
+    fn expect(&mut self, symbol: char) -> Result<(), String> {
+        match self.next()? {
+            Token::Symbol(c) if c == symbol => Ok(()),
+            token => Err(self.error(&format!("expected '{symbol}', found {token:?}"))),
+        }
+    }
+
+    // 跳过不支持的语句，连同其中的块
+    fn skip_statement(&mut self) -> Result<(), String> {
+        let mut depth = 0;
+        loop {
+            match self.next()? {
+                Token::Symbol('{') => depth += 1,
+                Token::Symbol('}') => {
+                    depth -= 1;
+                    if depth < 0 {
+                        return Err(self.error("unbalanced '}'"));
+                    }
+                }
+                Token::Symbol(';') if depth == 0 => return Ok(()),
+                _ => {}
+            }
+        }
+    }
+
+    fn glyph(&self, name: &str) -> Result<usize, String> {
+        self.glyphs.get(name).copied().ok_or_else(|| self.error(&format!("unknown glyph {name:?}")))
+    }
+
+    fn class(&self, name: &str) -> Result<usize, String> {
+        self.classes.get(name).copied().ok_or_else(|| self.error(&format!("unknown glyph class @{name}")))
+    }
+
+    fn define_class(&mut self, name: String, glyphs: Vec<usize>) -> usize {
+        match self.classes.get(&name) {
+            Some(&index) => {
+                self.features.classes[index].glyphs = glyphs;
+                index
+            }
+            None => {
+                self.features.classes.push(GlyphClass { name: name.clone(), glyphs });
+                self.classes.insert(name, self.features.classes.len() - 1);
+                self.features.classes.len() - 1
+            }
+        }
+    }
+
+    // [a b @c] 或 @c
+    fn class_glyphs(&mut self) -> Result<Vec<usize>, String> {
+        match self.next()? {
+            Token::Class(name) => Ok(self.features.classes[self.class(&name)?].glyphs.clone()),
+            Token::Symbol('[') => {
+                let mut glyphs = Vec::new();
+                loop {
+                    match self.next()? {
+                        Token::Symbol(']') => return Ok(glyphs),
+                        Token::Word(name) => glyphs.push(self.glyph(&name)?),
+                        Token::Class(name) => glyphs.extend(self.features.classes[self.class(&name)?].glyphs.clone()),
+                        token => return Err(self.error(&format!("unexpected {token:?} in glyph class"))),
+                    }
+                }
+            }
+            token => Err(self.error(&format!("expected a glyph class, found {token:?}"))),
+        }
+    }
+
+    fn glyph_ref(&mut self) -> Result<GlyphRef, String> {
+        match self.peek() {
+            Some(Token::Word(_)) => {
+                let name = self.word()?;
+                Ok(GlyphRef::Glyph { glyph: self.glyph(&name)? })
+            }
+            Some(Token::Class(name)) => {
+                let class = self.class(&name.clone())?;
+                self.pos += 1;
+                Ok(GlyphRef::Class { class })
+            }
+            // 内联的类别转换为匿名类别，只有一个字形时按单个字形处理
+            Some(Token::Symbol('[')) => {
+                let glyphs = self.class_glyphs()?;
+                if let [glyph] = glyphs[..] {
+                    return Ok(GlyphRef::Glyph { glyph });
+                }
+                let name = format!("class{}", self.features.classes.len());
+                Ok(GlyphRef::Class { class: self.define_class(name, glyphs) })
+            }
+            token => Err(self.error(&format!("expected a glyph, found {token:?}"))),
+        }
+    }
+
+    fn items(&mut self) -> Result<Vec<Item>, String> {
+        let mut items = Vec::new();
+        while !self.is_symbol(';') && !self.is_symbol(',') && !self.is_word("by") && !self.is_word("from") {
+            let glyphs = self.glyph_ref()?;
+            let marked = self.is_symbol('\'');
+            if marked {
+                self.pos += 1;
+            }
+            let mut lookup = None;
+            while self.is_word("lookup") {
+                self.pos += 1;
+                let name = self.word()?;
+                let index = *self.lookups.get(&name).ok_or_else(|| self.error(&format!("unknown lookup {name:?}")))?;
+                // 模型中每个位置只应用一个查找
+                lookup.get_or_insert(index);
+            }
+            items.push(Item { glyphs, marked, lookup });
+        }
+        Ok(items)
+    }
+
+    fn add_lookup(&mut self, name: String, rules: Vec<SubstitutionRule>) -> usize {
+        let mut unique = name.clone();
+        let mut n = 1;
+        while self.lookups.contains_key(&unique) {
+            unique = format!("{name}_{n}");
+            n += 1;
+        }
+        self.features.lookups.push(SubstitutionLookup { name: unique.clone(), rules });
+        self.lookups.insert(unique, self.features.lookups.len() - 1);
+        self.features.lookups.len() - 1
+    }
+
+    fn chain(items: Vec<Item>) -> SubstitutionRule {
+        let first = items.iter().position(|i| i.marked).unwrap_or(0);
+        let last = items.iter().rposition(|i| i.marked).unwrap_or(0);
+        let mut backtrack = Vec::new();
+        let mut input = Vec::new();
+        let mut lookahead = Vec::new();
+        for (i, item) in items.into_iter().enumerate() {
+            if i < first {
+                backtrack.push(item.glyphs);
+            } else if i <= last {
+                input.push(ChainInput { glyphs: item.glyphs, lookup: item.lookup });
+            } else {
+                lookahead.push(item.glyphs);
+            }
+        }
+        SubstitutionRule::Chain { backtrack, input, lookahead }
+    }
+
+    fn plain_rule(&self, input: &[Item], replacement: &[GlyphRef]) -> Result<SubstitutionRule, String> {
+        let glyph = |g: &GlyphRef| match *g {
+            GlyphRef::Glyph { glyph } => Ok(glyph),
+            GlyphRef::Class { .. } => Err(self.error("glyph classes are only supported in single substitutions")),
+        };
+        match (input, replacement) {
+            ([input], [replacement]) => Ok(SubstitutionRule::Single { input: input.glyphs, replacement: *replacement }),
+            ([input], _) => Ok(SubstitutionRule::Multiple {
+                input: glyph(&input.glyphs)?,
+                replacement: replacement.iter().map(glyph).collect::<Result<_, _>>()?,
+            }),
+            (_, [ligature]) => Ok(SubstitutionRule::Ligature {
+                components: input.iter().map(|i| i.glyphs).collect(),
+                ligature: glyph(ligature)?,
+            }),
+            _ => Err(self.error("substituting a sequence by a sequence is not supported")),
+        }
+    }
+
+    // sub/ignore sub 之后的部分；不支持的形式（from、by NULL）返回 None 并跳过
+    fn rule(&mut self, ignore: bool, owner: &str) -> Result<Vec<SubstitutionRule>, String> {
+        if ignore {
+            let mut rules = Vec::new();
+            loop {
+                let mut items = self.items()?;
+                if !items.iter().any(|i| i.marked) {
+                    if let Some(first) = items.first_mut() {
+                        first.marked = true;
+                    }
+                }
+                rules.push(Self::chain(items));
+                match self.next()? {
+                    Token::Symbol(',') => {}
+                    Token::Symbol(';') => return Ok(rules),
+                    token => return Err(self.error(&format!("unexpected {token:?} in ignore rule"))),
+                }
+            }
+        }
+        let mut items = self.items()?;
+        if self.is_word("from") || items.is_empty() {
+            self.skip_statement()?;
+            return Ok(Vec::new());
+        }
+        let mut replacement = Vec::new();
+        if self.is_word("by") {
+            self.pos += 1;
+            if self.is_word("NULL") {
+                self.skip_statement()?;
+                return Ok(Vec::new());
+            }
+            while !self.is_symbol(';') {
+                replacement.push(self.glyph_ref()?);
+            }
+        }
+        self.expect(';')?;
+        let contextual = items.iter().any(|i| i.marked || i.lookup.is_some());
+        if !contextual {
+            return Ok(vec![self.plain_rule(&items, &replacement)?]);
+        }
+        // 上下文中直接写替换结果时，生成一个匿名查找应用在第一个标记位置
+        if !replacement.is_empty() {
+            let marked: Vec<Item> = items
+                .iter()
+                .filter(|i| i.marked)
+                .map(|i| Item { glyphs: i.glyphs, marked: true, lookup: None })
+                .collect();
+            let rule = self.plain_rule(&marked, &replacement)?;
+            let lookup = self.add_lookup(format!("{owner}_inline"), vec![rule]);
+            if let Some(first) = items.iter_mut().find(|i| i.marked) {
+                first.lookup = Some(lookup);
+            }
+        }
+        Ok(vec![Self::chain(items)])
+    }
+
+    // 查找块中的规则，其他语句（lookupflag、pos 等）忽略
+    fn lookup_body(&mut self, name: &str) -> Result<Vec<SubstitutionRule>, String> {
+        self.expect('{')?;
+        let mut rules = Vec::new();
+        while !self.is_symbol('}') {
+            rules.extend(self.statement_rule(name)?.unwrap_or_default());
+        }
+        self.expect('}')?;
+        let end = self.word()?;
+        if end != name {
+            return Err(self.error(&format!("lookup {name} is closed as {end}")));
+        }
+        self.expect(';')?;
+        Ok(rules)
+    }
+
+    // sub/substitute/ignore sub 规则，其余语句跳过后返回 None
+    fn statement_rule(&mut self, owner: &str) -> Result<Option<Vec<SubstitutionRule>>, String> {
+        if self.is_word("sub") || self.is_word("substitute") {
+            self.pos += 1;
+            return self.rule(false, owner).map(Some);
+        }
+        if self.is_word("ignore") {
+            let next = self.tokens.get(self.pos + 1).map(|(t, _)| t);
+            if matches!(next, Some(Token::Word(w)) if w == "sub" || w == "substitute") {
+                self.pos += 2;
+                return self.rule(true, owner).map(Some);
+            }
+        }
+        self.skip_statement()?;
+        Ok(None)
+    }
+
+    fn lookup_block(&mut self) -> Result<usize, String> {
+        let name = self.word()?;
+        if self.is_word("useExtension") {
+            self.pos += 1;
+        }
+        // 先登记名称，块中的内联查找排在它之后
+        let index = self.add_lookup(name.clone(), Vec::new());
+        let rules = self.lookup_body(&name)?;
+        self.features.lookups[index].rules = rules;
+        Ok(index)
+    }
+
+    fn register(&mut self, feature: usize, language: &Option<(String, String)>, lookup: usize) {
+        let definition = &mut self.features.features[feature];
+        match language {
+            None => definition.lookups.push(lookup),
+            Some((script, language)) => {
+                match definition.languages.iter_mut().find(|l| &l.script == script && &l.language == language) {
+                    Some(entry) => entry.lookups.push(lookup),
+                    None => definition.languages.push(FeatureLanguage {
+                        script: script.clone(),
+                        language: language.clone(),
+                        lookups: vec![lookup],
+                    }),
+                }
+            }
+        }
+    }
+
+    fn feature_block(&mut self) -> Result<(), String> {
+        let tag = self.word()?;
+        if self.is_word("useExtension") {
+            self.pos += 1;
+        }
+        self.expect('{')?;
+        let feature = match self.features.features.iter().position(|f| f.tag == tag) {
+            Some(index) => index,
+            None => {
+                self.features.features.push(FeatureDefinition { tag: tag.clone(), ..Default::default() });
+                self.features.features.len() - 1
+            }
+        };
+        let mut language: Option<(String, String)> = None;
+        // 特性块中直接写的规则按类型连续分组为匿名查找
+        let mut current: Option<usize> = None;
+        while !self.is_symbol('}') {
+            if self.is_word("script") {
+                self.pos += 1;
+                language = Some((self.word()?, String::from("dflt")));
+                current = None;
+                self.skip_statement()?;
+            } else if self.is_word("language") {
+                self.pos += 1;
+                let script = language.as_ref().map_or_else(|| String::from("DFLT"), |(s, _)| s.clone());
+                language = Some((script, self.word()?));
+                current = None;
+                self.skip_statement()?;
+            } else if self.is_word("lookup") {
+                self.pos += 1;
+                let is_reference = matches!(self.tokens.get(self.pos + 1), Some((Token::Symbol(';'), _)));
+                let lookup = if is_reference {
+                    let name = self.word()?;
+                    self.expect(';')?;
+                    *self.lookups.get(&name).ok_or_else(|| self.error(&format!("unknown lookup {name:?}")))?
+                } else {
+                    self.lookup_block()?
+                };
+                self.register(feature, &language, lookup);
+                current = None;
+            } else if let Some(rules) = self.statement_rule(&tag)? {
+                for rule in rules {
+                    let kind = std::mem::discriminant(&rule);
+                    let same_kind = current.is_some_and(|index| {
+                        self.features.lookups[index].rules.first().is_some_and(|r| std::mem::discriminant(r) == kind)
+                    });
+                    match current {
+                        Some(index) if same_kind => self.features.lookups[index].rules.push(rule),
+                        _ => {
+                            let index = self.add_lookup(tag.clone(), vec![rule]);
+                            self.register(feature, &language, index);
+                            current = Some(index);
+                        }
+                    }
+                }
+            }
+        }
+        self.expect('}')?;
+        let end = self.word()?;
+        if end != tag {
+            return Err(self.error(&format!("feature {tag} is closed as {end}")));
+        }
+        self.expect(';')
+    }
+
+    fn parse(&mut self) -> Result<(), String> {
+        while let Some(token) = self.peek().cloned() {
+            match token {
+                Token::Word(word) if word == "languagesystem" => {
+                    self.pos += 1;
+                    let (script, language) = (self.word()?, self.word()?);
+                    self.expect(';')?;
+                    self.features.language_systems.push(LanguageSystem { script, language });
+                }
+                Token::Word(word) if word == "lookup" => {
+                    self.pos += 1;
+                    self.lookup_block()?;
+                }
+                Token::Word(word) if word == "feature" => {
+                    self.pos += 1;
+                    self.feature_block()?;
+                }
+                Token::Class(name) => {
+                    self.pos += 1;
+                    self.expect('=')?;
+                    let glyphs = self.class_glyphs()?;
+                    self.expect(';')?;
+                    self.define_class(name, glyphs);
+                }
+                // table 块、markClass、include 等不在替换特性的模型中
+                _ => self.skip_statement()?,
+            }
+        }
+        Ok(())
+    }
+}
+
+// 解析 features.fea 中的替换特性；classes 为 groups.plist 中可在 .fea 中引用的非字偶距分组
+pub fn parse(text: &str, glyphs: &HashMap<String, usize>, classes: Vec<GlyphClass>) -> Result<Features, String> {
+    let class_index = classes.iter().enumerate().map(|(i, c)| (c.name.clone(), i)).collect();
+    let mut parser = Parser {
+        tokens: tokenize(text)?,
+        pos: 0,
+        glyphs,
+        features: Features { classes, ..Default::default() },
+        classes: class_index,
+        lookups: HashMap::new(),
+    };
+    parser.parse()?;
+    Ok(parser.features)
+}
@@ -0,0 +1,192 @@
+use crate::tables::name::LANGUAGES;
+use crate::FontInfo;
+
+use super::plist::{Dict, Value};
+
+// 字符串字段与 fontinfo.plist 键的对应
+fn string_fields(info: &mut FontInfo) -> [(&'static str, &mut String); 14] {
+    [
+        ("familyName", &mut info.family_name),
+        ("styleName", &mut info.style_name),
+        ("postscriptFullName", &mut info.full_name),
+        ("postscriptFontName", &mut info.post_script_name),
+        ("openTypeNameDesigner", &mut info.designer),
+        ("openTypeNameDesignerURL", &mut info.designer_url),
+        ("openTypeNameManufacturer", &mut info.manufacturer),
+        ("openTypeNameManufacturerURL", &mut info.manufacturer_url),
+        ("openTypeNameLicense", &mut info.license),
+        ("openTypeNameLicenseURL", &mut info.license_url),
+        ("openTypeNameVersion", &mut info.version),
+        ("openTypeNameDescription", &mut info.description),
+        ("copyright", &mut info.copyright),
+        ("trademark", &mut info.trademark),
+    ]
+}
+
+// 公历日期与 1970-01-01 起的天数互相转换
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year - era * 400;
+    let doy = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    (if month <= 2 { yoe + era * 400 + 1 } else { yoe + era * 400 }, month, day)
+}
+
+// openTypeHeadCreated 的格式为 YYYY/MM/DD HH:MM:SS（UTC）
+pub fn format_timestamp(timestamp: i64) -> String {
+    let (year, month, day) = civil_from_days(timestamp.div_euclid(86400));
+    let seconds = timestamp.rem_euclid(86400);
+    format!("{year:04}/{month:02}/{day:02} {:02}:{:02}:{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60)
+}
+
+pub fn parse_timestamp(value: &str) -> Result<i64, String> {
+    let invalid = || format!("invalid openTypeHeadCreated {value:?}");
+    let numbers: Vec<i64> = value
+        .split(['/', ' ', ':'])
+        .filter(|s| !s.is_empty())
+        .map(|s| s.parse().map_err(|_| invalid()))
+        .collect::<Result<_, _>>()?;
+    let [year, month, day, hour, minute, second] = numbers[..] else {
+        return Err(invalid());
+    };
+    Ok(days_from_civil(year, month, day) * 86400 + hour * 3600 + minute * 60 + second)
+}
+
+// "Version 1.002" → (1, 2)
+fn version_numbers(version: &str) -> Option<(i64, i64)> {
+    let number = version.trim().trim_start_matches("Version").trim().split(';').next()?.trim();
+    let (major, minor) = number.split_once('.').unwrap_or((number, "0"));
+    let minor: String = minor.chars().take_while(char::is_ascii_digit).collect();
+    Some((major.parse().ok()?, if minor.is_empty() { 0 } else { minor.parse().ok()? }))
+}
+
+pub fn write(info: &FontInfo) -> Dict {
+    let mut dict = Dict::default();
+    let mut info = info.clone();
+    let mut defaults = FontInfo::default();
+    // 缺省值非空的字段即使为空也写出，读回时不会被缺省值替换
+    for ((key, value), (_, default)) in string_fields(&mut info).into_iter().zip(string_fields(&mut defaults)) {
+        if !value.is_empty() || !default.is_empty() {
+            dict.insert(key, Value::String(value.clone()));
+        }
+    }
+    if let Some((major, minor)) = version_numbers(&info.version) {
+        dict.insert("versionMajor", Value::Integer(major));
+        dict.insert("versionMinor", Value::Integer(minor));
+    }
+    dict.insert("unitsPerEm", Value::Integer(info.units_per_em as i64));
+    dict.insert("ascender", Value::Integer(info.ascender as i64));
+    dict.insert("descender", Value::Integer(info.descender as i64));
+    dict.insert("openTypeHheaLineGap", Value::Integer(info.line_gap as i64));
+    if let Some(cap_height) = info.cap_height {
+        dict.insert("capHeight", Value::Integer(cap_height as i64));
+    }
+    if let Some(x_height) = info.x_height {
+        dict.insert("xHeight", Value::Integer(x_height as i64));
+    }
+    dict.insert("italicAngle", Value::number(info.italic_angle));
+    dict.insert("openTypeOS2WeightClass", Value::Integer(info.weight_class as i64));
+    dict.insert("openTypeOS2WidthClass", Value::Integer(info.width_class as i64));
+    dict.insert("openTypeOS2VendorID", Value::String(info.vendor_id.clone()));
+    if let Some(created) = info.created_timestamp {
+        dict.insert("openTypeHeadCreated", Value::String(format_timestamp(created)));
+    }
+
+    // 多语言名称写为 Windows 平台的名称记录，空字符串表示删除该记录
+    let records: Vec<Value> = info
+        .localized_names
+        .iter()
+        .flat_map(|(&name_id, values)| values.iter().map(move |(tag, value)| (name_id, tag, value)))
+        .filter_map(|(name_id, tag, value)| {
+            let language = LANGUAGES.iter().find(|l| l.tag.eq_ignore_ascii_case(tag))?;
+            let mut record = Dict::default();
+            record.insert("nameID", Value::Integer(name_id as i64));
+            record.insert("platformID", Value::Integer(3));
+            record.insert("encodingID", Value::Integer(1));
+            record.insert("languageID", Value::Integer(language.windows_id as i64));
+            record.insert("string", Value::String(value.clone()));
+            Some(Value::Dict(record))
+        })
+        .collect();
+    if !records.is_empty() {
+        dict.insert("openTypeNameRecords", Value::Array(records));
+    }
+    dict
+}
+
+fn integer<T: TryFrom<i64>>(dict: &Dict, key: &str) -> Result<Option<T>, String> {
+    dict.number(key)
+        .map(|v| T::try_from(v.round() as i64).map_err(|_| format!("fontinfo.plist {key} {v} is out of range")))
+        .transpose()
+}
+
+pub fn read(dict: &Dict) -> Result<FontInfo, String> {
+    let mut info = FontInfo::default();
+    for (key, value) in string_fields(&mut info) {
+        if let Some(s) = dict.str(key) {
+            *value = s.to_string();
+        }
+    }
+    if dict.get("openTypeNameVersion").is_none() {
+        if let Some(major) = dict.number("versionMajor") {
+            info.version = format!("Version {}.{:03}", major as i64, dict.number("versionMinor").unwrap_or(0.0) as i64);
+        }
+    }
+    if let Some(v) = integer(dict, "unitsPerEm")? {
+        info.units_per_em = v;
+    }
+    if let Some(v) = integer(dict, "ascender")? {
+        info.ascender = v;
+    }
+    if let Some(v) = integer(dict, "descender")? {
+        info.descender = v;
+    }
+    if let Some(v) = integer(dict, "openTypeHheaLineGap")? {
+        info.line_gap = v;
+    }
+    info.cap_height = integer(dict, "capHeight")?;
+    info.x_height = integer(dict, "xHeight")?;
+    if let Some(v) = dict.number("italicAngle") {
+        info.italic_angle = v;
+    }
+    if let Some(v) = integer(dict, "openTypeOS2WeightClass")? {
+        info.weight_class = v;
+    }
+    if let Some(v) = integer(dict, "openTypeOS2WidthClass")? {
+        info.width_class = v;
+    }
+    if let Some(s) = dict.str("openTypeOS2VendorID") {
+        info.vendor_id = s.to_string();
+    }
+    info.created_timestamp = dict.str("openTypeHeadCreated").map(parse_timestamp).transpose()?;
+
+    // 只读取支持的语言的 Windows 平台记录
+    for record in dict.get("openTypeNameRecords").and_then(Value::as_array).unwrap_or_default() {
+        let Some(record) = record.as_dict() else {
+            continue;
+        };
+        let number = |key: &str| record.number(key).map(|v| v as i64);
+        let (Some(name_id), Some(3), Some(language_id), Some(value)) =
+            (number("nameID"), number("platformID"), number("languageID"), record.str("string"))
+        else {
+            continue;
+        };
+        if let Some(language) = LANGUAGES.iter().find(|l| l.windows_id as i64 == language_id) {
+            info.localized_names.entry(name_id as u16).or_default().insert(language.tag.to_string(), value.to_string());
+        }
+    }
+    Ok(info)
+}
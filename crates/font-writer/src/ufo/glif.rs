@@ -0,0 +1,299 @@
+use crate::outline::{Contour, Contours, Point, Segment};
+use crate::xml::{escape, number};
+
+use super::plist::{self, Dict, Value};
+
+// glif 中组件变换属性的名称与缺省值，顺序与 [a, b, c, d, e, f] 一致
+const TRANSFORM_ATTRIBUTES: [(&str, f64); 6] =
+    [("xScale", 1.0), ("xyScale", 0.0), ("yxScale", 0.0), ("yScale", 1.0), ("xOffset", 0.0), ("yOffset", 0.0)];
+
+// 一个 .glif 文件的内容，组件按字形名引用
+#[derive(Debug, Clone, Default)]
+pub struct Glif {
+    pub name: String,
+    pub unicodes: Vec<u32>,
+    pub width: f64,
+    pub height: Option<f64>,
+    pub contours: Contours,
+    pub components: Vec<(String, Option<[f64; 6]>)>,
+    pub lib: Dict,
+}
+
+fn parse_number(node: roxmltree::Node, name: &str) -> Result<Option<f64>, String> {
+    node.attribute(name)
+        .map(|v| v.trim().parse::<f64>().map_err(|_| format!("invalid {name} {v:?} in <{}>", node.tag_name().name())))
+        .transpose()
+}
+
+// UFO 轮廓点：kind 为 None 表示控制点
+struct UfoPoint {
+    point: Point,
+    kind: Option<String>,
+}
+
+fn lerp(a: Point, b: Point, t: f64) -> Point {
+    Point { x: a.x + (b.x - a.x) * t, y: a.y + (b.y - a.y) * t }
+}
+
+fn midpoint(a: Point, b: Point) -> Point {
+    lerp(a, b, 0.5)
+}
+
+// 一个落点及其之前的控制点转换为 Segment；qcurve 的相邻控制点之间有隐含的落点
+fn push_segments(
+    segments: &mut Contour,
+    start: Point,
+    controls: &[Point],
+    end: Point,
+    kind: &str,
+) -> Result<(), String> {
+    match (kind, controls) {
+        (_, []) => segments.push(Segment::Line { start, end }),
+        ("curve", &[control]) => {
+            // 单个控制点的 curve 按二次曲线升阶
+            let control1 = lerp(start, control, 2.0 / 3.0);
+            let control2 = lerp(end, control, 2.0 / 3.0);
+            segments.push(Segment::Cubic { start, control1, control2, end });
+        }
+        ("curve", &[control1, control2]) => segments.push(Segment::Cubic { start, control1, control2, end }),
+        ("curve", _) => return Err(format!("curve point with {} off-curve points is not supported", controls.len())),
+        ("qcurve", _) => {
+            let mut start = start;
+            for (i, &control) in controls.iter().enumerate() {
+                let end = controls.get(i + 1).map_or(end, |&next| midpoint(control, next));
+                segments.push(Segment::Quadratic { start, control, end });
+                start = end;
+            }
+        }
+        (kind, _) => return Err(format!("{kind} point cannot follow off-curve points")),
+    }
+    Ok(())
+}
+
+fn parse_contour(points: &[UfoPoint]) -> Result<Contour, String> {
+    let mut segments = Vec::new();
+    if points.is_empty() {
+        return Ok(segments);
+    }
+    // 开放轮廓从 move 点开始
+    if points[0].kind.as_deref() == Some("move") {
+        let mut start = points[0].point;
+        let mut controls = Vec::new();
+        for p in &points[1..] {
+            match &p.kind {
+                None => controls.push(p.point),
+                Some(kind) => {
+                    push_segments(&mut segments, start, &controls, p.point, kind)?;
+                    controls.clear();
+                    start = p.point;
+                }
+            }
+        }
+        return Ok(segments);
+    }
+    let n = points.len();
+    let Some(last) = points.iter().rposition(|p| p.kind.is_some()) else {
+        // 只有控制点的 TrueType 轮廓，落点全部隐含在控制点之间
+        let controls: Vec<Point> = points.iter().map(|p| p.point).collect();
+        for i in 0..n {
+            let (control, next) = (controls[i], controls[(i + 1) % n]);
+            let start = midpoint(controls[(i + n - 1) % n], control);
+            segments.push(Segment::Quadratic { start, control, end: midpoint(control, next) });
+        }
+        return Ok(segments);
+    };
+    let mut start = points[last].point;
+    let mut controls = Vec::new();
+    for i in 1..=n {
+        let p = &points[(last + i) % n];
+        match &p.kind {
+            None => controls.push(p.point),
+            Some(kind) => {
+                push_segments(&mut segments, start, &controls, p.point, kind)?;
+                controls.clear();
+                start = p.point;
+            }
+        }
+    }
+    Ok(segments)
+}
+
+pub fn parse(text: &str) -> Result<Glif, String> {
+    let doc = roxmltree::Document::parse(text).map_err(|err| format!("invalid glif: {err}"))?;
+    let root = doc.root_element();
+    if root.tag_name().name() != "glyph" {
+        return Err(format!("expected <glyph>, found <{}>", root.tag_name().name()));
+    }
+    let mut glif = Glif { name: root.attribute("name").unwrap_or_default().to_string(), ..Default::default() };
+    for node in root.children().filter(|n| n.is_element()) {
+        match node.tag_name().name() {
+            "advance" => {
+                glif.width = parse_number(node, "width")?.unwrap_or(0.0);
+                glif.height = parse_number(node, "height")?;
+            }
+            "unicode" => {
+                let hex = node.attribute("hex").unwrap_or_default();
+                let unicode = u32::from_str_radix(hex, 16).map_err(|_| format!("invalid unicode {hex:?}"))?;
+                glif.unicodes.push(unicode);
+            }
+            "outline" => {
+                for item in node.children().filter(|n| n.is_element()) {
+                    match item.tag_name().name() {
+                        "contour" => {
+                            let mut points = Vec::new();
+                            for p in item.children().filter(|n| n.is_element() && n.tag_name().name() == "point") {
+                                let (Some(x), Some(y)) = (parse_number(p, "x")?, parse_number(p, "y")?) else {
+                                    return Err(String::from("<point> needs x and y"));
+                                };
+                                let kind = p.attribute("type").filter(|&t| t != "offcurve").map(str::to_string);
+                                points.push(UfoPoint { point: Point { x, y }, kind });
+                            }
+                            let contour =
+                                parse_contour(&points).map_err(|err| format!("glyph {}: {err}", glif.name))?;
+                            if !contour.is_empty() {
+                                glif.contours.push(contour);
+                            }
+                        }
+                        "component" => {
+                            let base = item.attribute("base").ok_or("<component> needs a base")?.to_string();
+                            let values = TRANSFORM_ATTRIBUTES
+                                .iter()
+                                .map(|(name, _)| parse_number(item, name))
+                                .collect::<Result<Vec<Option<f64>>, String>>()?;
+                            // 写了任一变换属性时才视为有变换
+                            let transform = values
+                                .iter()
+                                .any(Option::is_some)
+                                .then(|| std::array::from_fn(|i| values[i].unwrap_or(TRANSFORM_ATTRIBUTES[i].1)));
+                            glif.components.push((base, transform));
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            "lib" => {
+                if let Some(dict) = node.children().find(|n| n.is_element()) {
+                    if let Value::Dict(dict) = plist::parse_value(dict)? {
+                        glif.lib = dict;
+                    }
+                }
+            }
+            // 参考线、锚点、图片与注释不在工程模型中
+            _ => {}
+        }
+    }
+    Ok(glif)
+}
+
+// 落点两侧的切线共线且方向一致，并且至少一侧为曲线时标为 smooth
+fn is_smooth(incoming: &Segment, outgoing: &Segment) -> bool {
+    let (before, point) = match incoming {
+        Segment::Line { start, end } => (*start, *end),
+        Segment::Quadratic { control, end, .. } => (*control, *end),
+        Segment::Cubic { control2, end, .. } => (*control2, *end),
+    };
+    let after = match outgoing {
+        Segment::Line { end, .. } => *end,
+        Segment::Quadratic { control, .. } => *control,
+        Segment::Cubic { control1, .. } => *control1,
+    };
+    if matches!((incoming, outgoing), (Segment::Line { .. }, Segment::Line { .. })) {
+        return false;
+    }
+    let (ax, ay) = (point.x - before.x, point.y - before.y);
+    let (bx, by) = (after.x - point.x, after.y - point.y);
+    let (la, lb) = (ax.hypot(ay), bx.hypot(by));
+    la > 0.0 && lb > 0.0 && (ax * by - ay * bx).abs() <= 1e-3 * la * lb && ax * bx + ay * by > 0.0
+}
+
+fn write_point(xml: &mut String, p: Point, kind: Option<&str>, smooth: bool) {
+    xml.push_str(&format!("\t\t\t<point x=\"{}\" y=\"{}\"", number(p.x), number(p.y)));
+    if let Some(kind) = kind {
+        xml.push_str(&format!(" type=\"{kind}\""));
+    }
+    if smooth {
+        xml.push_str(" smooth=\"yes\"");
+    }
+    xml.push_str("/>\n");
+}
+
+// 最后一段的终点与起点重合；否则首尾之间隐含一条闭合线段，读回时会成为显式的第一段
+pub fn is_closed(contour: &Contour) -> bool {
+    match (contour.first(), contour.last()) {
+        (Some(first), Some(last)) => last.end().x == first.start().x && last.end().y == first.start().y,
+        _ => true,
+    }
+}
+
+// 轮廓都是闭合的：最后一段的终点与起点重合时省略起点，否则先写起点，闭合线段隐含在首尾之间
+fn write_contour(xml: &mut String, contour: &Contour) {
+    let Some(first) = contour.first() else {
+        return;
+    };
+    xml.push_str("\t\t<contour>\n");
+    let closed = is_closed(contour);
+    if !closed {
+        write_point(xml, first.start(), Some("line"), false);
+    }
+    for (i, segment) in contour.iter().enumerate() {
+        let next = contour.get(i + 1).or((closed).then_some(first));
+        let smooth = next.is_some_and(|next| is_smooth(segment, next));
+        match segment {
+            Segment::Line { end, .. } => write_point(xml, *end, Some("line"), smooth),
+            Segment::Quadratic { control, end, .. } => {
+                write_point(xml, *control, None, false);
+                write_point(xml, *end, Some("qcurve"), smooth);
+            }
+            Segment::Cubic { control1, control2, end, .. } => {
+                write_point(xml, *control1, None, false);
+                write_point(xml, *control2, None, false);
+                write_point(xml, *end, Some("curve"), smooth);
+            }
+        }
+    }
+    xml.push_str("\t\t</contour>\n");
+}
+
+pub fn write(glif: &Glif) -> String {
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!("<glyph name=\"{}\" format=\"2\">\n", escape(&glif.name)));
+    xml.push_str(&format!("\t<advance width=\"{}\"", number(glif.width)));
+    if let Some(height) = glif.height {
+        xml.push_str(&format!(" height=\"{}\"", number(height)));
+    }
+    xml.push_str("/>\n");
+    for unicode in &glif.unicodes {
+        xml.push_str(&format!("\t<unicode hex=\"{unicode:04X}\"/>\n"));
+    }
+    if !glif.contours.is_empty() || !glif.components.is_empty() {
+        xml.push_str("\t<outline>\n");
+        for contour in &glif.contours {
+            write_contour(&mut xml, contour);
+        }
+        for (base, transform) in &glif.components {
+            xml.push_str(&format!("\t\t<component base=\"{}\"", escape(base)));
+            if let Some(transform) = transform {
+                let mut attributes: Vec<String> = TRANSFORM_ATTRIBUTES
+                    .iter()
+                    .zip(transform)
+                    .filter(|((_, default), v)| *v != default)
+                    .map(|((name, _), v)| format!(" {name}=\"{}\"", number(*v)))
+                    .collect();
+                // 显式的单位变换也写出来，读回时仍为 Some
+                if attributes.is_empty() {
+                    attributes.push(String::from(" xScale=\"1\""));
+                }
+                xml.push_str(&attributes.concat());
+            }
+            xml.push_str("/>\n");
+        }
+        xml.push_str("\t</outline>\n");
+    }
+    if !glif.lib.is_empty() {
+        xml.push_str("\t<lib>\n");
+        plist::write_value(&mut xml, &Value::Dict(glif.lib.clone()), 2);
+        xml.push_str("\t</lib>\n");
+    }
+    xml.push_str("</glyph>\n");
+    xml
+}
@@ -0,0 +1,613 @@
+pub mod fea;
+pub mod fontinfo;
+pub mod glif;
+pub mod plist;
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::composite::GlyphComponent;
+use crate::features::GlyphClass;
+use crate::kerning::{Kerning, KerningClass, KerningPair, KerningSide};
+use crate::outline::Segment;
+use crate::vertical::{VerticalAlternate, VerticalLayout, VerticalMetric};
+use crate::{FontData, GlyphData, VariationSequence};
+
+use glif::Glif;
+use plist::{Dict, Value};
+
+// 工程中 UFO 没有对应字段的数据写入 lib.plist 与 glif 的 lib，键名使用该前缀
+pub const LIB_PREFIX: &str = "com.hitoysmaker.fontplayer";
+
+const LEFT_GROUP_PREFIX: &str = "public.kern1.";
+const RIGHT_GROUP_PREFIX: &str = "public.kern2.";
+const DEFAULT_LAYER: &str = "glyphs";
+
+fn lib_key(name: &str) -> String {
+    format!("{LIB_PREFIX}.{name}")
+}
+
+// UFO 3 规定的字形名到文件名的转换：大写字母后加下划线，非法字符替换为下划线，避开 Windows 保留名
+fn glyph_file_name(name: &str, used: &mut HashSet<String>) -> String {
+    const ILLEGAL: &str = "\"*+/:<>?[\\]|";
+    const RESERVED: [&str; 23] = [
+        "con", "prn", "aux", "clock$", "nul", "com1", "com2", "com3", "com4", "com5", "com6", "com7", "com8", "com9",
+        "lpt1", "lpt2", "lpt3", "lpt4", "lpt5", "lpt6", "lpt7", "lpt8", "lpt9",
+    ];
+    let mut base = String::new();
+    for (i, c) in name.chars().enumerate() {
+        if (i == 0 && c == '.') || c.is_control() || ILLEGAL.contains(c) {
+            base.push('_');
+        } else {
+            base.push(c);
+            if c.is_uppercase() {
+                base.push('_');
+            }
+        }
+    }
+    let base: Vec<String> = base
+        .split('.')
+        .map(
+            |part| if RESERVED.contains(&part.to_lowercase().as_str()) { format!("_{part}") } else { part.to_string() },
+        )
+        .collect();
+    let base: String = base.join(".").chars().take(200).collect();
+    // 文件系统可能不区分大小写，按小写判断重名
+    let mut file_name = format!("{base}.glif");
+    let mut n = 1;
+    while !used.insert(file_name.to_lowercase()) {
+        file_name = format!("{base}{n:015}.glif");
+        n += 1;
+    }
+    file_name
+}
+
+// 同时可以在 .fea 中直接使用的字形名
+fn is_valid_glyph_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    name.len() <= 63
+        && chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '.')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.' || c == '-')
+}
+
+// 工程中的字形名可能为空、重复或含有 .fea 不支持的字符，此时按码位生成字形名
+fn glyph_names(glyphs: &[GlyphData]) -> Vec<String> {
+    let mut used = HashSet::new();
+    glyphs
+        .iter()
+        .enumerate()
+        .map(|(i, glyph)| {
+            let base = if is_valid_glyph_name(&glyph.name) {
+                glyph.name.clone()
+            } else if glyph.unicode == 0 {
+                format!("glyph{i}")
+            } else if glyph.unicode <= 0xFFFF {
+                format!("uni{:04X}", glyph.unicode)
+            } else {
+                format!("u{:X}", glyph.unicode)
+            };
+            let mut name = base.clone();
+            let mut n = 1;
+            while !used.insert(name.clone()) {
+                name = format!("{base}.{n}");
+                n += 1;
+            }
+            name
+        })
+        .collect()
+}
+
+fn write_file(path: &Path, data: &str) -> Result<(), String> {
+    fs::write(path, data).map_err(|err| format!("failed to write {}: {err}", path.display()))
+}
+
+fn read_file(path: &Path) -> Result<String, String> {
+    fs::read_to_string(path).map_err(|err| format!("failed to read {}: {err}", path.display()))
+}
+
+fn read_plist(path: &Path) -> Result<Option<Value>, String> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    plist::parse(&read_file(path)?).map(Some).map_err(|err| format!("{}: {err}", path.display()))
+}
+
+fn read_dict(path: &Path) -> Result<Dict, String> {
+    match read_plist(path)? {
+        Some(Value::Dict(dict)) => Ok(dict),
+        Some(_) => Err(format!("{}: expected a dictionary", path.display())),
+        None => Ok(Dict::default()),
+    }
+}
+
+// 目标旁边的隐藏目录：.Name.ufo.tmp、.Name.ufo.old
+fn sibling(path: &Path, suffix: &str) -> Result<PathBuf, String> {
+    let name = path.file_name().ok_or_else(|| format!("invalid UFO path {}", path.display()))?;
+    Ok(path.with_file_name(format!(".{}.{suffix}", name.to_string_lossy())))
+}
+
+// 写入中的 UFO：内容先写到目标旁边的临时目录，commit 时再替换目标，写入失败时原有的 UFO 保持不变
+pub(crate) struct Staged {
+    temp: PathBuf,
+    path: PathBuf,
+}
+
+impl Staged {
+    // 已存在的 UFO 整体替换；其他已存在的非空目录不覆盖
+    fn new(path: &Path) -> Result<Staged, String> {
+        if path.exists()
+            && !path.join("metainfo.plist").is_file()
+            && (!path.is_dir()
+                || fs::read_dir(path)
+                    .map_err(|err| format!("failed to read {}: {err}", path.display()))?
+                    .next()
+                    .is_some())
+        {
+            return Err(format!("{} already exists and is not a UFO", path.display()));
+        }
+        let temp = sibling(path, "tmp")?;
+        // 上一次中断的写入遗留的临时目录
+        if temp.exists() {
+            fs::remove_dir_all(&temp).map_err(|err| format!("failed to remove {}: {err}", temp.display()))?;
+        }
+        let staged = Staged { temp, path: path.to_path_buf() };
+        fs::create_dir_all(staged.temp.join(DEFAULT_LAYER))
+            .map_err(|err| format!("failed to create {}: {err}", staged.temp.display()))?;
+        Ok(staged)
+    }
+
+    // 目录不能直接重命名覆盖已存在的目录（Windows），先把旧的 UFO 移开，替换失败时再移回
+    pub(crate) fn commit(&self) -> Result<(), String> {
+        let replace_error = |err| format!("failed to replace {}: {err}", self.path.display());
+        if !self.path.exists() {
+            return fs::rename(&self.temp, &self.path).map_err(replace_error);
+        }
+        let old = sibling(&self.path, "old")?;
+        if old.exists() {
+            fs::remove_dir_all(&old).map_err(|err| format!("failed to remove {}: {err}", old.display()))?;
+        }
+        fs::rename(&self.path, &old).map_err(replace_error)?;
+        if let Err(err) = fs::rename(&self.temp, &self.path) {
+            let _ = fs::rename(&old, &self.path);
+            return Err(replace_error(err));
+        }
+        let _ = fs::remove_dir_all(&old);
+        Ok(())
+    }
+}
+
+// 未提交（出错）时删除临时目录；提交后临时目录已不存在
+impl Drop for Staged {
+    fn drop(&mut self) {
+        if self.temp.exists() {
+            let _ = fs::remove_dir_all(&self.temp);
+        }
+    }
+}
+
+fn string_array(values: impl IntoIterator<Item = String>) -> Value {
+    Value::Array(values.into_iter().map(Value::String).collect())
+}
+
+// 字偶距分组写为 public.kern1./public.kern2. 分组，读回时去掉前缀
+fn kerning_groups(classes: &[KerningClass], prefix: &str, used: &mut HashSet<String>) -> Vec<String> {
+    classes
+        .iter()
+        .enumerate()
+        .map(|(i, class)| {
+            let base =
+                if class.name.is_empty() { format!("{prefix}class{i}") } else { format!("{prefix}{}", class.name) };
+            let mut name = base.clone();
+            let mut n = 1;
+            while !used.insert(name.clone()) {
+                name = format!("{base}_{n}");
+                n += 1;
+            }
+            name
+        })
+        .collect()
+}
+
+fn group_class_name(group: &str) -> &str {
+    group.strip_prefix(LEFT_GROUP_PREFIX).or_else(|| group.strip_prefix(RIGHT_GROUP_PREFIX)).unwrap_or(group)
+}
+
+fn glyph_name(names: &[String], glyph: usize) -> Result<&str, String> {
+    names.get(glyph).map(String::as_str).ok_or_else(|| format!("refers to missing glyph {glyph}"))
+}
+
+// 返回去掉前缀后与原名不同的分组（空名或重名），记录在 lib.plist 中
+fn write_kerning(font: &FontData, names: &[String], path: &Path) -> Result<Option<Value>, String> {
+    let kerning = &font.kerning;
+    let mut used = HashSet::new();
+    let left = kerning_groups(&kerning.left_classes, LEFT_GROUP_PREFIX, &mut used);
+    let right = kerning_groups(&kerning.right_classes, RIGHT_GROUP_PREFIX, &mut used);
+    let mut groups = Dict::default();
+    for (class, group) in kerning.left_classes.iter().zip(&left).chain(kerning.right_classes.iter().zip(&right)) {
+        let glyphs = class.glyphs.iter().map(|&g| glyph_name(names, g).map(str::to_string));
+        groups.insert(group, string_array(glyphs.collect::<Result<Vec<_>, _>>()?));
+    }
+    if !groups.is_empty() {
+        write_file(&path.join("groups.plist"), &plist::write(&Value::Dict(groups)))?;
+    }
+
+    let side = |side: &KerningSide, groups: &[String]| -> Result<String, String> {
+        match *side {
+            KerningSide::Glyph { glyph } => glyph_name(names, glyph).map(str::to_string),
+            KerningSide::Class { class } => {
+                groups.get(class).cloned().ok_or_else(|| format!("kerning refers to missing class {class}"))
+            }
+        }
+    };
+    let mut pairs = Dict::default();
+    for pair in &kerning.pairs {
+        let first = side(&pair.left, &left)?;
+        let second = side(&pair.right, &right)?;
+        let mut seconds = match pairs.remove(&first) {
+            Some(Value::Dict(dict)) => dict,
+            _ => Dict::default(),
+        };
+        seconds.insert(&second, Value::Integer(pair.value as i64));
+        pairs.insert(&first, Value::Dict(seconds));
+    }
+    if !pairs.is_empty() {
+        write_file(&path.join("kerning.plist"), &plist::write(&Value::Dict(pairs)))?;
+    }
+    let classes = kerning.left_classes.iter().chain(&kerning.right_classes);
+    let originals: Vec<&str> = classes.map(|class| class.name.as_str()).collect();
+    let mut renamed_groups = Dict::default();
+    for (original, group) in originals.iter().zip(left.iter().chain(&right)) {
+        if *original != group_class_name(group) {
+            renamed_groups.insert(group, Value::String(original.to_string()));
+        }
+    }
+    Ok((!renamed_groups.is_empty()).then_some(Value::Dict(renamed_groups)))
+}
+
+fn write_vertical(vertical: &VerticalLayout, names: &[String]) -> Result<Value, String> {
+    let mut dict = Dict::default();
+    if let Some(origin) = vertical.origin {
+        dict.insert("origin", Value::number(origin));
+    }
+    if let Some(advance) = vertical.advance {
+        dict.insert("advance", Value::number(advance));
+    }
+    let mut alternates = Vec::new();
+    for alternate in &vertical.alternates {
+        let mut entry = Dict::default();
+        entry.insert("glyph", Value::String(glyph_name(names, alternate.glyph)?.to_string()));
+        if let Some(glyph) = alternate.alternate {
+            entry.insert("alternate", Value::String(glyph_name(names, glyph)?.to_string()));
+        }
+        entry.insert("rotate", Value::Boolean(alternate.rotate));
+        alternates.push(Value::Dict(entry));
+    }
+    dict.insert("alternates", Value::Array(alternates));
+    Ok(Value::Dict(dict))
+}
+
+// 查找与类别名称转换为 .fea 标识符，改变了的名称记录在 lib.plist 中，读回时还原
+fn renamed(original: &[&str], converted: &[String]) -> Option<Value> {
+    let mut dict = Dict::default();
+    for (original, converted) in original.iter().zip(converted) {
+        if original != converted {
+            dict.insert(converted, Value::String(original.to_string()));
+        }
+    }
+    (!dict.is_empty()).then_some(Value::Dict(dict))
+}
+
+// 写出 UFO 3 目录：fontinfo、字形、分组与字偶距、features.fea 以及 lib.plist
+pub fn write(font: &FontData, path: &Path) -> Result<(), String> {
+    stage(font, path)?.commit()
+}
+
+// 写入临时目录，由调用方决定何时替换目标（designspace 在所有母版都写好后一起替换）
+pub(crate) fn stage(font: &FontData, path: &Path) -> Result<Staged, String> {
+    let names = glyph_names(&font.glyphs);
+    let staged = Staged::new(path)?;
+    let path = staged.temp.as_path();
+
+    let mut metainfo = Dict::default();
+    metainfo.insert("creator", Value::String(String::from(LIB_PREFIX)));
+    metainfo.insert("formatVersion", Value::Integer(3));
+    write_file(&path.join("metainfo.plist"), &plist::write(&Value::Dict(metainfo)))?;
+    write_file(&path.join("fontinfo.plist"), &plist::write(&Value::Dict(fontinfo::write(&font.info))))?;
+    let layers = Value::Array(vec![string_array([String::from("public.default"), String::from(DEFAULT_LAYER)])]);
+    write_file(&path.join("layercontents.plist"), &plist::write(&layers))?;
+
+    // 竖排度量写入各字形的 advance height 与 public.verticalOrigin
+    let mut vertical_metrics: HashMap<usize, &VerticalMetric> = HashMap::new();
+    for metric in font.vertical.iter().flat_map(|v| &v.metrics) {
+        vertical_metrics.insert(metric.glyph, metric);
+    }
+    let mut contents = Dict::default();
+    let mut used_files = HashSet::new();
+    for (i, glyph) in font.glyphs.iter().enumerate() {
+        let mut lib = Dict::default();
+        if glyph.name != names[i] {
+            lib.insert(&lib_key("name"), Value::String(glyph.name.clone()));
+        }
+        let metric = vertical_metrics.get(&i);
+        if let Some(origin) = metric.and_then(|m| m.origin) {
+            lib.insert("public.verticalOrigin", Value::number(origin));
+        }
+        let open: Vec<Value> = glyph
+            .contours
+            .iter()
+            .enumerate()
+            .filter(|(_, contour)| !glif::is_closed(contour))
+            .map(|(i, _)| Value::Integer(i as i64))
+            .collect();
+        if !open.is_empty() {
+            lib.insert(&lib_key("implicitlyClosedContours"), Value::Array(open));
+        }
+        let components = glyph
+            .components
+            .iter()
+            .map(|c| Ok((glyph_name(&names, c.glyph)?.to_string(), c.transform)))
+            .collect::<Result<Vec<_>, String>>()
+            .map_err(|err| format!("glyph {} {err}", names[i]))?;
+        let data = Glif {
+            name: names[i].clone(),
+            unicodes: if glyph.unicode == 0 { Vec::new() } else { vec![glyph.unicode] },
+            width: glyph.advance_width,
+            height: metric.and_then(|m| m.advance),
+            contours: glyph.contours.clone(),
+            components,
+            lib,
+        };
+        let file_name = glyph_file_name(&names[i], &mut used_files);
+        write_file(&path.join(DEFAULT_LAYER).join(&file_name), &glif::write(&data))?;
+        // 字形名各不相同，直接追加，避免大字库逐个查重
+        contents.0.push((names[i].clone(), Value::String(file_name)));
+    }
+    write_file(&path.join(DEFAULT_LAYER).join("contents.plist"), &plist::write(&Value::Dict(contents)))?;
+
+    let kerning_names = write_kerning(font, &names, path)?;
+
+    let mut lib = Dict::default();
+    lib.insert("public.glyphOrder", string_array(names.iter().cloned()));
+    if let Some(value) = kerning_names {
+        lib.insert(&lib_key("kerningClassNames"), value);
+    }
+    let features = &font.features;
+    let class_names = fea::identifiers(features.classes.iter().map(|c| c.name.as_str()), "class");
+    let lookup_names = fea::identifiers(features.lookups.iter().map(|l| l.name.as_str()), "lookup");
+    let fea_text = fea::write(features, &names, &class_names, &lookup_names)?;
+    if !fea_text.is_empty() {
+        write_file(&path.join("features.fea"), &fea_text)?;
+        let classes: Vec<&str> = features.classes.iter().map(|c| c.name.as_str()).collect();
+        if let Some(value) = renamed(&classes, &class_names) {
+            lib.insert(&lib_key("classNames"), value);
+        }
+        let lookups: Vec<&str> = features.lookups.iter().map(|l| l.name.as_str()).collect();
+        if let Some(value) = renamed(&lookups, &lookup_names) {
+            lib.insert(&lib_key("lookupNames"), value);
+        }
+        if !lookup_names.is_empty() {
+            lib.insert(&lib_key("lookupOrder"), string_array(lookup_names.iter().cloned()));
+        }
+    }
+    if let Some(modified) = font.info.modified_timestamp {
+        lib.insert(&lib_key("modifiedTimestamp"), Value::Integer(modified));
+    }
+    if !font.variation_sequences.is_empty() {
+        let mut sequences = Vec::new();
+        for sequence in &font.variation_sequences {
+            let mut entry = Dict::default();
+            entry.insert("unicode", Value::Integer(sequence.unicode as i64));
+            entry.insert("selector", Value::Integer(sequence.selector as i64));
+            if let Some(glyph) = sequence.glyph {
+                entry.insert("glyph", Value::String(glyph_name(&names, glyph)?.to_string()));
+            }
+            sequences.push(Value::Dict(entry));
+        }
+        lib.insert(&lib_key("variationSequences"), Value::Array(sequences));
+    }
+    if let Some(vertical) = &font.vertical {
+        lib.insert(&lib_key("vertical"), write_vertical(vertical, &names)?);
+    }
+    write_file(&path.join("lib.plist"), &plist::write(&Value::Dict(lib)))?;
+    Ok(staged)
+}
+
+fn glyph_index(glyphs: &HashMap<String, usize>, name: &str, context: &str) -> Result<usize, String> {
+    glyphs.get(name).copied().ok_or_else(|| format!("{context} refers to missing glyph {name:?}"))
+}
+
+// 分组中不存在的字形忽略
+fn group_glyphs(value: &Value, glyphs: &HashMap<String, usize>) -> Vec<usize> {
+    value.as_array().unwrap_or_default().iter().filter_map(|v| glyphs.get(v.as_str()?).copied()).collect()
+}
+
+fn read_kerning(groups: &Dict, pairs: &Dict, glyphs: &HashMap<String, usize>, names: Option<&Value>) -> Kerning {
+    let mut kerning = Kerning::default();
+    let mut left = HashMap::new();
+    let mut right = HashMap::new();
+    for (name, value) in &groups.0 {
+        let (classes, index) = if name.starts_with(LEFT_GROUP_PREFIX) {
+            (&mut kerning.left_classes, &mut left)
+        } else if name.starts_with(RIGHT_GROUP_PREFIX) {
+            (&mut kerning.right_classes, &mut right)
+        } else {
+            continue;
+        };
+        index.insert(name.as_str(), classes.len());
+        let mut class_name = group_class_name(name).to_string();
+        rename(names, name, &mut class_name);
+        classes.push(KerningClass { name: class_name, glyphs: group_glyphs(value, glyphs) });
+    }
+    // 引用不存在的字形或分组的字偶距忽略
+    let side = |name: &str, classes: &HashMap<&str, usize>| match classes.get(name) {
+        Some(&class) => Some(KerningSide::Class { class }),
+        None => glyphs.get(name).map(|&glyph| KerningSide::Glyph { glyph }),
+    };
+    for (first, seconds) in &pairs.0 {
+        let Some(seconds) = seconds.as_dict() else {
+            continue;
+        };
+        for (second, value) in &seconds.0 {
+            if let (Some(left), Some(right), Some(value)) = (side(first, &left), side(second, &right), value.as_f64()) {
+                let value = value.round().clamp(i16::MIN as f64, i16::MAX as f64) as i16;
+                kerning.pairs.push(KerningPair { left, right, value });
+            }
+        }
+    }
+    kerning
+}
+
+fn read_vertical(
+    value: Option<&Value>,
+    metrics: Vec<VerticalMetric>,
+    glyphs: &HashMap<String, usize>,
+) -> Result<Option<VerticalLayout>, String> {
+    let Some(dict) = value.and_then(Value::as_dict) else {
+        return Ok((!metrics.is_empty()).then(|| VerticalLayout { metrics, ..Default::default() }));
+    };
+    let mut alternates = Vec::new();
+    for entry in dict.get("alternates").and_then(Value::as_array).unwrap_or_default() {
+        let Some(entry) = entry.as_dict() else {
+            continue;
+        };
+        let glyph = glyph_index(glyphs, entry.str("glyph").unwrap_or_default(), "vertical alternate")?;
+        let alternate =
+            entry.str("alternate").map(|name| glyph_index(glyphs, name, "vertical alternate")).transpose()?;
+        let rotate = entry.get("rotate") == Some(&Value::Boolean(true));
+        alternates.push(VerticalAlternate { glyph, alternate, rotate });
+    }
+    Ok(Some(VerticalLayout { origin: dict.number("origin"), advance: dict.number("advance"), metrics, alternates }))
+}
+
+// 按 lib.plist 中记录的对应关系还原原名
+fn rename(names: Option<&Value>, key: &str, name: &mut String) {
+    if let Some(original) = names.and_then(Value::as_dict).and_then(|d| d.str(key)) {
+        *name = original.to_string();
+    }
+}
+
+// 读取 UFO 2/3 目录的默认图层。工程中每个字形只有一个码位，有多个 unicode 的字形只保留第一个
+pub fn read(path: &Path) -> Result<FontData, String> {
+    let metainfo = read_dict(&path.join("metainfo.plist"))?;
+    match metainfo.number("formatVersion") {
+        Some(version) if version == 2.0 || version == 3.0 => {}
+        Some(version) => return Err(format!("unsupported UFO format version {version}")),
+        None => return Err(format!("{} is not a UFO (missing metainfo.plist)", path.display())),
+    }
+    let info = fontinfo::read(&read_dict(&path.join("fontinfo.plist"))?)?;
+    let lib = read_dict(&path.join("lib.plist"))?;
+
+    let layer = match read_plist(&path.join("layercontents.plist"))? {
+        Some(Value::Array(layers)) => layers
+            .iter()
+            .filter_map(Value::as_array)
+            .find(|layer| layer.first().and_then(Value::as_str) == Some("public.default"))
+            .and_then(|layer| layer.get(1)?.as_str().map(str::to_string))
+            .unwrap_or_else(|| String::from(DEFAULT_LAYER)),
+        _ => String::from(DEFAULT_LAYER),
+    };
+    let layer_path = path.join(layer);
+    let contents = read_dict(&layer_path.join("contents.plist"))?;
+    let files: HashMap<&str, &str> =
+        contents.0.iter().filter_map(|(name, file)| Some((name.as_str(), file.as_str()?))).collect();
+
+    // 先按 public.glyphOrder 排列，其余字形按 contents.plist 中的顺序追加
+    let mut order: Vec<String> = Vec::new();
+    let mut seen = HashSet::new();
+    let listed = lib.get("public.glyphOrder").and_then(Value::as_array).unwrap_or_default();
+    for name in listed.iter().filter_map(Value::as_str).chain(contents.0.iter().map(|(name, _)| name.as_str())) {
+        if files.contains_key(name) && seen.insert(name.to_string()) {
+            order.push(name.to_string());
+        }
+    }
+    let glyph_indices: HashMap<String, usize> = order.iter().enumerate().map(|(i, name)| (name.clone(), i)).collect();
+
+    let mut glyphs = Vec::with_capacity(order.len());
+    let mut metrics = Vec::new();
+    for (i, name) in order.iter().enumerate() {
+        let file = layer_path.join(files[name.as_str()]);
+        let glif = glif::parse(&read_file(&file)?).map_err(|err| format!("{}: {err}", file.display()))?;
+        let components = glif
+            .components
+            .iter()
+            .map(|(base, transform)| {
+                Ok(GlyphComponent {
+                    glyph: glyph_index(&glyph_indices, base, &format!("glyph {name:?}"))?,
+                    transform: *transform,
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        let origin = glif.lib.number("public.verticalOrigin");
+        // 写出时首尾未重合的轮廓，读回时去掉解析出的闭合线段
+        let mut contours = glif.contours;
+        for value in glif.lib.get(&lib_key("implicitlyClosedContours")).and_then(Value::as_array).unwrap_or_default() {
+            let contour = value.as_f64().and_then(|i| contours.get_mut(i as usize));
+            if let Some(contour) = contour.filter(|contour| contour.len() > 1) {
+                if matches!(contour[0], Segment::Line { .. }) {
+                    contour.remove(0);
+                }
+            }
+        }
+        if glif.height.is_some() || origin.is_some() {
+            metrics.push(VerticalMetric { glyph: i, advance: glif.height, origin });
+        }
+        glyphs.push(GlyphData {
+            name: glif.lib.str(&lib_key("name")).map_or_else(|| name.clone(), str::to_string),
+            unicode: glif.unicodes.first().copied().unwrap_or(0),
+            advance_width: glif.width,
+            contours,
+            components,
+        });
+    }
+
+    let groups = read_dict(&path.join("groups.plist"))?;
+    let pairs = read_dict(&path.join("kerning.plist"))?;
+    let kerning = read_kerning(&groups, &pairs, &glyph_indices, lib.get(&lib_key("kerningClassNames")));
+
+    // 非字偶距分组可以在 .fea 中作为类别引用
+    let classes: Vec<GlyphClass> = groups
+        .0
+        .iter()
+        .filter(|(name, _)| !name.starts_with(LEFT_GROUP_PREFIX) && !name.starts_with(RIGHT_GROUP_PREFIX))
+        .map(|(name, value)| GlyphClass { name: name.clone(), glyphs: group_glyphs(value, &glyph_indices) })
+        .collect();
+    let fea_path = path.join("features.fea");
+    let mut features = if fea_path.exists() {
+        fea::parse(&read_file(&fea_path)?, &glyph_indices, classes)?
+    } else {
+        crate::features::Features { classes, ..Default::default() }
+    };
+    for class in &mut features.classes {
+        let key = class.name.clone();
+        rename(lib.get(&lib_key("classNames")), &key, &mut class.name);
+    }
+    if let Some(order) = lib.get(&lib_key("lookupOrder")).and_then(Value::as_array) {
+        let order: Vec<&str> = order.iter().filter_map(Value::as_str).collect();
+        fea::reorder_lookups(&mut features, &order);
+    }
+    for lookup in &mut features.lookups {
+        let key = lookup.name.clone();
+        rename(lib.get(&lib_key("lookupNames")), &key, &mut lookup.name);
+    }
+
+    let mut variation_sequences = Vec::new();
+    for entry in lib.get(&lib_key("variationSequences")).and_then(Value::as_array).unwrap_or_default() {
+        let Some(entry) = entry.as_dict() else {
+            continue;
+        };
+        variation_sequences.push(VariationSequence {
+            unicode: entry.number("unicode").unwrap_or(0.0) as u32,
+            selector: entry.number("selector").unwrap_or(0.0) as u32,
+            glyph: entry
+                .str("glyph")
+                .map(|name| glyph_index(&glyph_indices, name, "variation sequence"))
+                .transpose()?,
+        });
+    }
+    let vertical = read_vertical(lib.get(&lib_key("vertical")), metrics, &glyph_indices)?;
+
+    let mut info = info;
+    info.modified_timestamp = lib.number(&lib_key("modifiedTimestamp")).map(|v| v as i64);
+    Ok(FontData { info, glyphs, variation_sequences, kerning, features, vertical })
+}
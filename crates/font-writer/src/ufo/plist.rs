@@ -0,0 +1,183 @@
+use crate::xml::escape;
+
+// 属性列表的值；data 保留 base64 文本，date 保留 ISO 8601 文本
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    String(String),
+    Integer(i64),
+    Real(f64),
+    Boolean(bool),
+    Date(String),
+    Data(String),
+    Array(Vec<Value>),
+    Dict(Dict),
+}
+
+// 字典保持文件中的键顺序
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Dict(pub Vec<(String, Value)>);
+
+impl Dict {
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.0.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+    }
+
+    pub fn insert(&mut self, key: &str, value: Value) {
+        match self.0.iter_mut().find(|(k, _)| k == key) {
+            Some((_, v)) => *v = value,
+            None => self.0.push((key.to_string(), value)),
+        }
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<Value> {
+        let index = self.0.iter().position(|(k, _)| k == key)?;
+        Some(self.0.remove(index).1)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn str(&self, key: &str) -> Option<&str> {
+        self.get(key).and_then(Value::as_str)
+    }
+
+    pub fn number(&self, key: &str) -> Option<f64> {
+        self.get(key).and_then(Value::as_f64)
+    }
+}
+
+impl Value {
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            Value::Integer(v) => Some(v as f64),
+            Value::Real(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Value]> {
+        match self {
+            Value::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    pub fn as_dict(&self) -> Option<&Dict> {
+        match self {
+            Value::Dict(dict) => Some(dict),
+            _ => None,
+        }
+    }
+
+    // 整数值写为 integer，其余写为 real
+    pub fn number(v: f64) -> Value {
+        if v.fract() == 0.0 && v.abs() < i64::MAX as f64 {
+            Value::Integer(v as i64)
+        } else {
+            Value::Real(v)
+        }
+    }
+}
+
+fn element_children<'a, 'input>(
+    node: roxmltree::Node<'a, 'input>,
+) -> impl Iterator<Item = roxmltree::Node<'a, 'input>> {
+    node.children().filter(|n| n.is_element())
+}
+
+// 解析 <plist> 之下或 glif 中 <lib> 之下的单个值元素
+pub fn parse_value(node: roxmltree::Node) -> Result<Value, String> {
+    let text = || node.text().unwrap_or_default().to_string();
+    match node.tag_name().name() {
+        "string" => Ok(Value::String(text())),
+        "integer" => text().trim().parse().map(Value::Integer).map_err(|_| format!("invalid integer {:?}", text())),
+        "real" => text().trim().parse().map(Value::Real).map_err(|_| format!("invalid real {:?}", text())),
+        "true" => Ok(Value::Boolean(true)),
+        "false" => Ok(Value::Boolean(false)),
+        "date" => Ok(Value::Date(text().trim().to_string())),
+        "data" => Ok(Value::Data(text().split_whitespace().collect())),
+        "array" => element_children(node).map(parse_value).collect::<Result<_, _>>().map(Value::Array),
+        "dict" => {
+            let mut dict = Dict::default();
+            let mut children = element_children(node);
+            while let Some(key) = children.next() {
+                if key.tag_name().name() != "key" {
+                    return Err(format!("expected <key> in <dict>, found <{}>", key.tag_name().name()));
+                }
+                let value = children.next().ok_or("<key> without a value in <dict>")?;
+                dict.insert(key.text().unwrap_or_default(), parse_value(value)?);
+            }
+            Ok(Value::Dict(dict))
+        }
+        other => Err(format!("unknown plist element <{other}>")),
+    }
+}
+
+pub fn parse(text: &str) -> Result<Value, String> {
+    let options = roxmltree::ParsingOptions { allow_dtd: true, ..Default::default() };
+    let doc = roxmltree::Document::parse_with_options(text, options).map_err(|err| format!("invalid plist: {err}"))?;
+    let root = doc.root_element();
+    if root.tag_name().name() != "plist" {
+        return Err(format!("expected <plist>, found <{}>", root.tag_name().name()));
+    }
+    let value = match element_children(root).next() {
+        Some(node) => parse_value(node),
+        None => Ok(Value::Dict(Dict::default())),
+    };
+    value
+}
+
+// 解析以字典为根的属性列表
+pub fn parse_dict(text: &str) -> Result<Dict, String> {
+    match parse(text)? {
+        Value::Dict(dict) => Ok(dict),
+        _ => Err(String::from("expected a <dict> at the root of the plist")),
+    }
+}
+
+pub fn write_value(xml: &mut String, value: &Value, depth: usize) {
+    let indent = "\t".repeat(depth);
+    match value {
+        Value::String(s) => xml.push_str(&format!("{indent}<string>{}</string>\n", escape(s))),
+        Value::Integer(v) => xml.push_str(&format!("{indent}<integer>{v}</integer>\n")),
+        Value::Real(v) => xml.push_str(&format!("{indent}<real>{v}</real>\n")),
+        Value::Boolean(true) => xml.push_str(&format!("{indent}<true/>\n")),
+        Value::Boolean(false) => xml.push_str(&format!("{indent}<false/>\n")),
+        Value::Date(s) => xml.push_str(&format!("{indent}<date>{}</date>\n", escape(s))),
+        Value::Data(s) => xml.push_str(&format!("{indent}<data>{}</data>\n", escape(s))),
+        Value::Array(values) if values.is_empty() => xml.push_str(&format!("{indent}<array/>\n")),
+        Value::Array(values) => {
+            xml.push_str(&format!("{indent}<array>\n"));
+            values.iter().for_each(|v| write_value(xml, v, depth + 1));
+            xml.push_str(&format!("{indent}</array>\n"));
+        }
+        Value::Dict(dict) if dict.is_empty() => xml.push_str(&format!("{indent}<dict/>\n")),
+        Value::Dict(dict) => {
+            xml.push_str(&format!("{indent}<dict>\n"));
+            for (key, v) in &dict.0 {
+                xml.push_str(&format!("{indent}\t<key>{}</key>\n", escape(key)));
+                write_value(xml, v, depth + 1);
+            }
+            xml.push_str(&format!("{indent}</dict>\n"));
+        }
+    }
+}
+
+pub fn write(value: &Value) -> String {
+    let mut xml = String::from(concat!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+        "<!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n",
+        "<plist version=\"1.0\">\n",
+    ));
+    write_value(&mut xml, value, 0);
+    xml.push_str("</plist>\n");
+    xml
+}
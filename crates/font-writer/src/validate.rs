@@ -0,0 +1,838 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::Serialize;
+
+use crate::binary::{checksum, Reader};
+use crate::sfnt::{search_params, CFF, CHECKSUM_MAGIC, TRUETYPE};
+use crate::tables::{cff, cmap, glyf};
+use crate::woff;
+
+// head.magicNumber
+const HEAD_MAGIC: u32 = 0x5F0F_3CF5;
+// 所有字体都必须包含的表
+const REQUIRED: [&[u8; 4]; 8] = [b"cmap", b"head", b"hhea", b"hmtx", b"maxp", b"name", b"OS/2", b"post"];
+// 复合字形的最大嵌套深度，超过时视为循环引用
+const MAX_COMPONENT_DEPTH: u16 = 16;
+// PostScript 名称中不允许出现的字符
+const POST_SCRIPT_FORBIDDEN: &str = "[](){}<>/%";
+
+// 检查项，与报告中的 check 字段对应
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Check {
+    // 文件头与表目录
+    Structure,
+    // 表校验和与 head.checkSumAdjustment
+    Checksum,
+    // loca 与 glyf 的一致性
+    Glyf,
+    // maxp、hmtx 中的计数与字形数据
+    Maxp,
+    // cmap 子表的排序与字形 ID
+    Cmap,
+    // name 表的排序、编码与必需的名称
+    Name,
+    // head、hhea、OS/2 中的度量与字形边界
+    Metrics,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    Passed,
+    Failed,
+    Skipped,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Issue {
+    pub check: Check,
+    pub severity: Severity,
+    pub table: Option<String>,
+    pub message: String,
+}
+
+// 表目录中的一条记录，computed_checksum 为按数据重新计算的校验和
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TableRecord {
+    pub tag: String,
+    pub offset: u32,
+    pub length: u32,
+    pub checksum: u32,
+    pub computed_checksum: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CheckResult {
+    pub check: Check,
+    pub status: Status,
+}
+
+// 校验报告：valid 为 true 表示没有错误（可以有警告）
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidationReport {
+    pub valid: bool,
+    pub flavor: String,
+    pub num_glyphs: u16,
+    pub tables: Vec<TableRecord>,
+    pub checks: Vec<CheckResult>,
+    pub issues: Vec<Issue>,
+}
+
+// TrueType 字形的统计信息，bbox 为字形头中记录的边界
+#[derive(Debug, Clone, Default)]
+struct GlyphInfo {
+    contours: u16,
+    points: u16,
+    bbox: Option<[i16; 4]>,
+    components: Vec<u16>,
+    instructions: u16,
+    loose_bbox: bool,
+}
+
+struct Validator<'a> {
+    data: &'a [u8],
+    version: u32,
+    tables: BTreeMap<[u8; 4], &'a [u8]>,
+    records: Vec<TableRecord>,
+    num_glyphs: u16,
+    glyphs: Option<Vec<GlyphInfo>>,
+    check: Check,
+    issues: Vec<Issue>,
+    checks: Vec<CheckResult>,
+}
+
+fn tag_name(tag: &[u8; 4]) -> String {
+    String::from_utf8_lossy(tag).into_owned()
+}
+
+// head 表的校验和按 checkSumAdjustment 为 0 计算
+fn table_checksum(tag: &[u8; 4], data: &[u8]) -> u32 {
+    if tag == b"head" && data.len() >= 12 {
+        let mut data = data.to_vec();
+        data[8..12].fill(0);
+        checksum(&data)
+    } else {
+        checksum(data)
+    }
+}
+
+// 解析简单字形，返回点数、指令长度与按坐标计算出的边界
+fn simple_glyph(data: &[u8], num_contours: u16) -> Result<(u16, u16, Option<[i16; 4]>), String> {
+    let mut r = Reader::at(data, 10);
+    let mut num_points = 0usize;
+    for i in 0..num_contours {
+        let end = r.u16()? as usize + 1;
+        if i > 0 && end <= num_points {
+            return Err(String::from("endPtsOfContours is not increasing"));
+        }
+        num_points = end;
+    }
+    let instructions = r.u16()?;
+    r.skip(instructions as usize)?;
+    let mut flags = Vec::with_capacity(num_points);
+    while flags.len() < num_points {
+        let flag = r.u8()?;
+        let repeat = if flag & 0x08 != 0 { r.u8()? as usize + 1 } else { 1 };
+        flags.extend(std::iter::repeat(flag).take(repeat));
+    }
+    if flags.len() > num_points {
+        return Err(String::from("flag repeat count overflows the point count"));
+    }
+    // 坐标为相对上一个点的增量：short 标志为 1 字节，same 标志表示符号或沿用上一个值
+    let mut read_coordinates = |short: u8, same: u8| -> Result<Vec<i32>, String> {
+        let mut value = 0i32;
+        let mut coordinates = Vec::with_capacity(num_points);
+        for &flag in &flags {
+            if flag & short != 0 {
+                let delta = r.u8()? as i32;
+                value += if flag & same != 0 { delta } else { -delta };
+            } else if flag & same == 0 {
+                value += r.i16()? as i32;
+            }
+            coordinates.push(value);
+        }
+        Ok(coordinates)
+    };
+    let xs = read_coordinates(0x02, 0x10)?;
+    let ys = read_coordinates(0x04, 0x20)?;
+    let bbox = (num_points > 0).then(|| {
+        let clamp = |v: i32| v.clamp(i16::MIN as i32, i16::MAX as i32) as i16;
+        [
+            clamp(*xs.iter().min().unwrap()),
+            clamp(*ys.iter().min().unwrap()),
+            clamp(*xs.iter().max().unwrap()),
+            clamp(*ys.iter().max().unwrap()),
+        ]
+    });
+    let num_points = u16::try_from(num_points).map_err(|_| format!("{num_points} points exceed the 65535 limit"))?;
+    Ok((num_points, instructions, bbox))
+}
+
+fn read_bbox(data: &[u8], offset: usize) -> Result<[i16; 4], String> {
+    let mut r = Reader::at(data, offset);
+    Ok([r.i16()?, r.i16()?, r.i16()?, r.i16()?])
+}
+
+impl<'a> Validator<'a> {
+    fn issue(&mut self, severity: Severity, table: Option<&[u8; 4]>, message: String) {
+        self.issues.push(Issue { check: self.check, severity, table: table.map(tag_name), message });
+    }
+
+    fn error(&mut self, table: &[u8; 4], message: String) {
+        self.issue(Severity::Error, Some(table), message);
+    }
+
+    fn warning(&mut self, table: &[u8; 4], message: String) {
+        self.issue(Severity::Warning, Some(table), message);
+    }
+
+    fn table(&self, tag: &[u8; 4]) -> &'a [u8] {
+        self.tables.get(tag).copied().unwrap_or_default()
+    }
+
+    // 执行一项检查：缺少所需的表时跳过，解析失败记为错误
+    fn run(&mut self, check: Check, required: &[&[u8; 4]], f: fn(&mut Self) -> Result<(), String>) {
+        self.check = check;
+        let status = if required.iter().any(|tag| !self.tables.contains_key(*tag)) {
+            Status::Skipped
+        } else {
+            let before = self.issues.len();
+            if let Err(err) = f(self) {
+                self.issue(Severity::Error, None, err);
+            }
+            if self.issues[before..].iter().any(|issue| issue.severity == Severity::Error) {
+                Status::Failed
+            } else {
+                Status::Passed
+            }
+        };
+        self.checks.push(CheckResult { check, status });
+    }
+
+    fn structure(&mut self) -> Result<(), String> {
+        let data = self.data;
+        let mut r = Reader::new(data);
+        self.version = r.u32()?;
+        if self.version != TRUETYPE && self.version != CFF && &self.version.to_be_bytes() != b"true" {
+            return Err(format!("unsupported sfnt version 0x{:08X}", self.version));
+        }
+        let num_tables = r.u16()?;
+        let params = (r.u16()?, r.u16()?, r.u16()?);
+        if params != search_params(num_tables, 16) {
+            self.issue(Severity::Warning, None, format!("table directory search parameters {params:?} are incorrect"));
+        }
+        let mut previous: Option<[u8; 4]> = None;
+        let mut ranges = Vec::new();
+        for _ in 0..num_tables {
+            let (tag, recorded, offset, length) = (r.tag()?, r.u32()?, r.u32()?, r.u32()?);
+            if previous.is_some_and(|p| p >= tag) {
+                self.error(&tag, String::from("table directory is not sorted by tag or has duplicate tags"));
+            }
+            previous = Some(tag);
+            if offset % 4 != 0 {
+                self.warning(&tag, format!("table offset {offset} is not 4-byte aligned"));
+            }
+            let Some(table) = data.get(offset as usize..offset as usize + length as usize) else {
+                self.error(&tag, format!("table at {offset} with length {length} is out of bounds"));
+                continue;
+            };
+            ranges.push((offset, offset + length, tag));
+            self.records.push(TableRecord {
+                tag: tag_name(&tag),
+                offset,
+                length,
+                checksum: recorded,
+                computed_checksum: table_checksum(&tag, table),
+            });
+            self.tables.insert(tag, table);
+        }
+        ranges.sort();
+        for pair in ranges.windows(2) {
+            if pair[1].0 < pair[0].1 {
+                let message = format!("table overlaps '{}'", tag_name(&pair[0].2));
+                self.error(&pair[1].2, message);
+            }
+        }
+        for tag in REQUIRED {
+            if !self.tables.contains_key(tag) {
+                self.error(tag, String::from("required table is missing"));
+            }
+        }
+        let outlines: &[&[u8; 4]] = if self.version == CFF { &[b"CFF ", b"CFF2"] } else { &[b"glyf"] };
+        if !outlines.iter().any(|tag| self.tables.contains_key(*tag)) {
+            self.error(outlines[0], String::from("outline table is missing"));
+        }
+        if self.tables.contains_key(b"glyf") != self.tables.contains_key(b"loca") {
+            self.error(b"loca", String::from("glyf and loca must be present together"));
+        }
+        if let Some(head) = self.tables.get(b"head").copied() {
+            if head.len() < 54 {
+                self.error(b"head", format!("table is too short ({} bytes)", head.len()));
+            } else if Reader::at(head, 12).u32()? != HEAD_MAGIC {
+                self.error(b"head", String::from("magicNumber is not 0x5F0F3CF5"));
+            }
+        }
+        if let Some(maxp) = self.tables.get(b"maxp").copied() {
+            self.num_glyphs = Reader::at(maxp, 4).u16()?;
+        }
+        Ok(())
+    }
+
+    fn checksums(&mut self) -> Result<(), String> {
+        let mismatched: Vec<(String, u32, u32)> = self
+            .records
+            .iter()
+            .filter(|record| record.checksum != record.computed_checksum)
+            .map(|record| (record.tag.clone(), record.checksum, record.computed_checksum))
+            .collect();
+        for (tag, recorded, computed) in mismatched {
+            let message = format!("checksum 0x{recorded:08X} does not match 0x{computed:08X}");
+            self.issues.push(Issue { check: self.check, severity: Severity::Error, table: Some(tag), message });
+        }
+        if self.data.len() % 4 != 0 {
+            self.issue(Severity::Warning, None, String::from("file length is not a multiple of 4"));
+        }
+        // 整个文件按 checkSumAdjustment 为 0 计算校验和
+        let head = self.records.iter().find(|r| r.tag == "head").ok_or("missing 'head' table")?;
+        let position = head.offset as usize + 8;
+        let recorded = Reader::at(self.data, position).u32()?;
+        let mut data = self.data.to_vec();
+        data[position..position + 4].fill(0);
+        let expected = CHECKSUM_MAGIC.wrapping_sub(checksum(&data));
+        if recorded != expected {
+            self.error(b"head", format!("checkSumAdjustment 0x{recorded:08X} should be 0x{expected:08X}"));
+        }
+        Ok(())
+    }
+
+    fn glyf(&mut self) -> Result<(), String> {
+        let (glyf, loca) = (self.table(b"glyf"), self.table(b"loca"));
+        let index_to_loc_format = Reader::at(self.table(b"head"), 50).i16()?;
+        let entry_size = match index_to_loc_format {
+            0 => 2,
+            1 => 4,
+            other => return Err(format!("invalid indexToLocFormat {other}")),
+        };
+        let num_glyphs = self.num_glyphs as usize;
+        let expected = (num_glyphs + 1) * entry_size;
+        if loca.len() < expected {
+            return Err(format!("loca has {} bytes, {expected} expected for {num_glyphs} glyphs", loca.len()));
+        }
+        if loca.len() > expected {
+            self.warning(b"loca", format!("{} bytes after the last offset", loca.len() - expected));
+        }
+        let mut r = Reader::new(loca);
+        let offsets = (0..=num_glyphs)
+            .map(|_| if entry_size == 2 { r.u16().map(|v| 2 * v as usize) } else { r.u32().map(|v| v as usize) })
+            .collect::<Result<Vec<usize>, String>>()?;
+        if let Some(gid) = offsets.windows(2).position(|pair| pair[1] < pair[0]) {
+            return Err(format!("loca offsets decrease at glyph {gid}"));
+        }
+        let end = offsets[num_glyphs];
+        if end > glyf.len() {
+            return Err(format!("loca points to offset {end} beyond the glyf length {}", glyf.len()));
+        }
+        if end < glyf.len() {
+            self.warning(b"glyf", format!("{} bytes are not referenced by loca", glyf.len() - end));
+        }
+
+        let mut glyphs = Vec::with_capacity(num_glyphs);
+        for gid in 0..num_glyphs {
+            let data = &glyf[offsets[gid]..offsets[gid + 1]];
+            match self.glyph(gid, data) {
+                Ok(info) => glyphs.push(info),
+                Err(err) => {
+                    self.error(b"glyf", format!("glyph {gid}: {err}"));
+                    glyphs.push(GlyphInfo::default());
+                }
+            }
+        }
+        let loose: Vec<usize> = (0..num_glyphs).filter(|&gid| glyphs[gid].loose_bbox).collect();
+        if !loose.is_empty() {
+            let examples: Vec<String> = loose.iter().take(5).map(usize::to_string).collect();
+            let message = format!(
+                "{} glyphs have bounding boxes larger than their outlines (e.g. {})",
+                loose.len(),
+                examples.join(", ")
+            );
+            self.warning(b"glyf", message);
+        }
+        self.glyphs = Some(glyphs);
+        Ok(())
+    }
+
+    fn glyph(&mut self, gid: usize, data: &[u8]) -> Result<GlyphInfo, String> {
+        if data.is_empty() {
+            return Ok(GlyphInfo::default());
+        }
+        let num_contours = Reader::new(data).i16()?;
+        let bbox = read_bbox(data, 2)?;
+        if bbox[0] > bbox[2] || bbox[1] > bbox[3] {
+            return Err(format!("invalid bounding box {bbox:?}"));
+        }
+        if num_contours >= 0 {
+            let contours = num_contours as u16;
+            let (points, instructions, outline_bbox) = simple_glyph(data, contours)?;
+            // 轮廓超出字形头的边界会被裁切；边界偏大只影响度量，记为警告
+            let mut loose_bbox = false;
+            if let Some(outline) = outline_bbox {
+                if outline[0] < bbox[0] || outline[1] < bbox[1] || outline[2] > bbox[2] || outline[3] > bbox[3] {
+                    let message = format!("glyph {gid}: outline bounds {outline:?} exceed the bounding box {bbox:?}");
+                    self.error(b"glyf", message);
+                }
+                loose_bbox = outline != bbox;
+            }
+            return Ok(GlyphInfo {
+                contours,
+                points,
+                bbox: Some(bbox),
+                instructions,
+                loose_bbox,
+                ..Default::default()
+            });
+        }
+        let mut components = Vec::new();
+        for position in glyf::component_positions(data)? {
+            let component = Reader::at(data, position).u16()?;
+            if component >= self.num_glyphs {
+                return Err(format!("component glyph {component} does not exist"));
+            }
+            components.push(component);
+        }
+        Ok(GlyphInfo { bbox: Some(bbox), components, ..Default::default() })
+    }
+
+    // 复合字形展开后的点数、轮廓数与嵌套深度
+    fn composite_totals(&self, gid: u16, depth: u16) -> Result<(u32, u32, u16), String> {
+        if depth > MAX_COMPONENT_DEPTH {
+            return Err(format!("glyph {gid} has recursive components"));
+        }
+        let glyphs = self.glyphs.as_deref().unwrap_or_default();
+        let Some(info) = glyphs.get(gid as usize) else {
+            return Ok((0, 0, 0));
+        };
+        if info.components.is_empty() {
+            return Ok((info.points as u32, info.contours as u32, 0));
+        }
+        let (mut points, mut contours, mut max_depth) = (0, 0, 0);
+        for &component in &info.components {
+            let (p, c, d) = self.composite_totals(component, depth + 1)?;
+            points += p;
+            contours += c;
+            max_depth = max_depth.max(d);
+        }
+        Ok((points, contours, max_depth + 1))
+    }
+
+    fn maxp(&mut self) -> Result<(), String> {
+        let maxp = self.table(b"maxp");
+        let version = Reader::new(maxp).u32()?;
+        let cff_outlines = self.tables.contains_key(b"CFF ") || self.tables.contains_key(b"CFF2");
+        match (version, cff_outlines) {
+            (0x0000_5000, true) => {}
+            (0x0001_0000, false) if maxp.len() >= 32 => {}
+            (0x0001_0000, false) => return Err(format!("version 1.0 table is too short ({} bytes)", maxp.len())),
+            _ => return Err(format!("version 0x{version:08X} does not match the outline format")),
+        }
+        if self.num_glyphs == 0 {
+            self.error(b"maxp", String::from("numGlyphs is 0, .notdef is required"));
+        }
+        if let Some(cff_data) = self.tables.get(b"CFF ").copied() {
+            let count = cff::subset::num_glyphs(cff_data)?;
+            if count != self.num_glyphs as usize {
+                self.error(b"maxp", format!("numGlyphs {} does not match {count} CFF CharStrings", self.num_glyphs));
+            }
+        }
+        if let Some(glyphs) = &self.glyphs {
+            let mut actual = [0u32; 9];
+            for (gid, info) in glyphs.iter().enumerate() {
+                if info.components.is_empty() {
+                    actual[0] = actual[0].max(info.points as u32);
+                    actual[1] = actual[1].max(info.contours as u32);
+                    actual[6] = actual[6].max(info.instructions as u32);
+                } else {
+                    let (points, contours, depth) = self.composite_totals(gid as u16, 0)?;
+                    actual[2] = actual[2].max(points);
+                    actual[3] = actual[3].max(contours);
+                    actual[7] = actual[7].max(info.components.len() as u32);
+                    actual[8] = actual[8].max(depth as u32);
+                }
+            }
+            // 字段在表中的偏移：maxPoints, maxContours, maxCompositePoints, maxCompositeContours,
+            // maxSizeOfInstructions, maxComponentElements, maxComponentDepth
+            let fields = [
+                (0, 6, "maxPoints"),
+                (1, 8, "maxContours"),
+                (2, 10, "maxCompositePoints"),
+                (3, 12, "maxCompositeContours"),
+                (6, 26, "maxSizeOfInstructions"),
+                (7, 28, "maxComponentElements"),
+                (8, 30, "maxComponentDepth"),
+            ];
+            for (index, offset, name) in fields {
+                let recorded = Reader::at(maxp, offset).u16()? as u32;
+                if recorded < actual[index] {
+                    self.error(b"maxp", format!("{name} is {recorded}, glyph data requires {}", actual[index]));
+                }
+            }
+            let zones = Reader::at(maxp, 14).u16()?;
+            if !(1..=2).contains(&zones) {
+                self.error(b"maxp", format!("maxZones must be 1 or 2, got {zones}"));
+            }
+        }
+        // hmtx 至少包含 numberOfHMetrics 条完整记录与其余字形的左边距
+        if let (Some(hhea), Some(hmtx)) = (self.tables.get(b"hhea").copied(), self.tables.get(b"hmtx").copied()) {
+            let num_metrics = Reader::at(hhea, 34).u16()?;
+            if num_metrics == 0 || num_metrics > self.num_glyphs {
+                self.error(b"hhea", format!("numberOfHMetrics {num_metrics} is out of range 1..={}", self.num_glyphs));
+            } else {
+                let expected = 4 * num_metrics as usize + 2 * (self.num_glyphs - num_metrics) as usize;
+                if hmtx.len() < expected {
+                    self.error(b"hmtx", format!("table has {} bytes, {expected} expected", hmtx.len()));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn cmap(&mut self) -> Result<(), String> {
+        let data = self.table(b"cmap");
+        let mut r = Reader::new(data);
+        let version = r.u16()?;
+        if version != 0 {
+            self.error(b"cmap", format!("version {version} should be 0"));
+        }
+        let count = r.u16()?;
+        let mut previous = None;
+        let mut unicode = false;
+        for _ in 0..count {
+            let (platform_id, encoding_id, offset) = (r.u16()?, r.u16()?, r.u32()? as usize);
+            if previous.is_some_and(|p| p >= (platform_id, encoding_id)) {
+                self.error(b"cmap", format!("encoding record ({platform_id}, {encoding_id}) is out of order"));
+            }
+            previous = Some((platform_id, encoding_id));
+            unicode |= platform_id == 0 || (platform_id == 3 && matches!(encoding_id, 1 | 10));
+            let subtable = data.get(offset..).ok_or("cmap subtable is out of bounds")?;
+            if let Err(err) = self.cmap_subtable(subtable) {
+                self.error(b"cmap", format!("subtable ({platform_id}, {encoding_id}): {err}"));
+            }
+        }
+        if !unicode {
+            self.error(b"cmap", String::from("no Unicode subtable"));
+        }
+        let mapping = cmap::parse(data)?;
+        if let Some((cp, gid)) = mapping.iter().find(|(_, &gid)| gid >= self.num_glyphs) {
+            self.error(b"cmap", format!("U+{cp:04X} maps to missing glyph {gid}"));
+        }
+        Ok(())
+    }
+
+    fn cmap_subtable(&mut self, data: &[u8]) -> Result<(), String> {
+        let mut r = Reader::new(data);
+        match r.u16()? {
+            0 | 6 => {}
+            4 => {
+                r.skip(4)?;
+                let seg_count_x2 = r.u16()?;
+                if seg_count_x2 % 2 != 0 {
+                    return Err(String::from("segCountX2 is odd"));
+                }
+                let seg_count = seg_count_x2 / 2;
+                let params = (r.u16()?, r.u16()?, r.u16()?);
+                if params != search_params(seg_count, 2) {
+                    self.warning(b"cmap", format!("format 4 search parameters {params:?} are incorrect"));
+                }
+                let ends = (0..seg_count).map(|_| r.u16()).collect::<Result<Vec<u16>, String>>()?;
+                r.skip(2)?;
+                let starts = (0..seg_count).map(|_| r.u16()).collect::<Result<Vec<u16>, String>>()?;
+                for i in 0..seg_count as usize {
+                    if starts[i] > ends[i] {
+                        return Err(format!("segment {i} starts after it ends"));
+                    }
+                    if i > 0 && starts[i] <= ends[i - 1] {
+                        return Err(format!("segment {i} is not sorted or overlaps the previous segment"));
+                    }
+                }
+                if ends.last() != Some(&0xFFFF) {
+                    return Err(String::from("last segment must end at 0xFFFF"));
+                }
+            }
+            12 => {
+                r.skip(10)?;
+                let mut previous_end = None;
+                for i in 0..r.u32()? {
+                    let (start, end) = (r.u32()?, r.u32()?);
+                    r.skip(4)?;
+                    if start > end || end > 0x10FFFF {
+                        return Err(format!("group {i} has an invalid range U+{start:04X}..U+{end:04X}"));
+                    }
+                    if previous_end.is_some_and(|p| start <= p) {
+                        return Err(format!("group {i} is not sorted or overlaps the previous group"));
+                    }
+                    previous_end = Some(end);
+                }
+            }
+            14 => {
+                r.skip(4)?;
+                let mut previous = None;
+                for _ in 0..r.u32()? {
+                    let (selector, default_offset, glyph_offset) = (r.u24()?, r.u32()?, r.u32()?);
+                    if previous.is_some_and(|p| selector <= p) {
+                        return Err(format!("variation selector U+{selector:04X} is out of order"));
+                    }
+                    previous = Some(selector);
+                    if default_offset != 0 {
+                        let mut d = Reader::at(data, default_offset as usize);
+                        let mut previous_end = None;
+                        for _ in 0..d.u32()? {
+                            let (start, additional) = (d.u24()?, d.u8()?);
+                            if previous_end.is_some_and(|p| start <= p) {
+                                return Err(format!("default UVS ranges of U+{selector:04X} are not sorted"));
+                            }
+                            previous_end = Some(start + additional as u32);
+                        }
+                    }
+                    if glyph_offset != 0 {
+                        let mut g = Reader::at(data, glyph_offset as usize);
+                        let mut previous = None;
+                        for _ in 0..g.u32()? {
+                            let (unicode, gid) = (g.u24()?, g.u16()?);
+                            if previous.is_some_and(|p| unicode <= p) {
+                                return Err(format!("non-default UVS mappings of U+{selector:04X} are not sorted"));
+                            }
+                            if gid >= self.num_glyphs {
+                                return Err(format!("U+{unicode:04X} U+{selector:04X} maps to missing glyph {gid}"));
+                            }
+                            previous = Some(unicode);
+                        }
+                    }
+                }
+            }
+            format => self.warning(b"cmap", format!("subtable format {format} is not checked")),
+        }
+        Ok(())
+    }
+
+    fn name(&mut self) -> Result<(), String> {
+        let data = self.table(b"name");
+        let mut r = Reader::new(data);
+        let format = r.u16()?;
+        if format > 1 {
+            return Err(format!("unsupported format {format}"));
+        }
+        let count = r.u16()?;
+        let storage = r.u16()? as usize;
+        let mut previous = None;
+        let mut windows_names = BTreeSet::new();
+        let mut post_script_names = BTreeSet::new();
+        for _ in 0..count {
+            let key = (r.u16()?, r.u16()?, r.u16()?, r.u16()?);
+            let (platform_id, encoding_id, language_id, name_id) = key;
+            let (length, offset) = (r.u16()? as usize, r.u16()? as usize);
+            let label = format!("name {name_id} ({platform_id}, {encoding_id}, 0x{language_id:04X})");
+            if previous.is_some_and(|p| p >= key) {
+                self.error(b"name", format!("{label} is out of order or duplicated"));
+            }
+            previous = Some(key);
+            let Some(bytes) = data.get(storage + offset..storage + offset + length) else {
+                self.error(b"name", format!("{label} is out of bounds"));
+                continue;
+            };
+            // Unicode 与 Windows 平台为 UTF-16BE，Mac Roman 的每个字节都是合法字符
+            let value = match (platform_id, encoding_id) {
+                (0, _) | (3, 0 | 1 | 10) => {
+                    if length % 2 != 0 {
+                        self.error(b"name", format!("{label} has an odd UTF-16 length"));
+                        continue;
+                    }
+                    let units: Vec<u16> = bytes.chunks_exact(2).map(|c| u16::from_be_bytes([c[0], c[1]])).collect();
+                    match String::from_utf16(&units) {
+                        Ok(value) => value,
+                        Err(_) => {
+                            self.error(b"name", format!("{label} is not valid UTF-16"));
+                            continue;
+                        }
+                    }
+                }
+                (1, 0) => bytes.iter().map(|&b| if b.is_ascii() { b as char } else { '?' }).collect(),
+                _ => {
+                    self.warning(b"name", format!("{label} uses an unchecked encoding"));
+                    continue;
+                }
+            };
+            if platform_id == 3 {
+                windows_names.insert(name_id);
+            }
+            if name_id == 6 {
+                if let Err(err) = check_post_script_name(&value) {
+                    self.error(b"name", format!("{label}: {err}"));
+                }
+                post_script_names.insert(value);
+            }
+        }
+        for name_id in [1, 2, 4, 6] {
+            if !windows_names.contains(&name_id) {
+                self.error(b"name", format!("name {name_id} is missing for the Windows platform"));
+            }
+        }
+        if post_script_names.len() > 1 {
+            self.warning(b"name", format!("PostScript names differ between records: {post_script_names:?}"));
+        }
+        Ok(())
+    }
+
+    fn metrics(&mut self) -> Result<(), String> {
+        let head = self.table(b"head");
+        let units_per_em = Reader::at(head, 18).u16()?;
+        if !(16..=16384).contains(&units_per_em) {
+            self.error(b"head", format!("unitsPerEm {units_per_em} is out of range 16..=16384"));
+        }
+        let head_bbox = read_bbox(head, 36)?;
+        // TrueType 轮廓按各字形的边界校验 head 的边界，CFF 轮廓以 head 的边界为准
+        let glyph_bbox = match &self.glyphs {
+            Some(glyphs) => glyphs
+                .iter()
+                .filter_map(|g| g.bbox)
+                .reduce(|a, b| [a[0].min(b[0]), a[1].min(b[1]), a[2].max(b[2]), a[3].max(b[3])]),
+            None => Some(head_bbox),
+        };
+        let bbox = glyph_bbox.unwrap_or_default();
+        if head_bbox != bbox {
+            self.error(b"head", format!("bounding box {head_bbox:?} does not match the glyph bounds {bbox:?}"));
+        }
+
+        if let (Some(hhea), Some(hmtx)) = (self.tables.get(b"hhea").copied(), self.tables.get(b"hmtx").copied()) {
+            let num_metrics = Reader::at(hhea, 34).u16()?.min(self.num_glyphs) as usize;
+            let mut r = Reader::new(hmtx);
+            let mut max_advance = 0;
+            for _ in 0..num_metrics {
+                max_advance = max_advance.max(r.u16()?);
+                r.skip(2)?;
+            }
+            let advance_width_max = Reader::at(hhea, 10).u16()?;
+            if advance_width_max < max_advance {
+                self.error(b"hhea", format!("advanceWidthMax {advance_width_max} is less than {max_advance}"));
+            }
+        }
+
+        let os2 = self.table(b"OS/2");
+        let version = Reader::new(os2).u16()?;
+        let expected = match version {
+            0 => 78,
+            1 => 86,
+            2..=4 => 96,
+            5 => 100,
+            _ => return Err(format!("unknown OS/2 version {version}")),
+        };
+        if os2.len() < expected {
+            return Err(format!("OS/2 version {version} table has {} bytes, {expected} expected", os2.len()));
+        }
+        let win_ascent = Reader::at(os2, 74).u16()?;
+        let win_descent = Reader::at(os2, 76).u16()?;
+        if (win_ascent as i32) < bbox[3] as i32 {
+            self.warning(b"OS/2", format!("usWinAscent {win_ascent} is below yMax {}, glyphs may be clipped", bbox[3]));
+        }
+        if (win_descent as i32) < -(bbox[1] as i32) {
+            let message =
+                format!("usWinDescent {win_descent} is below -yMin {}, glyphs may be clipped", -(bbox[1] as i32));
+            self.warning(b"OS/2", message);
+        }
+        let typo_ascender = Reader::at(os2, 68).i16()?;
+        let typo_descender = Reader::at(os2, 70).i16()?;
+        if typo_ascender < typo_descender {
+            self.error(b"OS/2", format!("sTypoAscender {typo_ascender} is below sTypoDescender {typo_descender}"));
+        }
+        if let Ok(mapping) = cmap::parse(self.table(b"cmap")) {
+            let first = mapping.keys().next().map_or(0, |&cp| cp.min(0xFFFF) as u16);
+            let last = mapping.keys().next_back().map_or(0, |&cp| cp.min(0xFFFF) as u16);
+            let recorded = (Reader::at(os2, 64).u16()?, Reader::at(os2, 66).u16()?);
+            if recorded != (first, last) {
+                let message = format!(
+                    "usFirstCharIndex/usLastCharIndex are U+{:04X}/U+{:04X}, cmap covers U+{first:04X}/U+{last:04X}",
+                    recorded.0, recorded.1
+                );
+                self.warning(b"OS/2", message);
+            }
+        }
+        Ok(())
+    }
+}
+
+// PostScript 名称：不超过 63 个字符，只能使用除 [](){}<>/% 外的可打印 ASCII 字符
+fn check_post_script_name(name: &str) -> Result<(), String> {
+    if name.is_empty() || name.len() > 63 {
+        return Err(format!("PostScript name {name:?} must be 1 to 63 characters long"));
+    }
+    if let Some(c) = name.chars().find(|&c| !('!'..='~').contains(&c) || POST_SCRIPT_FORBIDDEN.contains(c)) {
+        return Err(format!("PostScript name {name:?} contains invalid character {c:?}"));
+    }
+    Ok(())
+}
+
+// 解析导出的字体（TTF/OTF 或 WOFF）并逐项校验，结果写入报告而不是返回错误
+pub fn validate(data: &[u8]) -> ValidationReport {
+    let mut flavor = "sfnt";
+    let decoded;
+    let mut data = data;
+    let mut decode_error = None;
+    if woff::is_woff(data) {
+        flavor = "woff";
+        match woff::decode(data) {
+            Ok(file) => {
+                decoded = file.sfnt;
+                data = &decoded;
+            }
+            Err(err) => decode_error = Some(err),
+        }
+    } else if data.starts_with(b"wOF2") {
+        flavor = "woff2";
+        decode_error = Some(String::from("WOFF2 fonts are not supported, validate the font before compression"));
+    }
+
+    let mut validator = Validator {
+        data,
+        version: 0,
+        tables: BTreeMap::new(),
+        records: Vec::new(),
+        num_glyphs: 0,
+        glyphs: None,
+        check: Check::Structure,
+        issues: Vec::new(),
+        checks: Vec::new(),
+    };
+    match decode_error {
+        Some(err) => {
+            validator.issue(Severity::Error, None, err);
+            validator.checks.push(CheckResult { check: Check::Structure, status: Status::Failed });
+        }
+        None => validator.run(Check::Structure, &[], Validator::structure),
+    }
+    validator.run(Check::Checksum, &[b"head"], Validator::checksums);
+    validator.run(Check::Glyf, &[b"head", b"maxp", b"glyf", b"loca"], Validator::glyf);
+    validator.run(Check::Maxp, &[b"maxp"], Validator::maxp);
+    validator.run(Check::Cmap, &[b"cmap"], Validator::cmap);
+    validator.run(Check::Name, &[b"name"], Validator::name);
+    validator.run(Check::Metrics, &[b"head", b"OS/2"], Validator::metrics);
+
+    let valid = !validator.issues.iter().any(|issue| issue.severity == Severity::Error);
+    ValidationReport {
+        valid,
+        flavor: String::from(flavor),
+        num_glyphs: validator.num_glyphs,
+        tables: validator.records,
+        checks: validator.checks,
+        issues: validator.issues,
+    }
+}
@@ -0,0 +1,512 @@
+use std::collections::{BTreeMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::tables::fvar::FvarInstance;
+use crate::tables::gvar::TupleVariation;
+use crate::tables::name::{self, NameRecord};
+use crate::tables::stat::AxisValue;
+use crate::tables::{avar, fvar, glyf, gvar, hvar, stat};
+use crate::{advance_width, composite, prepare_glyphs, sfnt, truetype_tables, vertical, FontData, GlyphData};
+
+// 变化区域：按 fvar 中轴的顺序，每个轴为归一化坐标下的 (起点, 峰值, 终点)
+pub type Region = Vec<[f64; 3]>;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AxisLabel {
+    pub value: f64,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Axis {
+    pub tag: String,
+    pub name: String,
+    pub min_value: f64,
+    pub default_value: f64,
+    pub max_value: f64,
+    pub hidden: bool,
+    // avar 分段映射 [用户坐标, 设计坐标]，两者都在轴的取值范围内
+    pub map: Vec<[f64; 2]>,
+    // 写入 STAT 的命名取值，如 wght 400 = Regular
+    pub labels: Vec<AxisLabel>,
+}
+
+// 命名实例，未给出的轴取默认值
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Instance {
+    pub name: String,
+    pub post_script_name: String,
+    pub coordinates: BTreeMap<String, f64>,
+}
+
+// 位于某个轴坐标（用户坐标）上的母版，字形与默认母版按序号一一对应
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Master {
+    pub location: BTreeMap<String, f64>,
+    pub glyphs: Vec<GlyphData>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct VariableFontData {
+    pub font: FontData,
+    pub axes: Vec<Axis>,
+    pub instances: Vec<Instance>,
+    pub masters: Vec<Master>,
+}
+
+// 按 F2DOT14 精度取整，保证区域计算与写入的数值一致
+fn quantize(v: f64) -> f64 {
+    (v * 16384.0).round() / 16384.0
+}
+
+// 分段线性插值，segments 按输入坐标升序排列
+pub(crate) fn piecewise(segments: &[(f64, f64)], v: f64) -> f64 {
+    let Some(i) = segments.iter().position(|&(from, _)| from >= v) else {
+        return v;
+    };
+    let (from, to) = segments[i];
+    if i == 0 || from == v {
+        return to;
+    }
+    let (prev_from, prev_to) = segments[i - 1];
+    prev_to + (to - prev_to) * (v - prev_from) / (from - prev_from)
+}
+
+impl Axis {
+    // 标签不足 4 个字符时补空格
+    pub fn tag_bytes(&self) -> [u8; 4] {
+        let mut tag = [b' '; 4];
+        for (b, c) in tag.iter_mut().zip(self.tag.bytes()) {
+            *b = c;
+        }
+        tag
+    }
+
+    // 用户坐标按默认规则归一化到 [-1, 1]
+    pub fn normalize(&self, value: f64) -> f64 {
+        let value = value.clamp(self.min_value, self.max_value);
+        if value < self.default_value {
+            (value - self.default_value) / (self.default_value - self.min_value)
+        } else if value > self.default_value {
+            (value - self.default_value) / (self.max_value - self.default_value)
+        } else {
+            0.0
+        }
+    }
+
+    // avar 中该轴的映射，没有映射时为空
+    pub fn avar_segments(&self) -> Vec<(f64, f64)> {
+        if self.map.is_empty() {
+            return Vec::new();
+        }
+        let mut segments: Vec<(f64, f64)> = self
+            .map
+            .iter()
+            .map(|&[input, output]| (quantize(self.normalize(input)), quantize(self.normalize(output))))
+            .collect();
+        segments.extend([(-1.0, -1.0), (0.0, 0.0), (1.0, 1.0)]);
+        segments.sort_by(|a, b| a.0.total_cmp(&b.0));
+        segments.dedup_by(|a, b| a.0 == b.0);
+        segments
+    }
+
+    // 用户坐标经 avar 映射后的归一化坐标
+    pub fn normalize_mapped(&self, value: f64) -> f64 {
+        quantize(piecewise(&self.avar_segments(), quantize(self.normalize(value))))
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.tag.is_empty() || self.tag.len() > 4 || !self.tag.bytes().all(|b| (0x20..0x7F).contains(&b)) {
+            return Err(format!("invalid axis tag: {:?}", self.tag));
+        }
+        if !(self.min_value <= self.default_value && self.default_value <= self.max_value) {
+            return Err(format!("axis {}: default value must lie between min and max", self.tag));
+        }
+        for &[input, output] in &self.map {
+            let range = self.min_value..=self.max_value;
+            if !range.contains(&input) || !range.contains(&output) {
+                return Err(format!("axis {}: mapping {input} -> {output} is out of range", self.tag));
+            }
+        }
+        let segments = self.avar_segments();
+        if segments.iter().any(|&(from, to)| from == 0.0 && to != 0.0) {
+            return Err(format!("axis {}: mapping must keep the default value", self.tag));
+        }
+        if segments.windows(2).any(|w| w[1].1 < w[0].1) {
+            return Err(format!("axis {}: mapping must be monotonic", self.tag));
+        }
+        Ok(())
+    }
+}
+
+fn location_label(location: &BTreeMap<String, f64>) -> String {
+    location.iter().map(|(tag, value)| format!("{tag}={value}")).collect::<Vec<_>>().join(", ")
+}
+
+// 用户坐标位置 → 按轴顺序排列的归一化坐标
+fn normalize_location(axes: &[Axis], location: &BTreeMap<String, f64>) -> Result<Vec<f64>, String> {
+    for (tag, value) in location {
+        let Some(axis) = axes.iter().find(|a| &a.tag == tag) else {
+            return Err(format!("unknown axis {tag}"));
+        };
+        if *value < axis.min_value || *value > axis.max_value {
+            return Err(format!("{tag}={value} is outside the axis range"));
+        }
+    }
+    Ok(axes.iter().map(|axis| location.get(&axis.tag).map_or(0.0, |&value| axis.normalize_mapped(value))).collect())
+}
+
+// 区域在某个位置上的权重
+fn support_scalar(location: &[f64], region: &Region) -> f64 {
+    let mut scalar = 1.0;
+    for (&v, &[lower, peak, upper]) in location.iter().zip(region) {
+        if peak == 0.0 || lower > peak || peak > upper || (lower < 0.0 && upper > 0.0) || v == peak {
+            continue;
+        }
+        if v <= lower || upper <= v {
+            return 0.0;
+        }
+        scalar *= if v < peak { (v - lower) / (peak - lower) } else { (v - upper) / (peak - upper) };
+    }
+    scalar
+}
+
+// 母版插值模型：为每个非默认母版确定支撑区域，并把母版数值换算成各区域上的增量
+pub struct VariationModel {
+    // 按计算顺序排列的母版原始下标
+    pub order: Vec<usize>,
+    pub regions: Vec<Region>,
+    delta_weights: Vec<Vec<(usize, f64)>>,
+}
+
+impl VariationModel {
+    pub fn new(locations: &[Vec<f64>]) -> Self {
+        let axis_count = locations.first().map_or(0, Vec::len);
+        // 单轴母版所在的坐标，排序时优先处理落在这些坐标上的母版
+        let mut axis_points: Vec<Vec<f64>> = vec![vec![0.0]; axis_count];
+        for location in locations {
+            let active: Vec<usize> = (0..axis_count).filter(|&a| location[a] != 0.0).collect();
+            if let [a] = active[..] {
+                axis_points[a].push(location[a]);
+            }
+        }
+        let sort_key = |location: &Vec<f64>| {
+            let active: Vec<usize> = (0..axis_count).filter(|&a| location[a] != 0.0).collect();
+            let on_point = active.iter().filter(|&&a| axis_points[a].contains(&location[a])).count();
+            let signs: Vec<bool> = active.iter().map(|&a| location[a] > 0.0).collect();
+            (active.len(), std::cmp::Reverse(on_point), active, signs)
+        };
+        let mut order: Vec<usize> = (0..locations.len()).collect();
+        order.sort_by(|&a, &b| {
+            let (la, lb) = (&locations[a], &locations[b]);
+            sort_key(la).cmp(&sort_key(lb)).then_with(|| {
+                la.iter()
+                    .zip(lb)
+                    .map(|(x, y)| x.abs().total_cmp(&y.abs()))
+                    .find(|o| o.is_ne())
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            })
+        });
+
+        // 初始区域：从 0 延伸到该轴上所有母版的最远坐标
+        let mut min_values = vec![0.0f64; axis_count];
+        let mut max_values = vec![0.0f64; axis_count];
+        for location in locations {
+            for (a, &v) in location.iter().enumerate() {
+                min_values[a] = min_values[a].min(v);
+                max_values[a] = max_values[a].max(v);
+            }
+        }
+        let sorted: Vec<&Vec<f64>> = order.iter().map(|&i| &locations[i]).collect();
+        let mut regions: Vec<Region> = sorted
+            .iter()
+            .map(|location| {
+                location
+                    .iter()
+                    .enumerate()
+                    .map(|(a, &v)| match v {
+                        v if v > 0.0 => [0.0, v, max_values[a]],
+                        v if v < 0.0 => [min_values[a], v, 0.0],
+                        _ => [0.0, 0.0, 0.0],
+                    })
+                    .collect()
+            })
+            .collect();
+
+        // 之前的母版落在当前区域内时，沿比例最大的方向收缩区域
+        for i in 0..regions.len() {
+            let active: Vec<usize> = (0..axis_count).filter(|&a| regions[i][a][1] != 0.0).collect();
+            for j in 0..i {
+                let prev_active: Vec<usize> = (0..axis_count).filter(|&a| regions[j][a][1] != 0.0).collect();
+                if prev_active != active {
+                    continue;
+                }
+                let relevant = active.iter().all(|&a| {
+                    let [lower, peak, upper] = regions[i][a];
+                    let v = regions[j][a][1];
+                    v == peak || (lower < v && v < upper)
+                });
+                if !relevant {
+                    continue;
+                }
+                let mut best_ratio = -1.0;
+                let mut best: Vec<(usize, [f64; 3])> = Vec::new();
+                for &a in &active {
+                    let v = regions[j][a][1];
+                    let [lower, peak, upper] = regions[i][a];
+                    let (tent, ratio) = if v < peak {
+                        ([v, peak, upper], (v - peak) / (lower - peak))
+                    } else if peak < v {
+                        ([lower, peak, v], (v - peak) / (upper - peak))
+                    } else {
+                        continue;
+                    };
+                    if ratio > best_ratio {
+                        best.clear();
+                        best_ratio = ratio;
+                    }
+                    if ratio == best_ratio {
+                        best.push((a, tent));
+                    }
+                }
+                for (a, tent) in best {
+                    regions[i][a] = tent;
+                }
+            }
+        }
+
+        let delta_weights = sorted
+            .iter()
+            .enumerate()
+            .map(|(i, location)| {
+                (0..i)
+                    .map(|j| (j, support_scalar(location, &regions[j])))
+                    .filter(|&(_, scalar)| scalar != 0.0)
+                    .collect()
+            })
+            .collect();
+        VariationModel { order, regions, delta_weights }
+    }
+
+    // values 为各母版（原始顺序）相对默认母版的差值，返回按区域顺序排列的增量；逐个取整以免误差累积
+    pub fn deltas(&self, values: &[f64]) -> Vec<i32> {
+        let mut out: Vec<i32> = Vec::with_capacity(self.order.len());
+        for (&master, weights) in self.order.iter().zip(&self.delta_weights) {
+            let mut delta = values[master];
+            for &(j, weight) in weights {
+                delta -= out[j] as f64 * weight;
+            }
+            out.push(delta.round() as i32);
+        }
+        out
+    }
+}
+
+// 为轴名、实例名等分配 256 起的 nameID，相同的字符串共用
+#[derive(Default)]
+struct NameIds {
+    strings: Vec<String>,
+}
+
+impl NameIds {
+    fn get(&mut self, value: &str) -> u16 {
+        let index = match self.strings.iter().position(|s| s == value) {
+            Some(index) => index,
+            None => {
+                self.strings.push(value.to_string());
+                self.strings.len() - 1
+            }
+        };
+        256 + index as u16
+    }
+
+    fn records(&self) -> Vec<NameRecord> {
+        self.strings.iter().enumerate().flat_map(|(i, value)| name::string_records(256 + i as u16, value)).collect()
+    }
+}
+
+pub(crate) fn validate(data: &VariableFontData) -> Result<Vec<Vec<f64>>, String> {
+    if data.axes.is_empty() {
+        return Err(String::from("variable font needs at least one axis"));
+    }
+    let mut tags = HashSet::new();
+    for axis in &data.axes {
+        axis.validate()?;
+        if !tags.insert(&axis.tag) {
+            return Err(format!("duplicate axis tag: {}", axis.tag));
+        }
+    }
+    for instance in &data.instances {
+        normalize_location(&data.axes, &instance.coordinates)
+            .map_err(|err| format!("instance {:?}: {err}", instance.name))?;
+    }
+
+    let mut locations: Vec<Vec<f64>> = Vec::with_capacity(data.masters.len());
+    for master in &data.masters {
+        let label = location_label(&master.location);
+        let location =
+            normalize_location(&data.axes, &master.location).map_err(|err| format!("master {label}: {err}"))?;
+        if location.iter().all(|&v| v == 0.0) {
+            return Err(format!("master {label} is at the default location"));
+        }
+        if locations.contains(&location) {
+            return Err(format!("master {label} duplicates another master location"));
+        }
+        if master.glyphs.len() != data.font.glyphs.len() {
+            return Err(format!(
+                "master {label} has {} glyphs, default has {}",
+                master.glyphs.len(),
+                data.font.glyphs.len()
+            ));
+        }
+        locations.push(location);
+    }
+    Ok(locations)
+}
+
+// 生成 TrueType 轮廓的可变字体：默认母版写入 glyf，其余母版写入 gvar 与 HVAR
+pub fn build_ttf(data: &VariableFontData) -> Result<Vec<u8>, String> {
+    let locations = validate(data)?;
+    let info = &data.font.info;
+    let axes = &data.axes;
+    let glyphs = prepare_glyphs(&data.font)?;
+    // 自动插入 .notdef 时，母版字形的序号整体后移一位
+    let inserted = glyphs.len() - data.font.glyphs.len();
+    // gvar 只描述简单字形的点，组件在各母版中分别展开
+    let glyphs = composite::flatten(&glyphs, inserted)?;
+    let masters: Vec<Vec<GlyphData>> =
+        data.masters.iter().map(|master| composite::flatten(&master.glyphs, 0)).collect::<Result<_, _>>()?;
+    let masters = &masters;
+    let master_glyph = move |master: usize, gid: usize| gid.checked_sub(inserted).map(|i| &masters[master][i]);
+
+    // 逐个字形检查母版兼容性，汇总所有不兼容的字形
+    let mut errors = Vec::new();
+    for (gid, glyph) in glyphs.iter().enumerate() {
+        for (m, master) in data.masters.iter().enumerate() {
+            let Some(master_glyph) = master_glyph(m, gid) else {
+                continue;
+            };
+            if let Err(err) = glyf::check_compatible(&glyph.contours, &master_glyph.contours) {
+                errors.push(format!(
+                    "glyph {:?} is incompatible with master {}: {err}",
+                    glyph.name,
+                    location_label(&master.location)
+                ));
+            }
+        }
+    }
+    if !errors.is_empty() {
+        return Err(errors.join("\n"));
+    }
+
+    let model = VariationModel::new(&locations);
+    let mut simple_glyphs = Vec::with_capacity(glyphs.len());
+    let mut glyph_variations = Vec::with_capacity(glyphs.len());
+    let mut advance_deltas = Vec::with_capacity(glyphs.len());
+    for (gid, glyph) in glyphs.iter().enumerate() {
+        let default_advance = advance_width(glyph.advance_width) as f64;
+        let mut contours = vec![glyph.contours.as_slice()];
+        let mut advances = Vec::with_capacity(data.masters.len());
+        for m in 0..data.masters.len() {
+            match master_glyph(m, gid) {
+                Some(g) => {
+                    contours.push(&g.contours);
+                    advances.push(advance_width(g.advance_width) as f64 - default_advance);
+                }
+                None => {
+                    contours.push(&glyph.contours);
+                    advances.push(0.0);
+                }
+            }
+        }
+        let compatible = glyf::compatible_glyphs(&contours);
+        let points: Vec<Vec<&glyf::GlyphPoint>> =
+            compatible.iter().map(|g| g.contours.iter().flatten().collect()).collect();
+
+        // 每个坐标（含 4 个幻影点，仅第二个幻影点随步进宽度变化）在各母版上相对默认母版的差值
+        let mut coordinate_values: Vec<Vec<f64>> = Vec::with_capacity(2 * (points[0].len() + 4));
+        for (p, base) in points[0].iter().enumerate() {
+            coordinate_values.push(points[1..].iter().map(|m| m[p].x as f64 - base.x as f64).collect());
+            coordinate_values.push(points[1..].iter().map(|m| m[p].y as f64 - base.y as f64).collect());
+        }
+        for phantom in 0..4 {
+            let xs = if phantom == 1 { advances.clone() } else { vec![0.0; advances.len()] };
+            coordinate_values.push(xs);
+            coordinate_values.push(vec![0.0; advances.len()]);
+        }
+        let coordinate_deltas: Vec<Vec<i32>> = coordinate_values.iter().map(|values| model.deltas(values)).collect();
+        let variations: Vec<TupleVariation> = model
+            .regions
+            .iter()
+            .enumerate()
+            .filter_map(|(r, region)| {
+                let deltas: Vec<(i16, i16)> =
+                    coordinate_deltas.chunks(2).map(|d| (clamp_i16(d[0][r]), clamp_i16(d[1][r]))).collect();
+                deltas.iter().any(|&d| d != (0, 0)).then(|| TupleVariation { region: region.clone(), deltas })
+            })
+            .collect();
+
+        simple_glyphs.push(compatible.into_iter().next().unwrap_or_default());
+        glyph_variations.push(variations);
+        advance_deltas.push(model.deltas(&advances));
+    }
+
+    // 轴名、实例名与 STAT 取值名使用 256 起的 nameID
+    let mut name_ids = NameIds::default();
+    let axis_name_ids: Vec<u16> =
+        axes.iter().map(|axis| name_ids.get(if axis.name.is_empty() { &axis.tag } else { &axis.name })).collect();
+    let instances: Vec<FvarInstance> = data
+        .instances
+        .iter()
+        .map(|instance| FvarInstance {
+            subfamily_name_id: name_ids.get(&instance.name),
+            post_script_name_id: (!instance.post_script_name.is_empty())
+                .then(|| name_ids.get(&name::sanitize_post_script_name(&instance.post_script_name))),
+            coordinates: axes
+                .iter()
+                .map(|axis| instance.coordinates.get(&axis.tag).copied().unwrap_or(axis.default_value))
+                .collect(),
+        })
+        .collect();
+    let axis_values: Vec<AxisValue> = axes
+        .iter()
+        .enumerate()
+        .flat_map(|(a, axis)| axis.labels.iter().map(move |label| (a, axis, label)))
+        .map(|(a, axis, label)| AxisValue {
+            axis_index: a as u16,
+            value: label.value,
+            name_id: name_ids.get(&label.name),
+            elidable: label.value == axis.default_value,
+        })
+        .collect();
+    let mut name_records = name::records(info)?;
+    name_records.extend(name_ids.records());
+
+    // 竖排替换只写入 GSUB，旋转生成的字形没有对应的母版
+    let font = vertical::expand(&data.font)?;
+    if font.glyphs.len() != data.font.glyphs.len() {
+        return Err(String::from("rotated vertical glyphs are not supported in variable fonts"));
+    }
+    let mut tables = truetype_tables(&font, &glyphs, &simple_glyphs, &[], false)?;
+    tables.retain(|table| &table.tag != b"name");
+    tables.push(sfnt::Table::new(b"name", name::build_from_records(name_records)));
+    tables.push(sfnt::Table::new(b"fvar", fvar::build(axes, &axis_name_ids, &instances)));
+    tables.push(sfnt::Table::new(b"gvar", gvar::build(axes.len(), &glyph_variations)));
+    tables.push(sfnt::Table::new(b"HVAR", hvar::build(axes.len(), &model.regions, &advance_deltas)));
+    tables.push(sfnt::Table::new(b"STAT", stat::build(axes, &axis_name_ids, &axis_values)));
+    if axes.iter().any(|axis| !axis.map.is_empty()) {
+        let segments: Vec<Vec<(f64, f64)>> = axes.iter().map(Axis::avar_segments).collect();
+        tables.push(sfnt::Table::new(b"avar", avar::build(&segments)));
+    }
+    Ok(sfnt::assemble(sfnt::TRUETYPE, tables))
+}
+
+fn clamp_i16(v: i32) -> i16 {
+    v.clamp(i16::MIN as i32, i16::MAX as i32) as i16
+}
@@ -0,0 +1,230 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::binary::Writer;
+use crate::composite::GlyphComponent;
+use crate::features::{FeatureDefinition, GlyphRef, SubstitutionLookup, SubstitutionRule};
+use crate::tables::{hmtx, GlyphMetrics};
+use crate::{sfnt, FontData, GlyphData};
+
+// 单个字形的竖排度量，未指定的项沿用字身框，origin 为垂直原点的 y 坐标
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct VerticalMetric {
+    pub glyph: usize,
+    pub advance: Option<f64>,
+    pub origin: Option<f64>,
+}
+
+// 竖排替换字形：alternate 为单独设计的竖排字形，rotate 为 true 时由基础字形顺时针旋转 90° 生成
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct VerticalAlternate {
+    pub glyph: usize,
+    pub alternate: Option<usize>,
+    pub rotate: bool,
+}
+
+// 竖排设置：origin 与 advance 为字身框的默认垂直原点与竖排步进，缺省时为 unitsPerEm + descender 与 unitsPerEm
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct VerticalLayout {
+    pub origin: Option<f64>,
+    pub advance: Option<f64>,
+    pub metrics: Vec<VerticalMetric>,
+    pub alternates: Vec<VerticalAlternate>,
+}
+
+impl VerticalLayout {
+    fn default_origin(&self, font: &FontData) -> f64 {
+        self.origin.unwrap_or(font.info.units_per_em as f64 + font.info.descender as f64)
+    }
+
+    fn default_advance(&self, font: &FontData) -> f64 {
+        self.advance.unwrap_or(font.info.units_per_em as f64)
+    }
+}
+
+// 追加旋转生成的竖排字形，并把竖排替换写入 vert 与 vrt2 特性；没有竖排替换时原样返回
+pub fn expand(font: &FontData) -> Result<Cow<'_, FontData>, String> {
+    let Some(vertical) = font.vertical.as_ref().filter(|v| !v.alternates.is_empty()) else {
+        return Ok(Cow::Borrowed(font));
+    };
+    let origin = vertical.default_origin(font);
+    let bottom = origin - font.info.units_per_em as f64;
+    let mut glyphs = font.glyphs.clone();
+    let mut metrics = vertical.metrics.clone();
+    let mut rules = Vec::with_capacity(vertical.alternates.len());
+    for alternate in &vertical.alternates {
+        let Some(base) = font.glyphs.get(alternate.glyph) else {
+            return Err(format!("vertical alternate refers to missing glyph {}", alternate.glyph));
+        };
+        let replacement = match (alternate.alternate, alternate.rotate) {
+            (Some(index), false) => {
+                if index >= font.glyphs.len() {
+                    return Err(format!("vertical alternate refers to missing glyph {index}"));
+                }
+                index
+            }
+            (None, true) => {
+                let name = if base.name.is_empty() { format!("glyph{}", alternate.glyph) } else { base.name.clone() };
+                // 顺时针旋转 90°：原字形的左边对齐字身框顶部，原字身框的底边对齐竖排字形的左边
+                glyphs.push(GlyphData {
+                    name: format!("{name}.vert"),
+                    unicode: 0,
+                    advance_width: font.info.units_per_em as f64,
+                    contours: Vec::new(),
+                    components: vec![GlyphComponent {
+                        glyph: alternate.glyph,
+                        transform: Some([0.0, -1.0, 1.0, 0.0, -bottom, origin]),
+                    }],
+                });
+                // 旋转后的竖排步进等于原字形的横排步进
+                metrics.push(VerticalMetric {
+                    glyph: glyphs.len() - 1,
+                    advance: Some(base.advance_width),
+                    origin: None,
+                });
+                glyphs.len() - 1
+            }
+            _ => {
+                return Err(format!(
+                    "vertical alternate for glyph {} needs either an alternate glyph or rotate, but not both",
+                    alternate.glyph
+                ))
+            }
+        };
+        rules.push(SubstitutionRule::Single {
+            input: GlyphRef::Glyph { glyph: alternate.glyph },
+            replacement: GlyphRef::Glyph { glyph: replacement },
+        });
+    }
+
+    // 已经定义了 vert/vrt2 时把竖排替换追加到其中
+    let mut features = font.features.clone();
+    let lookup = features.lookups.len();
+    features.lookups.push(SubstitutionLookup { name: String::from("vertical alternates"), rules });
+    for tag in ["vert", "vrt2"] {
+        match features.features.iter_mut().find(|f| f.tag == tag) {
+            Some(feature) => feature.lookups.push(lookup),
+            None => features.features.push(FeatureDefinition {
+                tag: String::from(tag),
+                lookups: vec![lookup],
+                languages: Vec::new(),
+            }),
+        }
+    }
+    let vertical = VerticalLayout { metrics, ..vertical.clone() };
+    Ok(Cow::Owned(FontData { glyphs, features, vertical: Some(vertical), ..font.clone() }))
+}
+
+fn fword(v: f64) -> i16 {
+    v.round().clamp(i16::MIN as f64, i16::MAX as f64) as i16
+}
+
+fn vhea(font: &FontData, metrics: &[GlyphMetrics], vertical: &[(u16, i16)], number_of_v_metrics: u16) -> Vec<u8> {
+    let advance_height_max = vertical.iter().map(|&(advance, _)| advance).max().unwrap_or(0);
+    // 侧边距极值只统计有轮廓的字形
+    let mut min_tsb = i16::MAX;
+    let mut min_bsb = i16::MAX;
+    let mut y_max_extent = i16::MIN;
+    for (m, &(advance, tsb)) in metrics.iter().zip(vertical) {
+        if let Some(bbox) = m.bbox {
+            let height = bbox.y_max as i32 - bbox.y_min as i32;
+            let clamp = |v: i32| v.clamp(i16::MIN as i32, i16::MAX as i32) as i16;
+            min_tsb = min_tsb.min(tsb);
+            min_bsb = min_bsb.min(clamp(advance as i32 - tsb as i32 - height));
+            y_max_extent = y_max_extent.max(clamp(tsb as i32 + height));
+        }
+    }
+    if y_max_extent == i16::MIN {
+        (min_tsb, min_bsb, y_max_extent) = (0, 0, 0);
+    }
+
+    // 1.1 版：竖排的上下伸部为字身框中线到左右两边的距离
+    let half_em = (font.info.units_per_em / 2) as i16;
+    let mut w = Writer::new();
+    w.u32(0x00011000);
+    w.i16(half_em);
+    w.i16(-half_em);
+    w.i16(0);
+    w.u16(advance_height_max);
+    w.i16(min_tsb);
+    w.i16(min_bsb);
+    w.i16(y_max_extent);
+    // 竖排光标为水平方向
+    w.i16(0);
+    w.i16(1);
+    w.i16(0);
+    for _ in 0..4 {
+        w.i16(0);
+    }
+    w.i16(0);
+    w.u16(number_of_v_metrics);
+    w.into_bytes()
+}
+
+fn vorg(default_origin: i16, origins: &[i16]) -> Vec<u8> {
+    let records: Vec<(u16, i16)> = origins
+        .iter()
+        .enumerate()
+        .filter(|(_, &origin)| origin != default_origin)
+        .map(|(gid, &origin)| (gid as u16, origin))
+        .collect();
+    let mut w = Writer::new();
+    w.u16(1);
+    w.u16(0);
+    w.i16(default_origin);
+    w.u16(records.len() as u16);
+    for (gid, origin) in records {
+        w.u16(gid);
+        w.i16(origin);
+    }
+    w.into_bytes()
+}
+
+// 竖排度量表：vhea、vmtx，CFF 轮廓另外写入 VORG；glyphs 为含 .notdef 的字形，metrics 与之一一对应
+pub fn tables(
+    font: &FontData,
+    glyphs: &[GlyphData],
+    metrics: &[GlyphMetrics],
+    cff: bool,
+) -> Result<Vec<sfnt::Table>, String> {
+    let Some(vertical) = &font.vertical else {
+        return Ok(Vec::new());
+    };
+    let shift = glyphs.len() - font.glyphs.len();
+    let mut overrides: HashMap<usize, &VerticalMetric> = HashMap::new();
+    for metric in &vertical.metrics {
+        if metric.glyph >= font.glyphs.len() {
+            return Err(format!("vertical metrics refer to missing glyph {}", metric.glyph));
+        }
+        overrides.insert(metric.glyph + shift, metric);
+    }
+
+    let default_origin = fword(vertical.default_origin(font));
+    let default_advance = vertical.default_advance(font);
+    let mut origins = Vec::with_capacity(glyphs.len());
+    let mut vmtx = Vec::with_capacity(glyphs.len());
+    for (gid, m) in metrics.iter().enumerate() {
+        let metric = overrides.get(&gid);
+        let origin = metric.and_then(|m| m.origin).map_or(default_origin, fword);
+        let advance = metric.and_then(|m| m.advance).unwrap_or(default_advance);
+        let tsb =
+            m.bbox.map_or(0, |bbox| (origin as i32 - bbox.y_max as i32).clamp(i16::MIN as i32, i16::MAX as i32) as i16);
+        origins.push(origin);
+        vmtx.push((crate::advance_width(advance), tsb));
+    }
+
+    let (vmtx_data, number_of_v_metrics) = hmtx::build_raw(&vmtx);
+    let mut tables = vec![
+        sfnt::Table::new(b"vhea", vhea(font, metrics, &vmtx, number_of_v_metrics)),
+        sfnt::Table::new(b"vmtx", vmtx_data),
+    ];
+    if cff {
+        tables.push(sfnt::Table::new(b"VORG", vorg(default_origin, &origins)));
+    }
+    Ok(tables)
+}
@@ -0,0 +1,275 @@
+use std::io::{Read, Write};
+
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+
+use crate::binary::{checksum, Reader, Writer};
+use crate::sfnt::{Sfnt, Table};
+use crate::xml::escape;
+use crate::FontInfo;
+
+const SIGNATURE: u32 = 0x774F_4646;
+const HEADER_SIZE: usize = 44;
+const DIRECTORY_ENTRY_SIZE: usize = 20;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct WoffVendor {
+    pub name: String,
+    pub url: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct WoffCredit {
+    pub name: String,
+    pub url: String,
+    pub role: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct WoffLicense {
+    pub url: String,
+    pub text: String,
+}
+
+// WOFF 扩展元数据，写入时生成 XML 并用 zlib 压缩
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct WoffMetadata {
+    pub unique_id: String,
+    pub vendor: Option<WoffVendor>,
+    pub credits: Vec<WoffCredit>,
+    pub description: String,
+    pub license: Option<WoffLicense>,
+    pub copyright: String,
+    pub trademark: String,
+}
+
+fn text_element(xml: &mut String, name: &str, text: &str) {
+    if !text.is_empty() {
+        xml.push_str(&format!("\t<{name}>\n\t\t<text>{}</text>\n\t</{name}>\n", escape(text)));
+    }
+}
+
+impl WoffMetadata {
+    // 由字体信息生成默认的元数据
+    pub fn from_info(info: &FontInfo) -> Self {
+        let mut credits = Vec::new();
+        if !info.designer.is_empty() {
+            credits.push(WoffCredit { name: info.designer.clone(), url: info.designer_url.clone(), role: String::from("Designer") });
+        }
+        WoffMetadata {
+            unique_id: format!("{};{}", info.post_script_name(), info.version),
+            vendor: (!info.manufacturer.is_empty())
+                .then(|| WoffVendor { name: info.manufacturer.clone(), url: info.manufacturer_url.clone() }),
+            credits,
+            description: info.description.clone(),
+            license: (!info.license.is_empty() || !info.license_url.is_empty())
+                .then(|| WoffLicense { url: info.license_url.clone(), text: info.license.clone() }),
+            copyright: info.copyright.clone(),
+            trademark: info.trademark.clone(),
+        }
+    }
+
+    pub fn to_xml(&self) -> String {
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<metadata version=\"1.0\">\n");
+        if !self.unique_id.is_empty() {
+            xml.push_str(&format!("\t<uniqueid id=\"{}\"/>\n", escape(&self.unique_id)));
+        }
+        if let Some(vendor) = &self.vendor {
+            xml.push_str(&format!("\t<vendor name=\"{}\"", escape(&vendor.name)));
+            if !vendor.url.is_empty() {
+                xml.push_str(&format!(" url=\"{}\"", escape(&vendor.url)));
+            }
+            xml.push_str("/>\n");
+        }
+        if !self.credits.is_empty() {
+            xml.push_str("\t<credits>\n");
+            for credit in &self.credits {
+                xml.push_str(&format!("\t\t<credit name=\"{}\"", escape(&credit.name)));
+                if !credit.url.is_empty() {
+                    xml.push_str(&format!(" url=\"{}\"", escape(&credit.url)));
+                }
+                if !credit.role.is_empty() {
+                    xml.push_str(&format!(" role=\"{}\"", escape(&credit.role)));
+                }
+                xml.push_str("/>\n");
+            }
+            xml.push_str("\t</credits>\n");
+        }
+        text_element(&mut xml, "description", &self.description);
+        if let Some(license) = &self.license {
+            xml.push_str("\t<license");
+            if !license.url.is_empty() {
+                xml.push_str(&format!(" url=\"{}\"", escape(&license.url)));
+            }
+            if license.text.is_empty() {
+                xml.push_str("/>\n");
+            } else {
+                xml.push_str(&format!(">\n\t\t<text>{}</text>\n\t</license>\n", escape(&license.text)));
+            }
+        }
+        text_element(&mut xml, "copyright", &self.copyright);
+        text_element(&mut xml, "trademark", &self.trademark);
+        xml.push_str("</metadata>\n");
+        xml
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct WoffOptions {
+    pub metadata: Option<WoffMetadata>,
+    pub private_data: Option<Vec<u8>>,
+}
+
+// 解包后的 WOFF：sfnt 字体数据以及可选的元数据 XML、私有数据
+#[derive(Debug, Clone)]
+pub struct WoffFile {
+    pub sfnt: Vec<u8>,
+    pub metadata: Option<String>,
+    pub private_data: Option<Vec<u8>>,
+}
+
+pub fn is_woff(data: &[u8]) -> bool {
+    data.len() >= 4 && u32::from_be_bytes([data[0], data[1], data[2], data[3]]) == SIGNATURE
+}
+
+fn compress(data: &[u8]) -> Result<Vec<u8>, String> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::best());
+    encoder.write_all(data).map_err(|err| format!("zlib compression failed: {err}"))?;
+    encoder.finish().map_err(|err| format!("zlib compression failed: {err}"))
+}
+
+// 解压时最多读取 expected_len + 1 字节，声明的原始长度与实际不符时报错，避免压缩炸弹耗尽内存
+fn decompress(data: &[u8], expected_len: usize) -> Result<Vec<u8>, String> {
+    let mut out = Vec::new();
+    ZlibDecoder::new(data)
+        .take(expected_len as u64 + 1)
+        .read_to_end(&mut out)
+        .map_err(|err| format!("zlib decompression failed: {err}"))?;
+    if out.len() != expected_len {
+        return Err(format!("decompressed length does not match original length {expected_len}"));
+    }
+    Ok(out)
+}
+
+// 将 TTF/OTF 字节封装为 WOFF 1.0，每个表单独压缩，压缩后没有变小的表保持原样
+pub fn encode(data: &[u8], options: &WoffOptions) -> Result<Vec<u8>, String> {
+    let mut font = Sfnt::parse(data)?;
+    font.tables.sort_by_key(|t| t.tag);
+    let num_tables = font.tables.len();
+
+    let mut entries = Vec::with_capacity(num_tables);
+    let mut total_sfnt_size = 12 + 16 * num_tables;
+    for table in &font.tables {
+        let compressed = compress(&table.data)?;
+        let stored = if compressed.len() < table.data.len() { compressed } else { table.data.clone() };
+        total_sfnt_size += table.data.len().next_multiple_of(4);
+        entries.push((table, stored));
+    }
+
+    let data_start = HEADER_SIZE + DIRECTORY_ENTRY_SIZE * num_tables;
+    let mut directory = Writer::new();
+    let mut body = Writer::new();
+    for (table, stored) in &entries {
+        directory.tag(&table.tag);
+        directory.u32((data_start + body.len()) as u32);
+        directory.u32(stored.len() as u32);
+        directory.u32(table.data.len() as u32);
+        directory.u32(checksum(&table.data));
+        body.bytes(stored);
+        body.align(4);
+    }
+
+    // 元数据与私有数据块依次放在表数据之后，均 4 字节对齐
+    let mut meta = (0, 0, 0);
+    if let Some(metadata) = &options.metadata {
+        let xml = metadata.to_xml();
+        let compressed = compress(xml.as_bytes())?;
+        meta = (data_start + body.len(), compressed.len(), xml.len());
+        body.bytes(&compressed);
+    }
+    let mut private = (0, 0);
+    if let Some(private_data) = options.private_data.as_ref().filter(|d| !d.is_empty()) {
+        body.align(4);
+        private = (data_start + body.len(), private_data.len());
+        body.bytes(private_data);
+    }
+    let length = HEADER_SIZE + directory.len() + body.len();
+
+    let mut w = Writer::new();
+    w.u32(SIGNATURE);
+    w.u32(font.version);
+    w.u32(length as u32);
+    w.u16(num_tables as u16);
+    w.u16(0);
+    w.u32(total_sfnt_size as u32);
+    w.u16(1);
+    w.u16(0);
+    w.u32(meta.0 as u32);
+    w.u32(meta.1 as u32);
+    w.u32(meta.2 as u32);
+    w.u32(private.0 as u32);
+    w.u32(private.1 as u32);
+    w.bytes(directory.as_bytes());
+    w.bytes(body.as_bytes());
+    Ok(w.into_bytes())
+}
+
+// 解包 WOFF 1.0，还原为 sfnt 字体数据
+pub fn decode(data: &[u8]) -> Result<WoffFile, String> {
+    let mut r = Reader::new(data);
+    if r.u32()? != SIGNATURE {
+        return Err(String::from("not a WOFF file"));
+    }
+    let version = r.u32()?;
+    let length = r.u32()? as usize;
+    if length != data.len() {
+        return Err(format!("invalid WOFF length: header says {length}, file has {}", data.len()));
+    }
+    let num_tables = r.u16()?;
+    r.skip(2 + 4 + 4)?;
+    let meta_offset = r.u32()? as usize;
+    let meta_length = r.u32()? as usize;
+    let meta_orig_length = r.u32()? as usize;
+    let private_offset = r.u32()? as usize;
+    let private_length = r.u32()? as usize;
+
+    let mut tables = Vec::with_capacity(num_tables as usize);
+    for _ in 0..num_tables {
+        let tag = r.tag()?;
+        let offset = r.u32()? as usize;
+        let comp_length = r.u32()? as usize;
+        let orig_length = r.u32()? as usize;
+        let _checksum = r.u32()?;
+        let name = String::from_utf8_lossy(&tag).into_owned();
+        let stored = Reader::at(data, offset).bytes(comp_length).map_err(|_| format!("table '{name}' is out of bounds"))?;
+        let table_data = if comp_length < orig_length {
+            decompress(stored, orig_length).map_err(|err| format!("table '{name}': {err}"))?
+        } else if comp_length == orig_length {
+            stored.to_vec()
+        } else {
+            return Err(format!("table '{name}' has invalid compressed length"));
+        };
+        if table_data.len() != orig_length {
+            return Err(format!("table '{name}' has invalid length after decompression"));
+        }
+        tables.push(Table { tag, data: table_data });
+    }
+
+    let metadata = if meta_length > 0 {
+        let stored = Reader::at(data, meta_offset).bytes(meta_length)?;
+        let xml = decompress(stored, meta_orig_length).map_err(|err| format!("metadata: {err}"))?;
+        Some(String::from_utf8(xml).map_err(|err| format!("metadata is not valid UTF-8: {err}"))?)
+    } else {
+        None
+    };
+    let private_data =
+        if private_length > 0 { Some(Reader::at(data, private_offset).bytes(private_length)?.to_vec()) } else { None };
+
+    Ok(WoffFile { sfnt: Sfnt { version, tables }.into_bytes(), metadata, private_data })
+}
@@ -0,0 +1,351 @@
+use brotli::enc::backward_references::BrotliEncoderMode;
+use brotli::enc::BrotliEncoderParams;
+
+use crate::binary::{Reader, Writer};
+use crate::sfnt::{self, Sfnt, Table};
+
+const SIGNATURE: u32 = 0x774F_4632;
+
+// head.flags 第 11 位：字体经过无损的优化变换或压缩
+const HEAD_FLAG_LOSSLESS: u16 = 1 << 11;
+
+// WOFF2 表目录中的已知标签，序号写入 flags 低 6 位
+const KNOWN_TAGS: [&[u8; 4]; 63] = [
+    b"cmap", b"head", b"hhea", b"hmtx", b"maxp", b"name", b"OS/2", b"post", b"cvt ", b"fpgm", b"glyf", b"loca", b"prep",
+    b"CFF ", b"VORG", b"EBDT", b"EBLC", b"gasp", b"hdmx", b"kern", b"LTSH", b"PCLT", b"VDMX", b"vhea", b"vmtx", b"BASE",
+    b"GDEF", b"GPOS", b"GSUB", b"EBSC", b"JSTF", b"MATH", b"CBDT", b"CBLC", b"COLR", b"CPAL", b"SVG ", b"sbix", b"acnt",
+    b"avar", b"bdat", b"bloc", b"bsln", b"cvar", b"fdsc", b"feat", b"fmtx", b"fvar", b"gvar", b"hsty", b"just", b"lcar",
+    b"mort", b"morx", b"opbd", b"prop", b"trak", b"Zapf", b"Silf", b"Glat", b"Gloc", b"Feat", b"Sill",
+];
+
+// 复合字形的组件标志
+const ARG_1_AND_2_ARE_WORDS: u16 = 0x0001;
+const WE_HAVE_A_SCALE: u16 = 0x0008;
+const MORE_COMPONENTS: u16 = 0x0020;
+const WE_HAVE_AN_X_AND_Y_SCALE: u16 = 0x0040;
+const WE_HAVE_A_TWO_BY_TWO: u16 = 0x0080;
+const WE_HAVE_INSTRUCTIONS: u16 = 0x0100;
+
+// UIntBase128：每字节 7 位，高位为续接标志
+fn write_base128(w: &mut Writer, v: u32) {
+    let mut groups = vec![(v & 0x7F) as u8];
+    let mut rest = v >> 7;
+    while rest > 0 {
+        groups.push(((rest & 0x7F) as u8) | 0x80);
+        rest >>= 7;
+    }
+    for b in groups.into_iter().rev() {
+        w.u8(b);
+    }
+}
+
+// 255UInt16 变长编码
+fn write_255_u16(out: &mut Vec<u8>, v: u16) {
+    match v {
+        0..=252 => out.push(v as u8),
+        253..=505 => out.extend_from_slice(&[255, (v - 253) as u8]),
+        506..=761 => out.extend_from_slice(&[254, (v - 506) as u8]),
+        _ => {
+            out.push(253);
+            out.extend_from_slice(&v.to_be_bytes());
+        }
+    }
+}
+
+// 点坐标增量的 triplet 编码：flag 写入 flagStream，其余字节写入 glyphStream
+fn write_triplet(flags: &mut Vec<u8>, glyphs: &mut Vec<u8>, on_curve: bool, x: i32, y: i32) {
+    let (abs_x, abs_y) = (x.unsigned_abs(), y.unsigned_abs());
+    let on_curve_bit = if on_curve { 0 } else { 128 };
+    let x_sign = if x < 0 { 0 } else { 1 };
+    let y_sign = if y < 0 { 0 } else { 1 };
+    let xy_sign = x_sign + 2 * y_sign;
+    if x == 0 && abs_y < 1280 {
+        flags.push((on_curve_bit + ((abs_y & 0xF00) >> 7) + y_sign) as u8);
+        glyphs.push((abs_y & 0xFF) as u8);
+    } else if y == 0 && abs_x < 1280 {
+        flags.push((on_curve_bit + 10 + ((abs_x & 0xF00) >> 7) + x_sign) as u8);
+        glyphs.push((abs_x & 0xFF) as u8);
+    } else if abs_x < 65 && abs_y < 65 {
+        flags.push((on_curve_bit + 20 + ((abs_x - 1) & 0x30) + (((abs_y - 1) & 0x30) >> 2) + xy_sign) as u8);
+        glyphs.push(((((abs_x - 1) & 0xF) << 4) | ((abs_y - 1) & 0xF)) as u8);
+    } else if abs_x < 769 && abs_y < 769 {
+        flags.push((on_curve_bit + 84 + 12 * (((abs_x - 1) & 0x300) >> 8) + (((abs_y - 1) & 0x300) >> 6) + xy_sign) as u8);
+        glyphs.push(((abs_x - 1) & 0xFF) as u8);
+        glyphs.push(((abs_y - 1) & 0xFF) as u8);
+    } else if abs_x < 4096 && abs_y < 4096 {
+        flags.push((on_curve_bit + 120 + xy_sign) as u8);
+        glyphs.push((abs_x >> 4) as u8);
+        glyphs.push((((abs_x & 0xF) << 4) | (abs_y >> 8)) as u8);
+        glyphs.push((abs_y & 0xFF) as u8);
+    } else {
+        flags.push((on_curve_bit + 124 + xy_sign) as u8);
+        glyphs.extend_from_slice(&(abs_x as u16).to_be_bytes());
+        glyphs.extend_from_slice(&(abs_y as u16).to_be_bytes());
+    }
+}
+
+#[derive(Default)]
+struct GlyfStreams {
+    n_contours: Writer,
+    n_points: Vec<u8>,
+    flags: Vec<u8>,
+    glyphs: Vec<u8>,
+    composites: Vec<u8>,
+    bbox_bitmap: Vec<u8>,
+    bboxes: Writer,
+    instructions: Vec<u8>,
+}
+
+impl GlyfStreams {
+    fn explicit_bbox(&mut self, gid: usize, bbox: &[u8]) {
+        self.bbox_bitmap[gid >> 3] |= 0x80 >> (gid & 7);
+        self.bboxes.bytes(bbox);
+    }
+
+    fn simple(&mut self, gid: usize, data: &[u8], num_contours: u16) -> Result<(), String> {
+        let mut r = Reader::new(data);
+        r.skip(2)?;
+        let bbox = r.bytes(8)?;
+        let mut end_points = Vec::with_capacity(num_contours as usize);
+        for _ in 0..num_contours {
+            end_points.push(r.u16()? as usize);
+        }
+        let num_points = end_points.last().map_or(0, |&e| e + 1);
+        let instruction_len = r.u16()?;
+        let instructions = r.bytes(instruction_len as usize)?;
+
+        let mut flags = Vec::with_capacity(num_points);
+        while flags.len() < num_points {
+            let flag = r.u8()?;
+            flags.push(flag);
+            if flag & 0x08 != 0 {
+                for _ in 0..r.u8()? {
+                    flags.push(flag);
+                }
+            }
+        }
+        flags.truncate(num_points);
+        let read_coords = |r: &mut Reader, short: u8, same: u8| -> Result<Vec<i32>, String> {
+            let mut values = Vec::with_capacity(num_points);
+            for &flag in &flags {
+                let v = if flag & short != 0 {
+                    let v = r.u8()? as i32;
+                    if flag & same != 0 { v } else { -v }
+                } else if flag & same != 0 {
+                    0
+                } else {
+                    r.i16()? as i32
+                };
+                values.push(v);
+            }
+            Ok(values)
+        };
+        let dx = read_coords(&mut r, 0x02, 0x10)?;
+        let dy = read_coords(&mut r, 0x04, 0x20)?;
+
+        let mut previous = 0;
+        for &end in &end_points {
+            if end < previous || end >= num_points.max(1) {
+                return Err(format!("glyph {gid} has invalid contour end points"));
+            }
+            write_255_u16(&mut self.n_points, (end + 1 - previous) as u16);
+            previous = end + 1;
+        }
+        let (mut x, mut y) = (0i32, 0i32);
+        let (mut x_min, mut y_min, mut x_max, mut y_max) = (i32::MAX, i32::MAX, i32::MIN, i32::MIN);
+        for i in 0..num_points {
+            write_triplet(&mut self.flags, &mut self.glyphs, flags[i] & 0x01 != 0, dx[i], dy[i]);
+            x += dx[i];
+            y += dy[i];
+            x_min = x_min.min(x);
+            y_min = y_min.min(y);
+            x_max = x_max.max(x);
+            y_max = y_max.max(y);
+        }
+        write_255_u16(&mut self.glyphs, instruction_len);
+        self.instructions.extend_from_slice(instructions);
+
+        // 包围盒与点坐标计算结果一致时省略
+        let mut computed = Writer::new();
+        for v in [x_min, y_min, x_max, y_max] {
+            computed.i16(v as i16);
+        }
+        if num_points == 0 || computed.as_bytes() != bbox {
+            self.explicit_bbox(gid, bbox);
+        }
+        Ok(())
+    }
+
+    fn composite(&mut self, gid: usize, data: &[u8]) -> Result<(), String> {
+        let mut r = Reader::at(data, 10);
+        let mut has_instructions = false;
+        loop {
+            let flags = r.u16()?;
+            r.skip(2)?;
+            r.skip(if flags & ARG_1_AND_2_ARE_WORDS != 0 { 4 } else { 2 })?;
+            if flags & WE_HAVE_A_SCALE != 0 {
+                r.skip(2)?;
+            } else if flags & WE_HAVE_AN_X_AND_Y_SCALE != 0 {
+                r.skip(4)?;
+            } else if flags & WE_HAVE_A_TWO_BY_TWO != 0 {
+                r.skip(8)?;
+            }
+            has_instructions |= flags & WE_HAVE_INSTRUCTIONS != 0;
+            if flags & MORE_COMPONENTS == 0 {
+                break;
+            }
+        }
+        self.composites.extend_from_slice(&data[10..r.pos()]);
+        self.explicit_bbox(gid, &data[2..10]);
+        if has_instructions {
+            let len = r.u16()?;
+            write_255_u16(&mut self.glyphs, len);
+            self.instructions.extend_from_slice(r.bytes(len as usize)?);
+        }
+        Ok(())
+    }
+}
+
+// glyf/loca 变换：把字形拆分为轮廓数、点数、标志、坐标、复合、包围盒、指令等多个数据流
+fn transform_glyf(glyf: &[u8], loca: &[u8], num_glyphs: u16, index_format: i16) -> Result<Vec<u8>, String> {
+    let mut r = Reader::new(loca);
+    let mut offsets = Vec::with_capacity(num_glyphs as usize + 1);
+    for _ in 0..=num_glyphs {
+        let offset = if index_format == 0 { r.u16()? as usize * 2 } else { r.u32()? as usize };
+        offsets.push(offset);
+    }
+
+    let mut streams = GlyfStreams { bbox_bitmap: vec![0; (num_glyphs as usize).div_ceil(32) * 4], ..Default::default() };
+    for gid in 0..num_glyphs as usize {
+        let (start, end) = (offsets[gid], offsets[gid + 1]);
+        if start > end || end > glyf.len() {
+            return Err(format!("glyph {gid} is out of bounds"));
+        }
+        let data = &glyf[start..end];
+        if data.is_empty() {
+            streams.n_contours.i16(0);
+            continue;
+        }
+        let num_contours = Reader::new(data).i16()?;
+        streams.n_contours.i16(num_contours);
+        if num_contours >= 0 {
+            streams.simple(gid, data, num_contours as u16)?;
+        } else {
+            streams.composite(gid, data)?;
+        }
+    }
+
+    let bbox_len = streams.bbox_bitmap.len() + streams.bboxes.len();
+    let mut w = Writer::new();
+    w.u16(0);
+    w.u16(0);
+    w.u16(num_glyphs);
+    w.u16(index_format as u16);
+    for len in [
+        streams.n_contours.len(),
+        streams.n_points.len(),
+        streams.flags.len(),
+        streams.glyphs.len(),
+        streams.composites.len(),
+        bbox_len,
+        streams.instructions.len(),
+    ] {
+        w.u32(len as u32);
+    }
+    w.bytes(streams.n_contours.as_bytes());
+    w.bytes(&streams.n_points);
+    w.bytes(&streams.flags);
+    w.bytes(&streams.glyphs);
+    w.bytes(&streams.composites);
+    w.bytes(&streams.bbox_bitmap);
+    w.bytes(streams.bboxes.as_bytes());
+    w.bytes(&streams.instructions);
+    Ok(w.into_bytes())
+}
+
+fn brotli_compress(data: &[u8]) -> Result<Vec<u8>, String> {
+    let params = BrotliEncoderParams {
+        quality: 11,
+        lgwin: 22,
+        mode: BrotliEncoderMode::BROTLI_MODE_FONT,
+        size_hint: data.len(),
+        ..Default::default()
+    };
+    let mut out = Vec::new();
+    brotli::BrotliCompress(&mut &data[..], &mut out, &params).map_err(|err| format!("brotli compression failed: {err}"))?;
+    Ok(out)
+}
+
+// 将 TTF/OTF 字节编码为 WOFF2
+pub fn encode(data: &[u8]) -> Result<Vec<u8>, String> {
+    let mut font = Sfnt::parse(data)?;
+    if let Some(head) = font.tables.iter_mut().find(|t| &t.tag == b"head") {
+        if head.data.len() >= 54 {
+            let flags = u16::from_be_bytes([head.data[16], head.data[17]]) | HEAD_FLAG_LOSSLESS;
+            head.data[16..18].copy_from_slice(&flags.to_be_bytes());
+        }
+    }
+    // 重新组装以更新 checkSumAdjustment，并得到解码后字体的大小
+    let sfnt_data = font.into_bytes();
+    let font = Sfnt::parse(&sfnt_data)?;
+
+    let transform = font.version != sfnt::CFF && font.table(b"glyf").is_some() && font.table(b"loca").is_some();
+    let mut tables: Vec<&Table> = font.tables.iter().collect();
+    tables.sort_by_key(|t| t.tag);
+    // 变换后的 loca 必须紧跟在 glyf 之后
+    if transform {
+        let loca = tables.iter().position(|t| &t.tag == b"loca").unwrap_or(0);
+        let loca = tables.remove(loca);
+        let glyf = tables.iter().position(|t| &t.tag == b"glyf").unwrap_or(0);
+        tables.insert(glyf + 1, loca);
+    }
+
+    let mut directory = Writer::new();
+    let mut stream: Vec<u8> = Vec::new();
+    for table in &tables {
+        let known = KNOWN_TAGS.iter().position(|&tag| tag == &table.tag);
+        directory.u8(known.unwrap_or(63) as u8);
+        if known.is_none() {
+            directory.tag(&table.tag);
+        }
+        write_base128(&mut directory, table.data.len() as u32);
+        if transform && &table.tag == b"glyf" {
+            let head = font.table(b"head").ok_or("missing head table")?;
+            let maxp = font.table(b"maxp").ok_or("missing maxp table")?;
+            let num_glyphs = Reader::at(maxp, 4).u16()?;
+            let index_format = Reader::at(head, 50).i16()?;
+            let loca = font.table(b"loca").unwrap_or_default();
+            let transformed = transform_glyf(&table.data, loca, num_glyphs, index_format)?;
+            write_base128(&mut directory, transformed.len() as u32);
+            stream.extend_from_slice(&transformed);
+        } else if transform && &table.tag == b"loca" {
+            // loca 由解码器根据 glyf 重建
+            write_base128(&mut directory, 0);
+        } else {
+            stream.extend_from_slice(&table.data);
+        }
+    }
+    let compressed = brotli_compress(&stream)?;
+
+    let mut w = Writer::new();
+    w.u32(SIGNATURE);
+    w.u32(font.version);
+    // length 在最后回填
+    w.u32(0);
+    w.u16(tables.len() as u16);
+    w.u16(0);
+    w.u32(sfnt_data.len() as u32);
+    w.u32(compressed.len() as u32);
+    w.u16(1);
+    w.u16(0);
+    // metaOffset, metaLength, metaOrigLength, privOffset, privLength
+    for _ in 0..5 {
+        w.u32(0);
+    }
+    w.bytes(directory.as_bytes());
+    w.bytes(&compressed);
+    w.align(4);
+    let length = w.len() as u32;
+    w.set_u32(8, length);
+    Ok(w.into_bytes())
+}
@@ -0,0 +1,20 @@
+// 写出 XML（glif、plist、designspace、WOFF 元数据与 SVG 表）时共用的转义与数值格式
+
+pub(crate) fn escape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+pub(crate) fn number(v: f64) -> String {
+    // 避免写出 -0
+    format!("{}", v + 0.0)
+}
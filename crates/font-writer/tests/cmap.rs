@@ -0,0 +1,57 @@
+use std::collections::BTreeMap;
+
+use font_writer::sfnt::Sfnt;
+use font_writer::tables::cmap;
+use font_writer::{build_font, ExportOptions, FontData, GlyphData};
+
+// 码位与字形索引都不连续，每个字符单独成段，format 4 超出 64KB
+#[test]
+fn sparse_bmp_falls_back_to_format12() {
+    let glyphs: Vec<GlyphData> = (0..9000u32)
+        .map(|i| GlyphData {
+            name: format!("g{i}"),
+            unicode: 0x4E00 + 2 * i,
+            advance_width: 1000.0,
+            ..Default::default()
+        })
+        .collect();
+    let expected: BTreeMap<u32, u16> = glyphs.iter().enumerate().map(|(i, g)| (g.unicode, i as u16 + 1)).collect();
+    assert!(cmap::format4(&expected).is_none());
+
+    let font = build_font(&FontData { glyphs, ..Default::default() }, &ExportOptions::default()).unwrap();
+    let sfnt = Sfnt::parse(&font).unwrap();
+    let data = sfnt.table(b"cmap").unwrap();
+    assert_eq!(cmap::parse(data).unwrap(), expected);
+    // (3,1) 与 (3,10) 两条记录都存在
+    let count = u16::from_be_bytes([data[2], data[3]]) as usize;
+    let records: Vec<(u16, u16)> = (0..count)
+        .map(|i| {
+            let record = &data[4 + i * 8..];
+            (u16::from_be_bytes([record[0], record[1]]), u16::from_be_bytes([record[2], record[3]]))
+        })
+        .collect();
+    assert!(records.contains(&(3, 1)) && records.contains(&(3, 10)));
+}
+
+// 只含一个 (3,10) format 12 子表的 cmap
+fn format12_cmap(groups: &[(u32, u32, u32)]) -> Vec<u8> {
+    let mut data = Vec::new();
+    data.extend_from_slice(&[0, 0, 0, 1, 0, 3, 0, 10, 0, 0, 0, 12]);
+    data.extend_from_slice(&[0, 12, 0, 0]);
+    data.extend_from_slice(&(16 + 12 * groups.len() as u32).to_be_bytes());
+    data.extend_from_slice(&[0, 0, 0, 0]);
+    data.extend_from_slice(&(groups.len() as u32).to_be_bytes());
+    for &(start, end, start_gid) in groups {
+        for value in [start, end, start_gid] {
+            data.extend_from_slice(&value.to_be_bytes());
+        }
+    }
+    data
+}
+
+#[test]
+fn format12_skips_overflowing_glyph_ids() {
+    let data = format12_cmap(&[(0x41, 0x45, u32::MAX - 1), (0x61, 0x63, 0xFFFE), (0x30, 0x31, 5)]);
+    let expected = BTreeMap::from([(0x30, 5), (0x31, 6), (0x61, 0xFFFE), (0x62, 0xFFFF)]);
+    assert_eq!(cmap::parse(&data).unwrap(), expected);
+}
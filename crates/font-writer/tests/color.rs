@@ -0,0 +1,42 @@
+use font_writer::color::{ColorFontData, ColorGlyph, ColorLayer, Fill};
+use font_writer::outline::{Point, Segment};
+use font_writer::{build_color_font, ExportOptions, FontData, GlyphData};
+
+fn layer(color: &str) -> ColorLayer {
+    let p = |x, y| Point { x, y };
+    let contour = vec![
+        Segment::Line { start: p(0.0, 0.0), end: p(0.0, 500.0) },
+        Segment::Line { start: p(0.0, 500.0), end: p(500.0, 0.0) },
+        Segment::Line { start: p(500.0, 0.0), end: p(0.0, 0.0) },
+    ];
+    ColorLayer { contours: vec![contour], fill: Fill::Solid { color: color.to_string() }, transform: None }
+}
+
+fn font(color_glyphs: Vec<ColorGlyph>) -> ColorFontData {
+    let glyphs = vec![
+        GlyphData { name: String::from("a"), unicode: 0x61, advance_width: 500.0, ..Default::default() },
+        GlyphData { name: String::from("b"), unicode: 0x62, advance_width: 500.0, ..Default::default() },
+    ];
+    ColorFontData { font: FontData { glyphs, ..Default::default() }, color_glyphs, ..Default::default() }
+}
+
+#[test]
+fn rejects_duplicate_and_empty_color_glyphs() {
+    let options = ExportOptions::default();
+    let valid = font(vec![
+        ColorGlyph { glyph: 0, layers: vec![layer("#FF0000"), layer("#0000FF80")] },
+        ColorGlyph { glyph: 1, layers: vec![layer("#00FF00")] },
+    ]);
+    assert!(build_color_font(&valid, &options).is_ok());
+
+    let duplicate = font(vec![
+        ColorGlyph { glyph: 0, layers: vec![layer("#FF0000")] },
+        ColorGlyph { glyph: 0, layers: vec![layer("#00FF00")] },
+    ]);
+    let err = build_color_font(&duplicate, &options).unwrap_err();
+    assert!(err.contains("more than one color definition"), "{err}");
+
+    let empty = font(vec![ColorGlyph { glyph: 1, layers: Vec::new() }]);
+    let err = build_color_font(&empty, &options).unwrap_err();
+    assert!(err.contains("has no layers"), "{err}");
+}
@@ -0,0 +1,30 @@
+use font_writer::outline::Contour;
+use font_writer::tables::glyf::SimpleGlyph;
+use serde_json::json;
+
+fn glyph() -> SimpleGlyph {
+    let contour: Contour = serde_json::from_value(json!([
+        { "type": "LINE", "start": { "x": 0, "y": 0 }, "end": { "x": 100, "y": 0 } },
+        { "type": "LINE", "start": { "x": 100, "y": 0 }, "end": { "x": 100, "y": 100 } },
+        { "type": "LINE", "start": { "x": 100, "y": 100 }, "end": { "x": 0, "y": 0 } }
+    ]))
+    .unwrap();
+    SimpleGlyph::from_contours(&[contour])
+}
+
+// 头部 10 字节加一个 endPtsOfContours，之后是 instructionLength
+fn instruction_length(data: &[u8]) -> usize {
+    u16::from_be_bytes([data[12], data[13]]) as usize
+}
+
+#[test]
+fn oversized_instructions_are_dropped() {
+    let glyph = glyph();
+    let hinted = glyph.encode(&[0xB0, 0x00]);
+    assert_eq!(instruction_length(&hinted), 2);
+
+    let unhinted = glyph.encode(&[]);
+    let oversized = glyph.encode(&vec![0xB0; u16::MAX as usize + 1]);
+    assert_eq!(instruction_length(&oversized), 0);
+    assert_eq!(oversized, unhinted);
+}
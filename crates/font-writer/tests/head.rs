@@ -0,0 +1,30 @@
+use font_writer::sfnt::Sfnt;
+use font_writer::{build_font, ExportOptions, FontData, FontInfo};
+
+fn long_date_times(font: &[u8]) -> (i64, i64) {
+    let sfnt = Sfnt::parse(font).unwrap();
+    let head = sfnt.table(b"head").unwrap();
+    (i64::from_be_bytes(head[20..28].try_into().unwrap()), i64::from_be_bytes(head[28..36].try_into().unwrap()))
+}
+
+// 1904-01-01 与 1970-01-01 之间的秒数
+const MAC_EPOCH_OFFSET: i64 = 2_082_844_800;
+
+#[test]
+fn timestamps_are_reproducible() {
+    let info = FontInfo {
+        created_timestamp: Some(1_600_000_000),
+        modified_timestamp: Some(1_700_000_000),
+        ..Default::default()
+    };
+    let font = FontData { info, ..Default::default() };
+    let built = build_font(&font, &ExportOptions::default()).unwrap();
+    assert_eq!(long_date_times(&built), (1_600_000_000 + MAC_EPOCH_OFFSET, 1_700_000_000 + MAC_EPOCH_OFFSET));
+
+    // 未指定时间戳时使用 SOURCE_DATE_EPOCH，两次构建结果一致
+    std::env::set_var("SOURCE_DATE_EPOCH", "1500000000");
+    let font = FontData::default();
+    let first = build_font(&font, &ExportOptions::default()).unwrap();
+    assert_eq!(long_date_times(&first), (1_500_000_000 + MAC_EPOCH_OFFSET, 1_500_000_000 + MAC_EPOCH_OFFSET));
+    assert_eq!(build_font(&font, &ExportOptions::default()).unwrap(), first);
+}
@@ -0,0 +1,90 @@
+use std::collections::{BTreeMap, HashSet};
+
+use font_writer::kerning::{self, Kerning, KerningSide};
+use font_writer::sfnt::{self, Sfnt, Table};
+use font_writer::tables::layout::gpos::{Gpos, Positioning, ValueRecord};
+use font_writer::tables::layout::{Feature, LangSys, Lookup, Script};
+use font_writer::{build_font, ExportOptions, FontData, GlyphData};
+
+fn font() -> FontData {
+    let glyphs = ['a', 'b', 'c', 'd']
+        .iter()
+        .map(|&c| GlyphData { name: c.to_string(), unicode: c as u32, advance_width: 500.0, ..Default::default() })
+        .collect();
+    FontData { glyphs, ..Default::default() }
+}
+
+fn kern(x_advance: i16) -> (ValueRecord, ValueRecord) {
+    (ValueRecord { x_advance, ..Default::default() }, ValueRecord::default())
+}
+
+fn lookup(subtables: Vec<Positioning>) -> Lookup<Positioning> {
+    Lookup { flag: 0, mark_filtering_set: None, subtables }
+}
+
+// 每个字形对实际的字偶距：字形对优先于分组
+fn effective(kerning: &Kerning) -> BTreeMap<(usize, usize), i16> {
+    let glyphs = |side: &KerningSide, classes: &[font_writer::kerning::KerningClass]| match *side {
+        KerningSide::Glyph { glyph } => vec![glyph],
+        KerningSide::Class { class } => classes[class].glyphs.clone(),
+    };
+    let mut pairs = BTreeMap::new();
+    for glyph_pairs in [false, true] {
+        for pair in kerning.pairs.iter().filter(|p| matches!(p.left, KerningSide::Glyph { .. }) == glyph_pairs) {
+            for left in glyphs(&pair.left, &kerning.left_classes) {
+                for right in glyphs(&pair.right, &kerning.right_classes) {
+                    pairs.insert((left, right), pair.value);
+                }
+            }
+        }
+    }
+    pairs
+}
+
+// .notdef 之后依次为 a b c d（字形 ID 1~4）
+#[test]
+fn import_splits_overlapping_classes_and_saturates() {
+    let font = font();
+    let mut sfnt = Sfnt::parse(&build_font(&font, &ExportOptions::default()).unwrap()).unwrap();
+    // 两个格式 2 子表的第二侧分组 {c d} 与 {d} 相交；两个查找中 a b 的值相加超出 i16
+    let gpos = Gpos {
+        scripts: vec![Script {
+            tag: *b"DFLT",
+            default_lang_sys: Some(LangSys { required_feature: None, features: vec![0] }),
+            lang_systems: Vec::new(),
+        }],
+        features: vec![Feature { tag: *b"kern", params: None, lookups: vec![0, 1, 2] }],
+        lookups: vec![
+            lookup(vec![
+                Positioning::PairClass {
+                    coverage: vec![1],
+                    classes1: BTreeMap::from([(1, 1)]),
+                    classes2: BTreeMap::from([(3, 1), (4, 1)]),
+                    values: vec![vec![kern(0), kern(0)], vec![kern(0), kern(-50)]],
+                },
+                Positioning::PairClass {
+                    coverage: vec![2],
+                    classes1: BTreeMap::from([(2, 1)]),
+                    classes2: BTreeMap::from([(4, 1)]),
+                    values: vec![vec![kern(0), kern(0)], vec![kern(0), kern(-70)]],
+                },
+            ]),
+            lookup(vec![Positioning::Pair(BTreeMap::from([(1, BTreeMap::from([(2, kern(30000))]))]))]),
+            lookup(vec![Positioning::Pair(BTreeMap::from([(1, BTreeMap::from([(2, kern(30000))]))]))]),
+        ],
+    };
+    sfnt.tables.retain(|table| &table.tag != b"GPOS");
+    sfnt.tables.push(Table { tag: *b"GPOS", data: gpos.build().unwrap() });
+    let data = sfnt::assemble(sfnt.version, sfnt.tables);
+
+    let imported = kerning::import(&data, &font).unwrap();
+    let expected = BTreeMap::from([((0, 1), i16::MAX), ((0, 2), -50), ((0, 3), -50), ((1, 3), -70)]);
+    assert_eq!(effective(&imported), expected);
+    let mut seen = HashSet::new();
+    assert!(imported.right_classes.iter().flat_map(|class| &class.glyphs).all(|&glyph| seen.insert(glyph)));
+
+    // 导入的字偶距可以再次导出，读回后不变
+    let font = FontData { kerning: imported, ..font };
+    let rebuilt = build_font(&font, &ExportOptions::default()).unwrap();
+    assert_eq!(effective(&kerning::import(&rebuilt, &font).unwrap()), expected);
+}
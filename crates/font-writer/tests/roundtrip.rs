@@ -0,0 +1,312 @@
+use std::collections::BTreeSet;
+
+use font_writer::sfnt::{self, Table};
+use font_writer::validate::validate;
+use font_writer::variable::VariableFontData;
+use font_writer::{
+    build_font, build_otf, build_variable_font, subset_font, woff2, ExportOptions, Flavor, FontData, Outlines,
+};
+use serde_json::{json, Value};
+use ttf_parser::{Face, GlyphId, OutlineBuilder, Rect, Tag};
+
+fn line(x0: f64, y0: f64, x1: f64, y1: f64) -> Value {
+    json!({ "type": "LINE", "start": { "x": x0, "y": y0 }, "end": { "x": x1, "y": y1 } })
+}
+
+// 工程约定外轮廓逆时针、内轮廓顺时针
+fn rect(x0: f64, y0: f64, x1: f64, y1: f64, counter_clockwise: bool) -> Value {
+    let points = [(x0, y0), (x1, y0), (x1, y1), (x0, y1)];
+    let points: Vec<(f64, f64)> =
+        if counter_clockwise { points.to_vec() } else { points.iter().rev().copied().collect() };
+    let segments: Vec<Value> =
+        (0..4).map(|i| line(points[i].0, points[i].1, points[(i + 1) % 4].0, points[(i + 1) % 4].1)).collect();
+    Value::Array(segments)
+}
+
+// o 为带孔的方框，c 含三次曲线，oo 为两个 o 组成的复合字形
+fn font(weight: f64) -> FontData {
+    let curve = json!([
+        line(500.0, 100.0, 500.0, 200.0),
+        {
+            "type": "CUBIC_BEZIER",
+            "start": { "x": 500.0, "y": 200.0 },
+            "control1": { "x": 200.0, "y": 100.0 },
+            "control2": { "x": 200.0, "y": 600.0 },
+            "end": { "x": 500.0, "y": 500.0 }
+        },
+        line(500.0, 500.0, 500.0, 600.0),
+        {
+            "type": "CUBIC_BEZIER",
+            "start": { "x": 500.0, "y": 600.0 },
+            "control1": { "x": 50.0, "y": 700.0 },
+            "control2": { "x": 50.0, "y": 0.0 },
+            "end": { "x": 500.0, "y": 100.0 }
+        }
+    ]);
+    serde_json::from_value(json!({
+        "info": { "familyName": "Round Trip", "styleName": "Regular", "unitsPerEm": 1000, "ascender": 800,
+                  "descender": -200, "createdTimestamp": 1700000000 },
+        "glyphs": [
+            { "name": "o", "unicode": 0x6F, "advanceWidth": 700,
+              "contours": [
+                  rect(100.0, 0.0, 600.0, 700.0, true),
+                  rect(100.0 + weight, weight, 600.0 - weight, 700.0 - weight, false)
+              ] },
+            { "name": "c", "unicode": 0x63, "advanceWidth": 600, "contours": [curve] },
+            { "name": "oo", "unicode": 0x221E, "advanceWidth": 1400,
+              "components": [{ "glyph": 0 }, { "glyph": 0, "transform": [1, 0, 0, 1, 700, 0] }] }
+        ]
+    }))
+    .unwrap()
+}
+
+// 收集每条轮廓的端点，用于计算方向
+#[derive(Default)]
+struct Points(Vec<Vec<(f32, f32)>>);
+
+impl OutlineBuilder for Points {
+    fn move_to(&mut self, x: f32, y: f32) {
+        self.0.push(vec![(x, y)]);
+    }
+    fn line_to(&mut self, x: f32, y: f32) {
+        self.0.last_mut().unwrap().push((x, y));
+    }
+    fn quad_to(&mut self, _: f32, _: f32, x: f32, y: f32) {
+        self.0.last_mut().unwrap().push((x, y));
+    }
+    fn curve_to(&mut self, _: f32, _: f32, _: f32, _: f32, x: f32, y: f32) {
+        self.0.last_mut().unwrap().push((x, y));
+    }
+    fn close(&mut self) {}
+}
+
+fn signed_areas(face: &Face, glyph: GlyphId) -> (Vec<f32>, Rect) {
+    let mut points = Points::default();
+    let bbox = face.outline_glyph(glyph, &mut points).unwrap();
+    let areas = points
+        .0
+        .iter()
+        .map(|contour| {
+            (0..contour.len())
+                .map(|i| {
+                    let (a, b) = (contour[i], contour[(i + 1) % contour.len()]);
+                    a.0 * b.1 - b.0 * a.1
+                })
+                .sum::<f32>()
+                / 2.0
+        })
+        .collect();
+    (areas, bbox)
+}
+
+// 校验器无错误，ttf-parser 能读出字形，外轮廓方向符合轮廓格式的约定
+fn check(data: &[u8], outlines: Outlines) {
+    let report = validate(data);
+    assert!(report.valid, "{:#?}", report.issues);
+    let face = Face::parse(data, 0).unwrap();
+    assert_eq!(face.units_per_em(), 1000);
+
+    let o = face.glyph_index('o').unwrap();
+    assert_eq!(face.glyph_hor_advance(o), Some(700));
+    let (areas, bbox) = signed_areas(&face, o);
+    assert_eq!((bbox.x_min, bbox.y_min, bbox.x_max, bbox.y_max), (100, 0, 600, 700));
+    assert_eq!(areas.len(), 2);
+    // TrueType 外轮廓顺时针，CFF 外轮廓逆时针，内轮廓与外轮廓相反
+    let outer = areas.iter().copied().max_by(|a, b| a.abs().total_cmp(&b.abs())).unwrap();
+    match outlines {
+        Outlines::TrueType => assert!(outer < 0.0),
+        Outlines::Cff => assert!(outer > 0.0),
+    }
+    assert!(areas.iter().all(|&area| area == outer || area.signum() != outer.signum()));
+
+    let c = face.glyph_index('c').unwrap();
+    assert!(face.outline_glyph(c, &mut Points::default()).is_some());
+    let oo = face.glyph_index('\u{221E}').unwrap();
+    let (areas, bbox) = signed_areas(&face, oo);
+    assert_eq!(areas.len(), 4);
+    assert_eq!((bbox.x_min, bbox.x_max), (100, 1300));
+}
+
+fn options(outlines: Outlines) -> ExportOptions {
+    ExportOptions { outlines, ..Default::default() }
+}
+
+#[test]
+fn glyf_font() {
+    check(&build_font(&font(100.0), &options(Outlines::TrueType)).unwrap(), Outlines::TrueType);
+}
+
+#[test]
+fn cff_font() {
+    let options = ExportOptions { subroutinize: true, ..options(Outlines::Cff) };
+    check(&build_font(&font(100.0), &options).unwrap(), Outlines::Cff);
+}
+
+// WOFF2 中的一个表，data 为数据流中的内容（可能经过变换）
+struct Woff2Table {
+    tag: [u8; 4],
+    orig_length: usize,
+    data: Vec<u8>,
+}
+
+// 读取 WOFF2 表目录并解压数据流，返回 sfnt 版本与各表
+fn woff2_tables(data: &[u8]) -> (u32, Vec<Woff2Table>) {
+    // 已知标签表的前 29 项，测试字体中的表都在其中
+    const KNOWN_TAGS: [&[u8; 4]; 29] = [
+        b"cmap", b"head", b"hhea", b"hmtx", b"maxp", b"name", b"OS/2", b"post", b"cvt ", b"fpgm", b"glyf", b"loca",
+        b"prep", b"CFF ", b"VORG", b"EBDT", b"EBLC", b"gasp", b"hdmx", b"kern", b"LTSH", b"PCLT", b"VDMX", b"vhea",
+        b"vmtx", b"BASE", b"GDEF", b"GPOS", b"GSUB",
+    ];
+    let u32_at = |offset: usize| u32::from_be_bytes(data[offset..offset + 4].try_into().unwrap());
+    assert_eq!(&data[..4], b"wOF2");
+    let version = u32_at(4);
+    let num_tables = u16::from_be_bytes([data[12], data[13]]) as usize;
+    let compressed_length = u32_at(20) as usize;
+
+    let mut offset = 48;
+    let read_base128 = |offset: &mut usize| {
+        let mut value = 0usize;
+        loop {
+            let byte = data[*offset];
+            *offset += 1;
+            value = (value << 7) | (byte & 0x7F) as usize;
+            if byte & 0x80 == 0 {
+                return value;
+            }
+        }
+    };
+    let mut entries = Vec::new();
+    for _ in 0..num_tables {
+        let flags = data[offset];
+        offset += 1;
+        let tag: [u8; 4] = if flags & 0x3F == 0x3F {
+            offset += 4;
+            data[offset - 4..offset].try_into().unwrap()
+        } else {
+            *KNOWN_TAGS[(flags & 0x3F) as usize]
+        };
+        let orig_length = read_base128(&mut offset);
+        let version = flags >> 6;
+        let transformed = if &tag == b"glyf" || &tag == b"loca" { version == 0 } else { version != 0 };
+        let length = if transformed { read_base128(&mut offset) } else { orig_length };
+        entries.push((tag, orig_length, length));
+    }
+    let mut stream = Vec::new();
+    brotli::BrotliDecompress(&mut &data[offset..offset + compressed_length], &mut stream).unwrap();
+    assert_eq!(stream.len(), entries.iter().map(|entry| entry.2).sum::<usize>());
+
+    let mut start = 0;
+    let tables = entries
+        .into_iter()
+        .map(|(tag, orig_length, length)| {
+            start += length;
+            Woff2Table { tag, orig_length, data: stream[start - length..start].to_vec() }
+        })
+        .collect();
+    (version, tables)
+}
+
+#[test]
+fn woff2_cff_font() {
+    let options = ExportOptions { flavor: Flavor::Woff2, ..options(Outlines::Cff) };
+    let (version, tables) = woff2_tables(&build_font(&font(100.0), &options).unwrap());
+    assert_eq!(version, sfnt::CFF);
+    // CFF 字体不做变换，数据流中的表可以直接组装回 OTF
+    let tables = tables
+        .into_iter()
+        .map(|table| {
+            assert_eq!(table.data.len(), table.orig_length);
+            Table { tag: table.tag, data: table.data }
+        })
+        .collect();
+    check(&sfnt::assemble(version, tables), Outlines::Cff);
+}
+
+#[test]
+fn woff2_glyf_font() {
+    let font = font(100.0);
+    let sfnt_data = build_font(&font, &options(Outlines::TrueType)).unwrap();
+    let original = sfnt::Sfnt::parse(&sfnt_data).unwrap();
+    let options = ExportOptions { flavor: Flavor::Woff2, ..options(Outlines::TrueType) };
+    let woff2_data = build_font(&font, &options).unwrap();
+    assert_eq!(woff2_data, woff2::encode(&sfnt_data).unwrap());
+
+    let (_, tables) = woff2_tables(&woff2_data);
+    assert_eq!(tables.len(), original.tables.len());
+    for Woff2Table { tag, orig_length, data } in tables {
+        let table = original.table(&tag).unwrap();
+        assert_eq!(orig_length, table.len());
+        match &tag {
+            // glyf 与 loca 经过变换，loca 由解码器重建
+            b"glyf" => assert!(!data.is_empty()),
+            b"loca" => assert!(data.is_empty()),
+            // head 只多了无损变换标志
+            b"head" => assert_eq!(data[18..], table[18..]),
+            _ => assert_eq!(data, table),
+        }
+    }
+}
+
+#[test]
+fn gvar_font() {
+    let variable: VariableFontData = serde_json::from_value(json!({
+        "font": serde_json::to_value(font(100.0)).unwrap(),
+        "axes": [{ "tag": "wght", "name": "Weight", "minValue": 100, "defaultValue": 400, "maxValue": 900 }],
+        "masters": [{ "location": { "wght": 900 }, "glyphs": serde_json::to_value(font(200.0).glyphs).unwrap() }]
+    }))
+    .unwrap();
+    let data = build_variable_font(&variable, &options(Outlines::TrueType)).unwrap();
+    check(&data, Outlines::TrueType);
+
+    // 粗体母版的内轮廓向内收缩 100 个单位
+    let mut face = Face::parse(&data, 0).unwrap();
+    assert!(face.is_variable());
+    let o = face.glyph_index('o').unwrap();
+    let inner = |face: &Face| {
+        let mut points = Points::default();
+        face.outline_glyph(o, &mut points).unwrap();
+        let contour = points.0.iter().find(|contour| contour.iter().all(|p| p.0 > 100.0)).unwrap().clone();
+        contour.iter().map(|p| p.0).fold(f32::MAX, f32::min)
+    };
+    assert_eq!(inner(&face), 200.0);
+    face.set_variation(Tag::from_bytes(b"wght"), 900.0).unwrap();
+    assert_eq!(inner(&face), 300.0);
+    face.set_variation(Tag::from_bytes(b"wght"), 650.0).unwrap();
+    assert_eq!(inner(&face), 250.0);
+}
+
+#[test]
+fn subset_then_validate() {
+    for outlines in [Outlines::TrueType, Outlines::Cff] {
+        let data = build_font(&font(100.0), &options(outlines)).unwrap();
+        // 只保留复合字形，其引用的 o 也要保留
+        let subset = subset_font(&data, &BTreeSet::from([0x221E]), Flavor::Sfnt).unwrap();
+        let report = validate(&subset);
+        assert!(report.valid, "{:#?}", report.issues);
+        let face = Face::parse(&subset, 0).unwrap();
+        assert!(face.glyph_index('c').is_none());
+        let oo = face.glyph_index('\u{221E}').unwrap();
+        let (areas, bbox) = signed_areas(&face, oo);
+        assert_eq!(areas.len(), 4);
+        assert_eq!((bbox.x_min, bbox.y_min, bbox.x_max, bbox.y_max), (100, 0, 1300, 700));
+    }
+}
+
+#[test]
+fn otf_vertical_font() {
+    let mut font = font(100.0);
+    font.vertical = serde_json::from_value(json!({ "alternates": [{ "glyph": 1, "rotate": true }] })).unwrap();
+    for data in [build_otf(&font, true).unwrap(), build_font(&font, &options(Outlines::Cff)).unwrap()] {
+        let report = validate(&data);
+        assert!(report.valid, "{:#?}", report.issues);
+        let face = Face::parse(&data, 0).unwrap();
+        let tables = face.tables();
+        assert!(tables.vhea.is_some() && tables.vmtx.is_some() && tables.vorg.is_some());
+        // 旋转生成的 c.vert 追加在末尾，并由 vert 特性替换
+        assert_eq!(face.number_of_glyphs(), 5);
+        let features = tables.gsub.unwrap().features;
+        assert!(features.find(Tag::from_bytes(b"vert")).is_some());
+        let (_, bbox) = signed_areas(&face, GlyphId(4));
+        assert!(bbox.width() > 0 && bbox.height() > 0);
+    }
+}
@@ -0,0 +1,121 @@
+use std::fs;
+use std::path::PathBuf;
+
+use font_writer::{ufo, FontData};
+use serde_json::{json, Value};
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("font_writer_ufo_{name}_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn line(x0: f64, y0: f64, x1: f64, y1: f64) -> Value {
+    json!({ "type": "LINE", "start": { "x": x0, "y": y0 }, "end": { "x": x1, "y": y1 } })
+}
+
+// 覆盖 UFO 中需要额外记录才能还原的数据：首尾未重合的轮廓、被上下文规则引用的后定义查找、
+// 需要改名的字形、分组与查找名，以及 Windows 保留名
+fn font() -> FontData {
+    let square = json!([line(0.0, 0.0, 0.0, 500.0), line(0.0, 500.0, 500.0, 500.0), line(500.0, 500.0, 0.0, 0.0)]);
+    let open = json!([
+        line(100.0, 0.0, 100.0, 700.0),
+        {
+            "type": "QUADRATIC_BEZIER",
+            "start": { "x": 100.0, "y": 700.0 },
+            "control": { "x": 300.0, "y": 800.0 },
+            "end": { "x": 400.0, "y": 600.0 }
+        },
+        {
+            "type": "CUBIC_BEZIER",
+            "start": { "x": 400.0, "y": 600.0 },
+            "control1": { "x": 450.0, "y": 400.0 },
+            "control2": { "x": 350.0, "y": 100.0 },
+            "end": { "x": 200.0, "y": 0.0 }
+        }
+    ]);
+    serde_json::from_value(json!({
+        "info": { "familyName": "Round Trip", "styleName": "Regular", "unitsPerEm": 1000, "ascender": 800,
+                  "descender": -200, "modifiedTimestamp": 1700000000 },
+        "glyphs": [
+            { "name": "a", "unicode": 0x61, "advanceWidth": 500, "contours": [square] },
+            { "name": "b", "unicode": 0x62, "advanceWidth": 600, "contours": [open] },
+            { "name": "a b", "unicode": 0, "advanceWidth": 1100,
+              "components": [{ "glyph": 0 }, { "glyph": 1, "transform": [1, 0, 0.2, 1, 500, 0] }] },
+            { "name": "lpt9", "unicode": 0x4E00, "advanceWidth": 1000 }
+        ],
+        "variationSequences": [{ "unicode": 0x4E00, "selector": 0xE0100, "glyph": 3 }],
+        "kerning": {
+            "leftClasses": [{ "name": "round", "glyphs": [0, 1] }],
+            "rightClasses": [{ "name": "", "glyphs": [1, 3] }],
+            "pairs": [
+                { "left": { "type": "class", "class": 0 }, "right": { "type": "class", "class": 0 }, "value": -40 },
+                { "left": { "type": "glyph", "glyph": 0 }, "right": { "type": "glyph", "glyph": 1 }, "value": 25 }
+            ]
+        },
+        "features": {
+            "languageSystems": [{ "script": "DFLT", "language": "dflt" }, { "script": "latn", "language": "TRK" }],
+            "classes": [{ "name": "both glyphs", "glyphs": [0, 1] }],
+            "lookups": [
+                { "name": "context", "rules": [{ "type": "chain", "backtrack": [{ "type": "glyph", "glyph": 3 }],
+                  "input": [{ "glyphs": { "type": "class", "class": 0 }, "lookup": 1 }] }] },
+                { "name": "swap", "rules": [{ "type": "single", "input": { "type": "glyph", "glyph": 0 },
+                  "replacement": { "type": "glyph", "glyph": 1 } }] },
+                { "name": "liga", "rules": [{ "type": "ligature",
+                  "components": [{ "type": "glyph", "glyph": 0 }, { "type": "glyph", "glyph": 1 }], "ligature": 2 }] }
+            ],
+            "features": [
+                { "tag": "calt", "lookups": [0] },
+                { "tag": "liga", "lookups": [2], "languages": [{ "script": "latn", "language": "TRK", "lookups": [1] }] }
+            ]
+        },
+        "vertical": {
+            "origin": 880,
+            "metrics": [{ "glyph": 3, "advance": 1000, "origin": 900 }],
+            "alternates": [{ "glyph": 0, "alternate": 1, "rotate": false }]
+        }
+    }))
+    .unwrap()
+}
+
+#[test]
+fn write_read_round_trip() {
+    let dir = temp_dir("round_trip");
+    let path = dir.join("RoundTrip.ufo");
+    let font = font();
+    ufo::write(&font, &path).unwrap();
+    let read = ufo::read(&path).unwrap();
+    assert_eq!(serde_json::to_value(&read).unwrap(), serde_json::to_value(&font).unwrap());
+
+    // 再写一次覆盖已有的 UFO，结果不变且不留下临时目录
+    ufo::write(&read, &path).unwrap();
+    assert_eq!(serde_json::to_value(ufo::read(&path).unwrap()).unwrap(), serde_json::to_value(&font).unwrap());
+    let entries: Vec<_> = fs::read_dir(&dir).unwrap().map(|entry| entry.unwrap().file_name()).collect();
+    assert_eq!(entries, vec![std::ffi::OsString::from("RoundTrip.ufo")]);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn failed_write_keeps_existing_ufo() {
+    let dir = temp_dir("failed_write");
+    let path = dir.join("Keep.ufo");
+    let font = font();
+    ufo::write(&font, &path).unwrap();
+
+    // 组件引用不存在的字形，写入中途出错
+    let mut broken = font.clone();
+    broken.glyphs[2].components[0].glyph = 99;
+    assert!(ufo::write(&broken, &path).is_err());
+    assert_eq!(serde_json::to_value(ufo::read(&path).unwrap()).unwrap(), serde_json::to_value(&font).unwrap());
+    let entries: Vec<_> = fs::read_dir(&dir).unwrap().map(|entry| entry.unwrap().file_name()).collect();
+    assert_eq!(entries, vec![std::ffi::OsString::from("Keep.ufo")]);
+
+    // 其他非空目录不覆盖
+    let other = dir.join("Other.ufo");
+    fs::create_dir_all(&other).unwrap();
+    fs::write(other.join("notes.txt"), "keep").unwrap();
+    assert!(ufo::write(&font, &other).is_err());
+    assert_eq!(fs::read_to_string(other.join("notes.txt")).unwrap(), "keep");
+    fs::remove_dir_all(&dir).unwrap();
+}
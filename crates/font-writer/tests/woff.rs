@@ -0,0 +1,53 @@
+use font_writer::sfnt::Sfnt;
+use font_writer::woff::{self, WoffOptions};
+use font_writer::{build_font, ExportOptions, FontData, GlyphData};
+
+fn woff_data() -> (Vec<u8>, Vec<u8>) {
+    let glyphs = ('a'..='z')
+        .map(|c| GlyphData { name: c.to_string(), unicode: c as u32, advance_width: 500.0, ..Default::default() })
+        .collect();
+    let font = build_font(&FontData { glyphs, ..Default::default() }, &ExportOptions::default()).unwrap();
+    let woff = woff::encode(&font, &WoffOptions::default()).unwrap();
+    (font, woff)
+}
+
+// 表目录从第 44 字节开始，每项 20 字节：tag、offset、compLength、origLength、checksum
+fn compressed_entry(woff: &[u8]) -> usize {
+    let num_tables = u16::from_be_bytes([woff[12], woff[13]]) as usize;
+    (0..num_tables)
+        .map(|i| 44 + i * 20)
+        .find(|&entry| {
+            let comp = u32::from_be_bytes(woff[entry + 8..entry + 12].try_into().unwrap());
+            let orig = u32::from_be_bytes(woff[entry + 12..entry + 16].try_into().unwrap());
+            comp < orig
+        })
+        .unwrap()
+}
+
+#[test]
+fn decode_round_trip() {
+    let (font, woff) = woff_data();
+    let decoded = Sfnt::parse(&woff::decode(&woff).unwrap().sfnt).unwrap();
+    let original = Sfnt::parse(&font).unwrap();
+    assert_eq!(decoded.tables.len(), original.tables.len());
+    for table in &original.tables {
+        assert_eq!(decoded.tables.iter().find(|t| t.tag == table.tag).unwrap().data, table.data);
+    }
+}
+
+#[test]
+fn decode_rejects_mismatched_original_length() {
+    let (_, woff) = woff_data();
+    let entry = compressed_entry(&woff);
+    let orig = u32::from_be_bytes(woff[entry + 12..entry + 16].try_into().unwrap());
+
+    // 实际解压结果比声明的长
+    let mut shorter = woff.clone();
+    shorter[entry + 12..entry + 16].copy_from_slice(&(orig - 1).to_be_bytes());
+    assert!(woff::decode(&shorter).is_err());
+
+    // 声明的长度远大于实际，不按声明分配内存
+    let mut longer = woff;
+    longer[entry + 12..entry + 16].copy_from_slice(&u32::MAX.to_be_bytes());
+    assert!(woff::decode(&longer).is_err());
+}
@@ -1,15 +1,34 @@
 #![allow(unused)]
 
-use native_dialog::FileDialog;
-use std::collections::HashMap;
+mod project;
+mod recovery;
+
+use native_dialog::{FileDialog, MessageDialog, MessageType};
+use std::collections::{BTreeSet, HashMap};
 use std::fs::write;
 use std::fs::File;
 use std::io::{self, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
 use tauri::menu::{Menu, MenuItem, MenuItemBuilder, MenuItemKind, PredefinedMenuItem, Submenu};
 use tauri::Size;
 use tauri::{AppHandle, Emitter};
-use tauri::{Manager, Window};
+use tauri::{Manager, State, Window};
+use overlap_wasm::font_reader::{self, FontFile};
+use overlap_wasm::svg_export::{self, GlyphSvg, SvgExportOptions};
+use overlap_wasm::svg_import::{self, SvgImportOptions};
+use overlap_wasm::transform::{self, ComponentPlacement, Matrix};
+use overlap_wasm::Contours;
+use font_writer::bitmap::{self, BitmapOptions};
+use font_writer::color::ColorFontData;
+use font_writer::composite;
+use font_writer::kerning::Kerning;
+use font_writer::validate::ValidationReport;
+use font_writer::variable::VariableFontData;
+use font_writer::{ExportOptions, Flavor, FontData};
+use project::ProjectFile;
+use recovery::RecoverySnapshot;
+use serde::Deserialize;
 
 #[tauri::command]
 fn test(app: AppHandle) {
@@ -36,6 +55,101 @@ fn save_as(app: AppHandle) {
   app.emit("save-as", ()).unwrap();
 }
 
+// 原子保存工程：写入临时文件后替换，保留 backups 个轮换备份（缺省 5 个）
+#[tauri::command]
+fn save_project(path: String, data: String, backups: Option<usize>) -> Result<(), String> {
+  project::save(Path::new(&path), &data, backups.unwrap_or(project::DEFAULT_BACKUPS))
+}
+
+// 通过原生对话框选择位置保存工程，取消时返回 None，否则返回保存的路径
+#[tauri::command]
+fn save_project_as(data: String, filename: Option<String>, backups: Option<usize>) -> Result<Option<String>, String> {
+  let filename = filename.unwrap_or_else(|| String::from("untitled.json"));
+  let path = FileDialog::new()
+    .set_filename(&filename)
+    .add_filter("JSON", &["json"])
+    .show_save_single_file()
+    .map_err(|err| format!("failed to show save dialog: {err}"))?;
+  let Some(path) = path else {
+    return Ok(None);
+  };
+  project::save(&path, &data, backups.unwrap_or(project::DEFAULT_BACKUPS))?;
+  Ok(Some(path.to_string_lossy().to_string()))
+}
+
+// 读取工程 JSON，文件被截断时返回明确的错误
+#[tauri::command]
+fn load_project(path: String) -> Result<String, String> {
+  project::load(Path::new(&path))
+}
+
+// 通过原生对话框选择并读取工程，取消时返回 None
+#[tauri::command]
+fn open_project() -> Result<Option<ProjectFile>, String> {
+  let path = FileDialog::new()
+    .add_filter("JSON", &["json"])
+    .show_open_single_file()
+    .map_err(|err| format!("failed to show open dialog: {err}"))?;
+  let Some(path) = path else {
+    return Ok(None);
+  };
+  let data = project::load(&path)?;
+  Ok(Some(ProjectFile { path: path.to_string_lossy().to_string(), data }))
+}
+
+// 启动时发现并由用户确认恢复的快照，等待前端取走
+#[derive(Default)]
+struct PendingRecovery(Mutex<Vec<RecoverySnapshot>>);
+
+fn recovery_directory(app: &AppHandle) -> Result<PathBuf, String> {
+  let app_data = app.path().app_data_dir().map_err(|err| format!("failed to locate app data directory: {err}"))?;
+  Ok(recovery::directory(&app_data))
+}
+
+// 前端定时提交的工程快照，写入 $APPDATA/recovery，崩溃后下次启动时可以恢复
+#[tauri::command]
+fn autosave_project(app: AppHandle, name: String, data: String) -> Result<(), String> {
+  recovery::write(&recovery_directory(&app)?, &name, &data)?;
+  Ok(())
+}
+
+// 工程已保存或关闭时删除快照，name 为 None 时删除全部
+#[tauri::command]
+fn discard_autosave(app: AppHandle, name: Option<String>) -> Result<(), String> {
+  recovery::discard(&recovery_directory(&app)?, name.as_deref())
+}
+
+// 前端加载完成后取走需要恢复的快照，再用 load_project 读取
+#[tauri::command]
+fn take_recovery_snapshots(pending: State<PendingRecovery>) -> Result<Vec<RecoverySnapshot>, String> {
+  let mut snapshots = pending.0.lock().map_err(|err| format!("failed to read recovery snapshots: {err}"))?;
+  Ok(std::mem::take(&mut *snapshots))
+}
+
+// 启动时检查遗留的快照并询问是否恢复，放弃的快照改名保留一份
+fn offer_recovery(app: &AppHandle) -> Result<(), String> {
+  let directory = recovery_directory(app)?;
+  let snapshots = recovery::snapshots(&directory);
+  if snapshots.is_empty() {
+    return Ok(());
+  }
+  let names: Vec<&str> = snapshots.iter().map(|snapshot| snapshot.name.as_str()).collect();
+  let text = format!("发现上次未正常保存的工程：{}。是否恢复？", names.join("、"));
+  let restore = MessageDialog::new()
+    .set_type(MessageType::Info)
+    .set_title("恢复工程")
+    .set_text(&text)
+    .show_confirm()
+    .map_err(|err| format!("failed to show recovery dialog: {err}"))?;
+  if restore {
+    let pending = app.state::<PendingRecovery>();
+    *pending.0.lock().map_err(|err| format!("failed to store recovery snapshots: {err}"))? = snapshots;
+    Ok(())
+  } else {
+    recovery::set_aside(&snapshots)
+  }
+}
+
 #[tauri::command]
 fn undo(app: AppHandle) {
   app.emit("undo", ()).unwrap();
@@ -71,6 +185,13 @@ fn import_font_file(app: AppHandle) {
   app.emit("import-font-file", ()).unwrap();
 }
 
+// 在原生侧解析 TTF/OTF/TTC 字体文件，index 为字体集合中的序号
+#[tauri::command]
+fn read_font_file(path: String, index: Option<u32>) -> Result<FontFile, String> {
+  let data = std::fs::read(&path).map_err(|err| format!("failed to read {path}: {err}"))?;
+  font_reader::read_font(&data, index.unwrap_or(0))
+}
+
 #[tauri::command]
 fn import_templates_file(app: AppHandle) {
   app.emit("import-templates-file", ()).unwrap();
@@ -91,6 +212,13 @@ fn import_svg(app: AppHandle) {
   app.emit("import-svg", ()).unwrap();
 }
 
+// 在原生侧解析 SVG 文件，返回字体坐标系下的轮廓
+#[tauri::command]
+fn parse_svg_file(path: String, options: Option<SvgImportOptions>) -> Result<Contours, String> {
+  let svg = std::fs::read_to_string(&path).map_err(|err| format!("failed to read {path}: {err}"))?;
+  svg_import::parse_svg(&svg, &options.unwrap_or_default())
+}
+
 #[tauri::command]
 fn export_font_file(app: AppHandle) {
   app.emit("export-font-file", ()).unwrap();
@@ -116,16 +244,150 @@ fn export_svg(app: AppHandle) {
   app.emit("export-svg", ()).unwrap();
 }
 
+// 将当前字符轮廓写为 SVG 文件
+#[tauri::command]
+fn write_svg_file(path: String, contours: Contours, options: Option<SvgExportOptions>) -> Result<(), String> {
+  let svg = svg_export::contours_to_svg(&contours, &options.unwrap_or_default());
+  write(&path, svg).map_err(|err| format!("failed to write {path}: {err}"))
+}
+
+// 批量导出：每个字符一个 SVG，打包为 zip 文件
+#[tauri::command]
+fn write_svg_zip(path: String, glyphs: Vec<GlyphSvg>, options: Option<SvgExportOptions>) -> Result<(), String> {
+  let data = svg_export::glyphs_to_svg_zip(&glyphs, &options.unwrap_or_default())?;
+  write(&path, data).map_err(|err| format!("failed to write {path}: {err}"))
+}
+
+// 前端的导出选项：removeOverlap 在这里用 overlap_wasm 处理，其余选项原样交给 font_writer
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct FontExportOptions {
+  #[serde(flatten)]
+  font: ExportOptions,
+  remove_overlap: bool,
+}
+
+// 去除重叠：组件先展开为轮廓，再对每个字形的全部轮廓做并集
+fn remove_glyph_overlap(font: FontData, options: &FontExportOptions) -> Result<FontData, String> {
+  if !options.remove_overlap {
+    return Ok(font);
+  }
+  let mut glyphs = composite::flatten(&font.glyphs, 0)?;
+  for glyph in glyphs.iter_mut().filter(|glyph| !glyph.contours.is_empty()) {
+    glyph.contours = overlap_wasm::remove_overlap_contours(&glyph.contours);
+  }
+  Ok(FontData { glyphs, ..font })
+}
+
+// 原生生成字体（TrueType 或 CFF 轮廓）并直接写入文件；未去除重叠时共用的部件写成复合字形
+#[tauri::command]
+fn write_font_file(path: String, font: FontData, options: Option<FontExportOptions>) -> Result<(), String> {
+  let options = options.unwrap_or_default();
+  let font = remove_glyph_overlap(font, &options)?;
+  let data = font_writer::build_font(&font, &options.font)?;
+  write(&path, data).map_err(|err| format!("failed to write {path}: {err}"))
+}
+
 #[tauri::command]
 fn export_var_font_file(app: AppHandle) {
   app.emit("export-var-font-file", ()).unwrap();
 }
 
+// 原生生成可变字体（fvar/gvar/avar/STAT/HVAR），母版不兼容时返回逐个字形的错误
+#[tauri::command]
+fn write_variable_font_file(path: String, font: VariableFontData, options: Option<ExportOptions>) -> Result<(), String> {
+  let data = font_writer::build_variable_font(&font, &options.unwrap_or_default())?;
+  write(&path, data).map_err(|err| format!("failed to write {path}: {err}"))
+}
+
+// 多母版工程导出为 designspace 与每个母版一个 UFO，可在应用外用 fontmake 构建
+#[tauri::command]
+fn export_designspace(path: String, font: VariableFontData) -> Result<(), String> {
+  font_writer::designspace::write(&font, Path::new(&path))
+}
+
 #[tauri::command]
 fn export_color_font(app: AppHandle) {
   app.emit("export-color-font", ()).unwrap();
 }
 
+// 原生生成 COLR/CPAL 彩色字体，COLRv1 支持渐变与图层变换
+#[tauri::command]
+fn write_color_font_file(path: String, font: ColorFontData, options: Option<FontExportOptions>) -> Result<(), String> {
+  let options = options.unwrap_or_default();
+  let font = ColorFontData { font: remove_glyph_overlap(font.font, &options)?, ..font };
+  let data = font_writer::build_color_font(&font, &options.font)?;
+  write(&path, data).map_err(|err| format!("failed to write {path}: {err}"))
+}
+
+// 对已有字体文件（TTF/OTF/WOFF）按码位子集化，保留仍然适用的 GSUB/GPOS 查找
+#[tauri::command]
+fn subset_font_file(path: String, output_path: String, codepoints: Vec<u32>, flavor: Option<Flavor>) -> Result<(), String> {
+  let data = std::fs::read(&path).map_err(|err| format!("failed to read {path}: {err}"))?;
+  let codepoints: BTreeSet<u32> = codepoints.into_iter().collect();
+  let subset = font_writer::subset_font(&data, &codepoints, flavor.unwrap_or_default())?;
+  write(&output_path, subset).map_err(|err| format!("failed to write {output_path}: {err}"))
+}
+
+// 按码位生成工程的子集字体
+#[tauri::command]
+fn write_subset_font_file(path: String, font: FontData, codepoints: Vec<u32>, options: Option<FontExportOptions>) -> Result<(), String> {
+  let codepoints: BTreeSet<u32> = codepoints.into_iter().collect();
+  let options = options.unwrap_or_default();
+  let font = remove_glyph_overlap(font, &options)?;
+  let data = font_writer::subset_project(&font, &codepoints, &options.font)?;
+  write(&path, data).map_err(|err| format!("failed to write {path}: {err}"))
+}
+
+// 按像素大小栅格化工程生成 BDF（可选 PCF），path 为 Font.bdf 时写出 Font-16.bdf 等，返回写出的文件路径
+#[tauri::command]
+fn write_bitmap_font_files(path: String, font: FontData, options: Option<BitmapOptions>) -> Result<Vec<String>, String> {
+  let options = options.unwrap_or_default();
+  let path = PathBuf::from(path);
+  let directory = path.parent().map(Path::to_path_buf).unwrap_or_default();
+  let stem = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_else(|| String::from("font"));
+  let mut written = Vec::new();
+  for &size in &options.sizes {
+    let bitmap_font = bitmap::build(&font, size, &options)?;
+    let mut outputs = vec![("bdf", bitmap::bdf::write(&bitmap_font).into_bytes())];
+    if options.pcf {
+      outputs.push(("pcf", bitmap::pcf::write(&bitmap_font)));
+    }
+    for (extension, data) in outputs {
+      let file = directory.join(format!("{stem}-{size}.{extension}"));
+      write(&file, data).map_err(|err| format!("failed to write {}: {err}", file.display()))?;
+      written.push(file.to_string_lossy().to_string());
+    }
+  }
+  Ok(written)
+}
+
+// 校验字体文件的表结构与数据一致性，返回结构化的校验报告
+#[tauri::command]
+fn validate_font_file(path: String) -> Result<ValidationReport, String> {
+  let data = std::fs::read(&path).map_err(|err| format!("failed to read {path}: {err}"))?;
+  Ok(font_writer::validate::validate(&data))
+}
+
+// 从已有字体导入字偶距，按码位对应到当前工程的字形
+#[tauri::command]
+fn import_kerning(path: String, font: FontData) -> Result<Kerning, String> {
+  let data = std::fs::read(&path).map_err(|err| format!("failed to read {path}: {err}"))?;
+  font_writer::kerning::import(&data, &font)
+}
+
+// 导入 UFO 3 目录（fontinfo、字形、分组与字偶距、features.fea）为工程
+#[tauri::command]
+fn import_ufo(path: String) -> Result<FontData, String> {
+  font_writer::ufo::read(Path::new(&path))
+}
+
+// 工程导出为 UFO 3 目录，目标为已有的 UFO 时整体替换
+#[tauri::command]
+fn export_ufo(path: String, font: FontData) -> Result<(), String> {
+  font_writer::ufo::write(&font, Path::new(&path))
+}
+
 #[tauri::command]
 fn add_character(app: AppHandle) {
   app.emit("add-character", ()).unwrap();
@@ -196,6 +458,17 @@ fn remove_overlap(app: AppHandle) {
   app.emit("remove_overlap", ()).unwrap();
 }
 
+#[tauri::command]
+fn transform_contours(contours: Contours, matrix: Matrix) -> Contours {
+  transform::transform_contours(&contours, &matrix)
+}
+
+// 按矩阵放置各组件并拼合为一个字形
+#[tauri::command]
+fn compose_glyph(components: Vec<ComponentPlacement>, remove_overlap: bool) -> Contours {
+  transform::compose_glyph(&components, remove_overlap)
+}
+
 #[tauri::command]
 fn format_all_characters(app: AppHandle) {
   app.emit("format-all-characters", ()).unwrap();
@@ -444,7 +717,21 @@ pub fn run() {
     .plugin(tauri_plugin_clipboard_manager::init())
     .plugin(tauri_plugin_dialog::init())
     .plugin(tauri_plugin_fs::init())
+    .manage(PendingRecovery::default())
     .setup(|app| {
+      // 先注册日志插件，之后的 log 输出才会被记录
+      if cfg!(debug_assertions) {
+        app.handle().plugin(
+          tauri_plugin_log::Builder::default()
+            .level(log::LevelFilter::Info)
+            .build(),
+        )?;
+      }
+
+      if let Err(err) = offer_recovery(app.handle()) {
+        log::error!("{err}");
+      }
+
       // 获取名为 "main" 的 Webview 窗口句柄
       let window = app.get_webview_window("main").unwrap();
 
@@ -560,13 +847,6 @@ pub fn run() {
         }
       });
 
-      if cfg!(debug_assertions) {
-        app.handle().plugin(
-          tauri_plugin_log::Builder::default()
-            .level(log::LevelFilter::Info)
-            .build(),
-        )?;
-      }
       Ok(())
     })
     .menu(|handle| {
@@ -766,7 +1046,77 @@ pub fn run() {
         ],
       )
     })
-    .invoke_handler(tauri::generate_handler![toggle_menu_disabled])
+    .invoke_handler(tauri::generate_handler![
+      toggle_menu_disabled,
+      parse_svg_file,
+      read_font_file,
+      write_svg_file,
+      write_svg_zip,
+      transform_contours,
+      compose_glyph,
+      write_font_file,
+      write_variable_font_file,
+      export_designspace,
+      write_color_font_file,
+      subset_font_file,
+      write_subset_font_file,
+      write_bitmap_font_files,
+      validate_font_file,
+      import_kerning,
+      import_ufo,
+      export_ufo,
+      save_project,
+      save_project_as,
+      load_project,
+      open_project,
+      autosave_project,
+      discard_autosave,
+      take_recovery_snapshots,
+    ])
     .run(tauri::generate_context!())
     .expect("error while running tauri application");
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn square(x: f64, y: f64, size: f64) -> serde_json::Value {
+    let p = [(x, y), (x + size, y), (x + size, y + size), (x, y + size)];
+    let segments: Vec<serde_json::Value> = (0..4)
+      .map(|i| {
+        let (start, end) = (p[i], p[(i + 1) % 4]);
+        serde_json::json!({ "type": "LINE", "start": { "x": start.0, "y": start.1 }, "end": { "x": end.0, "y": end.1 } })
+      })
+      .collect();
+    serde_json::Value::Array(segments)
+  }
+
+  fn font() -> FontData {
+    serde_json::from_value(serde_json::json!({
+      "glyphs": [
+        { "name": "a", "unicode": 0x61, "contours": [square(0.0, 0.0, 100.0), square(50.0, 50.0, 100.0)] },
+        { "name": "b", "unicode": 0x62, "components": [{ "glyph": 0 }, { "glyph": 0, "transform": [1, 0, 0, 1, 100, 0] }] }
+      ]
+    }))
+    .unwrap()
+  }
+
+  #[test]
+  fn remove_glyph_overlap_merges_contours() {
+    let options = FontExportOptions { remove_overlap: true, ..Default::default() };
+    let font = remove_glyph_overlap(font(), &options).unwrap();
+    // 没有组件的字形同样去除重叠，两个相交的方框合并为一条轮廓
+    assert_eq!(font.glyphs[0].contours.len(), 1);
+    // 组件展开后再合并
+    assert!(font.glyphs[1].components.is_empty());
+    assert_eq!(font.glyphs[1].contours.len(), 1);
+  }
+
+  #[test]
+  fn remove_glyph_overlap_is_optional() {
+    let font = remove_glyph_overlap(font(), &FontExportOptions::default()).unwrap();
+    assert_eq!(font.glyphs[0].contours.len(), 2);
+    assert_eq!(font.glyphs[1].components.len(), 2);
+  }
+}
@@ -0,0 +1,198 @@
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use serde::de::IgnoredAny;
+use serde::Serialize;
+
+// 默认保留的备份数量
+pub const DEFAULT_BACKUPS: usize = 5;
+
+// 通过对话框打开的工程：文件路径与工程 JSON
+#[derive(Serialize)]
+pub struct ProjectFile {
+  pub path: String,
+  pub data: String,
+}
+
+fn file_name(path: &Path) -> Result<String, String> {
+  path
+    .file_name()
+    .map(|name| name.to_string_lossy().to_string())
+    .ok_or_else(|| format!("invalid project path {}", path.display()))
+}
+
+fn sibling(path: &Path, name: String) -> PathBuf {
+  path.with_file_name(name)
+}
+
+// 第 n 个备份：project.json.1.bak 为最近一次保存前的版本
+pub fn backup_path(path: &Path, n: usize) -> Result<PathBuf, String> {
+  Ok(sibling(path, format!("{}.{n}.bak", file_name(path)?)))
+}
+
+// 已有的备份，从新到旧
+pub fn backups(path: &Path) -> Vec<PathBuf> {
+  (1..)
+    .map_while(|n| backup_path(path, n).ok().filter(|backup| backup.is_file()))
+    .collect()
+}
+
+// 把 project.json.(n-1).bak 依次移到 .n.bak，再把当前文件复制为 .1.bak；最旧的备份被覆盖
+fn rotate(path: &Path, count: usize) -> Result<(), String> {
+  if count == 0 || !path.is_file() {
+    return Ok(());
+  }
+  for n in (1..count).rev() {
+    let from = backup_path(path, n)?;
+    if from.is_file() {
+      let to = backup_path(path, n + 1)?;
+      fs::rename(&from, &to).map_err(|err| format!("failed to rotate backup {}: {err}", from.display()))?;
+    }
+  }
+  let backup = backup_path(path, 1)?;
+  fs::copy(path, &backup).map_err(|err| format!("failed to back up {}: {err}", path.display()))?;
+  Ok(())
+}
+
+// 写入同目录下的临时文件并刷到磁盘
+fn write_temp(temp: &Path, data: &[u8]) -> Result<(), String> {
+  let mut file = File::create(temp).map_err(|err| format!("failed to create {}: {err}", temp.display()))?;
+  file.write_all(data).map_err(|err| format!("failed to write {}: {err}", temp.display()))?;
+  file.sync_all().map_err(|err| format!("failed to write {}: {err}", temp.display()))
+}
+
+// 保存工程：先完整写入临时文件，轮换备份后再原子替换目标文件，任何一步失败都不会损坏原工程
+pub fn save(path: &Path, data: &str, backups: usize) -> Result<(), String> {
+  serde_json::from_str::<IgnoredAny>(data).map_err(|err| format!("refusing to save invalid project JSON: {err}"))?;
+  let temp = sibling(path, format!(".{}.tmp", file_name(path)?));
+  if let Err(err) = write_temp(&temp, data.as_bytes()) {
+    let _ = fs::remove_file(&temp);
+    return Err(err);
+  }
+  if let Err(err) = rotate(path, backups) {
+    let _ = fs::remove_file(&temp);
+    return Err(err);
+  }
+  if let Err(err) = fs::rename(&temp, path) {
+    let _ = fs::remove_file(&temp);
+    return Err(format!("failed to replace {}: {err}", path.display()));
+  }
+  // 同步目录项，保证重命名在断电后仍然有效
+  #[cfg(unix)]
+  if let Some(directory) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+    let _ = File::open(directory).and_then(|d| d.sync_all());
+  }
+  Ok(())
+}
+
+// 读取工程 JSON，文件被截断或不是合法 JSON 时给出明确的错误并列出可用的备份
+pub fn load(path: &Path) -> Result<String, String> {
+  let data = fs::read_to_string(path).map_err(|err| format!("failed to read {}: {err}", path.display()))?;
+  if let Err(err) = serde_json::from_str::<IgnoredAny>(&data) {
+    let reason = if err.is_eof() {
+      format!(
+        "project file {} is truncated ({} bytes, ends unexpectedly at line {} column {}); the last save was probably interrupted",
+        path.display(),
+        data.len(),
+        err.line(),
+        err.column()
+      )
+    } else {
+      format!("project file {} is not valid JSON: {err}", path.display())
+    };
+    let backups = backups(path);
+    return Err(if backups.is_empty() {
+      reason
+    } else {
+      let list: Vec<String> = backups.iter().map(|backup| backup.display().to_string()).collect();
+      format!("{reason}. Backups: {}", list.join(", "))
+    });
+  }
+  Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("project_{name}_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+  }
+
+  fn project(version: usize) -> String {
+    format!(r#"{{"name":"demo","version":{version}}}"#)
+  }
+
+  #[test]
+  fn second_save_creates_backup() {
+    let path = temp_dir("backup").join("demo.json");
+    save(&path, &project(1), DEFAULT_BACKUPS).unwrap();
+    assert!(backups(&path).is_empty());
+    save(&path, &project(2), DEFAULT_BACKUPS).unwrap();
+    assert_eq!(backups(&path), vec![backup_path(&path, 1).unwrap()]);
+    assert_eq!(fs::read_to_string(backup_path(&path, 1).unwrap()).unwrap(), project(1));
+    assert_eq!(load(&path).unwrap(), project(2));
+    // 临时文件在替换后不再存在
+    assert_eq!(fs::read_dir(path.parent().unwrap()).unwrap().count(), 2);
+  }
+
+  #[test]
+  fn rotation_keeps_at_most_n_backups() {
+    let path = temp_dir("rotation").join("demo.json");
+    for version in 1..=6 {
+      save(&path, &project(version), 3).unwrap();
+    }
+    let list = backups(&path);
+    assert_eq!(list.len(), 3);
+    assert!(!backup_path(&path, 4).unwrap().exists());
+    // .1.bak 最新，.3.bak 最旧
+    let versions: Vec<String> = list.iter().map(|backup| fs::read_to_string(backup).unwrap()).collect();
+    assert_eq!(versions, vec![project(5), project(4), project(3)]);
+  }
+
+  #[test]
+  fn truncated_load_lists_backups() {
+    let path = temp_dir("truncated").join("demo.json");
+    save(&path, &project(1), DEFAULT_BACKUPS).unwrap();
+    save(&path, &project(2), DEFAULT_BACKUPS).unwrap();
+    let data = project(3);
+    fs::write(&path, &data[..data.len() / 2]).unwrap();
+    let err = load(&path).unwrap_err();
+    assert!(err.contains("is truncated"), "{err}");
+    assert!(err.contains(&backup_path(&path, 1).unwrap().display().to_string()), "{err}");
+
+    // 没有备份时不列出
+    let path = temp_dir("truncated_alone").join("demo.json");
+    fs::write(&path, "{\"name\":").unwrap();
+    let err = load(&path).unwrap_err();
+    assert!(err.contains("is truncated") && !err.contains("Backups"), "{err}");
+  }
+
+  #[test]
+  fn failed_save_keeps_original() {
+    let dir = temp_dir("failed");
+    let path = dir.join("demo.json");
+    save(&path, &project(1), DEFAULT_BACKUPS).unwrap();
+
+    // 内容不是合法 JSON 时拒绝保存
+    assert!(save(&path, "{\"name\":", DEFAULT_BACKUPS).is_err());
+    assert_eq!(fs::read_to_string(&path).unwrap(), project(1));
+
+    // 临时文件位置被目录占用，写入失败
+    fs::create_dir(dir.join(".demo.json.tmp")).unwrap();
+    assert!(save(&path, &project(2), DEFAULT_BACKUPS).is_err());
+    assert_eq!(fs::read_to_string(&path).unwrap(), project(1));
+    assert!(backups(&path).is_empty());
+    fs::remove_dir(dir.join(".demo.json.tmp")).unwrap();
+
+    // 备份位置被目录占用，轮换失败，临时文件被清理
+    fs::create_dir(backup_path(&path, 1).unwrap()).unwrap();
+    assert!(save(&path, &project(2), DEFAULT_BACKUPS).is_err());
+    assert_eq!(fs::read_to_string(&path).unwrap(), project(1));
+    assert!(!dir.join(".demo.json.tmp").exists());
+  }
+}
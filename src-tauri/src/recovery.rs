@@ -0,0 +1,175 @@
+use std::cmp::Reverse;
+use std::ffi::OsStr;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use serde::Serialize;
+
+use crate::project;
+
+// 自动保存的快照，modified 为毫秒时间戳
+#[derive(Debug, Clone, Serialize)]
+pub struct RecoverySnapshot {
+  pub name: String,
+  pub path: String,
+  pub modified: u64,
+}
+
+// 快照目录为 $APPDATA/recovery
+pub fn directory(app_data: &Path) -> PathBuf {
+  app_data.join("recovery")
+}
+
+// 32 位 FNV-1a，结果不随编译器版本变化，升级后仍能找到之前的快照
+fn name_hash(name: &str) -> u32 {
+  name.bytes().fold(0x811c_9dc5, |hash, byte| (hash ^ byte as u32).wrapping_mul(0x0100_0193))
+}
+
+// 工程名只保留字母、数字、- 与 _，再加上原名的哈希，避免 "a b" 与 "a_b" 使用同一个快照
+fn file_name(name: &str) -> String {
+  let stem: String =
+    name.trim().chars().map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '_' }).collect();
+  let stem = if stem.is_empty() { "untitled" } else { stem.as_str() };
+  format!("{stem}-{:08x}.json", name_hash(name))
+}
+
+// 从快照文件名中去掉哈希后缀，得到显示用的工程名
+fn display_name(stem: &str) -> &str {
+  match stem.rsplit_once('-') {
+    Some((name, hash)) if hash.len() == 8 && hash.chars().all(|c| c.is_ascii_hexdigit()) => name,
+    _ => stem,
+  }
+}
+
+// 写入一个工程的快照，同名快照被原子替换
+pub fn write(directory: &Path, name: &str, data: &str) -> Result<PathBuf, String> {
+  fs::create_dir_all(directory).map_err(|err| format!("failed to create {}: {err}", directory.display()))?;
+  let path = directory.join(file_name(name));
+  project::save(&path, data, 0)?;
+  Ok(path)
+}
+
+// 目录中遗留的快照，从新到旧
+pub fn snapshots(directory: &Path) -> Vec<RecoverySnapshot> {
+  let Ok(entries) = fs::read_dir(directory) else {
+    return Vec::new();
+  };
+  let mut snapshots: Vec<RecoverySnapshot> = entries
+    .filter_map(|entry| {
+      let path = entry.ok()?.path();
+      if !path.is_file() || path.extension() != Some(OsStr::new("json")) {
+        return None;
+      }
+      let modified = fs::metadata(&path).and_then(|metadata| metadata.modified()).ok()?;
+      Some(RecoverySnapshot {
+        name: display_name(&path.file_stem()?.to_string_lossy()).to_string(),
+        path: path.to_string_lossy().to_string(),
+        modified: modified.duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0),
+      })
+    })
+    .collect();
+  snapshots.sort_by_key(|snapshot| Reverse(snapshot.modified));
+  snapshots
+}
+
+// 工程已正常保存后删除快照，name 为 None 时删除全部
+pub fn discard(directory: &Path, name: Option<&str>) -> Result<(), String> {
+  let paths: Vec<PathBuf> = match name {
+    Some(name) => vec![directory.join(file_name(name))],
+    None => snapshots(directory).into_iter().map(|snapshot| PathBuf::from(snapshot.path)).collect(),
+  };
+  for path in paths.iter().filter(|path| path.is_file()) {
+    fs::remove_file(path).map_err(|err| format!("failed to remove {}: {err}", path.display()))?;
+  }
+  Ok(())
+}
+
+// 用户放弃恢复时不直接删除，改名为 .1.bak 保留到下一次放弃
+pub fn set_aside(snapshots: &[RecoverySnapshot]) -> Result<(), String> {
+  for snapshot in snapshots {
+    let path = Path::new(&snapshot.path);
+    let backup = project::backup_path(path, 1)?;
+    fs::rename(path, &backup).map_err(|err| format!("failed to set aside {}: {err}", path.display()))?;
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::fs::File;
+  use std::time::{Duration, SystemTime};
+
+  fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("recovery_{name}_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    dir
+  }
+
+  fn set_modified(path: &Path, seconds: u64) {
+    let file = File::options().write(true).open(path).unwrap();
+    file.set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(seconds)).unwrap();
+  }
+
+  #[test]
+  fn snapshot_names_are_unique() {
+    let dir = temp_dir("names");
+    let spaced = write(&dir, "a b", "{}").unwrap();
+    let underscored = write(&dir, "a_b", "{}").unwrap();
+    assert_ne!(spaced, underscored);
+    // 同一工程再次写入时替换原快照
+    assert_eq!(write(&dir, "a b", "[]").unwrap(), spaced);
+    assert_eq!(fs::read_to_string(&spaced).unwrap(), "[]");
+    // 路径分隔符不会逃出快照目录
+    let nested = write(&dir, "../x/y", "{}").unwrap();
+    assert_eq!(nested.parent(), Some(dir.as_path()));
+    let untitled = write(&dir, " ", "{}").unwrap();
+    assert!(untitled.file_name().unwrap().to_string_lossy().starts_with("untitled-"));
+
+    let mut names: Vec<String> = snapshots(&dir).into_iter().map(|snapshot| snapshot.name).collect();
+    names.sort();
+    assert_eq!(names, vec!["___x_y", "a_b", "a_b", "untitled"]);
+  }
+
+  #[test]
+  fn snapshots_are_newest_first() {
+    let dir = temp_dir("order");
+    for (name, seconds) in [("old", 1_000), ("new", 3_000), ("middle", 2_000)] {
+      set_modified(&write(&dir, name, "{}").unwrap(), seconds);
+    }
+    let list = snapshots(&dir);
+    let names: Vec<&str> = list.iter().map(|snapshot| snapshot.name.as_str()).collect();
+    assert_eq!(names, vec!["new", "middle", "old"]);
+    assert_eq!(list[0].modified, 3_000_000);
+  }
+
+  #[test]
+  fn discard_removes_only_that_project() {
+    let dir = temp_dir("discard");
+    let kept = write(&dir, "kept", "{}").unwrap();
+    let saved = write(&dir, "saved", "{}").unwrap();
+    discard(&dir, Some("saved")).unwrap();
+    assert!(!saved.exists() && kept.exists());
+    // 没有快照的工程不报错
+    discard(&dir, Some("missing")).unwrap();
+    discard(&dir, None).unwrap();
+    assert!(!kept.exists());
+  }
+
+  #[test]
+  fn set_aside_keeps_a_backup() {
+    let dir = temp_dir("set_aside");
+    let path = write(&dir, "demo", "{\"v\":1}").unwrap();
+    set_aside(&snapshots(&dir)).unwrap();
+    assert!(!path.exists());
+    assert!(snapshots(&dir).is_empty());
+    let backup = project::backup_path(&path, 1).unwrap();
+    assert_eq!(fs::read_to_string(&backup).unwrap(), "{\"v\":1}");
+
+    // 下一次放弃时覆盖上一次留下的备份
+    write(&dir, "demo", "{\"v\":2}").unwrap();
+    set_aside(&snapshots(&dir)).unwrap();
+    assert_eq!(fs::read_to_string(&backup).unwrap(), "{\"v\":2}");
+  }
+}
@@ -0,0 +1,203 @@
+import { PathType } from '../fontManager'
+import type { ILine, IQuadraticBezierCurve, ICubicBezierCurve } from '../fontManager'
+import type { ICharacterFile, IFile } from '../fontEditor/stores/files'
+import { toFontKerning, type IFontKerning } from './kerning'
+
+// 与 font_writer 中 Segment 的 JSON 格式一致
+// same JSON shape as Segment in font_writer
+interface IFontPoint {
+	x: number;
+	y: number;
+}
+
+type IFontSegment =
+	| { type: 'LINE', start: IFontPoint, end: IFontPoint }
+	| { type: 'QUADRATIC_BEZIER', start: IFontPoint, control: IFontPoint, end: IFontPoint }
+	| { type: 'CUBIC_BEZIER', start: IFontPoint, control1: IFontPoint, control2: IFontPoint, end: IFontPoint }
+
+type IFontContours = Array<Array<IFontSegment>>
+
+// 组件引用 font.glyphs 中的字形，transform 为 [a, b, c, d, e, f]
+// component referencing a glyph in font.glyphs, transform is [a, b, c, d, e, f]
+interface IFontComponent {
+	glyph: number;
+	transform: [number, number, number, number, number, number];
+}
+
+interface IFontGlyph {
+	name: string;
+	unicode: number;
+	advanceWidth: number;
+	contours: IFontContours;
+	components?: Array<IFontComponent>;
+}
+
+// 导出命令使用的 FontData，未给出的字段由 Rust 端取默认值
+// FontData for the export commands, omitted fields use the Rust defaults
+interface IFontData {
+	info: {
+		familyName: string;
+		styleName: string;
+		unitsPerEm: number;
+		ascender: number;
+		descender: number;
+	};
+	glyphs: Array<IFontGlyph>;
+	kerning: IFontKerning;
+}
+
+type IContours = Array<Array<ILine | IQuadraticBezierCurve | ICubicBezierCurve>>
+
+// 字符自身的轮廓加上引用的部件，部件轮廓位于原点，由 transform 放置
+// a character's own contours plus referenced parts, part contours sit at the origin and are placed by transform
+interface IGlyphOutline {
+	contours: IContours;
+	components: Array<{
+		name: string;
+		contours: IContours;
+		transform: [number, number, number, number, number, number];
+	}>;
+}
+
+const point = (p: { x: number, y: number }): IFontPoint => ({ x: p.x, y: p.y })
+
+// 编辑器轮廓转换为导出用的轮廓，去掉空轮廓
+// convert editor contours for export, dropping empty contours
+const toFontContours = (contours: IContours): IFontContours => {
+	return contours.filter((contour) => contour && contour.length).map((contour) => contour.map((segment) => {
+		switch (segment.type) {
+			case PathType.QUADRATIC_BEZIER: {
+				const { start, control, end } = segment as IQuadraticBezierCurve
+				return { type: 'QUADRATIC_BEZIER', start: point(start), control: point(control), end: point(end) }
+			}
+			case PathType.CUBIC_BEZIER: {
+				const { start, control1, control2, end } = segment as ICubicBezierCurve
+				return {
+					type: 'CUBIC_BEZIER',
+					start: point(start),
+					control1: point(control1),
+					control2: point(control2),
+					end: point(end),
+				}
+			}
+			default:
+				return { type: 'LINE', start: point(segment.start), end: point(segment.end) }
+		}
+	}))
+}
+
+// Rust 端读取或解析得到的轮廓转换为编辑器轮廓
+// convert contours read or parsed on the Rust side into editor contours
+const fromFontContours = (contours: IFontContours): IContours => {
+	return contours.map((contour) => contour.map((segment) => {
+		switch (segment.type) {
+			case 'QUADRATIC_BEZIER':
+				return { type: PathType.QUADRATIC_BEZIER, start: segment.start, control: segment.control, end: segment.end }
+			case 'CUBIC_BEZIER':
+				return {
+					type: PathType.CUBIC_BEZIER,
+					start: segment.start,
+					control1: segment.control1,
+					control2: segment.control2,
+					end: segment.end,
+				}
+			default:
+				return { type: PathType.LINE, start: segment.start, end: segment.end }
+		}
+	}))
+}
+
+/**
+ * 由工程生成导出用的 FontData，字形按码位排序，.notdef 字符排在最前，缺少空格时补一个空字形；
+ * 轮廓相同的部件只生成一个不编码的字形，追加在末尾并以组件引用
+ * @param file 字体文件
+ * @param outlineOf 生成字符轮廓的函数，可以同时给出引用的部件
+ * @param onProgress 每处理一个字符调用一次
+ * @returns font 与 characters，characters[i] 为 font.glyphs[i] 对应的字符，补充的字形与部件字形为 null
+ */
+/**
+ * build FontData for export, glyphs sorted by code point with .notdef first and a blank space added if missing;
+ * parts with identical contours become one unencoded glyph, appended at the end and referenced as a component
+ * @param file font file
+ * @param outlineOf generates contours of a character, optionally with referenced parts
+ * @param onProgress called once per character
+ * @returns font and characters, characters[i] is the character of font.glyphs[i], null for added and part glyphs
+ */
+const createFontData = async (
+	file: IFile,
+	outlineOf: (character: ICharacterFile) => IContours | IGlyphOutline,
+	onProgress?: () => void,
+) => {
+	const { unitsPerEm, ascender, descender } = file.fontSettings
+	let entries: Array<{ character: ICharacterFile | null, glyph: IFontGlyph }> = []
+	let notdef: { character: ICharacterFile, glyph: IFontGlyph } | null = null
+	// 部件字形，key 为轮廓的 JSON；字符字形的组件先记录部件序号，排序后再换算为字形序号
+	const parts: Array<IFontGlyph> = []
+	const partIndices = new Map<string, number>()
+	for (let i = 0; i < file.characterList.length; i++) {
+		const character = file.characterList[i]
+		onProgress && onProgress()
+		const { text, unicode } = character.character
+		const outline = outlineOf(character)
+		const { contours, components } = Array.isArray(outline) ? { contours: outline, components: [] } : outline
+		const glyph: IFontGlyph = {
+			name: text,
+			unicode: parseInt(unicode, 16),
+			advanceWidth: character.info?.metrics?.advanceWidth || unitsPerEm,
+			contours: toFontContours(contours),
+		}
+		if (components.length) {
+			glyph.components = components.map((component) => {
+				const partContours = toFontContours(component.contours)
+				const key = JSON.stringify(partContours)
+				if (!partIndices.has(key)) {
+					partIndices.set(key, parts.length)
+					parts.push({ name: component.name, unicode: 0, advanceWidth: unitsPerEm, contours: partContours })
+				}
+				return { glyph: partIndices.get(key), transform: component.transform }
+			})
+		}
+		if (text === '.notdef') {
+			glyph.name = '.notdef'
+			glyph.unicode = 0
+			glyph.advanceWidth = character.info?.metrics?.advanceWidth || Math.max(file.width, file.height)
+			notdef = { character, glyph }
+		} else {
+			entries.push({ character, glyph })
+		}
+		// 每处理50个字符让出主线程
+		if (i % 50 === 0) {
+			await new Promise((resolve) => requestAnimationFrame(resolve))
+		}
+	}
+	if (!entries.some((entry) => entry.glyph.unicode === 0x20)) {
+		entries.push({ character: null, glyph: { name: 'space', unicode: 0x20, advanceWidth: unitsPerEm, contours: [] } })
+	}
+	entries.sort((a, b) => a.glyph.unicode - b.glyph.unicode)
+	if (notdef) {
+		entries = [notdef, ...entries]
+	}
+	const partStart = entries.length
+	for (const entry of entries) {
+		entry.glyph.components?.forEach((component) => {
+			component.glyph += partStart
+		})
+	}
+	entries.push(...parts.map((glyph) => ({ character: null, glyph })))
+
+	const indices = new Map<string, number>()
+	entries.forEach((entry, index) => {
+		if (entry.character) {
+			indices.set(entry.character.uuid, index)
+		}
+	})
+	const font: IFontData = {
+		info: { familyName: file.name, styleName: 'Regular', unitsPerEm, ascender, descender },
+		glyphs: entries.map((entry) => entry.glyph),
+		kerning: toFontKerning(file.kerning, indices),
+	}
+	return { font, characters: entries.map((entry) => entry.character) }
+}
+
+export type { IFontData, IFontGlyph, IFontComponent, IFontContours, IGlyphOutline }
+export { toFontContours, fromFontContours, createFontData }
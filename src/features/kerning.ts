@@ -0,0 +1,68 @@
+import type { IKerning, IKerningClass, IKerningSide } from '../fontEditor/stores/files'
+
+// font_writer 中的字偶距按 FontData.glyphs 的序号引用字形
+// font_writer's kerning refers to glyphs by index into FontData.glyphs
+interface IFontKerningClass {
+	name: string;
+	glyphs: Array<number>;
+}
+
+type IFontKerningSide =
+	| { type: 'glyph', glyph: number }
+	| { type: 'class', class: number }
+
+interface IFontKerning {
+	leftClasses: Array<IFontKerningClass>;
+	rightClasses: Array<IFontKerningClass>;
+	pairs: Array<{ left: IFontKerningSide, right: IFontKerningSide, value: number }>;
+}
+
+// 工程字偶距转换为导出用的字偶距，indices 为字符 uuid 到字形序号的映射，不在导出字形中的字符忽略
+// convert project kerning for export; characters missing from indices are dropped
+const toFontKerning = (kerning: IKerning | undefined, indices: Map<string, number>): IFontKerning => {
+	if (!kerning) {
+		return { leftClasses: [], rightClasses: [], pairs: [] }
+	}
+	const classes = (classes: Array<IKerningClass>) => classes.map((kerningClass) => ({
+		name: kerningClass.name,
+		glyphs: kerningClass.characters.filter((uuid) => indices.has(uuid)).map((uuid) => indices.get(uuid)),
+	}))
+	const side = (side: IKerningSide): IFontKerningSide | null => {
+		if (side.type === 'class') return side
+		return indices.has(side.uuid) ? { type: 'glyph', glyph: indices.get(side.uuid) } : null
+	}
+	const pairs = []
+	for (const pair of kerning.pairs) {
+		const left = side(pair.left)
+		const right = side(pair.right)
+		if (left && right) {
+			pairs.push({ left, right, value: Math.round(pair.value) })
+		}
+	}
+	return { leftClasses: classes(kerning.leftClasses), rightClasses: classes(kerning.rightClasses), pairs }
+}
+
+// import_kerning 返回的字偶距转换为工程字偶距，uuids[i] 为 FontData.glyphs[i] 对应的字符 uuid
+// convert kerning returned by import_kerning; uuids[i] is the character uuid of FontData.glyphs[i]
+const fromFontKerning = (kerning: IFontKerning, uuids: Array<string>): IKerning => {
+	const classes = (classes: Array<IFontKerningClass>) => classes.map((fontClass) => ({
+		name: fontClass.name,
+		characters: fontClass.glyphs.map((glyph) => uuids[glyph]).filter((uuid) => !!uuid),
+	}))
+	const side = (side: IFontKerningSide): IKerningSide | null => {
+		if (side.type === 'class') return side
+		return uuids[side.glyph] ? { type: 'glyph', uuid: uuids[side.glyph] } : null
+	}
+	const pairs = []
+	for (const pair of kerning.pairs) {
+		const left = side(pair.left)
+		const right = side(pair.right)
+		if (left && right) {
+			pairs.push({ left, right, value: pair.value })
+		}
+	}
+	return { leftClasses: classes(kerning.leftClasses), rightClasses: classes(kerning.rightClasses), pairs }
+}
+
+export type { IFontKerning }
+export { toFontKerning, fromFontKerning }
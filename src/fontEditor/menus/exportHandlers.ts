@@ -16,7 +16,10 @@ import {
 import { base, canvas, fontRenderStyle, loaded, loadingMsg, total, useFixedCurves } from '../stores/global'
 import { saveAs } from 'file-saver'
 import * as R from 'ramda'
-import { ElMessageBox } from 'element-plus'
+import { ElMessageBox, ElNotification } from 'element-plus'
+import { h } from 'vue'
+import { invoke } from '@tauri-apps/api/core'
+import { save as tauriSave } from '@tauri-apps/plugin-dialog'
 import { componentsToSvg } from '../../features/svg'
 import { create, toArrayBuffer } from '../../fontManager'
 import type {
@@ -46,6 +49,8 @@ import JSZip from 'jszip'
 import { createOptimizedPath, isAlreadyOptimized, mergePathsWithPrecision } from './remove_overlap'
 import { removeOverlapWithWasm } from '../../utils/overlap-remover'
 import { PathType } from '../../fontManager'
+import { createFontData, toFontContours } from '../../features/fontData'
+import type { IGlyphOutline } from '../../features/fontData'
 
 interface CreateFontOptions {
   contour_storage?: string
@@ -53,7 +58,56 @@ interface CreateFontOptions {
   is_color_font?: boolean;
 }
 
+// 字符列表中批量导出：每个字符一个 SVG，由 Rust 端打包为 zip
+const exportCharacterSVGs_tauri = async () => {
+  const path = await tauriSave({
+    defaultPath: `${selectedFile.value.name}.zip`,
+    filters: [
+      {
+        name: 'Filter',
+        extensions: ['zip'],
+      },
+    ],
+  })
+  if (!path) return
+  const characters = selectedFile.value.characterList
+  loaded.value = 0
+  total.value = characters.length
+  loading.value = true
+  try {
+    const glyphs = []
+    for (let i = 0; i < characters.length; i++) {
+      const { text, unicode } = characters[i].character
+      glyphs.push({
+        name: text,
+        unicode: parseInt(unicode, 16) || null,
+        contours: toFontContours(characterContours(characters[i])),
+      })
+      loaded.value++
+      // 每处理50个字符让出主线程
+      if (i % 50 === 0) {
+        await new Promise((resolve) => requestAnimationFrame(resolve))
+      }
+    }
+    await invoke('write_svg_zip', { path, glyphs, options: svgExportOptions() })
+  } catch (err) {
+    ElNotification({
+      title: '导出失败',
+      message: h('i', String(err)),
+      type: 'error',
+    })
+  } finally {
+    loading.value = false
+    loaded.value = 0
+    total.value = 0
+  }
+}
+
 const exportGlyphs_tauri = async () => {
+  if (editStatus.value === Status.CharacterList) {
+    await exportCharacterSVGs_tauri()
+    return
+  }
   loaded.value = 0
   let temp_glyphs = null
   let temp_glyphs_name = ''
@@ -131,30 +185,128 @@ const exportPNG_tauri = async () => {
   render(_canvas, true)
 }
 
+const svgExportOptions = () => {
+  return {
+    unitsPerEm: selectedFile.value.fontSettings.unitsPerEm,
+    descender: selectedFile.value.fontSettings.descender,
+  }
+}
+
 const exportSVG_tauri = async () => {
-  // 导出SVG
+  // 导出SVG，轮廓由 Rust 端序列化
   if (editStatus.value !== Status.Edit && editStatus.value !== Status.Glyph ) return
-  const components = editStatus.value === Status.Edit ? orderedListWithItemsForCurrentCharacterFile.value : editGlyph.value._o.components
-  const data = componentsToSvg(components, selectedFile.value.width, selectedFile.value.height)
+  let contours = []
   let fileName = 'untitled.svg'
   if (editStatus.value === Status.Edit) {
+    contours = characterContours(editCharacterFile.value)
     fileName = `${editCharacterFile.value.character.text}.svg`
   } else {
+    if (!editGlyph.value._o) {
+      executeScript(editGlyph.value)
+    }
+    contours = componentsToContours(editGlyph.value._o.components, {
+      unitsPerEm: selectedFile.value.fontSettings.unitsPerEm,
+      descender: selectedFile.value.fontSettings.descender,
+      advanceWidth: selectedFile.value.fontSettings.unitsPerEm,
+    }, { x: 0, y: 0 }, true, false, false)
     fileName = `${editGlyph.value.name}.svg`
   }
-  nativeSaveText(data, fileName, ['svg'])
+  try {
+    const path = await tauriSave({
+      defaultPath: fileName,
+      filters: [
+        {
+          name: 'Filter',
+          extensions: ['svg'],
+        },
+      ],
+    })
+    if (path) {
+      await invoke('write_svg_file', { path, contours: toFontContours(contours), options: svgExportOptions() })
+    }
+  } catch (err) {
+    ElNotification({
+      title: '导出失败',
+      message: h('i', String(err)),
+      type: 'error',
+    })
+  }
 }
 
-const exportFont_tauri = async (options: CreateFontOptions) => {
-  const font = await createFont(options)
+// 桌面端导出：选择保存路径后由 Rust 端的 font_writer 生成并写入字库文件
+// desktop export: pick a path, then font_writer on the Rust side builds and writes the font
+const writeFontFile_tauri = async (command: string, args: Record<string, any>, extension: string) => {
   loadingMsg.value = '已经处理完所有字符，正在生成字库文件，请稍候...'
-  const buffer = toArrayBuffer(font) as ArrayBuffer
-  const filename = `${selectedFile.value.name}.otf`
-  nativeSaveBinary(buffer, filename, ['otf'])
-  loading.value = false
-  loaded.value = 0;
-	total.value = 0;
-  loadingMsg.value = ''
+  try {
+    const path = await tauriSave({
+      defaultPath: `${selectedFile.value.name}.${extension}`,
+      filters: [
+        {
+          name: 'Filter',
+          extensions: [extension],
+        },
+      ],
+    })
+    if (path) {
+      await invoke(command, { path, ...args })
+    }
+  } catch (err) {
+    ElNotification({
+      title: '导出失败',
+      message: h('i', String(err)),
+      type: 'error',
+    })
+  } finally {
+    loading.value = false
+    loaded.value = 0
+    total.value = 0
+    loadingMsg.value = ''
+  }
+}
+
+const characterContours = (character: ICharacterFile, forceUpdate: boolean = false) => {
+  return componentsToContours(orderedListWithItemsForCharacterFile(character), {
+    unitsPerEm: selectedFile.value.fontSettings.unitsPerEm,
+    descender: selectedFile.value.fontSettings.descender,
+    advanceWidth: selectedFile.value.fontSettings.unitsPerEm,
+  }, { x: 0, y: 0 }, false, false, forceUpdate)
+}
+
+// 字形组件（部首、笔画等）作为组件引用导出，以便写成复合字形；其余组件展开为字符自身的轮廓
+// 编辑器坐标 y 轴向下，字形组件的偏移 (ox, oy) 对应字体坐标中的平移 (ox, -oy)
+const characterOutline = (character: ICharacterFile): IGlyphOutline => {
+  const options = {
+    unitsPerEm: selectedFile.value.fontSettings.unitsPerEm,
+    descender: selectedFile.value.fontSettings.descender,
+    advanceWidth: selectedFile.value.fontSettings.unitsPerEm,
+  }
+  const components = orderedListWithItemsForCharacterFile(character).filter((component) => component?.usedInCharacter)
+  const glyphComponents = components.filter((component) => component.type === 'glyph') as unknown as Array<IGlyphComponent>
+  return {
+    contours: componentsToContours(components.filter((component) => component.type !== 'glyph'), options, { x: 0, y: 0 }, false, false, false),
+    components: glyphComponents.map((component) => ({
+      name: component.value.name,
+      // 抵消组件自身的偏移，得到位于原点的轮廓
+      contours: componentsToContours([component as any], options, { x: -component.ox, y: -component.oy }, false, false, false),
+      transform: [1, 0, 0, 1, component.ox, -component.oy] as [number, number, number, number, number, number],
+    })),
+  }
+}
+
+const exportFont_tauri = async (options: CreateFontOptions) => {
+  // 去除重叠时 Rust 端会展开所有组件，此时直接发送展开后的轮廓
+  const outlineOf = options.remove_overlap ? characterContours : characterOutline
+  const { font } = await createFontData(selectedFile.value, (character) => outlineOf(character), () => {
+    loaded.value++
+  })
+  const truetype = options.contour_storage === 'glyf'
+  await writeFontFile_tauri('write_font_file', {
+    font,
+    options: {
+      outlines: truetype ? 'truetype' : 'cff',
+      removeOverlap: !!options.remove_overlap,
+    },
+  }, truetype ? 'ttf' : 'otf')
 }
 
 const showExportFontDialog_tauri = () => {
@@ -195,7 +347,7 @@ const showExportVarFontDialog = () => {
 
 
 const exportGlyphs = () => {
-  if (editStatus.value === Status.CharacterList) {
+  if (editStatus.value === Status.CharacterList && ENV.value !== 'tauri') {
     const { locale } = i18n.global
     if (locale === 'zh') {
       ElMessageBox.alert(
@@ -1154,15 +1306,32 @@ const exportColorFont = async (options: CreateFontOptions) => {
 }
 
 const exportColorFont_tauri = async (options: CreateFontOptions) => {
-  const font = await createColorFont(options)
-  loadingMsg.value = '已经处理完所有字符，正在生成字库文件，请稍候...'
-  const buffer = toArrayBuffer(font) as ArrayBuffer
-  const filename = `${selectedFile.value.name}.otf`
-  nativeSaveBinary(buffer, filename, ['otf'])
-  loading.value = false
-  loaded.value = 0;
-	total.value = 0;
-  loadingMsg.value = ''
+  const outlineOf = options.remove_overlap ? characterContours : characterOutline
+  const { font, characters } = await createFontData(selectedFile.value, (character) => outlineOf(character), () => {
+    loaded.value++
+  })
+  // 每个字形的组件按顺序作为 COLR 图层，使用组件的填充色
+  const colorGlyphs = []
+  for (let i = 0; i < characters.length; i++) {
+    if (!characters[i]) continue
+    const layers = generateLayers(characters[i])
+      .filter((layer) => layer.contourNum)
+      .map((layer) => ({
+        contours: toFontContours(layer.contours),
+        fill: { type: 'solid', color: layer.fillColor },
+      }))
+      .filter((layer) => layer.contours.length)
+    if (layers.length) {
+      colorGlyphs.push({ glyph: i, layers })
+    }
+  }
+  await writeFontFile_tauri('write_color_font_file', {
+    font: { font, colorGlyphs, version: 0 },
+    options: {
+      outlines: 'cff',
+      removeOverlap: !!options.remove_overlap,
+    },
+  }, 'otf')
 }
 
 const exportVarFont = async (options: CreateFontOptions) => {
@@ -1190,15 +1359,81 @@ const exportVarFont = async (options: CreateFontOptions) => {
 }
 
 const exportVarFont_tauri = async (options: CreateFontOptions) => {
-  const font = await createVarFont(options)
-  loadingMsg.value = '已经处理完所有字符，正在生成字库文件，请稍候...'
-  const buffer = toArrayBuffer(font) as ArrayBuffer
-  const filename = `${selectedFile.value.name}.otf`
-  nativeSaveBinary(buffer, filename, ['otf'])
-  loading.value = false
-  loaded.value = 0;
-	total.value = 0;
-  loadingMsg.value = ''
+  const axes = selectedFile.value.variants?.axes || []
+  const combinations: any = generateAllAxisCombinations(axes.length)
+  const origin_constants = R.clone(constants.value)
+  useFixedCurves.value = true
+
+  // 设置每个轴对应的 constant 并更新 constantsMap
+  const setAxisValues = (values: Array<number>) => {
+    for (let i = 0; i < axes.length; i++) {
+      const constant = constants.value.find((constant) => constant.uuid === axes[i].uuid)
+      if (constant) {
+        constant.value = values[i]
+      }
+    }
+    constantsMap.update(constants.value)
+  }
+
+  try {
+    // 默认字形使用各轴的 defaultValue
+    setAxisValues(axes.map((axis) => axis.defaultValue))
+    const { font, characters } = await createFontData(selectedFile.value, (character) => characterContours(character, true), () => {
+      loaded.value++
+    })
+
+    // 每个组合生成一个 master，字形顺序与 font.glyphs 一致，补充的字形没有轮廓
+    const masters = []
+    for (let i = 0; i < combinations.length; i++) {
+      const tuple = combinations[i].tuple
+      const values = axes.map((axis, j) => {
+        const normalized = tuple[j]
+        if (normalized >= 0) {
+          return axis.defaultValue + (axis.maxValue - axis.defaultValue) * normalized
+        }
+        return axis.defaultValue + (axis.defaultValue - axis.minValue) * normalized
+      })
+      setAxisValues(values)
+      const glyphs = []
+      for (let k = 0; k < characters.length; k++) {
+        glyphs.push({
+          ...font.glyphs[k],
+          contours: characters[k] ? toFontContours(characterContours(characters[k], true)) : [],
+        })
+        loaded.value++
+        if (k % 50 === 0) {
+          await new Promise((resolve) => requestAnimationFrame(resolve))
+        }
+      }
+      const location = {}
+      axes.forEach((axis, j) => {
+        location[axis.axisTag] = values[j]
+      })
+      masters.push({ location, glyphs })
+    }
+
+    await writeFontFile_tauri('write_variable_font_file', {
+      font: {
+        font,
+        axes: axes.map((axis) => ({
+          tag: axis.axisTag,
+          name: axis.name,
+          minValue: axis.minValue,
+          defaultValue: axis.defaultValue,
+          maxValue: axis.maxValue,
+        })),
+        instances: [],
+        masters,
+      },
+      options: {
+        outlines: 'truetype',
+      },
+    }, 'ttf')
+  } finally {
+    constants.value = origin_constants
+    constantsMap.update(constants.value)
+    useFixedCurves.value = false
+  }
 }
 
 
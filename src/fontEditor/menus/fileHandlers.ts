@@ -17,6 +17,7 @@ import {
   visibleCount,
   setSelectedFileUUID,
   clearCharacterRenderList,
+  projectPaths,
 } from '../stores/files'
 import {
   loading,
@@ -58,6 +59,7 @@ import {
   readFile,
   readTextFile,
 } from '@tauri-apps/plugin-fs'
+import { invoke } from '@tauri-apps/api/core'
 import router from '../../router'
 import { ENV } from '../stores/system'
 import type { ICharacterFile, IFile, IPenComponent } from '../stores/files'
@@ -224,6 +226,7 @@ const plainFile = async (file: IFile) => {
     saved: file.saved,
     iconsCount: file.iconsCount,
     fontSettings: R.clone(file.fontSettings),
+    kerning: R.clone(file.kerning),
   }
 }
 
@@ -378,8 +381,35 @@ const getProjectData = async () => {
   })
 }
 
+// open_project 返回的工程文件
+interface IProjectFile {
+  path: string,
+  data: string,
+}
+
+// 已保存过的工程直接写回原路径，由 Rust 端原子替换并保留备份；未保存过的工程打开保存窗口
 const saveFile_tauri = async () => {
-  setSaveDialogVisible(true)
+  const path = selectedFile.value && projectPaths.value[selectedFile.value.uuid]
+  if (!path) {
+    setSaveDialogVisible(true)
+    return
+  }
+  loaded.value = 0
+  loading.value = true
+  total.value = selectedFile.value.characterList.length
+  try {
+    const data = await getProjectData()
+    await invoke('save_project', { path, data })
+    discardAutosave(selectedFile.value.name)
+  } catch (err) {
+    ElNotification({
+      title: '保存失败',
+      message: h('i', String(err)),
+      type: 'error',
+    })
+  } finally {
+    loading.value = false
+  }
 }
 
 const saveAs_tauri = async () => {
@@ -494,10 +524,80 @@ const openFile_tauri = async () => {
     tips.value = '目前字玩仅支持同时编辑一个工程，请关闭当前工程再打开新工程。注意，关闭工程前请保存工程以避免数据丢失。'
     tipsDialogVisible.value = true
   } else {
-    const { data: rawdata } = await nativeImportTextFile(['json'])
-    if (!rawdata) return
-    const data = JSON.parse(rawdata)
+    let project: IProjectFile | null = null
+    try {
+      project = await invoke<IProjectFile | null>('open_project')
+    } catch (err) {
+      ElNotification({
+        title: '打开失败',
+        message: h('i', String(err)),
+        type: 'error',
+      })
+      return
+    }
+    if (!project) return
+    const data = JSON.parse(project.data)
     await __openFile(data)
+    if (files.value.some((file: IFile) => file.uuid === data.file.uuid)) {
+      projectPaths.value[data.file.uuid] = project.path
+    }
+  }
+}
+
+// Rust 端记录的自动保存快照
+interface IRecoverySnapshot {
+  name: string,
+  path: string,
+  modified: number,
+}
+
+// 自动保存间隔（毫秒）
+const AUTOSAVE_INTERVAL = 2 * 60 * 1000
+let autosaveTimer: ReturnType<typeof setInterval> | null = null
+let autosaving = false
+
+// 定时把当前工程提交给 Rust 端写入快照，崩溃后下次启动时可以恢复
+const startAutosave_tauri = () => {
+  if (autosaveTimer) return
+  autosaveTimer = setInterval(async () => {
+    if (autosaving || loading.value || !selectedFile.value) return
+    autosaving = true
+    try {
+      const name = selectedFile.value.name
+      const data = await getProjectData()
+      await invoke('autosave_project', { name, data })
+    } catch (err) {
+      console.error(err)
+    } finally {
+      autosaving = false
+    }
+  }, AUTOSAVE_INTERVAL)
+}
+
+// 工程正常保存后删除它的快照，删除失败不影响保存结果
+const discardAutosave = (name: string) => {
+  invoke('discard_autosave', { name }).catch((err) => console.error(err))
+}
+
+// 启动时取走用户确认恢复的快照并打开，目前只能同时编辑一个工程，只恢复最新的一份
+const recoverProject_tauri = async () => {
+  try {
+    const snapshots = await invoke<IRecoverySnapshot[]>('take_recovery_snapshots')
+    const snapshot = snapshots[0]
+    if (!snapshot) return
+    const data = await invoke<string>('load_project', { path: snapshot.path })
+    if (router.currentRoute.value.name === 'welcome') {
+      router.push('/editor')
+      await nextTick()
+      await new Promise((resolve) => setTimeout(resolve, 100))
+    }
+    await __openFile(JSON.parse(data))
+  } catch (err) {
+    ElNotification({
+      title: '恢复失败',
+      message: h('i', String(err)),
+      type: 'error',
+    })
   }
 }
 
@@ -989,6 +1089,9 @@ export {
   createFile,
   openFile,
   openFile_tauri,
+  startAutosave_tauri,
+  discardAutosave,
+  recoverProject_tauri,
   saveFile_web,
   saveFile,
   clearCache,
@@ -52,6 +52,7 @@ import {
   exportColorFont_tauri,
   exportVarFont,
   exportVarFont_tauri,
+  generateAllAxisCombinations,
   exportJPEG,
   exportJPEG_tauri,
   exportPNG,
@@ -238,6 +239,7 @@ export {
   exportVarFont,
   exportColorFont,
   exportVarFont_tauri,
+  generateAllAxisCombinations,
   exportColorFont_tauri,
   exportJSONFromFileHandlers as exportJSON,
 }
\ No newline at end of file
@@ -70,6 +70,9 @@ import { nextTick } from 'vue'
 import { i18n } from '../../i18n'
 import router from '../../router'
 import { ENV } from '../stores/system'
+import { invoke } from '@tauri-apps/api/core'
+import { open as tauriOpen } from '@tauri-apps/plugin-dialog'
+import { fromFontContours } from '../../features/fontData'
 import { worker } from '../../main'
 import { WorkerEventType } from '../worker'
 import type { ICharacterFile, IFile } from '../stores/files'
@@ -84,16 +87,98 @@ const importFont_tauri = async () => {
     tips.value = '目前字玩仅支持同时编辑一个工程，请关闭当前工程再导入字体。注意，关闭工程前请保存工程以避免数据丢失。'
     tipsDialogVisible.value = true
   } else {
-    const options = await nativeImportFile(['otf', 'ttf'])
-    await _importFont_tauri(options)
+    const path = await tauriOpen({
+      filters: [
+        {
+          name: 'Filter',
+          extensions: ['otf', 'ttf', 'woff'],
+        },
+      ],
+    })
+    if (!path) return
+    await _importFont_tauri(path)
   }
 }
 
-const _importFont_tauri = (options) => {
-  const { uint8Array, name } = options
-  if (!uint8Array) return
-  const arrayBuffer = uint8Array.buffer
-  const font = parse(arrayBuffer)
+// WOFF 1.0 字体先解包为 sfnt 数据再解析，压缩的表为 zlib 格式，使用浏览器的 DecompressionStream 解压
+// 桌面端由 read_font_file 在 Rust 端解包
+const unwrapWoff = async (buffer: ArrayBuffer): Promise<ArrayBuffer> => {
+  const view = new DataView(buffer)
+  if (buffer.byteLength < 44 || view.getUint32(0) !== 0x774F4646) return buffer
+  const numTables = view.getUint16(12)
+  const tables = []
+  for (let i = 0; i < numTables; i++) {
+    const entry = 44 + i * 20
+    const offset = view.getUint32(entry + 4)
+    const compLength = view.getUint32(entry + 8)
+    const origLength = view.getUint32(entry + 12)
+    let data = new Uint8Array(buffer, offset, compLength)
+    if (compLength < origLength) {
+      const stream = new Blob([data]).stream().pipeThrough(new DecompressionStream('deflate'))
+      data = new Uint8Array(await new Response(stream).arrayBuffer())
+    }
+    if (data.length !== origLength) {
+      throw new Error('invalid WOFF table')
+    }
+    tables.push({ tag: view.getUint32(entry), checksum: view.getUint32(entry + 16), data })
+  }
+  // sfnt 头部、表目录，之后为 4 字节对齐的表数据
+  let size = 12 + numTables * 16
+  for (const table of tables) {
+    size += (table.data.length + 3) & ~3
+  }
+  const sfnt = new Uint8Array(size)
+  const out = new DataView(sfnt.buffer)
+  let searchRange = 1
+  let entrySelector = 0
+  while (searchRange * 2 <= numTables) {
+    searchRange *= 2
+    entrySelector++
+  }
+  out.setUint32(0, view.getUint32(4))
+  out.setUint16(4, numTables)
+  out.setUint16(6, searchRange * 16)
+  out.setUint16(8, entrySelector)
+  out.setUint16(10, numTables * 16 - searchRange * 16)
+  let offset = 12 + numTables * 16
+  tables.forEach((table, i) => {
+    out.setUint32(12 + i * 16, table.tag)
+    out.setUint32(16 + i * 16, table.checksum)
+    out.setUint32(20 + i * 16, offset)
+    out.setUint32(24 + i * 16, table.data.length)
+    sfnt.set(table.data, offset)
+    offset += (table.data.length + 3) & ~3
+  })
+  return sfnt.buffer
+}
+
+// 桌面端由 Rust 端的 read_font_file 读取字体（包括 WOFF），转换为 parse 返回的结构后交给 worker
+const _importFont_tauri = async (path: string) => {
+  let fontFile
+  try {
+    fontFile = await invoke('read_font_file', { path }) as any
+  } catch (err) {
+    ElMessageBox.alert(String(err), '导入失败', {
+      confirmButtonText: '确定',
+    })
+    return
+  }
+  const name = path.split(/[\\/]/).pop().split('.')[0]
+  const font = {
+    settings: {
+      unitsPerEm: fontFile.unitsPerEm,
+      ascender: fontFile.ascender,
+      descender: fontFile.descender,
+    },
+    characters: fontFile.characters.map((character) => ({
+      unicode: character.unicode,
+      name: character.name,
+      advanceWidth: character.advanceWidth,
+      leftSideBearing: character.leftSideBearing,
+      contours: fromFontContours(character.contours),
+      contourNum: character.contourNum,
+    })),
+  }
   const file: IFile = {
     uuid: genUUID(),
     width: font.settings.unitsPerEm as number,
@@ -131,7 +216,7 @@ const importFont = () => {
   const input = document.createElement('input')
   input.setAttribute('type', 'file')
   input.setAttribute('style', 'display: none')
-  input.setAttribute('accept', '.otf, .ttf')
+  input.setAttribute('accept', '.otf, .ttf, .woff')
   input.addEventListener('blur', async () => {
     loading.value = false
   })
@@ -142,8 +227,8 @@ const importFont = () => {
     const _file = filesList[0]
     const fullFileName = _file.name
     const fileName = fullFileName.substring(0, fullFileName.lastIndexOf('.'))
-    const buffer = _file.arrayBuffer()
-    const font = parse(await buffer)
+    const buffer = await unwrapWoff(await _file.arrayBuffer())
+    const font = parse(buffer)
 
     loaded.value = 0
     total.value = font.characters.length * 2 + Math.min(visibleCount.value, font.characters.length)
@@ -475,11 +560,32 @@ const importGlyphs = () => {
   input.click()
 }
 
+// 桌面端由 Rust 端的 parse_svg_file 解析 SVG，得到字体坐标系下的轮廓后转换为钢笔组件
 const importSVG_tauri = async () => {
-  const { data: rawdata } = await nativeImportTextFile(['svg'])
-  if (!rawdata) return
-  const svgEl: HTMLElement = parseStrToSvg(rawdata).childNodes[0] as HTMLElement
-  const components = parseSvgToComponents(svgEl as HTMLElement)
+  const path = await tauriOpen({
+    filters: [
+      {
+        name: 'Filter',
+        extensions: ['svg'],
+      },
+    ],
+  })
+  if (!path) return
+  const { unitsPerEm, descender } = selectedFile.value.fontSettings
+  let contours
+  try {
+    contours = await invoke('parse_svg_file', { path, options: { unitsPerEm, descender } })
+  } catch (err) {
+    ElMessageBox.alert(String(err), '导入失败', {
+      confirmButtonText: '确定',
+    })
+    return
+  }
+  const components = contoursToComponents(fromFontContours(contours as any), {
+    unitsPerEm,
+    descender,
+    advanceWidth: unitsPerEm,
+  })
   components.forEach((component: IComponent) => {
     addComponentForCurrentCharacterFile(component)
   })
@@ -1,5 +1,6 @@
 import { ENV } from '../stores/system'
 import { tauri_handlers } from '../menus/handlers'
+import { recoverProject_tauri, startAutosave_tauri } from '../menus/fileHandlers'
 import { Status, editStatus } from '../stores/font'
 import { watch } from 'vue'
 import { listen } from '@tauri-apps/api/event'
@@ -34,6 +35,8 @@ const initTauri = () => {
 				tauri_handlers[key]()
 			})
 		}
+		recoverProject_tauri()
+		startAutosave_tauri()
 	}
 }
 
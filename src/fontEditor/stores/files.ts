@@ -41,6 +41,34 @@ export interface IFile {
 	radical_glyphs?: Array<ICustomGlyph>;
 	comp_glyphs?: Array<ICustomGlyph>;
 	variants?: IVariants;
+	kerning?: IKerning;
+}
+
+// 字偶距分组，characters 为字符 uuid
+// kerning class, characters are character uuids
+export interface IKerningClass {
+	name: string;
+	characters: Array<string>;
+}
+
+// 字偶距的一侧：单个字符，或 leftClasses/rightClasses 中的分组序号
+// one side of a kerning pair: a character, or an index into leftClasses/rightClasses
+export type IKerningSide =
+	| { type: 'glyph', uuid: string }
+	| { type: 'class', class: number }
+
+export interface IKerningPair {
+	left: IKerningSide;
+	right: IKerningSide;
+	value: number;
+}
+
+// 字偶距数据结构，导出时转换为 font_writer 的 Kerning
+// kerning data struct, converted to font_writer's Kerning on export
+export interface IKerning {
+	leftClasses: Array<IKerningClass>;
+	rightClasses: Array<IKerningClass>;
+	pairs: Array<IKerningPair>;
 }
 
 interface IVariants {
@@ -293,6 +321,10 @@ const files: any = reactive({
 // selected font file uuid
 const selectedFileUUID: Ref<string> = ref('')
 
+// 工程在磁盘上的保存路径，按字体文件uuid记录，未保存过的工程没有路径
+// saved project path on disk, keyed by font file uuid
+const projectPaths: Ref<Record<string, string>> = ref({})
+
 // 剪贴板
 // clipboard
 const clipBoard: any = reactive({
@@ -607,6 +639,7 @@ const removeFile = (uuid: string) => {
 		setSelectedFileUUID('')
 	}
 	editCharacterFileUUID.value = ''
+	delete projectPaths.value[uuid]
 	const index = (() => {
 		for (let i = 0; i < files.value.length; i++) {
 			if (files.value[i].uuid === uuid)
@@ -1673,6 +1706,7 @@ export {
 	files,
 	editCharacterFileUUID,
 	selectedFileUUID,
+	projectPaths,
 	selectedFile,
 	enableMultiSelect,
 	selectedComponents,
@@ -5,6 +5,7 @@ let isInitialized = false;
 // 定义WASM模块的接口
 interface OverlapRemoverWasm {
   remove_overlap: (contoursJson: string) => string;
+  decode_woff: (data: Uint8Array) => Uint8Array;
 }
 
 // 扩展Window接口